@@ -0,0 +1,14 @@
+#import bevy_ui::ui_vertex_output::UiVertexOutput
+
+@group(1) @binding(0) var<uniform> color: vec4<f32>;
+// x: strength of the darkened edges, y: strength of the flash over the whole screen
+@group(1) @binding(1) var<uniform> params: vec4<f32>;
+
+@fragment
+fn fragment(in: UiVertexOutput) -> @location(0) vec4<f32> {
+    // 0 in the middle of the screen, 1 in the corners
+    let distance = length(in.uv - vec2(0.5)) * 1.41421356;
+    let edge = smoothstep(0.35, 1.0, distance);
+    let alpha = clamp(edge * params.x + params.y, 0.0, 1.0) * color.a;
+    return vec4(color.rgb, alpha);
+}
@@ -0,0 +1,39 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::{lights, view},
+}
+
+struct ToonMaterial {
+    color: vec4<f32>,
+    shadow_color: vec4<f32>,
+    rim_color: vec4<f32>,
+    bands: u32,
+    rim_power: f32,
+    rim_strength: f32,
+};
+
+@group(2) @binding(0) var<uniform> material: ToonMaterial;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.world_normal);
+    let to_camera = normalize(view.world_position.xyz - in.world_position.xyz);
+
+    // only the direction of the sun matters, the colours come from the material
+    var light = 0.0;
+    for (var i = 0u; i < lights.n_directional_lights; i++) {
+        let direction = lights.directional_lights[i].direction_to_light;
+        light = max(light, dot(normal, direction));
+    }
+
+    // flat bands from the shadow colour up to the full colour
+    let bands = f32(max(material.bands, 2u));
+    let stepped = clamp(floor(max(light, 0.0) * bands) / (bands - 1.0), 0.0, 1.0);
+    let base = mix(material.shadow_color.rgb, material.color.rgb, stepped);
+
+    // light catching the edges facing away from the camera
+    let facing = max(dot(normal, to_camera), 0.0);
+    let rim = step(0.5, pow(1.0 - facing, material.rim_power)) * material.rim_strength;
+
+    return vec4(base + material.rim_color.rgb * rim, material.color.a);
+}
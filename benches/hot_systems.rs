@@ -0,0 +1,117 @@
+//! Times the systems that run over the whole horde every frame, each on its
+//! own in a bare world of 1k and 5k enemies
+//!
+//! ```sh
+//! cargo bench --features testing
+//! ```
+
+use std::time::Duration;
+
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ExternalForce, ReadMassProperties, Velocity};
+use bunvivor::ai::{Separation, separate};
+use bunvivor::testing::{
+    AnimationFinished, AnimationFrameEvent, AnimationIndices, AnimationMode, AnimationTimer,
+    SpatialGrid, animate_sprites, entities_try_to_move, rebuild_grid,
+};
+use bunvivor::{MoveSpeed, MoveVector};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::prelude::*;
+
+const ENEMY_COUNTS: [usize; 2] = [1000, 5000];
+/// The same spread `horde_stress` spawns over
+const SPAWN_RADIUS: f32 = 50.0;
+const SEPARATION_CELL_SIZE: f32 = 4.0;
+const FRAME: Duration = Duration::from_micros(16_667);
+
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct Bench;
+
+/// A world with `count` enemies scattered around the origin, each with
+/// everything the benched systems read, and the grid already built over them
+fn horde(count: usize) -> World {
+    let mut world = World::new();
+    world.insert_resource(Time::<()>::default());
+    world.insert_resource(SpatialGrid::<Separation>::new(SEPARATION_CELL_SIZE));
+    world.init_resource::<Events<AnimationFrameEvent>>();
+    world.init_resource::<Events<AnimationFinished>>();
+
+    let mut rng = StdRng::seed_from_u64(0);
+    for i in 0..count {
+        let pos = Vec2::from_angle(rng.random_range(0.0..std::f32::consts::TAU))
+            * SPAWN_RADIUS
+            * rng.random::<f32>().sqrt();
+        let mut visibility = ViewVisibility::HIDDEN;
+        // about half of a big horde is off screen at any one time
+        if i % 2 == 0 {
+            visibility.set();
+        }
+        world.spawn((
+            Transform::from_xyz(pos.x, 0.0, pos.y),
+            Velocity::linear(Vec3::new(rng.random(), 0.0, rng.random())),
+            ExternalForce::default(),
+            ReadMassProperties::default(),
+            MoveVector {
+                vec: Vec3::new(-pos.x, 0.0, -pos.y).normalize_or_zero() * 3.0,
+            },
+            MoveSpeed(3.0),
+            Separation::default(),
+            (
+                AnimationIndices::new("walk", 0, 7, AnimationMode::Cycle),
+                AnimationTimer::from_seconds(0.1),
+                visibility,
+                Sprite {
+                    texture_atlas: Some(TextureAtlas::default()),
+                    ..default()
+                },
+            ),
+        ));
+    }
+    world
+        .run_system_once(rebuild_grid::<Separation>)
+        .expect("the grid is in the world");
+    return world;
+}
+
+/// Times one run of `system` per iteration over hordes of every size, a
+/// frame's worth of time passing between each
+fn bench_system<M>(c: &mut Criterion, name: &str, system: impl IntoSystem<(), (), M> + Copy) {
+    let mut group = c.benchmark_group(name);
+    for count in ENEMY_COUNTS {
+        let mut world = horde(count);
+        let mut schedule = Schedule::new(Bench);
+        schedule.add_systems(system);
+        // build the schedule outside of the timing
+        schedule.run(&mut world);
+
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            b.iter(|| {
+                world.resource_mut::<Time>().advance_by(FRAME);
+                schedule.run(&mut world);
+            });
+        });
+    }
+    group.finish();
+}
+
+fn movement(c: &mut Criterion) {
+    bench_system(c, "entities_try_to_move", entities_try_to_move);
+}
+
+fn separation(c: &mut Criterion) {
+    // nothing moves, so the grid built with the horde stays right
+    bench_system(c, "separate", separate);
+}
+
+fn animation(c: &mut Criterion) {
+    bench_system(c, "animate_sprites", animate_sprites);
+}
+
+fn spatial_grid(c: &mut Criterion) {
+    bench_system(c, "rebuild_grid", rebuild_grid::<Separation>);
+}
+
+criterion_group!(benches, movement, separation, animation, spatial_grid);
+criterion_main!(benches);
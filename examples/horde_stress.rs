@@ -0,0 +1,210 @@
+//! Spawns a large horde around the player and periodically prints frame
+//! times, entity counts and the most expensive systems seen so far.
+//!
+//! This is the canonical scenario for checking performance regressions:
+//!
+//! ```sh
+//! cargo run --release --example horde_stress --features trace
+//! cargo run --release --example horde_stress --features trace -- --headless
+//! ```
+//!
+//! Takes the same options as the game, so `--headless` runs it without a
+//! window or renderer and `--seed` makes the horde the same every time.
+//!
+//! Without the `trace` feature bevy does not emit per-system spans, so only
+//! frame times are reported.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use bevy::diagnostic::{
+    DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
+};
+use bevy::log::BoxedLayer;
+use bevy::log::tracing::Subscriber;
+use bevy::log::tracing::field::{Field, Visit};
+use bevy::log::tracing::span::{Attributes, Id};
+use bevy::log::tracing_subscriber::layer::{Context, Layer};
+use bevy::log::tracing_subscriber::registry::LookupSpan;
+use bevy::prelude::*;
+use bunvivor::cli::{CliError, USAGE};
+use bunvivor::enemies::SpawnEnemies;
+use bunvivor::{AppConfig, GameState};
+
+const ENEMY_COUNT: usize = 2000;
+const SPAWN_RADIUS: f32 = 50.0;
+/// Close enough to be on screen, far enough not to land on the player
+const MIN_SPAWN_DISTANCE: f32 = 10.0;
+const WITCH_FRACTION: f32 = 0.05;
+const REPORT_INTERVAL: f32 = 5.0;
+const TOP_SYSTEMS: usize = 15;
+
+static TIMINGS: LazyLock<Mutex<HashMap<String, SystemTiming>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Default)]
+struct SystemTiming {
+    total: Duration,
+    runs: u32,
+}
+
+fn main() {
+    let mut config = match AppConfig::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(CliError::Help) => {
+            print!("{USAGE}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    config.launch.skip_menu = true;
+    config.log_layer = Some(system_timing_layer);
+
+    let mut app = bunvivor::App::with_config(config);
+    app.add_plugins(HordeStressPlugin);
+    app.run();
+}
+
+fn system_timing_layer(_app: &mut App) -> Option<BoxedLayer> {
+    return Some(Box::new(SystemTimingLayer));
+}
+
+struct HordeStressPlugin;
+
+impl Plugin for HordeStressPlugin {
+    fn build(&self, app: &mut App) {
+        // debug builds already have these for the inspector
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+        if !app.is_plugin_added::<EntityCountDiagnosticsPlugin>() {
+            app.add_plugins(EntityCountDiagnosticsPlugin);
+        }
+        app.insert_resource(ReportTimer(Timer::from_seconds(
+            REPORT_INTERVAL,
+            TimerMode::Repeating,
+        )));
+        app.add_systems(OnEnter(GameState::InGame), spawn_horde);
+        app.add_systems(Last, report);
+    }
+}
+
+#[derive(Resource, Deref, DerefMut)]
+struct ReportTimer(Timer);
+
+/// Every run starts with the whole horde around the origin, where the
+/// players start
+fn spawn_horde(mut commands: Commands) {
+    let witches = (ENEMY_COUNT as f32 * WITCH_FRACTION).round() as usize;
+    for (prefab, count) in [("grunt", ENEMY_COUNT - witches), ("witch", witches)] {
+        commands.queue(SpawnEnemies {
+            prefab: prefab.to_string(),
+            count,
+            center: Vec3::Y * 1.1,
+            distance: MIN_SPAWN_DISTANCE..SPAWN_RADIUS,
+        });
+    }
+}
+
+fn report(time: Res<Time>, mut timer: ResMut<ReportTimer>, diagnostics: Res<DiagnosticsStore>) {
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|d| d.smoothed())
+        .unwrap_or_default();
+    let frame_time = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|d| d.smoothed())
+        .unwrap_or_default();
+    let entities = diagnostics
+        .get(&EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+        .and_then(|d| d.value())
+        .unwrap_or_default();
+
+    println!("---- horde_stress: {fps:.1} fps, {frame_time:.2} ms/frame, {entities} entities ----");
+
+    let timings = TIMINGS.lock().unwrap();
+    let mut sorted: Vec<_> = timings.iter().collect();
+    sorted.sort_by_key(|(_, timing)| std::cmp::Reverse(timing.total));
+    for (name, timing) in sorted.into_iter().take(TOP_SYSTEMS) {
+        let avg = timing.total.as_secs_f64() * 1000.0 / timing.runs.max(1) as f64;
+        println!("{avg:>9.4} ms avg  {:>6} runs  {name}", timing.runs);
+    }
+}
+
+/// Name of the system a span belongs to
+struct SystemName(String);
+
+/// When the span was last entered
+struct Entered(Instant);
+
+/// Accumulates the time spent inside bevy's per-system `system` spans
+struct SystemTimingLayer;
+
+impl<S> Layer<S> for SystemTimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "system" {
+            return;
+        }
+
+        let mut visitor = NameVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(name), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(SystemName(name));
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut extensions = span.extensions_mut();
+        if extensions.get_mut::<SystemName>().is_some() {
+            extensions.replace(Entered(Instant::now()));
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let extensions = span.extensions();
+        if let (Some(name), Some(entered)) =
+            (extensions.get::<SystemName>(), extensions.get::<Entered>())
+        {
+            let mut timings = TIMINGS.lock().unwrap();
+            let timing = timings.entry(name.0.clone()).or_default();
+            timing.total += entered.0.elapsed();
+            timing.runs += 1;
+        }
+    }
+}
+
+struct NameVisitor(Option<String>);
+
+impl Visit for NameVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "name" {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
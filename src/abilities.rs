@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::combat::{DamageEvent, DamageType};
+use crate::controls::{Action, InputBuffer, buffer_inputs};
+use crate::particles::{EmitParticles, ParticleEffect};
+use crate::platforms::launch;
+use crate::stamina::Stamina;
+use crate::stats::{Stat, Stats};
+use crate::{Enemy, GameState, GameplaySet, Player, PlayerSlot};
+
+/// The actions that use each ability slot, in slot order
+pub const ABILITY_ACTIONS: [Action; 2] = [Action::Ability1, Action::Ability2];
+const ICON_SIZE: f32 = 56.0;
+
+pub struct AbilitiesPlugin;
+
+impl Plugin for AbilitiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AbilitySlots>();
+        app.register_type::<Decoy>();
+        app.init_resource::<AbilityRegistry>();
+        app.add_systems(PreStartup, setup_ability_assets);
+        app.add_systems(
+            Update,
+            (
+                use_abilities.after(buffer_inputs),
+                expire_decoys,
+                spawn_ability_icons,
+                update_ability_icons,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+
+        app.add_ability(
+            "Slam",
+            Slam {
+                radius: 6.0,
+                damage: 30.0,
+                knock_up: 25.0,
+            },
+        );
+        app.add_ability("Decoy", SpawnDecoy { seconds: 5.0 });
+    }
+}
+
+/// Something a player can do on a button press, on top of their weapons
+/// firing by themselves
+pub trait Ability: Send + Sync + 'static {
+    /// Seconds before it can be used again
+    fn cooldown(&self) -> f32;
+
+    /// Stamina it takes to use
+    fn cost(&self) -> f32 {
+        return 0.0;
+    }
+
+    fn activate(&self, world: &mut World, caster: Entity);
+}
+
+/// Every ability players can have in a slot, by name
+#[derive(Resource, Default)]
+pub struct AbilityRegistry(BTreeMap<&'static str, Box<dyn Ability>>);
+
+impl AbilityRegistry {
+    pub fn get(&self, name: &str) -> Option<&dyn Ability> {
+        return self.0.get(name).map(|ability| ability.as_ref());
+    }
+}
+
+pub trait AbilitiesExt {
+    /// Make `ability` available for ability slots as `name`
+    fn add_ability(&mut self, name: &'static str, ability: impl Ability) -> &mut Self;
+}
+
+impl AbilitiesExt for App {
+    fn add_ability(&mut self, name: &'static str, ability: impl Ability) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<AbilityRegistry>()
+            .0
+            .insert(name, Box::new(ability));
+        return self;
+    }
+}
+
+#[derive(Reflect, Debug, Clone, Copy)]
+pub struct AbilitySlot {
+    /// Name the ability was registered under
+    pub ability: &'static str,
+    /// Seconds until it can be used again
+    pub cooldown: f32,
+    /// What `cooldown` started at, for showing how far along it is
+    pub max_cooldown: f32,
+}
+
+impl AbilitySlot {
+    pub fn new(ability: &'static str) -> Self {
+        return Self {
+            ability,
+            cooldown: 0.0,
+            max_cooldown: 0.0,
+        };
+    }
+
+    /// How much of the cooldown is left, from 1 just used to 0 ready
+    pub fn cooldown_fraction(&self) -> f32 {
+        if self.max_cooldown <= 0.0 {
+            return 0.0;
+        }
+        return (self.cooldown / self.max_cooldown).clamp(0.0, 1.0);
+    }
+}
+
+/// The abilities a player has, used with `ABILITY_ACTIONS`
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct AbilitySlots(pub [AbilitySlot; 2]);
+
+impl Default for AbilitySlots {
+    fn default() -> Self {
+        return Self([AbilitySlot::new("Slam"), AbilitySlot::new("Decoy")]);
+    }
+}
+
+/// Knock back and damage every enemy close to the caster
+pub struct Slam {
+    pub radius: f32,
+    /// Scaled by the caster's damage stat
+    pub damage: f32,
+    /// Speed enemies are thrown up at
+    pub knock_up: f32,
+}
+
+impl Ability for Slam {
+    fn cooldown(&self) -> f32 {
+        return 6.0;
+    }
+
+    fn cost(&self) -> f32 {
+        return 20.0;
+    }
+
+    fn activate(&self, world: &mut World, caster: Entity) {
+        let Some(&transform) = world.get::<Transform>(caster) else {
+            return;
+        };
+        let damage = world
+            .get::<Stats>(caster)
+            .map_or(1.0, |stats| stats.get(Stat::Damage))
+            * self.damage;
+
+        let hit: Vec<Entity> = world
+            .query_filtered::<(Entity, &Transform), With<Enemy>>()
+            .iter(world)
+            .filter(|(_, enemy)| {
+                enemy.translation.distance_squared(transform.translation)
+                    <= self.radius * self.radius
+            })
+            .map(|(enemy, _)| enemy)
+            .collect();
+
+        let mut commands = world.commands();
+        for &enemy in &hit {
+            launch(&mut commands, enemy, self.knock_up);
+        }
+        world.flush();
+        for enemy in hit {
+            world.send_event(DamageEvent {
+                target: enemy,
+                amount: damage,
+                source: Some(caster),
+                crit: false,
+                weapon: None,
+                kind: DamageType::Physical,
+            });
+        }
+        world.send_event(EmitParticles {
+            effect: ParticleEffect::Shockwave,
+            position: transform.translation,
+        });
+    }
+}
+
+/// Leave a decoy behind for enemies to go after instead
+pub struct SpawnDecoy {
+    pub seconds: f32,
+}
+
+impl Ability for SpawnDecoy {
+    fn cooldown(&self) -> f32 {
+        return 12.0;
+    }
+
+    fn activate(&self, world: &mut World, caster: Entity) {
+        let Some(&transform) = world.get::<Transform>(caster) else {
+            return;
+        };
+        let assets = world.resource::<AbilityAssets>();
+        let (mesh, material) = (assets.decoy_mesh.clone(), assets.decoy_material.clone());
+        world.spawn((
+            Mesh3d(mesh),
+            MeshMaterial3d(material),
+            Transform::from_translation(transform.translation),
+            Name::new("Decoy"),
+            Decoy(Timer::from_seconds(self.seconds, TimerMode::Once)),
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+/// Chased by enemies as if it were a player, until its timer runs out
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone)]
+#[reflect(Component)]
+pub struct Decoy(pub Timer);
+
+#[derive(Resource)]
+struct AbilityAssets {
+    decoy_mesh: Handle<Mesh>,
+    decoy_material: Handle<StandardMaterial>,
+}
+
+fn setup_ability_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(AbilityAssets {
+        decoy_mesh: meshes.add(Capsule3d::new(0.5, 1.0)),
+        decoy_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.9, 0.9, 1.0, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+    });
+}
+
+#[derive(Component)]
+struct AbilityIcons {
+    player: Entity,
+}
+
+/// Covers an icon from the top down while its ability is cooling down
+#[derive(Component)]
+struct CooldownOverlay {
+    player: Entity,
+    slot: usize,
+}
+
+/// Abilities need the whole world to run, so they're used in their own
+/// system. Like dashes, presses are buffered and need the stamina to pay
+fn use_abilities(world: &mut World) {
+    let delta = world.resource::<Time>().delta_secs();
+
+    world.resource_scope(|world, registry: Mut<AbilityRegistry>| {
+        let mut activated = Vec::new();
+        let mut players = world.query_filtered::<(
+            Entity,
+            &mut AbilitySlots,
+            &mut InputBuffer,
+            Option<&mut Stamina>,
+        ), With<Player>>();
+
+        for (player, mut slots, mut buffer, mut stamina) in players.iter_mut(world) {
+            for (slot, action) in slots.0.iter_mut().zip(ABILITY_ACTIONS) {
+                slot.cooldown = (slot.cooldown - delta).max(0.0);
+                if slot.cooldown > 0.0 || !buffer.take(action) {
+                    continue;
+                }
+                let Some(ability) = registry.get(slot.ability) else {
+                    warn!("No ability called {}", slot.ability);
+                    continue;
+                };
+                if stamina
+                    .as_mut()
+                    .is_some_and(|stamina| !stamina.try_spend(ability.cost()))
+                {
+                    continue;
+                }
+
+                slot.cooldown = ability.cooldown();
+                slot.max_cooldown = ability.cooldown();
+                activated.push((slot.ability, player));
+            }
+        }
+
+        for (name, player) in activated {
+            if let Some(ability) = registry.get(name) {
+                ability.activate(world, player);
+            }
+        }
+    });
+}
+
+fn expire_decoys(mut commands: Commands, time: Res<Time>, mut decoys: Query<(Entity, &mut Decoy)>) {
+    for (entity, mut decoy) in decoys.iter_mut() {
+        if decoy.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// A row of icons in the bottom right for each player, stacked up by slot
+fn spawn_ability_icons(
+    mut commands: Commands,
+    players: Query<(Entity, &PlayerSlot, &AbilitySlots), (With<Player>, Added<AbilitySlots>)>,
+) {
+    for (player, player_slot, slots) in players.iter() {
+        commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(16.0 + **player_slot as f32 * (ICON_SIZE + 8.0)),
+                    right: Val::Px(16.0),
+                    column_gap: Val::Px(8.0),
+                    ..default()
+                },
+                AbilityIcons { player },
+                Name::new(format!("Ability Icons {}", **player_slot + 1)),
+                StateScoped(GameState::InGame),
+            ))
+            .with_children(|icons| {
+                for (index, slot) in slots.0.iter().enumerate() {
+                    icons
+                        .spawn((
+                            Node {
+                                width: Val::Px(ICON_SIZE),
+                                height: Val::Px(ICON_SIZE),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgb(0.25, 0.35, 0.55)),
+                        ))
+                        .with_children(|icon| {
+                            icon.spawn((
+                                Node {
+                                    position_type: PositionType::Absolute,
+                                    top: Val::Px(0.0),
+                                    width: Val::Percent(100.0),
+                                    height: Val::Percent(0.0),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                                CooldownOverlay {
+                                    player,
+                                    slot: index,
+                                },
+                            ));
+                            icon.spawn((Text::new(slot.ability), TextFont::from_font_size(14.0)));
+                        });
+                }
+            });
+    }
+}
+
+fn update_ability_icons(
+    mut commands: Commands,
+    icons: Query<(Entity, &AbilityIcons)>,
+    players: Query<&AbilitySlots>,
+    mut overlays: Query<(&CooldownOverlay, &mut Node)>,
+) {
+    for (icons, link) in icons.iter() {
+        if !players.contains(link.player) {
+            commands.entity(icons).despawn();
+        }
+    }
+
+    for (overlay, mut node) in overlays.iter_mut() {
+        let Some(slot) = players
+            .get(overlay.player)
+            .ok()
+            .and_then(|slots| slots.0.get(overlay.slot))
+        else {
+            continue;
+        };
+        node.height = Val::Percent(slot.cooldown_fraction() * 100.0);
+    }
+}
@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::elites::EliteAssets;
+use crate::enemies::EnemyAssets;
+use crate::menu::button;
+use crate::prefab_library::PrefabLibrary;
+use crate::save::SaveData;
+use crate::toon::ToonMaterial;
+
+/// UI scale with the large HUD on
+const LARGE_HUD_SCALE: f32 = 1.25;
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AccessibilitySettings>();
+        app.init_resource::<AccessibilitySettings>();
+        app.add_systems(Startup, load_accessibility_settings);
+        app.add_systems(
+            Update,
+            (
+                save_accessibility_settings.run_if(resource_changed::<AccessibilitySettings>),
+                apply_hud_scale.run_if(resource_changed::<AccessibilitySettings>),
+                // prefabs are only made once loading is done, after the settings
+                apply_enemy_palette.run_if(
+                    resource_changed::<AccessibilitySettings>.or(resource_changed::<PrefabLibrary>),
+                ),
+            ),
+        );
+        app.add_systems(
+            Update,
+            (accessibility_buttons, update_accessibility_labels)
+                .run_if(in_state(GameState::MainMenu)),
+        );
+    }
+}
+
+/// Options for players who need the game to look or play a little
+/// differently, saved along with the other settings
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    /// Tone down the damage vignette and stop hit invulnerability flickering
+    pub reduce_flashing: bool,
+    pub palette: ColorPalette,
+    /// Press sprint once to start and again to stop, rather than holding it
+    pub toggle_sprint: bool,
+    pub large_hud: bool,
+}
+
+/// Colours enemies are told apart by
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorPalette {
+    #[default]
+    Standard,
+    /// Safe for protanopia and deuteranopia
+    RedGreen,
+    /// Safe for tritanopia
+    BlueYellow,
+}
+
+/// The tint of the enemies that aren't prefabs, prefabs carry their own
+/// colour for each palette
+#[derive(Debug, Clone, Copy)]
+pub struct EnemyTints {
+    pub boss: Color,
+    pub elite: Color,
+}
+
+/// The colour of each kind of damage number
+#[derive(Debug, Clone, Copy)]
+pub struct DamageNumberTints {
+    /// A player being hurt
+    pub player: Color,
+    pub crit: Color,
+    pub hit: Color,
+}
+
+impl ColorPalette {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorPalette::Standard => "Standard",
+            ColorPalette::RedGreen => "Red-Green Safe",
+            ColorPalette::BlueYellow => "Blue-Yellow Safe",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ColorPalette::Standard => ColorPalette::RedGreen,
+            ColorPalette::RedGreen => ColorPalette::BlueYellow,
+            ColorPalette::BlueYellow => ColorPalette::Standard,
+        }
+    }
+
+    /// Colourblind palettes keep every kind apart by brightness as well as hue
+    pub fn enemy_tints(&self) -> EnemyTints {
+        match self {
+            ColorPalette::Standard => EnemyTints {
+                boss: Color::srgb(0.3, 0.05, 0.05),
+                elite: Color::srgb(1.0, 0.75, 0.1),
+            },
+            ColorPalette::RedGreen => EnemyTints {
+                boss: Color::srgb(0.1, 0.1, 0.15),
+                elite: Color::srgb(0.95, 0.9, 0.25),
+            },
+            ColorPalette::BlueYellow => EnemyTints {
+                boss: Color::srgb(0.1, 0.1, 0.1),
+                elite: Color::srgb(0.95, 0.6, 0.8),
+            },
+        }
+    }
+
+    /// Player hits and crits have to stand out from every other hit, and
+    /// from each other
+    pub fn damage_number_tints(&self) -> DamageNumberTints {
+        match self {
+            ColorPalette::Standard => DamageNumberTints {
+                player: Color::srgb(1.0, 0.3, 0.3),
+                crit: Color::srgb(1.0, 0.85, 0.2),
+                hit: Color::WHITE,
+            },
+            ColorPalette::RedGreen => DamageNumberTints {
+                player: Color::srgb(0.3, 0.55, 1.0),
+                crit: Color::srgb(1.0, 0.85, 0.2),
+                hit: Color::WHITE,
+            },
+            ColorPalette::BlueYellow => DamageNumberTints {
+                player: Color::srgb(1.0, 0.3, 0.3),
+                crit: Color::srgb(0.4, 0.95, 1.0),
+                hit: Color::WHITE,
+            },
+        }
+    }
+}
+
+#[derive(Component)]
+enum AccessibilityButton {
+    Flashing,
+    Palette,
+    Sprint,
+    Hud,
+}
+
+fn load_accessibility_settings(save: Res<SaveData>, mut settings: ResMut<AccessibilitySettings>) {
+    *settings = save.settings.accessibility;
+}
+
+fn save_accessibility_settings(settings: Res<AccessibilitySettings>, mut save: ResMut<SaveData>) {
+    SaveData::update_setting(&mut save, |saved| &mut saved.accessibility, *settings);
+}
+
+/// Headless runs have no UI to scale
+fn apply_hud_scale(settings: Res<AccessibilitySettings>, ui_scale: Option<ResMut<UiScale>>) {
+    let Some(mut ui_scale) = ui_scale else {
+        return;
+    };
+    let scale = if settings.large_hud {
+        LARGE_HUD_SCALE
+    } else {
+        1.0
+    };
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+/// Every enemy of a kind shares one material, so recolouring it recolours
+/// them all
+fn apply_enemy_palette(
+    settings: Res<AccessibilitySettings>,
+    (enemy_assets, elite_assets): (Res<EnemyAssets>, Res<EliteAssets>),
+    library: Res<PrefabLibrary>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+) {
+    let tints = settings.palette.enemy_tints();
+    let recolor = [
+        (&enemy_assets.boss_material, tints.boss),
+        (&elite_assets.material, tints.elite),
+    ];
+    let prefabs = library
+        .iter()
+        .map(|prefab| (&prefab.material, prefab.definition.color(settings.palette)));
+    for (handle, color) in recolor.into_iter().chain(prefabs) {
+        let Some(material) = materials.get_mut(handle) else {
+            continue;
+        };
+        let shaded = ToonMaterial::from(color);
+        material.color = shaded.color;
+        material.shadow_color = shaded.shadow_color;
+    }
+}
+
+/// Part of the main menu's settings
+pub fn spawn_accessibility_options(column: &mut ChildSpawnerCommands) {
+    column.spawn((Text::new("Accessibility"), TextFont::from_font_size(28.0)));
+    for button_kind in [
+        AccessibilityButton::Flashing,
+        AccessibilityButton::Palette,
+        AccessibilityButton::Sprint,
+        AccessibilityButton::Hud,
+    ] {
+        column.spawn((
+            button(),
+            button_kind,
+            children![(Text::new(""), TextFont::from_font_size(18.0))],
+        ));
+    }
+}
+
+fn accessibility_buttons(
+    buttons: Query<(&Interaction, &AccessibilityButton), Changed<Interaction>>,
+    mut settings: ResMut<AccessibilitySettings>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            AccessibilityButton::Flashing => settings.reduce_flashing = !settings.reduce_flashing,
+            AccessibilityButton::Palette => settings.palette = settings.palette.next(),
+            AccessibilityButton::Sprint => settings.toggle_sprint = !settings.toggle_sprint,
+            AccessibilityButton::Hud => settings.large_hud = !settings.large_hud,
+        }
+    }
+}
+
+fn update_accessibility_labels(
+    settings: Res<AccessibilitySettings>,
+    buttons: Query<(&AccessibilityButton, &Children)>,
+    added: Query<(), Added<AccessibilityButton>>,
+    mut texts: Query<&mut Text>,
+) {
+    if !settings.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for (button, children) in buttons.iter() {
+        let label = match button {
+            AccessibilityButton::Flashing => match settings.reduce_flashing {
+                true => "Flashing: Reduced".to_string(),
+                false => "Flashing: Full".to_string(),
+            },
+            AccessibilityButton::Palette => format!("Colours: {}", settings.palette.name()),
+            AccessibilityButton::Sprint => match settings.toggle_sprint {
+                true => "Sprint: Toggle".to_string(),
+                false => "Sprint: Hold".to_string(),
+            },
+            AccessibilityButton::Hud => match settings.large_hud {
+                true => "HUD: Large".to_string(),
+                false => "HUD: Normal".to_string(),
+            },
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0 = label.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+
+use crate::experience::LevelUp;
+use crate::run_stats::RunStats;
+use crate::save::SaveData;
+use crate::{GameplaySet, Player};
+
+const TOAST_SECONDS: f32 = 4.0;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AchievementUnlocked>();
+        app.add_systems(Startup, spawn_toast_column);
+        app.add_systems(Update, check_achievements.in_set(GameplaySet));
+        app.add_systems(Update, (show_toasts, expire_toasts));
+    }
+}
+
+/// What has to happen, within a single run, to unlock an achievement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    Kills(u32),
+    BossKills(u32),
+    SurviveMinutes(f32),
+    /// Survive this long without taking any damage
+    UntouchedMinutes(f32),
+    ReachLevel(u32),
+}
+
+impl Condition {
+    fn met(&self, stats: &RunStats, level: u32) -> bool {
+        match *self {
+            Condition::Kills(kills) => stats.total_kills() >= kills,
+            Condition::BossKills(kills) => stats.boss_kills >= kills,
+            Condition::SurviveMinutes(minutes) => stats.survived_seconds >= minutes * 60.0,
+            Condition::UntouchedMinutes(minutes) => {
+                stats.damage_taken <= 0.0 && stats.survived_seconds >= minutes * 60.0
+            }
+            Condition::ReachLevel(target) => level >= target,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Achievement {
+    /// Stored in the save's unlocks, never change it once shipped
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub condition: Condition,
+}
+
+impl Achievement {
+    pub fn unlocked(&self, save: &SaveData) -> bool {
+        return save.unlocks.contains(self.id);
+    }
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "achievement.first_blood",
+        name: "First Blood",
+        description: "Defeat an enemy",
+        condition: Condition::Kills(1),
+    },
+    Achievement {
+        id: "achievement.hare_raiser",
+        name: "Hare Raiser",
+        description: "Defeat 500 enemies in one run",
+        condition: Condition::Kills(500),
+    },
+    Achievement {
+        id: "achievement.warren_wrecker",
+        name: "Warren Wrecker",
+        description: "Defeat 2000 enemies in one run",
+        condition: Condition::Kills(2000),
+    },
+    Achievement {
+        id: "achievement.slayer_slain",
+        name: "Slayer Slain",
+        description: "Defeat a boss",
+        condition: Condition::BossKills(1),
+    },
+    Achievement {
+        id: "achievement.survivor",
+        name: "Survivor",
+        description: "Survive for 10 minutes",
+        condition: Condition::SurviveMinutes(10.0),
+    },
+    Achievement {
+        id: "achievement.untouchable",
+        name: "Untouchable",
+        description: "Survive 5 minutes without taking damage",
+        condition: Condition::UntouchedMinutes(5.0),
+    },
+    Achievement {
+        id: "achievement.well_read",
+        name: "Well Read",
+        description: "Reach level 20",
+        condition: Condition::ReachLevel(20),
+    },
+];
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AchievementUnlocked(pub &'static Achievement);
+
+/// Holds the toasts, top right of the screen
+#[derive(Component)]
+struct ToastColumn;
+
+#[derive(Component)]
+struct Toast(Timer);
+
+fn check_achievements(
+    stats: Res<RunStats>,
+    mut level_ups: EventReader<LevelUp>,
+    players: Query<(), With<Player>>,
+    mut save: ResMut<SaveData>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+    mut level: Local<u32>,
+) {
+    if stats.is_added() {
+        *level = 1;
+    }
+    for level_up in level_ups.read() {
+        if players.contains(level_up.entity) {
+            *level = level_up.level;
+        }
+    }
+
+    for achievement in ACHIEVEMENTS {
+        // only touch the save on an unlock, every change writes it to disk
+        if achievement.unlocked(&save) || !achievement.condition.met(&stats, *level) {
+            continue;
+        }
+
+        save.unlocks.insert(achievement.id.to_string());
+        unlocked.write(AchievementUnlocked(achievement));
+    }
+}
+
+fn spawn_toast_column(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(16.0),
+            right: Val::Px(16.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(8.0),
+            ..default()
+        },
+        GlobalZIndex(10),
+        Name::new("Toasts"),
+        ToastColumn,
+    ));
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    mut unlocked: EventReader<AchievementUnlocked>,
+    column: Query<Entity, With<ToastColumn>>,
+) {
+    let Ok(column) = column.single() else {
+        return;
+    };
+
+    for AchievementUnlocked(achievement) in unlocked.read() {
+        let toast = commands
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                BorderColor(Color::srgb(1.0, 0.8, 0.2)),
+                BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.9)),
+                Toast(Timer::from_seconds(TOAST_SECONDS, TimerMode::Once)),
+                children![
+                    (
+                        Text::new(format!("Achievement: {}", achievement.name)),
+                        TextFont::from_font_size(20.0),
+                        TextColor(Color::srgb(1.0, 0.8, 0.2)),
+                    ),
+                    (
+                        Text::new(achievement.description),
+                        TextFont::from_font_size(16.0),
+                    ),
+                ],
+            ))
+            .id();
+        commands.entity(column).add_child(toast);
+    }
+}
+
+/// Toasts run on real time so they still go away while the game is paused
+fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut toasts: Query<(Entity, &mut Toast)>,
+) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
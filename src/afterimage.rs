@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::budget::{BudgetCategory, BudgetPriority, Budgeted};
+use crate::controls::Dash;
+use crate::pool::{Pool, PoolKey, ReleaseCommandsExt};
+use crate::{GameplaySet, Player};
+
+/// Seconds between ghosts left behind a dash
+const AFTERIMAGE_INTERVAL: f32 = 0.03;
+/// How long each ghost takes to fade out
+const AFTERIMAGE_SECONDS: f32 = 0.25;
+/// Opacity a ghost starts at
+const AFTERIMAGE_ALPHA: f32 = 0.5;
+const AFTERIMAGE_COLOR: Color = Color::srgb(0.6, 0.8, 1.0);
+
+pub struct AfterimagePlugin;
+
+impl Plugin for AfterimagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_afterimages,
+                ready_afterimage_materials,
+                fade_afterimages,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// A see-through copy of a dashing player, left where they were and
+/// released back to the pool once it's faded
+///
+/// Each ghost has a material of its own to fade, kept through the pool so
+/// reused ghosts don't make new ones
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone)]
+#[reflect(Component)]
+pub struct Afterimage(Timer);
+
+fn spawn_afterimages(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut since_ghost: Local<f32>,
+    mut pool: ResMut<Pool>,
+    players: Query<(&GlobalTransform, &Mesh3d, &Dash), With<Player>>,
+) {
+    *since_ghost += time.delta_secs();
+    if *since_ghost < AFTERIMAGE_INTERVAL {
+        return;
+    }
+    *since_ghost = 0.0;
+
+    for (transform, mesh, dash) in players.iter() {
+        if !dash.is_dashing() {
+            continue;
+        }
+        pool.spawn(
+            &mut commands,
+            PoolKey::Afterimage,
+            (
+                mesh.clone(),
+                transform.compute_transform(),
+                Afterimage(Timer::from_seconds(AFTERIMAGE_SECONDS, TimerMode::Once)),
+                Budgeted::new(BudgetCategory::Effect, BudgetPriority::Low),
+                Name::new("Afterimage"),
+            ),
+        );
+    }
+}
+
+/// New ghosts get a material, reused ones have theirs made opaque again
+fn ready_afterimage_materials(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    ghosts: Query<(Entity, Option<&MeshMaterial3d<StandardMaterial>>), Added<Afterimage>>,
+) {
+    for (entity, material) in ghosts.iter() {
+        if let Some(material) = material.and_then(|material| materials.get_mut(&**material)) {
+            material.base_color = AFTERIMAGE_COLOR.with_alpha(AFTERIMAGE_ALPHA);
+            continue;
+        }
+
+        let material = materials.add(StandardMaterial {
+            base_color: AFTERIMAGE_COLOR.with_alpha(AFTERIMAGE_ALPHA),
+            emissive: AFTERIMAGE_COLOR.to_linear(),
+            unlit: true,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        });
+        commands.entity(entity).insert(MeshMaterial3d(material));
+    }
+}
+
+fn fade_afterimages(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ghosts: Query<(Entity, &mut Afterimage, &MeshMaterial3d<StandardMaterial>)>,
+) {
+    for (entity, mut afterimage, material) in ghosts.iter_mut() {
+        if afterimage.tick(time.delta()).finished() {
+            commands.entity(entity).release();
+            continue;
+        }
+
+        if let Some(material) = materials.get_mut(&**material) {
+            let alpha = AFTERIMAGE_ALPHA * afterimage.fraction_remaining();
+            material.base_color = AFTERIMAGE_COLOR.with_alpha(alpha);
+        }
+    }
+}
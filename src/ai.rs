@@ -0,0 +1,371 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::abilities::Decoy;
+use crate::animation::AnimationState;
+use crate::combat::{DamageType, Faction};
+use crate::flow_field::FlowField;
+use crate::math::VecTools;
+use crate::pool::{Pool, PoolKey};
+use crate::projectile::{Homing, Projectile, ProjectileAssets, projectile_bundle};
+use crate::spatial::{SpatialGrid, SpatialGridPlugin, SpatialSet};
+use crate::status::{InflictsStatus, StatusEffect};
+use crate::telegraph::{TelegraphAssets, TelegraphShape, telegraph};
+use crate::{BaseMoveSpeed, GameplaySet, MoveSpeed, MoveVector, Player};
+
+/// How much an enemy's speed may differ from its archetype's base speed,
+/// so a horde spreads out instead of moving in lockstep
+const MOVE_SPEED_VARIATION: f32 = 0.15;
+/// Should be about the largest separation radius in use
+const SEPARATION_CELL_SIZE: f32 = 4.0;
+const BOLT_DAMAGE: f32 = 10.0;
+/// Slow enough to sidestep
+const BOLT_SPEED: f32 = 10.0;
+const BOLT_TURN_RATE: f32 = 0.6;
+const BOLT_LIFETIME: f32 = 6.0;
+/// Width of the lane a bolt's telegraph marks out, a little wider than the
+/// bolt itself
+const BOLT_LANE_WIDTH: f32 = 1.5;
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(SpatialGridPlugin::<Separation>::new(SEPARATION_CELL_SIZE));
+        app.add_systems(
+            Update,
+            (
+                chase_player.after(SpatialSet),
+                keep_distance,
+                ranged_attack.after(keep_distance),
+                separate.after(SpatialSet).after(ranged_attack),
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Walk towards the nearest player, around obstacles by the `FlowField`
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+#[require(MoveVector)]
+pub struct ChasePlayer;
+
+/// Hover around `distance` away from the nearest player
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[require(MoveVector)]
+pub struct KeepDistance {
+    pub distance: f32,
+    /// How far from `distance` the entity may drift before repositioning
+    pub tolerance: f32,
+}
+
+/// Periodically stop, telegraph with the attack animation and a lane on the
+/// ground, then fire a slow homing bolt down it at the nearest player in
+/// `range`
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct RangedAttack {
+    pub range: f32,
+    pub cooldown: Timer,
+    pub windup: Timer,
+    winding_up: bool,
+    /// Which way the telegraph was drawn, and so the bolt goes
+    aim: Vec3,
+}
+
+impl RangedAttack {
+    pub fn new(range: f32, cooldown: f32, windup: f32) -> Self {
+        Self {
+            range,
+            cooldown: Timer::from_seconds(cooldown, TimerMode::Once),
+            windup: Timer::from_seconds(windup, TimerMode::Once),
+            winding_up: false,
+            aim: Vec3::X,
+        }
+    }
+}
+
+/// Push away from other separating entities within `radius`,
+/// so a horde spreads out around its target instead of stacking up
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[require(MoveVector)]
+pub struct Separation {
+    pub radius: f32,
+    /// How strongly neighbours push, relative to the entity's `MoveSpeed`
+    pub strength: f32,
+}
+
+impl Default for Separation {
+    fn default() -> Self {
+        Self {
+            radius: 2.5,
+            strength: 1.5,
+        }
+    }
+}
+
+/// A `BaseMoveSpeed` randomly scattered around `base`
+pub fn varied_move_speed(base: f32, rng: &mut impl Rng) -> BaseMoveSpeed {
+    let variation = rng.random_range(-MOVE_SPEED_VARIATION..=MOVE_SPEED_VARIATION);
+    return BaseMoveSpeed(base * (1.0 + variation));
+}
+
+/// Where every player and decoy is, gathered once so the parallel steering
+/// systems share it rather than each walking the query
+fn target_positions(targets: &Query<&Transform, Or<(With<Player>, With<Decoy>)>>) -> Vec<Vec3> {
+    return targets.iter().map(|target| target.translation).collect();
+}
+
+/// The target closest to `pos`
+fn nearest_target(targets: &[Vec3], pos: Vec3) -> Option<Vec3> {
+    return targets
+        .iter()
+        .copied()
+        .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)));
+}
+
+/// `vec` pushed away from other separating entities around `entity`
+fn separated(
+    grid: &SpatialGrid<Separation>,
+    entity: Entity,
+    pos: Vec3,
+    separation: &Separation,
+    vec: Vec3,
+    move_speed: f32,
+) -> Vec3 {
+    let pos = pos.xz();
+    let mut push = Vec2::ZERO;
+
+    for (other, other_pos) in grid.within_radius(pos, separation.radius) {
+        if other == entity {
+            continue;
+        }
+
+        let away = pos - other_pos;
+        let dist = away.length();
+        // entities exactly on top of each other get pushed apart arbitrarily
+        let dir = away.try_normalize().unwrap_or(Vec2::X);
+        push += dir * (1.0 - dist / separation.radius);
+    }
+
+    if push == Vec2::ZERO {
+        return vec;
+    }
+
+    let steer = push * separation.strength * move_speed;
+    let blended = (vec.xz() + steer).clamp_length_max(move_speed);
+    return Vec3::new(blended.x, vec.y, blended.y);
+}
+
+/// Decoys are chased the same as players. Chasers follow the flow field
+/// where it has a way, and head straight for the nearest target off it and
+/// once they're on top of them
+///
+/// Chasers separate here too rather than in `separate`, so their
+/// `MoveVector` is written once and only when it changes, and anything
+/// watching for changes isn't woken by the whole horde every frame
+pub fn chase_player(
+    field: Res<FlowField>,
+    grid: Res<SpatialGrid<Separation>>,
+    targets: Query<&Transform, Or<(With<Player>, With<Decoy>)>>,
+    mut chasers: Query<
+        (
+            Entity,
+            &Transform,
+            &mut MoveVector,
+            &MoveSpeed,
+            Option<&Separation>,
+        ),
+        (With<ChasePlayer>, Without<Player>),
+    >,
+) {
+    let targets = target_positions(&targets);
+    // the grid was rebuilt before steering started and is only read from here
+    let grid = &*grid;
+    chasers.par_iter_mut().for_each(
+        |(entity, transform, mut move_vec, move_speed, separation)| {
+            let mut vec = match field.direction(transform.translation.xz()) {
+                Some(dir) => Vec3::new(dir.x, 0.0, dir.y) * **move_speed,
+                None => {
+                    nearest_target(&targets, transform.translation).map_or(Vec3::ZERO, |target| {
+                        let to_target = (target - transform.translation).horizontal();
+                        to_target.normalize_or_zero() * **move_speed
+                    })
+                }
+            };
+            if let Some(separation) = separation {
+                vec = separated(
+                    grid,
+                    entity,
+                    transform.translation,
+                    separation,
+                    vec,
+                    **move_speed,
+                );
+            }
+            move_vec.set_if_neq(MoveVector { vec });
+        },
+    );
+}
+
+/// Separation for everything that doesn't chase, on top of whatever the rest
+/// of its steering already decided
+pub fn separate(
+    grid: Res<SpatialGrid<Separation>>,
+    mut query: Query<
+        (Entity, &Transform, &Separation, &mut MoveVector, &MoveSpeed),
+        Without<ChasePlayer>,
+    >,
+) {
+    // the grid was rebuilt before steering started and is only read from here
+    let grid = &*grid;
+    query.par_iter_mut().for_each(
+        |(entity, transform, separation, mut move_vec, move_speed)| {
+            let vec = separated(
+                grid,
+                entity,
+                transform.translation,
+                separation,
+                move_vec.vec,
+                **move_speed,
+            );
+            move_vec.set_if_neq(MoveVector { vec });
+        },
+    );
+}
+
+/// The player closest to `pos`
+fn nearest_player<'a>(
+    players: impl Iterator<Item = (Entity, &'a Transform)>,
+    pos: Vec3,
+) -> Option<(Entity, &'a Transform)> {
+    players.min_by(|(_, a), (_, b)| {
+        let a = a.translation.distance_squared(pos);
+        let b = b.translation.distance_squared(pos);
+        a.total_cmp(&b)
+    })
+}
+
+pub fn keep_distance(
+    targets: Query<&Transform, Or<(With<Player>, With<Decoy>)>>,
+    mut query: Query<(&Transform, &KeepDistance, &mut MoveVector, &MoveSpeed), Without<Player>>,
+) {
+    let targets = target_positions(&targets);
+    query
+        .par_iter_mut()
+        .for_each(|(transform, keep, mut move_vec, move_speed)| {
+            let Some(target) = nearest_target(&targets, transform.translation) else {
+                move_vec.set_if_neq(MoveVector { vec: Vec3::ZERO });
+                return;
+            };
+
+            let to_target = (target - transform.translation).horizontal();
+            let dist = to_target.length();
+            let dir = to_target.normalize_or_zero();
+
+            let vec = if dist > keep.distance + keep.tolerance {
+                dir * **move_speed
+            } else if dist < keep.distance - keep.tolerance {
+                -dir * **move_speed
+            } else {
+                Vec3::ZERO
+            };
+            move_vec.set_if_neq(MoveVector { vec });
+        });
+}
+
+pub fn ranged_attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    (assets, telegraph_assets): (Res<ProjectileAssets>, Res<TelegraphAssets>),
+    mut pool: ResMut<Pool>,
+    players: Query<(Entity, &Transform), With<Player>>,
+    mut query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut RangedAttack,
+            &mut MoveVector,
+            Option<&mut AnimationState>,
+        ),
+        Without<Player>,
+    >,
+) {
+    for (entity, transform, mut attack, mut move_vec, anim_state) in query.iter_mut() {
+        attack.cooldown.tick(time.delta());
+
+        let Some((target, target_transform)) =
+            nearest_player(players.iter(), transform.translation)
+        else {
+            continue;
+        };
+        let to_target = target_transform.translation - transform.translation;
+
+        if !attack.winding_up {
+            if attack.cooldown.finished() && to_target.length() <= attack.range {
+                attack.winding_up = true;
+                attack.windup.reset();
+                attack.aim = to_target.horizontal().normalize_or(Vec3::X);
+                commands.spawn(telegraph(
+                    &telegraph_assets,
+                    TelegraphShape::Line {
+                        length: attack.range,
+                        width: BOLT_LANE_WIDTH,
+                    },
+                    attack.windup.duration().as_secs_f32(),
+                    transform.translation,
+                    attack.aim,
+                ));
+                if let Some(mut state) = anim_state {
+                    *state = AnimationState::Attack;
+                }
+            }
+            continue;
+        }
+
+        // stand still while telegraphing
+        **move_vec = Vec3::ZERO;
+        if !attack.windup.tick(time.delta()).finished() {
+            continue;
+        }
+
+        attack.winding_up = false;
+        attack.cooldown.reset();
+        if let Some(mut state) = anim_state {
+            *state = AnimationState::Idle;
+        }
+
+        let dir = attack.aim;
+        pool.spawn(
+            &mut commands,
+            PoolKey::Projectile,
+            (
+                projectile_bundle(
+                    assets.mesh.clone(),
+                    assets.enemy_material.clone(),
+                    transform.translation + dir,
+                    dir * BOLT_SPEED,
+                    Projectile {
+                        damage: BOLT_DAMAGE,
+                        hits: Faction::Player,
+                        lifetime: Timer::from_seconds(BOLT_LIFETIME, TimerMode::Once),
+                        owner: Some(entity),
+                        weapon: None,
+                        kind: DamageType::Arcane,
+                    },
+                ),
+                Homing {
+                    turn_rate: BOLT_TURN_RATE,
+                    target: Some(target),
+                },
+                InflictsStatus {
+                    effect: StatusEffect::Slow,
+                    stacks: 1,
+                },
+            ),
+        );
+    }
+}
@@ -1,14 +1,45 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::loading::GameAssets;
+use crate::ron_asset::RonAssetLoader;
+use crate::{BaseMoveSpeed, MainCamera, MoveSpeed, MoveVector};
+
+/// Sprite sheet pixels per world unit
+const PIXELS_PER_UNIT: f32 = 8.0;
+/// How far (as a fraction of the move direction) an entity has to be moving
+/// across the screen before its sprite flips
+const FACING_HYSTERESIS: f32 = 0.25;
+/// Limits on how far move speed can slow down or speed up an animation
+const MIN_ANIMATION_SPEED: f32 = 0.25;
+const MAX_ANIMATION_SPEED: f32 = 3.0;
+/// How long each frame of a controller's clips shows for, unless the clip
+/// sets its own fps
+const CONTROLLER_FRAME_SECONDS: f32 = 0.15;
 
-const LEVEL_UP_INDICES: AnimationIndices = AnimationIndices::new(8, 15, AnimationMode::Once);
-const PLAYER_RUN_INDICES: AnimationIndices = AnimationIndices::new(1, 3, AnimationMode::Bounce);
-const BLUEBERRY_INDICES: AnimationIndices = AnimationIndices::new(12, 13, AnimationMode::Cycle);
-const GRAPE_INDICES: AnimationIndices = AnimationIndices::new(68, 69, AnimationMode::Cycle);
-const BANANA_INDICES: AnimationIndices = AnimationIndices::new(4, 5, AnimationMode::Cycle);
-const MELON_INDICES: AnimationIndices = AnimationIndices::new(25, 26, AnimationMode::Cycle);
-const WITCH_IDLE_INDICES: AnimationIndices = AnimationIndices::new(6, 7, AnimationMode::Cycle);
-const WITCH_ATTACK_INDICES: AnimationIndices = AnimationIndices::new(10, 11, AnimationMode::Cycle);
+const PLAYER_IDLE_INDICES: AnimationIndices =
+    AnimationIndices::new("player_idle", 0, 0, AnimationMode::Cycle);
+const PLAYER_RUN_INDICES: AnimationIndices =
+    AnimationIndices::new("player_run", 1, 3, AnimationMode::Bounce);
+const BLUEBERRY_INDICES: AnimationIndices =
+    AnimationIndices::new("blueberry", 12, 13, AnimationMode::Cycle);
+const GRAPE_INDICES: AnimationIndices =
+    AnimationIndices::new("grape", 68, 69, AnimationMode::Cycle);
+const BANANA_INDICES: AnimationIndices =
+    AnimationIndices::new("banana", 4, 5, AnimationMode::Cycle);
+const MELON_INDICES: AnimationIndices =
+    AnimationIndices::new("melon", 25, 26, AnimationMode::Cycle);
 
+/// The pixel size of one cell of the sprite sheet an entity is drawn from
+///
+/// Inserting this alongside a `Sprite` assigns the matching atlas layout
+/// and world size
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[reflect(Component)]
 pub enum SpriteScale {
     X32,
     X16,
@@ -21,9 +52,66 @@ impl SpriteScale {
     const BLUEBERRY: Self = Self::X16;
     const GRAPE: Self = Self::X16;
     const PLAYER: Self = Self::X16;
+
+    pub fn pixels(&self) -> u32 {
+        match self {
+            SpriteScale::X32 => 32,
+            SpriteScale::X16 => 16,
+            SpriteScale::X8 => 8,
+        }
+    }
+
+    /// Size of one sprite cell in world units
+    pub fn world_size(&self) -> Vec2 {
+        return Vec2::splat(self.pixels() as f32 / PIXELS_PER_UNIT);
+    }
+}
+
+/// Spawn an animated sprite, its atlas layout is filled in by
+/// `assign_atlas_layout` based on `scale`
+pub fn animated_sprite(
+    image: Handle<Image>,
+    scale: SpriteScale,
+    clip: AnimationIndices,
+    frame_seconds: f32,
+) -> impl Bundle {
+    (
+        Sprite::from_image(image),
+        scale,
+        clip,
+        AnimationTimer::from_seconds(frame_seconds),
+    )
 }
 
-pub fn get_texture_atlas_layout(scale: SpriteScale) -> TextureAtlasLayout {
+/// One atlas layout for each `SpriteScale` of the sprite sheet, made once at
+/// startup and shared by everything drawn from it
+#[derive(Resource)]
+pub struct AtlasRegistry {
+    x8: Handle<TextureAtlasLayout>,
+    x16: Handle<TextureAtlasLayout>,
+    x32: Handle<TextureAtlasLayout>,
+}
+
+impl AtlasRegistry {
+    pub fn layout(&self, scale: SpriteScale) -> Handle<TextureAtlasLayout> {
+        let layout = match scale {
+            SpriteScale::X8 => &self.x8,
+            SpriteScale::X16 => &self.x16,
+            SpriteScale::X32 => &self.x32,
+        };
+        return layout.clone();
+    }
+}
+
+fn setup_atlas_registry(mut commands: Commands, mut layouts: ResMut<Assets<TextureAtlasLayout>>) {
+    commands.insert_resource(AtlasRegistry {
+        x8: layouts.add(texture_atlas_layout(SpriteScale::X8)),
+        x16: layouts.add(texture_atlas_layout(SpriteScale::X16)),
+        x32: layouts.add(texture_atlas_layout(SpriteScale::X32)),
+    });
+}
+
+fn texture_atlas_layout(scale: SpriteScale) -> TextureAtlasLayout {
     match scale {
         SpriteScale::X8 => {
             return TextureAtlasLayout::from_grid(UVec2::splat(8), 16, 16, None, None);
@@ -41,24 +129,52 @@ pub struct AnimationPlugin;
 
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, animate_sprites);
+        app.init_asset::<AnimationLibrary>();
+        app.register_asset_loader(RonAssetLoader::<AnimationLibrary>::new(&["anim.ron"]));
+        app.register_type::<AnimationState>();
+        app.register_type::<Facing>();
+        app.register_type::<AnimationSpeed>();
+        app.add_event::<AnimationFrameEvent>();
+        app.add_event::<AnimationFinished>();
+        app.add_systems(PreStartup, setup_atlas_registry);
+        app.add_systems(
+            Update,
+            (
+                assign_atlas_layout,
+                apply_animation_library,
+                locomotion_animation_state.after(apply_animation_library),
+                apply_animation_state.after(locomotion_animation_state),
+                animation_speed_from_move_speed,
+                animate_sprites
+                    .after(apply_animation_state)
+                    .after(animation_speed_from_move_speed)
+                    .after(assign_atlas_layout),
+                face_movement_direction,
+            ),
+        );
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct AnimationIndices {
+    name: Cow<'static, str>,
     first: usize,
     last: usize,
     mode: AnimationMode,
     dir: AnimationDir,
     cur: usize,
+    frame_events: Vec<usize>,
+    fps: Option<f32>,
+    frame_durations: Vec<(usize, f32)>,
 }
 
+#[derive(Clone, Copy)]
 pub enum AnimationDir {
     Forward,
     Backward,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub enum AnimationMode {
     Cycle,
     Bounce,
@@ -66,24 +182,108 @@ pub enum AnimationMode {
 }
 
 impl AnimationIndices {
-    pub const fn new(first: usize, last: usize, mode: AnimationMode) -> Self {
+    pub const fn new(name: &'static str, first: usize, last: usize, mode: AnimationMode) -> Self {
         Self {
+            name: Cow::Borrowed(name),
             first,
             last,
             mode,
             dir: AnimationDir::Forward,
             cur: first,
+            frame_events: Vec::new(),
+            fps: None,
+            frame_durations: Vec::new(),
         }
     }
 
+    /// Play every frame of this clip at `fps`, instead of using the
+    /// entity's `AnimationTimer` duration
+    pub fn with_fps(mut self, fps: f32) -> Self {
+        self.fps = Some(fps);
+        return self;
+    }
+
+    /// Hold `frame` on screen for `seconds`, overriding the clip's fps
+    ///
+    /// Useful for anticipation frames that need to linger
+    pub fn with_frame_duration(mut self, frame: usize, seconds: f32) -> Self {
+        self.frame_durations.retain(|(f, _)| *f != frame);
+        self.frame_durations.push((frame, seconds));
+        return self;
+    }
+
+    /// How long `frame` should be displayed for, if this clip overrides it.
+    /// Hot reloaded clips can say anything, so timings that aren't a positive
+    /// number of seconds are left to the entity's `AnimationTimer`
+    pub fn frame_duration(&self, frame: usize) -> Option<Duration> {
+        let seconds = match self.frame_durations.iter().find(|(f, _)| *f == frame) {
+            Some((_, seconds)) => *seconds,
+            None => 1.0 / self.fps?,
+        };
+
+        return Duration::try_from_secs_f32(seconds)
+            .ok()
+            .filter(|duration| !duration.is_zero());
+    }
+
+    /// Emit an `AnimationFrameEvent` whenever the clip reaches `frame`
+    ///
+    /// `frame` is an atlas index, not an offset into the clip
+    pub fn with_frame_event(mut self, frame: usize) -> Self {
+        debug_assert!(
+            (self.first..=self.last).contains(&frame),
+            "frame event {frame} is outside of clip {}",
+            self.name
+        );
+        self.frame_events.push(frame);
+        return self;
+    }
+
+    fn from_definition(name: &str, def: &ClipDefinition) -> Self {
+        let mut clip = Self::new("", def.first, def.last, def.mode);
+        clip.name = Cow::Owned(name.to_string());
+        clip.fps = def.fps;
+        clip.frame_durations = def.frame_durations.clone();
+        clip.frame_events = def.frame_events.clone();
+        return clip;
+    }
+
+    /// Replace this clip's timing and frames, keeping playback position
+    /// where possible
+    fn reload(&mut self, clip: AnimationIndices) {
+        let cur = self.cur.clamp(clip.first, clip.last);
+        *self = clip;
+        self.cur = cur;
+    }
+
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn first(&self) -> usize {
+        return self.first;
+    }
+
+    pub fn has_frame_event(&self, frame: usize) -> bool {
+        return self.frame_events.contains(&frame);
+    }
+
+    /// Looping clips with no frame events only change how things look, so
+    /// nothing is missed if they pause while nobody can see them
+    pub fn is_cosmetic(&self) -> bool {
+        return !matches!(self.mode, AnimationMode::Once) && self.frame_events.is_empty();
+    }
+
     /// Advance the animator to the next frame
-    /// Returns the index of the new frame
-    pub fn next(&mut self) -> usize {
+    /// Returns the index of the new frame, or `Finished` once a
+    /// `Once` clip has played its last frame
+    pub fn next(&mut self) -> AnimationFrame {
         match self.mode {
             AnimationMode::Once => {
-                if self.cur <= self.last {
-                    self.cur += 1;
+                if self.cur >= self.last {
+                    return AnimationFrame::Finished;
                 }
+                self.cur += 1;
             }
             AnimationMode::Cycle => {
                 self.cur += 1;
@@ -105,24 +305,418 @@ impl AnimationIndices {
             }
         };
 
-        return self.cur;
+        return AnimationFrame::Frame(self.cur);
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFrame {
+    Frame(usize),
+    Finished,
+}
+
 #[derive(Component, Deref, DerefMut)]
-pub struct AnimationTimer(pub Timer);
+#[require(AnimationSpeed)]
+pub struct AnimationTimer {
+    #[deref]
+    pub timer: Timer,
+    /// Frame duration used when the playing clip doesn't override it
+    pub base: Duration,
+    /// Time saved up while off screen, ticked all at once when back in view
+    hidden: Duration,
+}
+
+impl AnimationTimer {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Repeating),
+            base: Duration::from_secs_f32(seconds),
+            hidden: Duration::ZERO,
+        }
+    }
+}
+
+/// Multiplier on how fast an entity's frames advance. Animations run on
+/// virtual time, so slow motion slows them down on top of this
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct AnimationSpeed(pub f32);
+
+impl Default for AnimationSpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Sent when a `Once` animation has shown its last frame
+#[derive(Event, Debug, Clone)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub clip: Cow<'static, str>,
+}
+
+/// Sent when an animation reaches a frame registered with
+/// `AnimationIndices::with_frame_event`
+#[derive(Event, Debug, Clone)]
+pub struct AnimationFrameEvent {
+    pub entity: Entity,
+    pub clip: Cow<'static, str>,
+    pub frame: usize,
+}
+
+/// Clip definitions loaded from `assets/animations.anim.ron`
+///
+/// Clips defined here override the built in constants of the same name,
+/// and are re-applied to live entities whenever the file changes on disk
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct AnimationLibrary {
+    pub clips: std::collections::HashMap<String, ClipDefinition>,
+}
+
+impl AnimationLibrary {
+    pub fn clip(&self, name: &str) -> Option<AnimationIndices> {
+        let def = self.clips.get(name)?;
+        return Some(AnimationIndices::from_definition(name, def));
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipDefinition {
+    pub first: usize,
+    pub last: usize,
+    pub mode: AnimationMode,
+    #[serde(default)]
+    pub fps: Option<f32>,
+    /// `(atlas index, seconds)` pairs
+    #[serde(default)]
+    pub frame_durations: Vec<(usize, f32)>,
+    #[serde(default)]
+    pub frame_events: Vec<usize>,
+}
+
+/// Which way an entity's sprite faces on screen
+///
+/// Sprite art faces right, so `Left` flips it
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub enum Facing {
+    Left,
+    #[default]
+    Right,
+}
+
+/// The high level state an animated entity is in
+///
+/// Gameplay systems write to this component, and the entity's
+/// `AnimationController` decides which clip actually plays
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Reflect, Deserialize)]
+#[reflect(Component)]
+pub enum AnimationState {
+    #[default]
+    Idle,
+    Run,
+    Attack,
+    Hurt,
+    Die,
+}
+
+/// A small state graph mapping each `AnimationState` to a clip
+///
+/// States without any registered transitions may move to any other state,
+/// except `Die`, which is always terminal
+///
+/// Clips play whether or not the entity is drawn with a `Sprite`, so their
+/// frame events and `AnimationFinished` still come for meshes
+#[derive(Component)]
+#[require(
+    AnimationState,
+    AnimationTimer = AnimationTimer::from_seconds(CONTROLLER_FRAME_SECONDS)
+)]
+pub struct AnimationController {
+    current: AnimationState,
+    clips: HashMap<AnimationState, AnimationIndices>,
+    transitions: HashMap<AnimationState, Vec<AnimationState>>,
+}
+
+impl AnimationController {
+    pub fn new() -> Self {
+        Self {
+            current: AnimationState::Idle,
+            clips: HashMap::default(),
+            transitions: HashMap::default(),
+        }
+    }
+
+    pub fn player() -> Self {
+        Self::new()
+            .with_clip(AnimationState::Idle, PLAYER_IDLE_INDICES)
+            // footstep
+            .with_clip(AnimationState::Run, PLAYER_RUN_INDICES.with_frame_event(2))
+    }
 
-fn animate_sprites(
+    pub fn with_clip(mut self, state: AnimationState, clip: AnimationIndices) -> Self {
+        self.clips.insert(state, clip);
+        return self;
+    }
+
+    /// Restrict `from` to only be able to move into the given states
+    pub fn with_transition(mut self, from: AnimationState, to: AnimationState) -> Self {
+        self.transitions.entry(from).or_default().push(to);
+        return self;
+    }
+
+    /// Swap in any clips the library redefines, matched by clip name
+    pub fn apply_library(&mut self, library: &AnimationLibrary) {
+        for clip in self.clips.values_mut() {
+            if let Some(new) = library.clip(clip.name()) {
+                clip.reload(new);
+            }
+        }
+    }
+
+    pub fn current(&self) -> AnimationState {
+        return self.current;
+    }
+
+    pub fn clip(&self, state: AnimationState) -> Option<&AnimationIndices> {
+        return self.clips.get(&state);
+    }
+
+    pub fn can_transition(&self, from: AnimationState, to: AnimationState) -> bool {
+        if from == to {
+            return true;
+        }
+
+        if from == AnimationState::Die {
+            return false;
+        }
+
+        match self.transitions.get(&from) {
+            Some(allowed) => allowed.contains(&to),
+            None => true,
+        }
+    }
+}
+
+impl Default for AnimationController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn assign_atlas_layout(
+    mut query: Query<(&SpriteScale, &mut Sprite, Option<&AnimationIndices>), Added<SpriteScale>>,
+    atlases: Res<AtlasRegistry>,
+) {
+    for (scale, mut sprite, indices) in query.iter_mut() {
+        sprite.texture_atlas = Some(TextureAtlas {
+            layout: atlases.layout(*scale),
+            index: indices.map_or(0, |i| i.cur),
+        });
+        sprite.custom_size = Some(scale.world_size());
+    }
+}
+
+/// Push library clip definitions into controllers and playing clips
+///
+/// Runs for every controller when the library (re)loads, and for newly
+/// spawned controllers afterwards
+fn apply_animation_library(
+    mut events: EventReader<AssetEvent<AnimationLibrary>>,
+    libraries: Res<Assets<AnimationLibrary>>,
+    assets: Res<GameAssets>,
+    mut controllers: Query<&mut AnimationController>,
+    mut playing: Query<&mut AnimationIndices>,
+) {
+    let handle = &assets.animations;
+    let reloaded = events
+        .read()
+        .any(|e| e.is_loaded_with_dependencies(handle) || e.is_modified(handle));
+    let Some(library) = libraries.get(handle) else {
+        return;
+    };
+
+    for mut controller in controllers.iter_mut() {
+        if reloaded || controller.is_added() {
+            controller.apply_library(library);
+        }
+    }
+
+    if !reloaded {
+        return;
+    }
+
+    for mut indices in playing.iter_mut() {
+        if let Some(clip) = library.clip(indices.name()) {
+            indices.reload(clip);
+        }
+    }
+}
+
+/// Switch between `Idle` and `Run` based on whether the entity is trying to move
+///
+/// Any other state (attacking, hurt, dying) is owned by the system that set it
+fn locomotion_animation_state(mut query: Query<(&mut AnimationState, &MoveVector)>) {
+    for (mut state, move_vec) in query.iter_mut() {
+        let target = if move_vec.xz() == Vec2::ZERO {
+            AnimationState::Idle
+        } else {
+            AnimationState::Run
+        };
+
+        if matches!(*state, AnimationState::Idle | AnimationState::Run) {
+            state.set_if_neq(target);
+        }
+    }
+}
+
+/// Swap the playing clip whenever an entity's `AnimationState` changes
+///
+/// Transitions the controller does not allow are reverted
+fn apply_animation_state(
+    mut commands: Commands,
+    mut frame_events: EventWriter<AnimationFrameEvent>,
+    mut query: Query<
+        (
+            Entity,
+            &mut AnimationState,
+            &mut AnimationController,
+            Option<&mut Sprite>,
+            Option<&mut AnimationTimer>,
+        ),
+        Changed<AnimationState>,
+    >,
+) {
+    for (entity, mut state, mut controller, sprite, timer) in query.iter_mut() {
+        if !controller.can_transition(controller.current, *state) {
+            *state.bypass_change_detection() = controller.current;
+            continue;
+        }
+
+        controller.current = *state;
+        let Some(clip) = controller.clip(*state).cloned() else {
+            continue;
+        };
+
+        if let Some(atlas) = sprite.and_then(|s| s.into_inner().texture_atlas.as_mut()) {
+            atlas.index = clip.first;
+        }
+        // starting a clip reaches its first frame without ever advancing to it
+        if clip.has_frame_event(clip.first) {
+            frame_events.write(AnimationFrameEvent {
+                entity,
+                clip: clip.name.clone(),
+                frame: clip.first,
+            });
+        }
+        if let Some(mut timer) = timer {
+            let duration = clip.frame_duration(clip.first).unwrap_or(timer.base);
+            timer.set_duration(duration);
+            timer.reset();
+            timer.unpause();
+        }
+        commands.entity(entity).insert(clip);
+    }
+}
+
+/// Sprinting and speed boosts play animations faster, slows and chills play
+/// them slower
+fn animation_speed_from_move_speed(
+    mut query: Query<(&mut AnimationSpeed, &MoveSpeed, &BaseMoveSpeed), Changed<MoveSpeed>>,
+) {
+    for (mut speed, move_speed, base) in query.iter_mut() {
+        if **base <= 0.0 {
+            continue;
+        }
+        let ratio = (**move_speed / **base).clamp(MIN_ANIMATION_SPEED, MAX_ANIMATION_SPEED);
+        speed.set_if_neq(AnimationSpeed(ratio));
+    }
+}
+
+/// Cosmetic loops out of view only save up their time, which in a big horde
+/// is most of them. Entities without a sprite still step through their clip
+pub fn animate_sprites(
     time: Res<Time>,
-    mut query: Query<(&mut AnimationIndices, &mut AnimationTimer, &mut Sprite)>,
+    mut query: Query<(
+        Entity,
+        &mut AnimationIndices,
+        &mut AnimationTimer,
+        &AnimationSpeed,
+        &ViewVisibility,
+        Option<&mut Sprite>,
+    )>,
+    mut frame_events: EventWriter<AnimationFrameEvent>,
+    mut finished_events: EventWriter<AnimationFinished>,
 ) {
-    for (mut indices, mut timer, mut sprite) in &mut query {
-        timer.tick(time.delta());
+    for (entity, mut indices, mut timer, speed, visibility, sprite) in &mut query {
+        let delta = time.delta().mul_f32(speed.max(0.0));
+        if !visibility.get() && indices.is_cosmetic() {
+            timer.hidden += delta;
+            continue;
+        }
+
+        // catching up only ever moves on a frame, however long it was hidden
+        let delta = delta + std::mem::take(&mut timer.hidden);
+        timer.tick(delta);
+
+        if !timer.just_finished() {
+            continue;
+        }
 
-        if timer.just_finished() {
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                atlas.index = indices.next();
+        let prev = indices.cur;
+        let index = match indices.next() {
+            AnimationFrame::Frame(index) => index,
+            AnimationFrame::Finished => {
+                // stop ticking so the event is only sent once
+                timer.pause();
+                finished_events.write(AnimationFinished {
+                    entity,
+                    clip: indices.name.clone(),
+                });
+                continue;
             }
+        };
+        if let Some(atlas) = sprite.and_then(|s| s.into_inner().texture_atlas.as_mut()) {
+            atlas.index = index;
+        }
+
+        let duration = indices.frame_duration(index).unwrap_or(timer.base);
+        timer.set_duration(duration);
+
+        if index != prev && indices.has_frame_event(index) {
+            frame_events.write(AnimationFrameEvent {
+                entity,
+                clip: indices.name.clone(),
+                frame: index,
+            });
+        }
+    }
+}
+
+/// Flip sprites to face the direction they move across the screen
+fn face_movement_direction(
+    cam: Query<&GlobalTransform, With<MainCamera>>,
+    mut query: Query<(&MoveVector, &mut Facing, Option<&mut Sprite>)>,
+) {
+    let Ok(cam) = cam.single() else {
+        return;
+    };
+    let right = cam.right();
+
+    for (move_vec, mut facing, sprite) in query.iter_mut() {
+        let Some(dir) = move_vec.try_normalize() else {
+            continue;
+        };
+
+        let sideways = dir.dot(*right);
+        if sideways > FACING_HYSTERESIS {
+            facing.set_if_neq(Facing::Right);
+        } else if sideways < -FACING_HYSTERESIS {
+            facing.set_if_neq(Facing::Left);
+        }
+
+        if let Some(mut sprite) = sprite {
+            sprite.flip_x = *facing == Facing::Left;
         }
     }
 }
@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{GameState, GameplaySet};
+
+/// How long each announcement stays up, fades included
+const BANNER_SECONDS: f32 = 3.5;
+const FADE_SECONDS: f32 = 0.5;
+
+pub struct AnnouncementsPlugin;
+
+impl Plugin for AnnouncementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Announcement>();
+        app.init_resource::<AnnouncementQueue>();
+        app.add_systems(OnExit(GameState::InGame), clear_announcements);
+        app.add_systems(
+            Update,
+            (queue_announcements, show_announcements)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// A line of text across the top of the screen, like a boss arriving or a
+/// milestone in the run
+#[derive(Event, Debug, Clone)]
+pub struct Announcement {
+    pub text: String,
+}
+
+/// Announcements waiting for the one showing to finish, so ones arriving
+/// together are read one after another rather than over each other
+#[derive(Resource, Default, Debug)]
+struct AnnouncementQueue(VecDeque<String>);
+
+#[derive(Component)]
+struct AnnouncementBanner {
+    timer: Timer,
+}
+
+fn clear_announcements(mut queue: ResMut<AnnouncementQueue>) {
+    queue.0.clear();
+}
+
+fn queue_announcements(
+    mut announcements: EventReader<Announcement>,
+    mut queue: ResMut<AnnouncementQueue>,
+) {
+    for announcement in announcements.read() {
+        queue.0.push_back(announcement.text.clone());
+    }
+}
+
+/// One banner at a time, fading in and out, the next taking its place once
+/// it's gone
+fn show_announcements(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut queue: ResMut<AnnouncementQueue>,
+    mut banners: Query<(Entity, &mut AnnouncementBanner, &mut TextColor)>,
+) {
+    if let Ok((entity, mut banner, mut color)) = banners.single_mut() {
+        banner.timer.tick(time.delta());
+        if banner.timer.finished() {
+            commands.entity(entity).despawn();
+            return;
+        }
+        let elapsed = banner.timer.elapsed_secs();
+        let remaining = banner.timer.remaining_secs();
+        let alpha = (elapsed.min(remaining) / FADE_SECONDS).min(1.0);
+        color.0.set_alpha(alpha);
+        return;
+    }
+
+    let Some(text) = queue.0.pop_front() else {
+        return;
+    };
+    commands.spawn((
+        Text::new(text),
+        TextFont::from_font_size(36.0),
+        TextColor(Color::srgba(1.0, 0.95, 0.85, 0.0)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(20.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        AnnouncementBanner {
+            timer: Timer::from_seconds(BANNER_SECONDS, TimerMode::Once),
+        },
+        Name::new("Announcement"),
+        StateScoped(GameState::InGame),
+    ));
+}
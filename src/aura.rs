@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::combat::DamageEvent;
+use crate::physics::layers;
+use crate::stats::Stats;
+use crate::status::{ApplyStatus, StatusEffect};
+use crate::weapons::{Weapon, WeaponKind, Weapons, fire_weapons};
+use crate::{Enemy, GameplaySet, Player};
+
+pub struct AuraPlugin;
+
+impl Plugin for AuraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_aura_assets);
+        app.add_systems(
+            Update,
+            (sync_auras, tick_auras)
+                .chain()
+                .after(fire_weapons)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// A sensor field around a player carrying an aura weapon, damaging every
+/// enemy inside it each time its weapon comes off cooldown
+///
+/// Kept as a child of the player, so it follows them, and added, resized
+/// and removed by `sync_auras` to match their weapons and stats
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Aura {
+    pub kind: WeaponKind,
+}
+
+#[derive(Resource)]
+struct AuraAssets {
+    mesh: Handle<Mesh>,
+    thorn_material: Handle<StandardMaterial>,
+    bramble_material: Handle<StandardMaterial>,
+}
+
+fn setup_aura_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut translucent = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })
+    };
+
+    commands.insert_resource(AuraAssets {
+        mesh: meshes.add(Cylinder::new(1.0, 0.05)),
+        thorn_material: translucent(Color::srgba(0.3, 0.8, 0.2, 0.25)),
+        bramble_material: translucent(Color::srgba(0.5, 0.3, 0.7, 0.3)),
+    });
+}
+
+fn is_aura(kind: WeaponKind) -> bool {
+    return matches!(kind, WeaponKind::ThornAura | WeaponKind::BrambleField);
+}
+
+/// One aura per aura weapon carried, so evolving one swaps it for the new
+/// kind
+fn sync_auras(
+    mut commands: Commands,
+    assets: Res<AuraAssets>,
+    players: Query<
+        (Entity, &Stats, &Weapons, Option<&Children>),
+        (With<Player>, Or<(Changed<Stats>, Changed<Weapons>)>),
+    >,
+    mut auras: Query<(&Aura, &mut Transform)>,
+) {
+    for (player, stats, weapons, children) in players.iter() {
+        let mut missing: Vec<&Weapon> = weapons.iter().filter(|w| is_aura(w.kind)).collect();
+
+        for &child in children.into_iter().flatten() {
+            let Ok((aura, mut transform)) = auras.get_mut(child) else {
+                continue;
+            };
+            let Some(index) = missing.iter().position(|w| w.kind == aura.kind) else {
+                commands.entity(child).despawn();
+                continue;
+            };
+            let radius = missing.swap_remove(index).aura_radius(stats);
+            transform.set_if_neq(Transform::from_scale(Vec3::new(radius, 1.0, radius)));
+        }
+
+        for weapon in missing {
+            let material = match weapon.kind {
+                WeaponKind::BrambleField => assets.bramble_material.clone(),
+                _ => assets.thorn_material.clone(),
+            };
+            let radius = weapon.aura_radius(stats);
+            commands.entity(player).with_child((
+                Mesh3d(assets.mesh.clone()),
+                MeshMaterial3d(material),
+                // the mesh is a unit disc, the collider a tall cylinder so
+                // enemies standing in it overlap it
+                Transform::from_scale(Vec3::new(radius, 1.0, radius)),
+                Name::new(weapon.kind.name()),
+                Aura { kind: weapon.kind },
+                Collider::cylinder(1.0, 1.0),
+                // part of the player's body, which it shouldn't weigh down
+                ColliderMassProperties::Density(0.0),
+                layers::aura(),
+                Sensor,
+            ));
+        }
+    }
+}
+
+fn tick_auras(
+    rapier_context: ReadRapierContext,
+    auras: Query<(Entity, &Aura, &ChildOf)>,
+    players: Query<(&Stats, &Weapons)>,
+    enemies: Query<(), With<Enemy>>,
+    mut damage: EventWriter<DamageEvent>,
+    mut status: EventWriter<ApplyStatus>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+
+    for (entity, aura, child_of) in auras.iter() {
+        let player = child_of.parent();
+        let Ok((stats, weapons)) = players.get(player) else {
+            continue;
+        };
+        let Some(weapon) = weapons.get(aura.kind).filter(|w| w.just_fired()) else {
+            continue;
+        };
+
+        let amount = weapon.damage(stats);
+        for (a, b, intersecting) in context.intersection_pairs_with(entity) {
+            let other = if a == entity { b } else { a };
+            if !intersecting || !enemies.contains(other) {
+                continue;
+            }
+
+            damage.write(DamageEvent {
+                target: other,
+                amount,
+                source: Some(player),
+                crit: false,
+                weapon: Some(aura.kind),
+                kind: aura.kind.damage_type(),
+            });
+            if aura.kind == WeaponKind::BrambleField {
+                status.write(ApplyStatus {
+                    target: other,
+                    effect: StatusEffect::Slow,
+                    stacks: 1,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::loading::GameAssets;
+use crate::ron_asset::RonAssetLoader;
+use crate::run_config::RunConfig;
+use crate::stats::{Stat, Stats};
+use crate::{AirControl, FaceMovement, MovementProfile, Player};
+
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<BalanceConfig>();
+        app.register_asset_loader(RonAssetLoader::<BalanceConfig>::new(&["balance.ron"]));
+        app.register_type::<BalanceConfig>();
+        app.register_type::<StragglerPolicy>();
+        app.init_resource::<BalanceConfig>();
+        app.add_systems(
+            Update,
+            (
+                sync_balance_config,
+                (
+                    apply_player_move_speed,
+                    apply_player_air_control,
+                    apply_player_turn_speed,
+                )
+                    .run_if(resource_changed::<BalanceConfig>),
+                apply_player_movement,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Movement, camera and spawning tunables
+///
+/// Loaded from `assets/game.balance.ron` and hot reloaded, but also
+/// editable live from the inspector as a resource
+#[derive(Asset, Resource, Reflect, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct BalanceConfig {
+    /// Base move speed of the player, before upgrades
+    pub player_move_speed: f32,
+    /// Gravity scale of anything that isn't touching the ground
+    pub air_gravity: f32,
+    /// How much of their acceleration players keep while in the air
+    pub air_control: f32,
+    /// How the player speeds up, slows down and turns
+    pub player_movement: MovementProfile,
+    /// Radians per second the player model turns to face where it's going
+    pub player_turn_speed: f32,
+    /// Enemies further than this from every player are stragglers
+    pub straggler_radius: f32,
+    pub straggler_policy: StragglerPolicy,
+}
+
+/// What happens to enemies left too far behind to ever catch up
+#[derive(Reflect, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StragglerPolicy {
+    /// Move them back onto the spawn ring, ahead of where players are going
+    #[default]
+    Relocate,
+    /// Remove them and spawn as many new enemies straight away
+    Refund,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            player_move_speed: 23.6,
+            air_gravity: 30.0,
+            air_control: 0.35,
+            player_movement: MovementProfile {
+                acceleration: 300.0,
+                deceleration: 220.0,
+                turn_rate: 14.0,
+            },
+            player_turn_speed: 12.0,
+            straggler_radius: 80.0,
+            straggler_policy: StragglerPolicy::Relocate,
+        }
+    }
+}
+
+/// Copy the config asset into the live resource whenever the file (re)loads
+fn sync_balance_config(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<BalanceConfig>>,
+    configs: Res<Assets<BalanceConfig>>,
+    assets: Res<GameAssets>,
+) {
+    let handle = &assets.balance;
+    let reloaded = events
+        .read()
+        .any(|e| e.is_loaded_with_dependencies(handle) || e.is_modified(handle));
+    if !reloaded {
+        return;
+    }
+
+    if let Some(config) = configs.get(handle) {
+        commands.insert_resource(config.clone());
+    }
+}
+
+/// Move speed is a stat, so set its base rather than `MoveSpeed` directly,
+/// keeping the character's multiplier on top like `Character::stats` does
+fn apply_player_move_speed(
+    balance: Res<BalanceConfig>,
+    config: Res<RunConfig>,
+    mut players: Query<&mut Stats, With<Player>>,
+) {
+    let move_speed = balance.player_move_speed * config.character().move_speed;
+    for mut stats in players.iter_mut() {
+        if stats.base(Stat::MoveSpeed) != move_speed {
+            stats.set_base(Stat::MoveSpeed, move_speed);
+        }
+    }
+}
+
+fn apply_player_air_control(
+    balance: Res<BalanceConfig>,
+    mut players: Query<&mut AirControl, With<Player>>,
+) {
+    for mut air_control in players.iter_mut() {
+        air_control.set_if_neq(AirControl(balance.air_control));
+    }
+}
+
+fn apply_player_turn_speed(
+    balance: Res<BalanceConfig>,
+    mut players: Query<&mut FaceMovement, With<Player>>,
+) {
+    for mut face in players.iter_mut() {
+        face.set_if_neq(FaceMovement(balance.player_turn_speed));
+    }
+}
+
+/// Handling upgrades scale the whole profile, so this also follows the
+/// player's stats
+fn apply_player_movement(
+    balance: Res<BalanceConfig>,
+    mut players: Query<(Ref<Stats>, &mut MovementProfile), With<Player>>,
+) {
+    for (stats, mut profile) in players.iter_mut() {
+        if !balance.is_changed() && !stats.is_changed() {
+            continue;
+        }
+        profile.set_if_neq(balance.player_movement.scaled(stats.get(Stat::Handling)));
+    }
+}
@@ -0,0 +1,520 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::ai::{ChasePlayer, KeepDistance};
+use crate::boundary::keep_inside;
+use crate::combat::{ContactDamage, DamageType, DeathEvent, Faction, Health, apply_damage};
+use crate::damage_zone::{DamageZone, DamageZoneAssets, damage_zone};
+use crate::enemies::{EnemyAssets, enemy_body};
+use crate::move_speed::MoveSpeedModifiers;
+use crate::pickups::{Pickup, PickupAssets, PickupKind, Rarity, pickup_bundle};
+use crate::pool::{Pool, PoolKey};
+use crate::projectile::{Projectile, ProjectileAssets, projectile_bundle};
+use crate::run_config::RunConfig;
+use crate::status::{InflictsStatus, StatusEffect};
+use crate::telegraph::{
+    Telegraph, TelegraphAssets, TelegraphShape, expire_telegraphs, telegraph, tick_telegraphs,
+};
+use crate::{BaseMoveSpeed, GameState, GameplaySet, Player};
+
+/// Radius of the ring the player is locked into while a boss is alive
+const ARENA_RADIUS: f32 = 30.0;
+const BOSS_BASE_HEALTH: f32 = 500.0;
+const BOSS_SPEED: f32 = 10.0;
+const FRENZY_SPEED_MULTIPLIER: f32 = 1.5;
+const VOLLEY_BOLTS: usize = 12;
+const VOLLEY_SPEED: f32 = 12.0;
+const VOLLEY_DAMAGE: f32 = 15.0;
+const VOLLEY_LIFETIME: f32 = 5.0;
+/// Frenzied bosses scorch the ground under the player with every volley
+const FRENZY_ZONE_RADIUS: f32 = 3.0;
+const FRENZY_ZONE_SECONDS: f32 = 6.0;
+const FRENZY_ZONE_DPS: f32 = 12.0;
+/// Warning given before a frenzy zone lands
+const FRENZY_ZONE_WINDUP: f32 = 1.0;
+const HEALTH_COLOR: Color = Color::srgb(0.8, 0.1, 0.2);
+/// Health just lost, shown draining away behind what's left
+const TRAIL_COLOR: Color = Color::srgb(1.0, 0.85, 0.6);
+/// Fraction of the gap the trail closes per second
+const TRAIL_RATE: f32 = 3.0;
+/// How long the bar flashes when the boss enters a new phase
+const PHASE_FLASH_SECONDS: f32 = 0.6;
+const PIP_SIZE: f32 = 12.0;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                start_boss_encounter,
+                update_boss_phase,
+                boss_volley,
+                land_frenzy_zones
+                    .after(tick_telegraphs)
+                    .before(expire_telegraphs),
+                update_boss_health_bar,
+                enforce_arena_lock,
+                draw_arena_lock,
+                end_boss_encounter.after(apply_damage),
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Boss {
+    pub name: String,
+    pub phase: BossPhase,
+}
+
+/// Bosses move through their phases as they lose health, never going back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Reflect)]
+pub enum BossPhase {
+    /// Run at the player
+    Charge,
+    /// Keep away and fire rings of bolts
+    Volley,
+    /// Run at the player faster while still firing
+    Frenzy,
+}
+
+impl BossPhase {
+    const ALL: [BossPhase; 3] = [BossPhase::Charge, BossPhase::Volley, BossPhase::Frenzy];
+
+    fn for_health(fraction: f32) -> Self {
+        if fraction > 2.0 / 3.0 {
+            BossPhase::Charge
+        } else if fraction > 1.0 / 3.0 {
+            BossPhase::Volley
+        } else {
+            BossPhase::Frenzy
+        }
+    }
+}
+
+/// Fires a ring of bolts every time the timer finishes
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct BossVolley {
+    pub timer: Timer,
+    /// Rotates each ring so there are no permanently safe angles
+    offset: f32,
+}
+
+impl BossVolley {
+    pub fn new(interval: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(interval, TimerMode::Repeating),
+            offset: 0.0,
+        }
+    }
+}
+
+/// Keeps the player inside a circle while a boss is alive
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ArenaLock {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+/// The boss's name, health and phase across the top of the screen
+#[derive(Component)]
+struct BossHealthBar {
+    boss: Entity,
+    /// The phase last shown, to notice the boss moving into the next
+    phase: BossPhase,
+    /// Flashes the bar after a phase change
+    flash: Timer,
+}
+
+#[derive(Component)]
+struct BossHealthFill;
+
+#[derive(Component)]
+struct BossHealthTrail;
+
+/// Lit once the boss reaches `0`
+#[derive(Component)]
+struct BossPhasePip(BossPhase);
+
+/// The `number`th boss of the run, each one tougher than the last
+pub fn boss(assets: &EnemyAssets, number: usize, position: Vec3) -> impl Bundle {
+    let name = format!("Bunslayer {number}");
+    (
+        Mesh3d(assets.boss_mesh.clone()),
+        MeshMaterial3d(assets.boss_material.clone()),
+        Transform::from_translation(position + Vec3::Y * 2.0),
+        Name::new(name.clone()),
+        Boss {
+            name,
+            phase: BossPhase::Charge,
+        },
+        enemy_body(1.5, 1.5),
+        Health::new(boss_health(number)),
+        ContactDamage::new(25.0),
+        ChasePlayer,
+        BaseMoveSpeed(BOSS_SPEED),
+    )
+}
+
+pub fn boss_health(number: usize) -> f32 {
+    return BOSS_BASE_HEALTH * number as f32;
+}
+
+fn start_boss_encounter(
+    mut commands: Commands,
+    bosses: Query<(Entity, &Boss), Added<Boss>>,
+    players: Query<&Transform, With<Player>>,
+) {
+    for (entity, boss) in bosses.iter() {
+        // the arena closes around everyone still standing
+        let positions: Vec<Vec3> = players.iter().map(|player| player.translation).collect();
+        if !positions.is_empty() {
+            commands.insert_resource(ArenaLock {
+                center: positions.iter().sum::<Vec3>() / positions.len() as f32,
+                radius: ARENA_RADIUS,
+            });
+        }
+
+        // nothing to flash for until the first phase change
+        let mut flash = Timer::from_seconds(PHASE_FLASH_SECONDS, TimerMode::Once);
+        flash.tick(flash.duration());
+        commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(12.0),
+                    left: Val::Percent(25.0),
+                    width: Val::Percent(50.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                BossHealthBar {
+                    boss: entity,
+                    phase: boss.phase,
+                    flash,
+                },
+                Name::new("Boss Health Bar"),
+                StateScoped(GameState::InGame),
+            ))
+            .with_children(|bar| {
+                bar.spawn((Text::new(boss.name.clone()), TextFont::from_font_size(22.0)));
+                bar.spawn((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(18.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                ))
+                .with_children(|health| {
+                    let fill = Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    };
+                    // the trail first, so health is drawn over it
+                    health.spawn((fill.clone(), BackgroundColor(TRAIL_COLOR), BossHealthTrail));
+                    health.spawn((fill, BackgroundColor(HEALTH_COLOR), BossHealthFill));
+                });
+                bar.spawn(Node {
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                })
+                .with_children(|pips| {
+                    for phase in BossPhase::ALL {
+                        pips.spawn((
+                            Node {
+                                width: Val::Px(PIP_SIZE),
+                                height: Val::Px(PIP_SIZE),
+                                ..default()
+                            },
+                            BorderRadius::MAX,
+                            BackgroundColor(pip_color(phase <= boss.phase)),
+                            BossPhasePip(phase),
+                        ));
+                    }
+                });
+            });
+    }
+}
+
+fn pip_color(lit: bool) -> Color {
+    return match lit {
+        true => HEALTH_COLOR,
+        false => Color::srgba(1.0, 1.0, 1.0, 0.25),
+    };
+}
+
+fn update_boss_phase(
+    mut commands: Commands,
+    mut bosses: Query<(Entity, &mut Boss, &Health, &mut MoveSpeedModifiers), Changed<Health>>,
+) {
+    for (entity, mut boss, health, mut speed_modifiers) in bosses.iter_mut() {
+        let phase = BossPhase::for_health(health.fraction());
+        if phase <= boss.phase {
+            continue;
+        }
+
+        boss.phase = phase;
+        match phase {
+            BossPhase::Charge => {}
+            BossPhase::Volley => {
+                commands.entity(entity).remove::<ChasePlayer>().insert((
+                    KeepDistance {
+                        distance: 15.0,
+                        tolerance: 3.0,
+                    },
+                    BossVolley::new(2.0),
+                ));
+            }
+            BossPhase::Frenzy => {
+                commands
+                    .entity(entity)
+                    .remove::<KeepDistance>()
+                    .insert((ChasePlayer, BossVolley::new(1.2)));
+                speed_modifiers.insert("Frenzy", FRENZY_SPEED_MULTIPLIER, None);
+            }
+        }
+    }
+}
+
+fn boss_volley(
+    mut commands: Commands,
+    time: Res<Time>,
+    (assets, telegraph_assets): (Res<ProjectileAssets>, Res<TelegraphAssets>),
+    mut pool: ResMut<Pool>,
+    mut bosses: Query<(Entity, &Boss, &Transform, &mut BossVolley)>,
+    players: Query<&Transform, (With<Player>, Without<Boss>)>,
+) {
+    for (entity, boss, transform, mut volley) in bosses.iter_mut() {
+        if !volley.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        if boss.phase == BossPhase::Frenzy {
+            for player in players.iter() {
+                commands.spawn((
+                    telegraph(
+                        &telegraph_assets,
+                        TelegraphShape::Circle {
+                            radius: FRENZY_ZONE_RADIUS,
+                        },
+                        FRENZY_ZONE_WINDUP,
+                        player.translation,
+                        Vec3::NEG_Z,
+                    ),
+                    FrenzyZoneTelegraph,
+                ));
+            }
+        }
+
+        volley.offset += TAU / (VOLLEY_BOLTS as f32 * 2.0);
+        for i in 0..VOLLEY_BOLTS {
+            let angle = volley.offset + TAU * i as f32 / VOLLEY_BOLTS as f32;
+            let dir = Vec3::new(angle.cos(), 0.0, angle.sin());
+            pool.spawn(
+                &mut commands,
+                PoolKey::Projectile,
+                (
+                    projectile_bundle(
+                        assets.mesh.clone(),
+                        assets.enemy_material.clone(),
+                        transform.translation + dir * 2.0,
+                        dir * VOLLEY_SPEED,
+                        Projectile {
+                            damage: VOLLEY_DAMAGE,
+                            hits: Faction::Player,
+                            lifetime: Timer::from_seconds(VOLLEY_LIFETIME, TimerMode::Once),
+                            owner: Some(entity),
+                            weapon: None,
+                            kind: DamageType::Arcane,
+                        },
+                    ),
+                    InflictsStatus {
+                        effect: StatusEffect::Burn,
+                        stacks: 1,
+                    },
+                ),
+            );
+        }
+    }
+}
+
+/// Marks where a frenzy zone is about to land
+#[derive(Component)]
+struct FrenzyZoneTelegraph;
+
+/// The zone covers exactly what its telegraph did
+fn land_frenzy_zones(
+    mut commands: Commands,
+    assets: Res<DamageZoneAssets>,
+    telegraphs: Query<(&Telegraph, &Transform), With<FrenzyZoneTelegraph>>,
+) {
+    for (telegraph, transform) in telegraphs.iter() {
+        if !telegraph.windup.just_finished() {
+            continue;
+        }
+        let TelegraphShape::Circle { radius } = telegraph.shape else {
+            continue;
+        };
+        commands.spawn(damage_zone(
+            &assets,
+            transform.translation.with_y(0.0),
+            radius,
+            DamageZone::new(FRENZY_ZONE_DPS, FRENZY_ZONE_SECONDS, Faction::Player),
+        ));
+    }
+}
+
+/// The trail drains down to the health left, and reaching a new phase
+/// flashes the bar and lights its pip
+fn update_boss_health_bar(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bars: Query<(Entity, &mut BossHealthBar, &Children)>,
+    bosses: Query<(&Boss, &Health)>,
+    descendants: Query<&Children>,
+    mut fills: Query<
+        (
+            &mut Node,
+            &mut BackgroundColor,
+            Has<BossHealthFill>,
+            Has<BossHealthTrail>,
+            Option<&BossPhasePip>,
+        ),
+        Or<(
+            With<BossHealthFill>,
+            With<BossHealthTrail>,
+            With<BossPhasePip>,
+        )>,
+    >,
+) {
+    for (bar, mut link, children) in bars.iter_mut() {
+        let Ok((boss, health)) = bosses.get(link.boss) else {
+            commands.entity(bar).despawn();
+            continue;
+        };
+
+        if boss.phase != link.phase {
+            link.phase = boss.phase;
+            link.flash.reset();
+        }
+        link.flash.tick(time.delta());
+        let flash = 1.0 - link.flash.fraction();
+
+        let health = health.fraction() * 100.0;
+        let trail_step = (TRAIL_RATE * time.delta_secs()).min(1.0);
+        for child in children
+            .iter()
+            .flat_map(|child| descendants.iter_descendants(child))
+        {
+            let Ok((mut node, mut color, fill, trail, pip)) = fills.get_mut(child) else {
+                continue;
+            };
+            if fill {
+                node.width = Val::Percent(health);
+                color.0 = HEALTH_COLOR.mix(&Color::WHITE, flash);
+            } else if trail {
+                let Val::Percent(shown) = node.width else {
+                    continue;
+                };
+                // only ever drains, health coming back jumps straight up
+                let shown = shown.max(health);
+                node.width = Val::Percent(shown + (health - shown) * trail_step);
+            } else if let Some(pip) = pip {
+                let lit = pip.0 <= boss.phase;
+                color.0 = pip_color(lit);
+                // the newest pip swells as it lights
+                let size = match pip.0 == boss.phase {
+                    true => PIP_SIZE * (1.0 + flash * 0.6),
+                    false => PIP_SIZE,
+                };
+                node.width = Val::Px(size);
+                node.height = Val::Px(size);
+            }
+        }
+    }
+}
+
+fn enforce_arena_lock(
+    lock: Option<Res<ArenaLock>>,
+    mut players: Query<(&mut Transform, &mut Velocity), With<Player>>,
+) {
+    let Some(lock) = lock else {
+        return;
+    };
+
+    for (mut transform, mut velocity) in players.iter_mut() {
+        keep_inside(lock.center, lock.radius, &mut transform, &mut velocity);
+    }
+}
+
+fn draw_arena_lock(lock: Option<Res<ArenaLock>>, mut gizmos: Gizmos) {
+    let Some(lock) = lock else {
+        return;
+    };
+
+    gizmos.circle(
+        Isometry3d::new(
+            lock.center + Vec3::Y * 0.1,
+            Quat::from_rotation_x(FRAC_PI_2),
+        ),
+        lock.radius,
+        Color::srgb(0.9, 0.1, 0.2),
+    );
+}
+
+/// Drop the boss's guaranteed loot and release the arena once the last
+/// boss is dead
+fn end_boss_encounter(
+    mut commands: Commands,
+    mut deaths: EventReader<DeathEvent>,
+    bosses: Query<(&Transform, &Health), With<Boss>>,
+    assets: Res<PickupAssets>,
+    config: Res<RunConfig>,
+) {
+    let mut killed = 0;
+    for death in deaths.read() {
+        let Ok((transform, _)) = bosses.get(death.entity) else {
+            continue;
+        };
+
+        killed += 1;
+        let pos = transform.translation.with_y(1.0);
+        let drops: Vec<PickupKind> = [
+            PickupKind::Experience(250),
+            PickupKind::Heal(50.0),
+            PickupKind::Gold(50),
+        ]
+        .into_iter()
+        .filter(|kind| config.health_drops() || !matches!(kind, PickupKind::Heal(_)))
+        .collect();
+        for (i, &kind) in drops.iter().enumerate() {
+            let angle = TAU * i as f32 / drops.len() as f32;
+            let pickup = Pickup {
+                kind,
+                rarity: Rarity::Rare,
+            };
+            let offset = Vec3::new(angle.cos(), 0.0, angle.sin()) * 2.0;
+            commands.spawn(pickup_bundle(&assets, pickup, pos + offset));
+        }
+    }
+
+    let alive = bosses
+        .iter()
+        .filter(|(_, health)| !health.is_dead())
+        .count();
+    if killed > 0 && alive == 0 {
+        commands.remove_resource::<ArenaLock>();
+    }
+}
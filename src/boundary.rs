@@ -0,0 +1,162 @@
+//! The edge of the map, set by the level's `boundary`, so nobody wanders off
+//! past where the ground ends and falls forever
+//!
+//! Players are either walled in or hurt for as long as they stay out,
+//! depending on the boundary's `mode`. Enemies are always put back on the
+//! edge, so ones spawned outside walk in from there
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use serde::Deserialize;
+
+use crate::combat::{DamageEvent, DamageType};
+use crate::level::ActiveLevel;
+use crate::{Enemy, GameplaySet, Player};
+
+/// Players outside a damaging boundary are hurt this often rather than
+/// every frame
+const BOUNDARY_TICK_SECONDS: f32 = 0.5;
+const BOUNDARY_COLOR: Color = Color::srgb(0.9, 0.6, 0.1);
+
+pub struct BoundaryPlugin;
+
+impl Plugin for BoundaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (enforce_boundary, hurt_players_outside, draw_boundary).in_set(GameplaySet),
+        );
+    }
+}
+
+/// A ring around the playable part of the level
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct BoundaryDefinition {
+    #[serde(default)]
+    pub center: Vec3,
+    pub radius: f32,
+    #[serde(default)]
+    pub mode: BoundaryMode,
+}
+
+/// What the boundary does to players crossing it
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum BoundaryMode {
+    /// Stopped at the edge like a wall
+    #[default]
+    Wall,
+    /// Let through, but hurt every second they stay out
+    Damage { per_second: f32 },
+}
+
+/// Move `transform` back onto the ring of `radius` around `center` if it's
+/// outside, cancelling any velocity still carrying it out. Returns whether
+/// it had to be moved
+pub fn keep_inside(
+    center: Vec3,
+    radius: f32,
+    transform: &mut Transform,
+    velocity: &mut Velocity,
+) -> bool {
+    let offset = (transform.translation - center).xz();
+    if offset.length() <= radius {
+        return false;
+    }
+
+    let clamped = offset.clamp_length_max(radius);
+    transform.translation.x = center.x + clamped.x;
+    transform.translation.z = center.z + clamped.y;
+
+    let outward = offset.normalize();
+    let speed_out = velocity.linvel.xz().dot(outward);
+    if speed_out > 0.0 {
+        velocity.linvel.x -= outward.x * speed_out;
+        velocity.linvel.z -= outward.y * speed_out;
+    }
+    return true;
+}
+
+fn is_outside(boundary: &BoundaryDefinition, position: Vec3) -> bool {
+    return (position - boundary.center).xz().length() > boundary.radius;
+}
+
+fn enforce_boundary(
+    level: Option<Res<ActiveLevel>>,
+    mut players: Query<(&mut Transform, &mut Velocity), (With<Player>, Without<Enemy>)>,
+    mut enemies: Query<(&mut Transform, &mut Velocity), With<Enemy>>,
+) {
+    let Some(boundary) = level.as_ref().and_then(|level| level.boundary) else {
+        return;
+    };
+
+    if boundary.mode == BoundaryMode::Wall {
+        for (mut transform, mut velocity) in players.iter_mut() {
+            keep_inside(
+                boundary.center,
+                boundary.radius,
+                &mut transform,
+                &mut velocity,
+            );
+        }
+    }
+    for (mut transform, mut velocity) in enemies.iter_mut() {
+        keep_inside(
+            boundary.center,
+            boundary.radius,
+            &mut transform,
+            &mut velocity,
+        );
+    }
+}
+
+fn hurt_players_outside(
+    time: Res<Time>,
+    mut since_tick: Local<f32>,
+    level: Option<Res<ActiveLevel>>,
+    players: Query<(Entity, &Transform), With<Player>>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    let Some(boundary) = level.as_ref().and_then(|level| level.boundary) else {
+        return;
+    };
+    let BoundaryMode::Damage { per_second } = boundary.mode else {
+        return;
+    };
+
+    *since_tick += time.delta_secs();
+    if *since_tick < BOUNDARY_TICK_SECONDS {
+        return;
+    }
+    *since_tick -= BOUNDARY_TICK_SECONDS;
+
+    for (entity, transform) in players.iter() {
+        if !is_outside(&boundary, transform.translation) {
+            continue;
+        }
+        damage.write(DamageEvent {
+            target: entity,
+            amount: per_second * BOUNDARY_TICK_SECONDS,
+            source: None,
+            crit: false,
+            weapon: None,
+            kind: DamageType::Physical,
+        });
+    }
+}
+
+fn draw_boundary(level: Option<Res<ActiveLevel>>, mut gizmos: Gizmos) {
+    let Some(boundary) = level.as_ref().and_then(|level| level.boundary) else {
+        return;
+    };
+
+    gizmos.circle(
+        Isometry3d::new(
+            boundary.center + Vec3::Y * 0.1,
+            Quat::from_rotation_x(FRAC_PI_2),
+        ),
+        boundary.radius,
+        BOUNDARY_COLOR,
+    );
+}
@@ -0,0 +1,133 @@
+//! Caps how many projectiles and effects can be alive at once, so a late
+//! game screen full of weapons can't bury the frame
+//!
+//! Anything spawned with `Budgeted` counts towards its category's limit in
+//! `EntityBudget`. Once a category is over, the lowest priority entities in
+//! it are released, oldest first
+
+use std::cmp::Ordering;
+
+use bevy::ecs::component::Tick;
+use bevy::ecs::system::SystemChangeTick;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::pool::ReleaseCommandsExt;
+
+pub struct BudgetPlugin;
+
+impl Plugin for BudgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<EntityBudget>();
+        app.init_resource::<EntityBudget>();
+        // after Update's spawns have landed, so they're counted the frame
+        // they appear
+        app.add_systems(PostUpdate, enforce_entity_budgets);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum BudgetCategory {
+    Projectile,
+    Particle,
+    /// Purely visual leftovers, like dash afterimages
+    Effect,
+}
+
+/// Which entities go first when their category is over budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Reflect)]
+pub enum BudgetPriority {
+    /// Nobody would notice it going early
+    Low,
+    Normal,
+    /// Taking it away changes the fight, like a shot at the player
+    High,
+}
+
+/// Counts towards its category's limit in `EntityBudget`
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Budgeted {
+    pub category: BudgetCategory,
+    pub priority: BudgetPriority,
+}
+
+impl Budgeted {
+    pub fn new(category: BudgetCategory, priority: BudgetPriority) -> Self {
+        return Self { category, priority };
+    }
+}
+
+/// Most entities of each category alive at once
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct EntityBudget {
+    pub projectiles: usize,
+    pub particles: usize,
+    pub effects: usize,
+    /// Damage numbers aren't entities, but a horde's worth of them is capped
+    /// here all the same, the oldest make way past it
+    pub damage_numbers: usize,
+}
+
+impl Default for EntityBudget {
+    fn default() -> Self {
+        Self {
+            projectiles: 600,
+            particles: 800,
+            effects: 100,
+            damage_numbers: 512,
+        }
+    }
+}
+
+impl EntityBudget {
+    pub fn limit(&self, category: BudgetCategory) -> usize {
+        match category {
+            BudgetCategory::Projectile => return self.projectiles,
+            BudgetCategory::Particle => return self.particles,
+            BudgetCategory::Effect => return self.effects,
+        }
+    }
+}
+
+fn enforce_entity_budgets(
+    mut commands: Commands,
+    budget: Res<EntityBudget>,
+    budgeted: Query<(Entity, Ref<Budgeted>)>,
+    ticks: SystemChangeTick,
+) {
+    let mut alive: HashMap<BudgetCategory, Vec<(BudgetPriority, Tick, Entity)>> = HashMap::new();
+    for (entity, budgeted) in budgeted.iter() {
+        alive.entry(budgeted.category).or_default().push((
+            budgeted.priority,
+            budgeted.added(),
+            entity,
+        ));
+    }
+
+    for (category, mut entities) in alive {
+        let limit = budget.limit(category);
+        if entities.len() <= limit {
+            continue;
+        }
+
+        // lowest priority first, then the ones added longest ago. Ticks wrap,
+        // so they're only compared relative to this run
+        let this_run = ticks.this_run();
+        entities.sort_unstable_by(|(a_priority, a_added, _), (b_priority, b_added, _)| {
+            let age = if a_added.is_newer_than(*b_added, this_run) {
+                Ordering::Greater
+            } else if b_added.is_newer_than(*a_added, this_run) {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            };
+            return a_priority.cmp(b_priority).then(age);
+        });
+        let over = entities.len() - limit;
+        for (_, _, entity) in entities.into_iter().take(over) {
+            commands.entity(entity).release();
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::balance::BalanceConfig;
+use crate::menu::{button, highlight_buttons};
+use crate::run_config::{RunConfig, RunModifier};
+use crate::save::{DEFAULT_CHARACTER, SaveData};
+use crate::stats::{ModifierOp, Stat, StatModifier, Stats};
+use crate::transition::TransitionTo;
+use crate::unlocks::Unlock;
+use crate::weapons::WeaponKind;
+
+const LOCKED_COLOR: Color = Color::srgb(0.45, 0.45, 0.45);
+
+/// Every bun that can be played, the first is picked until another is
+pub const CHARACTERS: &[Character] = &[
+    Character {
+        name: DEFAULT_CHARACTER,
+        description: "A well-rounded bun",
+        move_speed: 1.0,
+        weapon: WeaponKind::CarrotBolt,
+        passive: "Well Fed",
+        modifiers: &[(Stat::MaxHealth, ModifierOp::Add(20.0))],
+        unlock: Unlock::Free,
+    },
+    Character {
+        name: "Hare",
+        description: "Fast on its feet but fragile",
+        move_speed: 1.2,
+        weapon: WeaponKind::Boomerang,
+        passive: "Long Legs",
+        modifiers: &[
+            (Stat::MaxStamina, ModifierOp::Add(25.0)),
+            (Stat::MaxHealth, ModifierOp::Add(-20.0)),
+        ],
+        unlock: Unlock::Gold {
+            id: "character.hare",
+            cost: 200,
+        },
+    },
+    Character {
+        name: "Lop",
+        description: "Slow and sturdy, wrapped in thorns",
+        move_speed: 0.85,
+        weapon: WeaponKind::ThornAura,
+        passive: "Thick Coat",
+        modifiers: &[(Stat::Armor, ModifierOp::Add(15.0))],
+        unlock: Unlock::Achievement("achievement.survivor"),
+    },
+    Character {
+        name: "Angora",
+        description: "Never fights alone",
+        move_speed: 1.0,
+        weapon: WeaponKind::Pet,
+        passive: "Fluffy",
+        modifiers: &[(Stat::PickupRadius, ModifierOp::Add(1.0))],
+        unlock: Unlock::Achievement("achievement.slayer_slain"),
+    },
+];
+
+pub struct CharactersPlugin;
+
+impl Plugin for CharactersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::CharacterSelect), spawn_character_select);
+        app.add_systems(
+            Update,
+            (
+                character_select_buttons,
+                highlight_buttons,
+                update_unlock_labels,
+                update_modifier_labels,
+            )
+                .run_if(in_state(GameState::CharacterSelect)),
+        );
+    }
+}
+
+/// A playable bun and what it starts a run with
+#[derive(Debug)]
+pub struct Character {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Multiplier on the player move speed from the balance config
+    pub move_speed: f32,
+    pub weapon: WeaponKind,
+    /// Name of its passive, which its modifiers are granted under
+    pub passive: &'static str,
+    pub modifiers: &'static [(Stat, ModifierOp)],
+    pub unlock: Unlock,
+}
+
+impl Character {
+    /// The stats a player starts a run with as this character
+    pub fn stats(&self, balance: &BalanceConfig) -> Stats {
+        let mut stats = Stats::player(balance);
+        stats.set_base(Stat::MoveSpeed, balance.player_move_speed * self.move_speed);
+        for &(stat, op) in self.modifiers {
+            stats.add_modifier(StatModifier {
+                source: self.passive.into(),
+                stat,
+                op,
+            });
+        }
+        return stats;
+    }
+}
+
+#[derive(Component)]
+enum CharacterSelectButton {
+    /// Index into `CHARACTERS`, bought first if it's a locked gold unlock
+    Pick(usize),
+    /// Buy a locked gold unlock weapon
+    Weapon(WeaponKind),
+    /// Toggle the run modifier at this index into `RunModifier::ALL`
+    Modifier(usize),
+    Back,
+}
+
+/// Tells what's locked and how to unlock it, kept up to date as gold is
+/// spent and unlocks bought
+#[derive(Component)]
+enum UnlockLabel {
+    Gold,
+    Character(usize),
+    Weapon(WeaponKind),
+}
+
+#[derive(Component)]
+struct ModifierLabel(usize);
+
+fn spawn_character_select(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Name::new("Character Select"),
+            StateScoped(GameState::CharacterSelect),
+        ))
+        .with_children(|menu| {
+            menu.spawn((Text::new("Choose Your Bun"), TextFont::from_font_size(48.0)));
+            menu.spawn((
+                Text::new(""),
+                TextFont::from_font_size(24.0),
+                TextColor(Color::srgb(1.0, 0.85, 0.1)),
+                UnlockLabel::Gold,
+            ));
+            for i in 0..CHARACTERS.len() {
+                menu.spawn((
+                    button(),
+                    CharacterSelectButton::Pick(i),
+                    children![(
+                        Text::new(""),
+                        TextFont::from_font_size(18.0),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        UnlockLabel::Character(i)
+                    )],
+                ));
+            }
+
+            menu.spawn((Text::new("Weapons"), TextFont::from_font_size(28.0)));
+            for kind in WeaponKind::ALL {
+                if kind.unlock() == Unlock::Free {
+                    continue;
+                }
+                menu.spawn((
+                    button(),
+                    CharacterSelectButton::Weapon(kind),
+                    children![(
+                        Text::new(""),
+                        TextFont::from_font_size(18.0),
+                        UnlockLabel::Weapon(kind)
+                    )],
+                ));
+            }
+
+            menu.spawn((Text::new("Modifiers"), TextFont::from_font_size(28.0)));
+            for i in 0..RunModifier::ALL.len() {
+                menu.spawn((
+                    button(),
+                    CharacterSelectButton::Modifier(i),
+                    children![(
+                        Text::new(""),
+                        TextFont::from_font_size(18.0),
+                        ModifierLabel(i)
+                    )],
+                ));
+            }
+            menu.spawn((
+                button(),
+                CharacterSelectButton::Back,
+                children![(Text::new("Back"), TextFont::from_font_size(20.0))],
+            ));
+        });
+}
+
+/// Enter starts with whoever was picked last time
+fn character_select_buttons(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Query<(&Interaction, &CharacterSelectButton), Changed<Interaction>>,
+    mut config: ResMut<RunConfig>,
+    mut save: ResMut<SaveData>,
+    mut transition: EventWriter<TransitionTo>,
+) {
+    if keys.just_pressed(KeyCode::Enter) {
+        transition.write(TransitionTo(GameState::InGame));
+    } else if keys.just_pressed(KeyCode::Escape) {
+        transition.write(TransitionTo(GameState::MainMenu));
+    }
+
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            CharacterSelectButton::Pick(i) => {
+                // buying one takes its own press, starting needs another
+                let unlock = CHARACTERS[*i].unlock;
+                if unlock.unlocked(&save) {
+                    config.character = *i;
+                    transition.write(TransitionTo(GameState::InGame));
+                } else {
+                    unlock.try_buy(&mut save);
+                }
+            }
+            CharacterSelectButton::Weapon(kind) => {
+                kind.unlock().try_buy(&mut save);
+            }
+            CharacterSelectButton::Modifier(i) => {
+                config.toggle(RunModifier::ALL[*i]);
+            }
+            CharacterSelectButton::Back => {
+                transition.write(TransitionTo(GameState::MainMenu));
+            }
+        }
+    }
+}
+
+fn update_unlock_labels(
+    save: Res<SaveData>,
+    mut labels: Query<(&mut Text, &mut TextColor, &UnlockLabel)>,
+    added: Query<(), Added<UnlockLabel>>,
+) {
+    if !save.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for (mut text, mut color, label) in labels.iter_mut() {
+        let (unlock, details) = match *label {
+            UnlockLabel::Gold => {
+                text.0 = format!("Gold: {}", save.gold);
+                continue;
+            }
+            UnlockLabel::Character(i) => {
+                let character = &CHARACTERS[i];
+                let details = format!(
+                    "{}\n{}\n{:.0}% speed, {}, {}",
+                    character.name,
+                    character.description,
+                    character.move_speed * 100.0,
+                    character.weapon.name(),
+                    character.passive
+                );
+                (character.unlock, details)
+            }
+            UnlockLabel::Weapon(kind) => (kind.unlock(), kind.name().to_string()),
+        };
+
+        if unlock.unlocked(&save) {
+            text.0 = details;
+            color.0 = Color::WHITE;
+        } else {
+            text.0 = format!("{details}\nLocked - {}", unlock.condition());
+            color.0 = LOCKED_COLOR;
+        }
+    }
+}
+
+fn update_modifier_labels(
+    config: Res<RunConfig>,
+    mut labels: Query<(&mut Text, &ModifierLabel)>,
+    added: Query<(), Added<ModifierLabel>>,
+) {
+    if !config.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for (mut text, label) in labels.iter_mut() {
+        let modifier = RunModifier::ALL[label.0];
+        let check = if config.has(modifier) { "x" } else { " " };
+        let bonus = (modifier.reward_multiplier() - 1.0) * 100.0;
+        text.0 = format!(
+            "[{check}] {} - {} (+{bonus:.0}% gold/xp)",
+            modifier.name(),
+            modifier.description()
+        );
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::animation::AtlasRegistry;
+use crate::boss::Boss;
+use crate::combat::{DeathEvent, apply_damage};
+use crate::elites::Elite;
+use crate::loading::GameAssets;
+use crate::pickups::{Pickup, PickupAssets, PickupCollected, PickupKind, Rarity, pickup_bundle};
+use crate::rng::GameRng;
+use crate::save::SaveData;
+use crate::stats::Stats;
+use crate::upgrades::{
+    CHOICE_KEYS, ICON_SCALE, LevelUpChoice, PendingLevelUps, UpgradeChosen, UpgradePool,
+};
+use crate::weapons::Weapons;
+use crate::{GameState, GameplaySet, Player, PlayerSlot};
+
+/// Upgrades a chest can hold, with the relative chance of each
+const CHEST_SIZES: [(u32, f32); 3] = [(1, 75.0), (3, 20.0), (5, 5.0)];
+/// Bosses never drop the smallest chests
+const BOSS_MIN_CHEST_SIZE: u32 = 3;
+/// How long the first reel spins, each one after stops a little later
+const REEL_SPIN_SECONDS: f32 = 1.0;
+const REEL_STAGGER_SECONDS: f32 = 0.4;
+/// How long each icon shows while a reel spins
+const REEL_TICK_SECONDS: f32 = 0.06;
+
+pub struct ChestsPlugin;
+
+impl Plugin for ChestsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingChests>();
+        app.add_systems(OnEnter(GameState::InGame), reset_pending_chests);
+        app.add_systems(
+            Update,
+            (
+                (drop_chests.after(apply_damage), queue_chests),
+                open_chest.run_if(chest_waiting),
+                spin_reels,
+                close_chest,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Chests picked up but not opened yet, by who picked them up and how many
+/// upgrades they hold, oldest first
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct PendingChests(pub VecDeque<(Entity, u32)>);
+
+/// The reward screen of an opened chest, the game is paused while it's open
+///
+/// Rewards are rolled and granted as the chest opens, the reels only reveal
+/// them
+#[derive(Component)]
+pub(crate) struct ChestMenu {
+    /// Real time the chest opened at, reels spin on real time since the
+    /// game is paused
+    opened: f32,
+    /// Icons the reels flick through while spinning
+    spin_icons: Vec<usize>,
+}
+
+#[derive(Component)]
+struct ChestReel {
+    /// Seconds after opening this reel stops
+    stops_at: f32,
+    reward: LevelUpChoice,
+    stopped: bool,
+}
+
+#[derive(Component)]
+struct ReelIcon;
+
+#[derive(Component)]
+struct ReelLabel;
+
+#[derive(Component)]
+struct ContinueButton;
+
+fn reset_pending_chests(mut pending: ResMut<PendingChests>) {
+    pending.clear();
+}
+
+/// Elites and bosses leave a chest behind
+fn drop_chests(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut deaths: EventReader<DeathEvent>,
+    dropping: Query<(&Transform, Has<Boss>), Or<(With<Elite>, With<Boss>)>>,
+    assets: Res<PickupAssets>,
+) {
+    for death in deaths.read() {
+        let Ok((transform, boss)) = dropping.get(death.entity) else {
+            continue;
+        };
+
+        let sizes: Vec<(u32, f32)> = CHEST_SIZES
+            .into_iter()
+            .filter(|(size, _)| !boss || *size >= BOSS_MIN_CHEST_SIZE)
+            .collect();
+        let Ok(&(size, _)) = sizes.choose_weighted(&mut **rng, |(_, weight)| *weight) else {
+            continue;
+        };
+
+        commands.spawn(pickup_bundle(
+            &assets,
+            Pickup {
+                kind: PickupKind::Chest(size),
+                rarity: Rarity::Rare,
+            },
+            transform.translation.with_y(0.5) + Vec3::Z,
+        ));
+    }
+}
+
+fn queue_chests(mut collected: EventReader<PickupCollected>, mut pending: ResMut<PendingChests>) {
+    for event in collected.read() {
+        if let PickupKind::Chest(size) = event.pickup.kind {
+            pending.push_back((event.collector, size));
+        }
+    }
+}
+
+/// Chests wait for level ups, so only one menu holds the pause
+fn chest_waiting(
+    pending: Res<PendingChests>,
+    level_ups: Res<PendingLevelUps>,
+    menus: Query<(), With<ChestMenu>>,
+) -> bool {
+    return !pending.is_empty() && level_ups.is_empty() && menus.is_empty();
+}
+
+/// Roll one reward like a level up would, favouring weapons: an evolution if
+/// one is ready, otherwise a weapon that can still level, otherwise anything
+fn roll_reward(
+    pool: &UpgradePool,
+    stats: &Stats,
+    weapons: &Weapons,
+    save: &SaveData,
+    rng: &mut impl Rng,
+) -> Option<LevelUpChoice> {
+    if let Some(evolution) = weapons.available_evolutions(stats).next() {
+        return Some(LevelUpChoice::Evolution(evolution));
+    }
+
+    let weapon_upgrades: Vec<_> = pool
+        .upgrades
+        .iter()
+        .filter(|upgrade| upgrade.weapon.is_some() && pool.can_offer(upgrade, stats, weapons, save))
+        .collect();
+    let candidates = match weapon_upgrades.is_empty() {
+        true => pool
+            .upgrades
+            .iter()
+            .filter(|upgrade| upgrade.is_passive() && pool.can_offer(upgrade, stats, weapons, save))
+            .collect(),
+        false => weapon_upgrades,
+    };
+
+    let upgrade = candidates
+        .choose_weighted(rng, |upgrade| upgrade.rarity.weight())
+        .ok()?;
+    return Some(LevelUpChoice::Upgrade((*upgrade).clone()));
+}
+
+fn open_chest(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    (mut time, real): (ResMut<Time<Virtual>>, Res<Time<Real>>),
+    (assets, atlases, pools, save): (
+        Res<GameAssets>,
+        Res<AtlasRegistry>,
+        Res<Assets<UpgradePool>>,
+        Res<SaveData>,
+    ),
+    mut pending: ResMut<PendingChests>,
+    mut players: Query<(&mut Stats, &mut Weapons, &PlayerSlot), With<Player>>,
+) {
+    let (Some(pool), Some(&(player, size))) = (pools.get(&assets.upgrades), pending.front()) else {
+        return;
+    };
+    let player_count = players.iter().len();
+    let Ok((mut stats, mut weapons, slot)) = players.get_mut(player) else {
+        // died before getting to open it
+        pending.pop_front();
+        return;
+    };
+
+    // each reward is granted before the next is rolled, so a weapon can
+    // level more than once, or reach max level and then evolve
+    let mut rewards = Vec::new();
+    for _ in 0..size {
+        let Some(reward) = roll_reward(pool, &stats, &weapons, &save, &mut **rng) else {
+            break;
+        };
+        reward.apply(&mut stats, &mut weapons);
+        rewards.push(reward);
+    }
+
+    let title = match player_count {
+        1 => "Treasure!".to_string(),
+        _ => format!("Player {} Treasure!", **slot + 1),
+    };
+
+    time.pause();
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            Name::new("Chest Menu"),
+            ChestMenu {
+                opened: real.elapsed_secs(),
+                spin_icons: pool.upgrades.iter().map(|upgrade| upgrade.icon).collect(),
+            },
+        ))
+        .with_children(|menu| {
+            menu.spawn((Text::new(title), TextFont::from_font_size(40.0)));
+
+            menu.spawn(Node {
+                column_gap: Val::Px(12.0),
+                ..default()
+            })
+            .with_children(|reels| {
+                for (i, reward) in rewards.into_iter().enumerate() {
+                    reels
+                        .spawn((
+                            Node {
+                                width: Val::Px(140.0),
+                                padding: UiRect::all(Val::Px(8.0)),
+                                row_gap: Val::Px(8.0),
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                border: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BorderColor(Color::srgb(0.4, 0.4, 0.4)),
+                            BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.9)),
+                            ChestReel {
+                                stops_at: REEL_SPIN_SECONDS + REEL_STAGGER_SECONDS * i as f32,
+                                reward,
+                                stopped: false,
+                            },
+                        ))
+                        .with_children(|reel| {
+                            reel.spawn((
+                                ImageNode::from_atlas_image(
+                                    assets.spritesheet.clone(),
+                                    TextureAtlas {
+                                        layout: atlases.layout(ICON_SCALE),
+                                        index: 0,
+                                    },
+                                ),
+                                Node {
+                                    width: Val::Px(64.0),
+                                    height: Val::Px(64.0),
+                                    ..default()
+                                },
+                                ReelIcon,
+                            ));
+                            reel.spawn((
+                                Text::default(),
+                                TextFont::from_font_size(14.0),
+                                ReelLabel,
+                            ));
+                        });
+                }
+            });
+
+            menu.spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.9)),
+                ContinueButton,
+            ))
+            .with_child((Text::new("1. Continue"), TextFont::from_font_size(20.0)));
+        });
+}
+
+/// Flick through icons until each reel's time is up, then land on its reward
+fn spin_reels(
+    real: Res<Time<Real>>,
+    menus: Query<&ChestMenu>,
+    mut reels: Query<(Entity, &mut ChestReel, &mut BorderColor, &ChildOf)>,
+    mut icons: Query<(&mut ImageNode, &ChildOf), With<ReelIcon>>,
+    mut labels: Query<(&mut Text, &mut TextColor, &ChildOf), With<ReelLabel>>,
+    parents: Query<&ChildOf>,
+) {
+    for (reel_entity, mut reel, mut border, row) in reels.iter_mut() {
+        if reel.stopped {
+            continue;
+        }
+        let Ok(menu) = parents
+            .get(row.parent())
+            .and_then(|menu| menus.get(menu.parent()))
+        else {
+            continue;
+        };
+
+        let elapsed = real.elapsed_secs() - menu.opened;
+        let stopped = elapsed >= reel.stops_at;
+        let icon = match stopped {
+            true => reel.reward.icon(),
+            false => {
+                // reels start out of step so they don't all show the same icon
+                let tick = (elapsed / REEL_TICK_SECONDS) as usize + reel_entity.index() as usize;
+                menu.spin_icons
+                    .get(tick % menu.spin_icons.len().max(1))
+                    .copied()
+                    .unwrap_or(0)
+            }
+        };
+
+        for (mut image, parent) in icons.iter_mut() {
+            if parent.parent() == reel_entity {
+                if let Some(atlas) = image.texture_atlas.as_mut() {
+                    atlas.index = icon;
+                }
+            }
+        }
+
+        if stopped {
+            reel.stopped = true;
+            *border = BorderColor(reel.reward.color());
+            for (mut text, mut color, parent) in labels.iter_mut() {
+                if parent.parent() == reel_entity {
+                    **text = reel.reward.label();
+                    *color = TextColor(reel.reward.color());
+                }
+            }
+        }
+    }
+}
+
+/// Rewards were granted on opening, so closing early just skips the reels
+fn close_chest(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    mut pending: ResMut<PendingChests>,
+    level_ups: Res<PendingLevelUps>,
+    mut chosen: EventWriter<UpgradeChosen>,
+    (keys, buttons): (
+        Res<ButtonInput<KeyCode>>,
+        Query<&Interaction, (Changed<Interaction>, With<ContinueButton>)>,
+    ),
+    menus: Query<Entity, With<ChestMenu>>,
+) {
+    let Ok(menu) = menus.single() else {
+        return;
+    };
+
+    let clicked = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if !clicked && !keys.just_pressed(CHOICE_KEYS[0]) {
+        return;
+    }
+    // recorded like a level up choice, so replays close it on the same frame
+    chosen.write(UpgradeChosen { index: 0 });
+
+    commands.entity(menu).despawn();
+    pending.pop_front();
+    if pending.is_empty() && level_ups.is_empty() {
+        time.unpause();
+    }
+}
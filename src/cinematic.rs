@@ -0,0 +1,210 @@
+//! Short scripted camera moves with time slowed, played as a list of shots
+//! one after another
+//!
+//! While one plays `camera_lock` is suspended and the shots move the camera
+//! instead. Shots are timed on real time, so they last as long however slow
+//! they play the game. Boss deaths get a kill cam, a freeze then a slow orbit
+//! around where the boss fell
+
+use std::collections::VecDeque;
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::math::cubic_splines::{CubicCardinalSpline, CubicCurve, CubicGenerator};
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::boss::Boss;
+use crate::combat::{DeathEvent, apply_damage};
+use crate::controls::DetachedCamera;
+use crate::interpolation::interpolate_translation;
+use crate::time_scale::{MAX_TIME_SCALE, MIN_TIME_SCALE, TimeScale};
+use crate::{GameSet, GameState, GameplaySet, MainCamera};
+
+/// How long the game all but stops the moment a boss dies
+const KILL_FREEZE_SECONDS: f32 = 0.4;
+const KILL_FREEZE_SPEED: f32 = 0.02;
+/// How long the camera takes to swing round the boss
+const KILL_ORBIT_SECONDS: f32 = 2.0;
+const KILL_ORBIT_SPEED: f32 = 0.2;
+const KILL_ORBIT_RADIUS: f32 = 14.0;
+/// The orbit starts this high and swoops down to `KILL_ORBIT_LOW`
+const KILL_ORBIT_HIGH: f32 = 9.0;
+const KILL_ORBIT_LOW: f32 = 3.0;
+/// Points the orbit's spline goes through, an eighth of a turn apart
+const KILL_ORBIT_POINTS: usize = 5;
+
+pub struct CinematicPlugin;
+
+impl Plugin for CinematicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Cinematic>();
+        app.add_systems(
+            Update,
+            start_boss_kill_cams.after(apply_damage).in_set(GameplaySet),
+        );
+        app.add_systems(
+            PostUpdate,
+            play_cinematic
+                .after(interpolate_translation)
+                .before(TransformSystem::TransformPropagate)
+                .in_set(GameSet::Camera),
+        );
+        app.add_systems(OnExit(GameState::InGame), stop_cinematic);
+    }
+}
+
+/// Where the camera is while a shot plays
+#[derive(Debug, Clone)]
+pub enum ShotCamera {
+    /// Left wherever it was
+    Hold,
+    /// Eased along `path` from start to end, facing `look_at` the whole way
+    Path {
+        path: CubicCurve<Vec3>,
+        look_at: Vec3,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Shot {
+    /// Real seconds it lasts
+    pub seconds: f32,
+    /// How fast the game runs meanwhile
+    pub time_speed: f32,
+    pub camera: ShotCamera,
+}
+
+/// The shots still to play, the first one playing now
+#[derive(Resource, Default, Debug)]
+pub struct Cinematic {
+    shots: VecDeque<Shot>,
+    /// Real seconds into the current shot
+    elapsed: f32,
+}
+
+impl Cinematic {
+    /// Queue `shots` up after whatever's already playing
+    pub fn play(&mut self, shots: impl IntoIterator<Item = Shot>) {
+        self.shots.extend(shots);
+    }
+
+    pub fn is_playing(&self) -> bool {
+        return !self.shots.is_empty();
+    }
+}
+
+/// Run condition for the camera systems a cinematic takes over from
+pub fn no_cinematic(cinematic: Res<Cinematic>) -> bool {
+    return !cinematic.is_playing();
+}
+
+/// Swinging down and round `center` from `start_angle`, half a turn in all
+fn kill_orbit(center: Vec3, start_angle: f32) -> Option<CubicCurve<Vec3>> {
+    let last = KILL_ORBIT_POINTS - 1;
+    // the spline only passes through the inner points, the outer two on
+    // each end just set which way it's heading
+    let points = (-1..=KILL_ORBIT_POINTS as i32).map(|i| {
+        let angle = start_angle + FRAC_PI_4 * i as f32;
+        let progress = (i as f32 / last as f32).clamp(0.0, 1.0);
+        let height = KILL_ORBIT_HIGH.lerp(KILL_ORBIT_LOW, progress);
+        let around = Vec2::from_angle(angle) * KILL_ORBIT_RADIUS;
+        return center + Vec3::new(around.x, height, around.y);
+    });
+    return CubicCardinalSpline::new_catmull_rom(points).to_curve().ok();
+}
+
+fn start_boss_kill_cams(
+    mut deaths: EventReader<DeathEvent>,
+    bosses: Query<&Transform, With<Boss>>,
+    cameras: Query<&Transform, With<MainCamera>>,
+    mut cinematic: ResMut<Cinematic>,
+) {
+    for death in deaths.read() {
+        let Ok(boss) = bosses.get(death.entity) else {
+            continue;
+        };
+        // two bosses dying together get the one kill cam
+        if cinematic.is_playing() {
+            continue;
+        }
+
+        let center = boss.translation;
+        // start the orbit from wherever the camera already is
+        let start_angle = cameras.single().map_or(0.0, |camera| {
+            let offset = (camera.translation - center).xz();
+            return offset.to_angle();
+        });
+
+        let mut shots = vec![Shot {
+            seconds: KILL_FREEZE_SECONDS,
+            time_speed: KILL_FREEZE_SPEED,
+            camera: ShotCamera::Hold,
+        }];
+        if let Some(path) = kill_orbit(center, start_angle) {
+            shots.push(Shot {
+                seconds: KILL_ORBIT_SECONDS,
+                time_speed: KILL_ORBIT_SPEED,
+                camera: ShotCamera::Path {
+                    path,
+                    look_at: center,
+                },
+            });
+        }
+        cinematic.play(shots);
+    }
+}
+
+/// Timed on real time, since virtual time is what the shots slow down
+fn play_cinematic(
+    real: Res<Time<Real>>,
+    scale: Res<TimeScale>,
+    mut cinematic: ResMut<Cinematic>,
+    mut time: ResMut<Time<Virtual>>,
+    mut cameras: Query<&mut Transform, (With<MainCamera>, Without<DetachedCamera>)>,
+) {
+    if !cinematic.is_playing() {
+        return;
+    }
+
+    let cinematic = &mut *cinematic;
+    cinematic.elapsed += real.delta_secs();
+    while let Some(shot) = cinematic.shots.front() {
+        if cinematic.elapsed < shot.seconds {
+            break;
+        }
+        cinematic.elapsed -= shot.seconds;
+        cinematic.shots.pop_front();
+    }
+
+    let Some(shot) = cinematic.shots.front() else {
+        // over, back to whatever speed the game was set to
+        cinematic.elapsed = 0.0;
+        time.set_relative_speed(scale.speed.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE));
+        return;
+    };
+    time.set_relative_speed(shot.time_speed);
+
+    let ShotCamera::Path { path, look_at } = &shot.camera else {
+        return;
+    };
+    let t = (cinematic.elapsed / shot.seconds).clamp(0.0, 1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+    let position = path.position(t * path.segments().len() as f32);
+    for mut camera in cameras.iter_mut() {
+        *camera = Transform::from_translation(position).looking_at(*look_at, Vec3::Y);
+    }
+}
+
+/// Leaving the run cuts any cinematic short, so it can't hold the next one
+/// in slow motion
+fn stop_cinematic(
+    scale: Res<TimeScale>,
+    mut cinematic: ResMut<Cinematic>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if !cinematic.is_playing() {
+        return;
+    }
+    *cinematic = Cinematic::default();
+    time.set_relative_speed(scale.speed.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE));
+}
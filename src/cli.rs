@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::{AppConfig, MAX_LOCAL_PLAYERS};
+
+pub const USAGE: &str = "\
+Usage: bunvivor [OPTIONS]
+
+Options:
+      --seed <SEED>           Seed gameplay randomness, for reproducing a run
+      --level <PATH>          Level file to load instead of the arena
+      --god-mode              The player never takes damage
+      --skip-menu             Start a run as soon as loading finishes
+      --players <N>           Local players, 1 or 2
+      --headless              Run without a window or renderer
+      --window-size <WxH>     Window size, e.g. 1920x1080
+      --record <PATH>         Record each run's input to a replay file
+      --replay <PATH>         Play a recorded run back
+      --leaderboard <URL>     Online leaderboard server, e.g. http://host:8080
+      --host [PORT]           Host an online co-op game, builds with `net` only
+      --join <ADDRESS>        Join an online co-op game, builds with `net` only
+  -h, --help                  Print this message
+";
+
+/// Startup choices that gameplay systems read, rather than the app builder
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LaunchOptions {
+    /// Seeds the first run, and fills in the main menu's seed field
+    pub seed: Option<u64>,
+    /// Asset path of the level to load instead of the default
+    pub level: Option<String>,
+    pub god_mode: bool,
+    /// Go straight from loading into a run
+    pub skip_menu: bool,
+    /// Local players to start with instead of one
+    pub players: Option<usize>,
+    /// Where to record a replay of each run
+    pub record: Option<PathBuf>,
+    /// Replay file to play back instead of taking input
+    pub replay: Option<PathBuf>,
+    /// Online leaderboard server to submit runs to and show scores from
+    pub leaderboard: Option<String>,
+    /// Host or join an online game
+    #[cfg(feature = "net")]
+    pub net: Option<crate::net::NetRole>,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("Unknown argument: {0}")]
+    UnknownArgument(String),
+    #[error("Missing value for {0}")]
+    MissingValue(&'static str),
+    #[error("Invalid value for {0}: {1}")]
+    InvalidValue(&'static str, String),
+    #[error("Help requested")]
+    Help,
+}
+
+impl AppConfig {
+    /// Build a config from command line arguments, not including the binary
+    /// name
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut config = Self::default();
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = next_value(&mut args, "--seed")?;
+                    let seed = value
+                        .parse()
+                        .map_err(|_| CliError::InvalidValue("--seed", value))?;
+                    config.launch.seed = Some(seed);
+                }
+                "--level" => config.launch.level = Some(next_value(&mut args, "--level")?),
+                "--god-mode" => config.launch.god_mode = true,
+                "--skip-menu" => config.launch.skip_menu = true,
+                "--players" => {
+                    let value = next_value(&mut args, "--players")?;
+                    let players = value
+                        .parse()
+                        .ok()
+                        .filter(|players| (1..=MAX_LOCAL_PLAYERS).contains(players))
+                        .ok_or(CliError::InvalidValue("--players", value))?;
+                    config.launch.players = Some(players);
+                }
+                "--headless" => {
+                    config = Self {
+                        launch: config.launch,
+                        ..Self::headless()
+                    };
+                }
+                "--window-size" => {
+                    let value = next_value(&mut args, "--window-size")?;
+                    config.resolution = parse_window_size(&value)
+                        .ok_or(CliError::InvalidValue("--window-size", value))?;
+                }
+                "--record" => {
+                    config.launch.record = Some(next_value(&mut args, "--record")?.into())
+                }
+                "--leaderboard" => {
+                    config.launch.leaderboard = Some(next_value(&mut args, "--leaderboard")?)
+                }
+                "--replay" => {
+                    config.launch.replay = Some(next_value(&mut args, "--replay")?.into())
+                }
+                #[cfg(feature = "net")]
+                "--host" => {
+                    let port = match args.peek().filter(|value| !value.starts_with('-')) {
+                        Some(_) => {
+                            let value = next_value(&mut args, "--host")?;
+                            value
+                                .parse()
+                                .map_err(|_| CliError::InvalidValue("--host", value))?
+                        }
+                        None => crate::net::DEFAULT_PORT,
+                    };
+                    config.launch.net = Some(crate::net::NetRole::Host { port });
+                }
+                #[cfg(feature = "net")]
+                "--join" => {
+                    let value = next_value(&mut args, "--join")?;
+                    let address = value
+                        .parse()
+                        .map_err(|_| CliError::InvalidValue("--join", value))?;
+                    config.launch.net = Some(crate::net::NetRole::Join { address });
+                }
+                "-h" | "--help" => return Err(CliError::Help),
+                _ => return Err(CliError::UnknownArgument(arg)),
+            }
+        }
+
+        return Ok(config);
+    }
+}
+
+fn next_value(
+    args: &mut impl Iterator<Item = String>,
+    flag: &'static str,
+) -> Result<String, CliError> {
+    return args.next().ok_or(CliError::MissingValue(flag));
+}
+
+/// Parses `WIDTHxHEIGHT`
+fn parse_window_size(value: &str) -> Option<Vec2> {
+    let (width, height) = value.split_once('x')?;
+    let size = Vec2::new(width.parse().ok()?, height.parse().ok()?);
+    return (size.x > 0.0 && size.y > 0.0).then_some(size);
+}
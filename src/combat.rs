@@ -0,0 +1,453 @@
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy_rapier3d::plugin::ReadRapierContext;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::accessibility::AccessibilitySettings;
+use crate::projectile::Projectile;
+use crate::revive::{REVIVE_HEALTH, REVIVE_INVULNERABILITY, Revived, Revives};
+use crate::rng::GameRng;
+use crate::toon::ToonMaterial;
+use crate::weapons::WeaponKind;
+use crate::{AuthoritySet, GameplaySet};
+
+/// Time between material swaps while an entity is flashing
+const FLASH_INTERVAL: f32 = 0.08;
+/// Armor that takes off half of incoming damage
+const ARMOR_SCALE: f32 = 100.0;
+/// Seconds between contact damage hits, unless given another interval
+pub const CONTACT_DAMAGE_INTERVAL: f32 = 0.5;
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Health>();
+        app.register_type::<Faction>();
+        app.register_type::<ContactDamage>();
+        app.register_type::<CritChance>();
+        app.register_type::<CritMultiplier>();
+        app.register_type::<Armor>();
+        app.register_type::<DamageTakenMultiplier>();
+        app.register_type::<DamageType>();
+        app.register_type::<Resistances>();
+        app.register_type::<DamageConversion>();
+        app.add_event::<DamageEvent>();
+        app.add_event::<DeathEvent>();
+        app.add_systems(PreStartup, setup_combat_assets);
+        app.add_systems(
+            Update,
+            (contact_damage, apply_damage, tick_invulnerability)
+                .chain()
+                .in_set(AuthoritySet),
+        );
+    }
+}
+
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        return self.current / self.max;
+    }
+
+    pub fn is_dead(&self) -> bool {
+        return self.current <= 0.0;
+    }
+}
+
+/// Which side an entity fights for, used to decide what attacks can hit it
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum Faction {
+    Player,
+    Enemy,
+}
+
+/// What a hit is made of, for `Resistances` to pick out
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Fire,
+    Arcane,
+    Poison,
+}
+
+impl DamageType {
+    pub const ALL: [DamageType; 4] = [
+        DamageType::Physical,
+        DamageType::Fire,
+        DamageType::Arcane,
+        DamageType::Poison,
+    ];
+}
+
+/// Systems reading this after `apply_damage` see the damage actually dealt,
+/// zero if it was ignored, and whether it crit
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    pub source: Option<Entity>,
+    /// Set by `apply_damage`, writers should leave it `false`
+    pub crit: bool,
+    /// The player weapon dealing the damage, if any
+    pub weapon: Option<WeaponKind>,
+    /// Changed by `apply_damage` to whatever most of the hit was converted
+    /// into, see `DamageConversion`
+    pub kind: DamageType,
+}
+
+/// Chance, from 0 to 1, for this entity's hits to crit
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct CritChance(pub f32);
+
+/// Damage multiplier applied to this entity's crits
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct CritMultiplier(pub f32);
+
+/// Mitigates damage taken by `armor / (armor + ARMOR_SCALE)`, so every point
+/// is worth a little less than the last and it never reaches full immunity
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Armor(pub f32);
+
+impl Armor {
+    /// Fraction of incoming damage taken off, from 0 up towards 1
+    pub fn reduction(&self) -> f32 {
+        let armor = self.0.max(0.0);
+        return armor / (armor + ARMOR_SCALE);
+    }
+}
+
+/// Scales all damage the entity takes, before armor
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct DamageTakenMultiplier(pub f32);
+
+/// Fraction of each type of damage taken off, from 1 for immune down to
+/// negative for a vulnerability. Applied before armor
+#[derive(Component, Reflect, Deserialize, Default, Debug, Clone)]
+#[reflect(Component)]
+pub struct Resistances(pub HashMap<DamageType, f32>);
+
+impl Resistances {
+    pub fn with(mut self, kind: DamageType, resistance: f32) -> Self {
+        self.0.insert(kind, resistance);
+        return self;
+    }
+
+    pub fn get(&self, kind: DamageType) -> f32 {
+        return self.0.get(&kind).copied().unwrap_or(0.0);
+    }
+
+    /// Multiplier on `kind` damage taken, never below zero
+    pub fn taken(&self, kind: DamageType) -> f32 {
+        return (1.0 - self.get(kind)).max(0.0);
+    }
+}
+
+/// Fractions of the physical damage this entity deals that turn into other
+/// types instead, from upgrades. Past a total of 1 they're scaled down to fit
+#[derive(Component, Reflect, Default, Debug, Clone)]
+#[reflect(Component)]
+pub struct DamageConversion(pub Vec<(DamageType, f32)>);
+
+impl DamageConversion {
+    /// How a hit of `kind` splits between types, as fractions adding up to 1
+    pub fn split(&self, kind: DamageType) -> Vec<(DamageType, f32)> {
+        if kind != DamageType::Physical {
+            return vec![(kind, 1.0)];
+        }
+
+        let total: f32 = self.0.iter().map(|(_, fraction)| fraction.max(0.0)).sum();
+        let scale = if total > 1.0 { 1.0 / total } else { 1.0 };
+        let mut split: Vec<(DamageType, f32)> = self
+            .0
+            .iter()
+            .map(|(to, fraction)| (*to, fraction.max(0.0) * scale))
+            .collect();
+        split.push((DamageType::Physical, (1.0 - total).max(0.0)));
+        return split;
+    }
+}
+
+/// Damage dealt to anything of the other faction this body is touching, at
+/// most once every `interval` seconds however many it's touching
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct ContactDamage {
+    pub amount: f32,
+    pub interval: f32,
+    /// Seconds until it can hurt anything again
+    cooldown: f32,
+}
+
+impl ContactDamage {
+    pub fn new(amount: f32) -> Self {
+        return Self::with_interval(amount, CONTACT_DAMAGE_INTERVAL);
+    }
+
+    pub fn with_interval(amount: f32, interval: f32) -> Self {
+        Self {
+            amount,
+            interval,
+            cooldown: 0.0,
+        }
+    }
+}
+
+/// Grants `Invulnerable` for this many seconds whenever the entity takes damage
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct InvulnerableOnHit(pub f32);
+
+/// Ignores every incoming `DamageEvent`, for debugging
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct GodMode;
+
+/// Ignores incoming `DamageEvent`s and flashes until the timer runs out
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
+    flash: Timer,
+    flashing: bool,
+    original_material: Option<Handle<ToonMaterial>>,
+}
+
+impl Invulnerable {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+            flash: Timer::from_seconds(FLASH_INTERVAL, TimerMode::Repeating),
+            flashing: false,
+            original_material: None,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct CombatAssets {
+    flash_material: Handle<ToonMaterial>,
+}
+
+/// Sent once when an entity's `Health` reaches zero
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DeathEvent {
+    pub entity: Entity,
+}
+
+fn setup_combat_assets(mut commands: Commands, mut materials: ResMut<Assets<ToonMaterial>>) {
+    commands.insert_resource(CombatAssets {
+        // flat white all over, shadow and all
+        flash_material: materials.add(ToonMaterial {
+            shadow_color: Color::WHITE,
+            rim_strength: 0.0,
+            ..ToonMaterial::from(Color::WHITE)
+        }),
+    });
+}
+
+/// Targets that can't be hurt right now are skipped, so an attacker's
+/// cooldown isn't spent on a player who is still flashing from the last hit
+fn contact_damage(
+    time: Res<Time>,
+    rapier_context: ReadRapierContext,
+    mut attackers: Query<(Entity, &mut ContactDamage, &Faction)>,
+    targets: Query<&Faction, (With<Health>, Without<Invulnerable>, Without<GodMode>)>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+
+    for (attacker, mut contact, faction) in attackers.iter_mut() {
+        contact.cooldown = (contact.cooldown - time.delta_secs()).max(0.0);
+        if contact.cooldown > 0.0 {
+            continue;
+        }
+
+        for pair in context.contact_pairs_with(attacker) {
+            if !pair.has_any_active_contact() {
+                continue;
+            }
+
+            let (Some(a), Some(b)) = (pair.collider1(), pair.collider2()) else {
+                continue;
+            };
+            let other = if a == attacker { b } else { a };
+            if targets.get(other).is_ok_and(|other| other != faction) {
+                damage.write(DamageEvent {
+                    target: other,
+                    amount: contact.amount,
+                    source: Some(attacker),
+                    crit: false,
+                    weapon: None,
+                    kind: DamageType::Physical,
+                });
+                contact.cooldown = contact.interval;
+                break;
+            }
+        }
+    }
+}
+
+/// Whoever is behind `event`, the owner of a projectile rather than the
+/// projectile itself
+pub fn attacker(event: &DamageEvent, projectiles: &Query<&Projectile>) -> Option<Entity> {
+    return event
+        .source
+        .and_then(|source| match projectiles.get(source) {
+            Ok(projectile) => projectile.owner,
+            Err(_) => Some(source),
+        });
+}
+
+pub fn apply_damage(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut damage: EventMutator<DamageEvent>,
+    mut query: Query<
+        (
+            &mut Health,
+            Option<&InvulnerableOnHit>,
+            (
+                Option<&Armor>,
+                Option<&DamageTakenMultiplier>,
+                Option<&Resistances>,
+            ),
+            Option<&mut Revives>,
+        ),
+        (Without<Invulnerable>, Without<GodMode>),
+    >,
+    projectiles: Query<&Projectile>,
+    attackers: Query<(&CritChance, &CritMultiplier)>,
+    conversions: Query<&DamageConversion>,
+    (mut deaths, mut revived): (EventWriter<DeathEvent>, EventWriter<Revived>),
+) {
+    // `Invulnerable` isn't inserted until commands apply, so track this frame's hits by hand
+    let mut became_invulnerable = HashSet::new();
+    for event in damage.read() {
+        let target = query
+            .get_mut(event.target)
+            .ok()
+            .filter(|(health, _, _, _)| !health.is_dead())
+            .filter(|_| !became_invulnerable.contains(&event.target));
+        let Some((mut health, invulnerable_on_hit, (armor, multiplier, resistances), revives)) =
+            target
+        else {
+            event.amount = 0.0;
+            continue;
+        };
+
+        // projectiles crit with their owner's stats
+        let attacker = attacker(event, &projectiles);
+        if let Some((chance, multiplier)) = attacker.and_then(|a| attackers.get(a).ok()) {
+            if rng.random_bool(chance.clamp(0.0, 1.0) as f64) {
+                event.amount *= **multiplier;
+                event.crit = true;
+            }
+        }
+
+        if let Some(multiplier) = multiplier {
+            event.amount *= **multiplier;
+        }
+        let split = match attacker.and_then(|a| conversions.get(a).ok()) {
+            Some(conversion) => conversion.split(event.kind),
+            None => vec![(event.kind, 1.0)],
+        };
+        if let Some(resistances) = resistances {
+            event.amount *= split
+                .iter()
+                .map(|(kind, fraction)| fraction * resistances.taken(*kind))
+                .sum::<f32>();
+        }
+        if let Some(&(kind, _)) = split.iter().max_by(|a, b| a.1.total_cmp(&b.1)) {
+            event.kind = kind;
+        }
+        if let Some(armor) = armor {
+            event.amount *= 1.0 - armor.reduction();
+        }
+
+        health.current = (health.current - event.amount).max(0.0);
+        if health.is_dead() && revives.is_some_and(|mut revives| revives.try_use()) {
+            health.current = health.max * REVIVE_HEALTH;
+            commands
+                .entity(event.target)
+                .insert(Invulnerable::from_seconds(REVIVE_INVULNERABILITY));
+            became_invulnerable.insert(event.target);
+            revived.write(Revived {
+                entity: event.target,
+            });
+        } else if health.is_dead() {
+            deaths.write(DeathEvent {
+                entity: event.target,
+            });
+        } else if let (Some(seconds), Some(_)) = (invulnerable_on_hit, event.source) {
+            // sourceless damage, like burn ticks, doesn't grant invulnerability
+            commands
+                .entity(event.target)
+                .insert(Invulnerable::from_seconds(**seconds));
+            became_invulnerable.insert(event.target);
+        }
+    }
+}
+
+fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    (assets, accessibility): (Res<CombatAssets>, Res<AccessibilitySettings>),
+    mut query: Query<(
+        Entity,
+        &mut Invulnerable,
+        Option<&mut MeshMaterial3d<ToonMaterial>>,
+        Option<&mut Sprite>,
+    )>,
+) {
+    for (entity, mut invulnerable, material, sprite) in query.iter_mut() {
+        if let Some(material) = &material {
+            if invulnerable.original_material.is_none() {
+                invulnerable.original_material = Some(material.0.clone());
+            }
+        }
+
+        invulnerable.timer.tick(time.delta());
+        invulnerable.flash.tick(time.delta());
+
+        let flashing = if invulnerable.timer.finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            false
+        } else if invulnerable.flash.just_finished() {
+            !invulnerable.flashing && !accessibility.reduce_flashing
+        } else {
+            continue;
+        };
+        invulnerable.flashing = flashing;
+
+        if let Some(mut material) = material {
+            material.0 = match flashing {
+                true => assets.flash_material.clone(),
+                false => invulnerable.original_material.clone().unwrap_or_default(),
+            };
+        }
+
+        if let Some(mut sprite) = sprite {
+            sprite.color = sprite.color.with_alpha(if flashing { 0.3 } else { 1.0 });
+        }
+    }
+}
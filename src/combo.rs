@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+
+use crate::combat::{DeathEvent, apply_damage};
+use crate::{Enemy, GameState, GameplaySet};
+
+/// Seconds after a kill before the combo starts to drop
+const COMBO_WINDOW_SECONDS: f32 = 3.0;
+/// Once dropping, a quarter of the combo is lost this often
+const DECAY_SECONDS: f32 = 0.5;
+/// Kills needed for each experience multiplier, lowest first
+const TIERS: [(u32, f32); 4] = [(10, 1.25), (25, 1.5), (50, 2.0), (100, 3.0)];
+/// Smallest combo worth showing
+const MIN_SHOWN: u32 = 2;
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Combo>();
+        app.init_resource::<Combo>();
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            (reset_combo, spawn_combo_counter),
+        );
+        app.add_systems(
+            Update,
+            (
+                // while the dead are still enemies, corpses aren't
+                count_combo_kills.after(apply_damage),
+                decay_combo,
+                update_combo_counter,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Kills made in quick succession, shared by every player, multiplying the
+/// experience they pick up while it lasts
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct Combo {
+    pub count: u32,
+    /// Highest count reached this run
+    pub best: u32,
+    window: Timer,
+    decay: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        return Self {
+            count: 0,
+            best: 0,
+            window: Timer::from_seconds(COMBO_WINDOW_SECONDS, TimerMode::Once),
+            decay: Timer::from_seconds(DECAY_SECONDS, TimerMode::Repeating),
+        };
+    }
+}
+
+impl Combo {
+    /// Experience multiplier for the current count
+    pub fn multiplier(&self) -> f32 {
+        return TIERS
+            .iter()
+            .rev()
+            .find(|(kills, _)| self.count >= *kills)
+            .map_or(1.0, |(_, multiplier)| *multiplier);
+    }
+
+    fn add_kill(&mut self) {
+        self.count += 1;
+        self.best = self.best.max(self.count);
+        self.window.reset();
+        self.decay.reset();
+    }
+}
+
+#[derive(Component)]
+struct ComboCounter;
+
+fn reset_combo(mut combo: ResMut<Combo>) {
+    *combo = Combo::default();
+}
+
+fn spawn_combo_counter(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        TextFont::from_font_size(28.0),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Right),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(12.0),
+            right: Val::Px(24.0),
+            ..default()
+        },
+        Visibility::Hidden,
+        ComboCounter,
+        Name::new("Combo Counter"),
+        StateScoped(GameState::InGame),
+    ));
+}
+
+fn count_combo_kills(
+    mut deaths: EventReader<DeathEvent>,
+    enemies: Query<(), With<Enemy>>,
+    mut combo: ResMut<Combo>,
+) {
+    for death in deaths.read() {
+        if enemies.contains(death.entity) {
+            combo.add_kill();
+        }
+    }
+}
+
+/// Holds for the window after each kill, then bleeds away rather than
+/// dropping straight to nothing
+fn decay_combo(time: Res<Time>, mut combo: ResMut<Combo>) {
+    if combo.count == 0 || !combo.window.tick(time.delta()).finished() {
+        return;
+    }
+    if combo.decay.tick(time.delta()).just_finished() {
+        let lost = (combo.count / 4).max(1);
+        combo.count -= lost;
+    }
+}
+
+fn update_combo_counter(
+    combo: Res<Combo>,
+    mut counters: Query<(&mut Text, &mut TextColor, &mut Visibility), With<ComboCounter>>,
+) {
+    let Ok((mut text, mut color, mut visibility)) = counters.single_mut() else {
+        return;
+    };
+    if combo.count < MIN_SHOWN {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+
+    let multiplier = combo.multiplier();
+    let label = match multiplier > 1.0 {
+        true => format!("{} COMBO\nx{multiplier} XP", combo.count),
+        false => format!("{} COMBO", combo.count),
+    };
+    if text.0 != label {
+        text.0 = label;
+    }
+    // warmer the higher the tier, fading a little once it's dropping
+    let tier = TIERS
+        .iter()
+        .filter(|(kills, _)| combo.count >= *kills)
+        .count();
+    let heat = tier as f32 / TIERS.len() as f32;
+    let alpha = match combo.window.finished() {
+        true => 0.6,
+        false => 1.0,
+    };
+    color.0 = Color::srgba(1.0, 1.0 - heat * 0.6, 1.0 - heat, alpha);
+}
@@ -0,0 +1,412 @@
+use std::collections::BTreeMap;
+
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+use thiserror::Error;
+
+use crate::animation::AnimationLibrary;
+use crate::combat::{DamageEvent, DamageType, GodMode, Health};
+use crate::controls::Action;
+use crate::experience::{Experience, LevelUp};
+use crate::pool::Pool;
+use crate::prefab_library::{PrefabLibrary, spawn_prefab};
+use crate::spawner::spawn_ring_position;
+use crate::time_scale::{MAX_TIME_SCALE, MIN_TIME_SCALE, TimeScale};
+use crate::{Enemy, Player};
+
+const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+/// Lines of output kept on screen, older ones scroll off the top
+const HISTORY_LINES: usize = 12;
+const FONT_SIZE: f32 = 16.0;
+
+/// Runs a console command with the words typed after its name, returning
+/// what to print back
+pub type ConsoleCommand = fn(&mut World, &[&str]) -> Result<String, ConsoleError>;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleCommands>();
+        app.init_resource::<Console>();
+        app.add_systems(Startup, spawn_console);
+        app.add_systems(
+            Update,
+            (
+                toggle_console,
+                type_in_console,
+                run_console_commands,
+                update_console_text,
+            )
+                .chain(),
+        );
+
+        app.add_console_command("help", "help: list every command", help);
+        app.add_console_command(
+            "give_xp",
+            "give_xp <amount>: give every player experience",
+            give_xp,
+        );
+        app.add_console_command(
+            "prefab",
+            "prefab <name> [count]: spawn prefabs from assets/prefabs around the first player",
+            prefab,
+        );
+        app.add_console_command("god", "god: toggle god mode for every player", god);
+        app.add_console_command("killall", "killall: kill every enemy", killall);
+        app.add_console_command(
+            "timescale",
+            "timescale <speed>: set how fast the game runs",
+            timescale,
+        );
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ConsoleError {
+    #[error("Unknown command {0}, try help")]
+    UnknownCommand(String),
+    /// Shown along with the command's usage
+    #[error("Wrong arguments")]
+    BadArguments,
+    #[error("{0}")]
+    Failed(String),
+}
+
+struct RegisteredCommand {
+    usage: &'static str,
+    run: ConsoleCommand,
+}
+
+/// Every command the console knows, by name
+#[derive(Resource, Default)]
+pub struct ConsoleCommands(BTreeMap<&'static str, RegisteredCommand>);
+
+pub trait ConsoleCommandsExt {
+    /// Make `run` available in the console as `name`, `usage` is shown by
+    /// `help` and when it's used wrong
+    fn add_console_command(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        run: ConsoleCommand,
+    ) -> &mut Self;
+}
+
+impl ConsoleCommandsExt for App {
+    fn add_console_command(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        run: ConsoleCommand,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ConsoleCommands>()
+            .0
+            .insert(name, RegisteredCommand { usage, run });
+        return self;
+    }
+}
+
+#[derive(Resource, Default)]
+struct Console {
+    open: bool,
+    input: String,
+    /// Entered but not yet run, commands need the whole world so they're run
+    /// in their own system
+    submitted: Vec<String>,
+    output: Vec<String>,
+    /// Every command entered, for bringing back with the up arrow
+    entered: Vec<String>,
+    /// How far back through `entered` the up arrow has gone
+    recalled: usize,
+}
+
+impl Console {
+    fn print(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        let overflow = self.output.len().saturating_sub(HISTORY_LINES);
+        self.output.drain(..overflow);
+    }
+}
+
+#[derive(Component)]
+struct ConsoleWindow;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn spawn_console(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        // over every menu
+        GlobalZIndex(100),
+        Visibility::Hidden,
+        ConsoleWindow,
+        Name::new("Console"),
+        children![(
+            Text::default(),
+            TextFont::from_font_size(FONT_SIZE),
+            ConsoleText
+        )],
+    ));
+}
+
+/// Players stop taking input while the console is open, so typing doesn't
+/// also move and dash them
+fn toggle_console(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut console: ResMut<Console>,
+    mut window: Query<&mut Visibility, With<ConsoleWindow>>,
+    mut actions: Query<&mut ActionState<Action>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    console.open = !console.open;
+
+    for mut window in window.iter_mut() {
+        *window = match console.open {
+            true => Visibility::Visible,
+            false => Visibility::Hidden,
+        };
+    }
+    for mut action_state in actions.iter_mut() {
+        if console.open {
+            action_state.disable();
+        } else {
+            action_state.enable();
+        }
+    }
+}
+
+fn type_in_console(mut console: ResMut<Console>, mut keyboard: EventReader<KeyboardInput>) {
+    if !console.open {
+        keyboard.clear();
+        return;
+    }
+
+    for event in keyboard.read() {
+        if event.state != ButtonState::Pressed || event.key_code == TOGGLE_KEY {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                console.entered.push(line.clone());
+                console.recalled = 0;
+                console.submitted.push(line);
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::ArrowUp if console.recalled < console.entered.len() => {
+                console.recalled += 1;
+                let index = console.entered.len() - console.recalled;
+                console.input = console.entered[index].clone();
+            }
+            Key::Space => console.input.push(' '),
+            Key::Character(text) => console.input.push_str(text),
+            _ => {}
+        }
+    }
+}
+
+fn run_console_commands(world: &mut World) {
+    let submitted = std::mem::take(&mut world.resource_mut::<Console>().submitted);
+
+    for line in submitted {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, args)) = words.split_first() else {
+            continue;
+        };
+
+        let command = world
+            .resource::<ConsoleCommands>()
+            .0
+            .get(name)
+            .map(|command| (command.run, command.usage));
+        let result = match command {
+            Some((run, usage)) => run(world, args).map_err(|e| match e {
+                ConsoleError::BadArguments => format!("Usage: {usage}"),
+                e => e.to_string(),
+            }),
+            None => Err(ConsoleError::UnknownCommand(name.to_string()).to_string()),
+        };
+
+        let mut console = world.resource_mut::<Console>();
+        console.print(format!("> {line}"));
+        match result {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => console.print(output),
+            Err(e) => console.print(e),
+        }
+    }
+}
+
+fn update_console_text(console: Res<Console>, mut text: Query<&mut Text, With<ConsoleText>>) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut text in text.iter_mut() {
+        let mut lines = console.output.join("\n");
+        if !lines.is_empty() {
+            lines.push('\n');
+        }
+        text.0 = format!("{lines}> {}_", console.input);
+    }
+}
+
+fn players(world: &mut World) -> Vec<Entity> {
+    return world
+        .query_filtered::<Entity, With<Player>>()
+        .iter(world)
+        .collect();
+}
+
+fn help(world: &mut World, _args: &[&str]) -> Result<String, ConsoleError> {
+    let usages: Vec<&str> = world
+        .resource::<ConsoleCommands>()
+        .0
+        .values()
+        .map(|command| command.usage)
+        .collect();
+    return Ok(usages.join("\n"));
+}
+
+fn give_xp(world: &mut World, args: &[&str]) -> Result<String, ConsoleError> {
+    let Some(Ok(amount)) = args.first().map(|arg| arg.parse::<u32>()) else {
+        return Err(ConsoleError::BadArguments);
+    };
+
+    let players = players(world);
+    for &player in &players {
+        let Some(mut experience) = world.get_mut::<Experience>(player) else {
+            continue;
+        };
+        for level in experience.gain(amount) {
+            world.send_event(LevelUp {
+                entity: player,
+                level,
+            });
+        }
+    }
+    return Ok(format!(
+        "Gave {} players {amount} experience",
+        players.len()
+    ));
+}
+
+fn prefab(world: &mut World, args: &[&str]) -> Result<String, ConsoleError> {
+    let Some(name) = args.first() else {
+        let library = world.resource::<PrefabLibrary>();
+        let mut names: Vec<&str> = library.names().collect();
+        names.sort();
+        return Ok(format!("Prefabs: {}", names.join(", ")));
+    };
+    let count = match args.get(1) {
+        Some(count) => count.parse().map_err(|_| ConsoleError::BadArguments)?,
+        None => 1,
+    };
+
+    world
+        .run_system_cached_with(spawn_prefabs, (name.to_string(), count))
+        .map_err(|e| ConsoleError::Failed(e.to_string()))??;
+    return Ok(format!("Spawned {count} {name}"));
+}
+
+/// Spawned on their own rng, cheats throw replays off anyway
+fn spawn_prefabs(
+    In((name, count)): In<(String, usize)>,
+    mut commands: Commands,
+    mut pool: ResMut<Pool>,
+    library: Res<PrefabLibrary>,
+    animations: Res<Assets<AnimationLibrary>>,
+    players: Query<&Transform, With<Player>>,
+) -> Result<(), ConsoleError> {
+    let Some(player) = players.iter().next() else {
+        return Err(ConsoleError::Failed(
+            "No player to spawn around".to_string(),
+        ));
+    };
+    if library.get(&name).is_none() {
+        return Err(ConsoleError::Failed(format!("No prefab called {name}")));
+    }
+
+    let mut rng = rand::rng();
+    for _ in 0..count {
+        let position = spawn_ring_position(player.translation, &mut rng);
+        spawn_prefab(
+            &mut commands,
+            &mut pool,
+            (&library, &animations),
+            &name,
+            position,
+            1.0,
+            &mut rng,
+        );
+    }
+    return Ok(());
+}
+
+fn god(world: &mut World, _args: &[&str]) -> Result<String, ConsoleError> {
+    let players = players(world);
+    let enable = players
+        .iter()
+        .any(|&player| !world.entity(player).contains::<GodMode>());
+
+    for player in players {
+        let mut player = world.entity_mut(player);
+        if enable {
+            player.insert(GodMode);
+        } else {
+            player.remove::<GodMode>();
+        }
+    }
+    return Ok(format!("God mode {}", if enable { "on" } else { "off" }));
+}
+
+/// Enemies are killed with damage rather than despawned, so they still drop
+/// experience and count as kills
+fn killall(world: &mut World, _args: &[&str]) -> Result<String, ConsoleError> {
+    let enemies: Vec<(Entity, f32)> = world
+        .query_filtered::<(Entity, &Health), With<Enemy>>()
+        .iter(world)
+        .map(|(enemy, health)| (enemy, health.current))
+        .collect();
+
+    for &(enemy, health) in &enemies {
+        world.send_event(DamageEvent {
+            target: enemy,
+            amount: health,
+            source: None,
+            crit: false,
+            weapon: None,
+            kind: DamageType::Physical,
+        });
+    }
+    return Ok(format!("Killed {} enemies", enemies.len()));
+}
+
+fn timescale(world: &mut World, args: &[&str]) -> Result<String, ConsoleError> {
+    let Some(Ok(speed)) = args.first().map(|arg| arg.parse::<f32>()) else {
+        return Err(ConsoleError::BadArguments);
+    };
+
+    let speed = speed.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+    world.resource_mut::<TimeScale>().speed = speed;
+    return Ok(format!("Time scale {speed}x"));
+}
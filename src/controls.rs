@@ -1,23 +1,43 @@
-use core::f32::consts::PI;
+use core::f32::consts::{PI, TAU};
+use std::collections::VecDeque;
 
 use bevy::{
     math::ops::{cos, sin},
     prelude::*,
+    transform::TransformSystem,
+    window::PrimaryWindow,
 };
 use bevy_rapier3d::{
     pipeline::CollisionEvent,
     plugin::ReadRapierContext,
     prelude::{
-        Collider, ExternalForce, GravityScale, QueryFilter, RigidBody, ShapeCastOptions, Velocity,
+        Collider, CollisionGroups, ExternalForce, GravityScale, PhysicsSet, QueryFilter,
+        ReadMassProperties, RigidBody, ShapeCastOptions, Velocity,
     },
 };
-use leafwing_input_manager::{Actionlike, prelude::ActionState};
+use leafwing_input_manager::{
+    Actionlike,
+    prelude::{ActionState, InputMap},
+};
+use serde::{Deserialize, Serialize};
 
-use crate::{CameraDistance, CollidedGrounds, MoveVector, Player};
+use crate::balance::BalanceConfig;
+use crate::cinematic::no_cinematic;
+use crate::interpolation::interpolate_translation;
+use crate::math::{VecTools, damped_lerp};
+use crate::physics::layers;
+use crate::platforms::{Launched, SurfaceProperties};
+use crate::replay::ReplayPlayer;
+use crate::save::SaveData;
+use crate::stamina::{DASH_STAMINA, Stamina};
+use crate::upgrades::{choose_upgrade, no_level_up_menu};
+use crate::{
+    AirControl, CameraDistance, CameraLookAhead, CameraPullIn, CameraYaw, CollidedGrounds, GameSet,
+    GameplaySet, GroundContacts, GroundNormal, MoveVector, MovementProfile, Player, PlayerSlot,
+    SlopeSettings,
+};
+use crate::{AlignToGround, FaceMovement, IntendedRotation};
 use crate::{Ground, MoveSpeed};
-use crate::{IntendedRotation, VecTools};
-
-const CAMERA_ANGLE: f32 = 30_f32.to_radians();
 
 pub struct ControlsPlugin;
 
@@ -25,46 +45,316 @@ impl Plugin for ControlsPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<MoveVector>();
         app.register_type::<CollidedGrounds>();
+        app.register_type::<GroundContacts>();
+        app.register_type::<GroundNormal>();
+        app.register_type::<SlopeSettings>();
+        app.register_type::<AirControl>();
+        app.register_type::<MovementProfile>();
+        app.register_type::<FaceMovement>();
+        app.register_type::<AlignToGround>();
+        app.register_type::<AimDirection>();
+        app.register_type::<Dash>();
+        app.register_type::<InputBuffer>();
+        app.register_type::<CameraLookAhead>();
+        app.register_type::<CameraSettings>();
+        app.init_resource::<CameraSettings>();
+        app.add_systems(Startup, load_camera_settings);
+        app.add_systems(
+            Update,
+            save_camera_settings.run_if(resource_changed::<CameraSettings>),
+        );
         app.add_systems(
             Update,
             (
-                control_player,
-                camera_lock.after(control_player),
-                entities_try_to_move.after(control_player),
-                gravity_control,
+                assign_gamepads,
+                orbit_camera,
+                control_player.run_if(any_with_component::<Player>),
+                // the level up menu picks with the ability buttons
+                buffer_inputs
+                    .run_if(no_level_up_menu)
+                    .before(choose_upgrade),
+                start_dash,
+                // replays play back the recorded aim instead
+                aim_player
+                    .run_if(any_with_component::<Player>.and(not(resource_exists::<ReplayPlayer>))),
                 check_collided_grounds,
+                face_movement,
+                align_to_ground,
                 fix_rotation,
-            ),
+            )
+                .chain()
+                .in_set(GameSet::Control),
         );
+        // forces are applied per physics step so they behave the same at any
+        // framerate
+        app.add_systems(
+            FixedUpdate,
+            (
+                update_ground_normals,
+                (entities_try_to_move, gravity_control),
+            )
+                .chain()
+                .before(PhysicsSet::SyncBackend)
+                .in_set(GameplaySet),
+        );
+        // follow the player's interpolated position, not its physics one
+        app.add_systems(
+            PostUpdate,
+            camera_lock
+                .run_if(any_with_component::<Player>.and(no_cinematic))
+                .after(interpolate_translation)
+                .before(TransformSystem::TransformPropagate)
+                .in_set(GameSet::Camera),
+        );
+    }
+}
+
+/// Right stick pushes shorter than this don't aim
+const AIM_DEADZONE: f32 = 0.3;
+/// Holding this aims the first player's weapons at the cursor
+const AIM_MOUSE_BUTTON: MouseButton = MouseButton::Left;
+
+/// Where a player is aiming over the ground, if they are
+///
+/// Manual-aim weapons fire this way while it's set, and go back to picking
+/// their own targets when it isn't
+#[derive(Component, Reflect, Deref, DerefMut, Default, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct AimDirection(pub Option<Vec2>);
+
+/// Speed of a dash, far past anything movement reaches
+const DASH_SPEED: f32 = 70.0;
+const DASH_SECONDS: f32 = 0.15;
+const DASH_COOLDOWN: f32 = 1.0;
+
+/// How long an ability press is remembered while it can't be used yet
+const INPUT_BUFFER_SECONDS: f32 = 0.15;
+/// Actions whose presses are buffered, the rest are held rather than pressed
+const BUFFERED_ACTIONS: [Action; 3] = [Action::Dash, Action::Ability1, Action::Ability2];
+
+/// Recent ability presses and when they were made, so a press that comes a
+/// few frames too early, say just before a cooldown ends, still counts
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+pub struct InputBuffer(VecDeque<(Action, f32)>);
+
+impl InputBuffer {
+    /// Use up the oldest buffered press of `action`, if there is one
+    pub fn take(&mut self, action: Action) -> bool {
+        let Some(index) = self.0.iter().position(|(pressed, _)| *pressed == action) else {
+            return false;
+        };
+        self.0.remove(index);
+        return true;
+    }
+}
+
+/// A short burst of speed in the direction the player is moving
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Dash {
+    /// Seconds until the player can dash again
+    pub cooldown: f32,
+    /// Seconds left of the current dash
+    pub remaining: f32,
+    pub direction: Vec2,
+}
+
+impl Dash {
+    pub fn is_dashing(&self) -> bool {
+        return self.remaining > 0.0;
     }
 }
 
-#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
+#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect, Serialize, Deserialize)]
 pub(crate) enum Action {
     Left,
     Right,
     Up,
     Down,
+    Dash,
+    /// Held to run faster, using up stamina
+    Sprint,
+    /// Use the ability in the first or second slot
+    Ability1,
+    Ability2,
+    /// Swing the camera around the players
+    OrbitLeft,
+    OrbitRight,
+    /// Use whatever `Interactable` is closest
+    Interact,
+    /// Analog movement, from a stick
+    #[actionlike(DualAxis)]
+    Move,
+}
+
+impl Action {
+    /// Every button action, for copying input states around. `Move` is an
+    /// axis and has to be copied on its own
+    pub const ALL: [Action; 11] = [
+        Action::Left,
+        Action::Right,
+        Action::Up,
+        Action::Down,
+        Action::Dash,
+        Action::Sprint,
+        Action::Ability1,
+        Action::Ability2,
+        Action::OrbitLeft,
+        Action::OrbitRight,
+        Action::Interact,
+    ];
+}
+
+/// Give each player their own gamepad, in the order they were connected
+///
+/// Players without one don't listen to any gamepad, otherwise they would all
+/// follow the first
+pub fn assign_gamepads(
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut players: Query<(&PlayerSlot, &mut InputMap<Action>)>,
+) {
+    let mut gamepads: Vec<Entity> = gamepads.iter().collect();
+    gamepads.sort();
+
+    for (slot, mut input_map) in players.iter_mut() {
+        let gamepad = gamepads.get(**slot).copied().unwrap_or(Entity::PLACEHOLDER);
+        if input_map.gamepad() != Some(gamepad) {
+            input_map.set_gamepad(gamepad);
+        }
+    }
 }
 
 pub fn control_player(
-    mut query: Query<(&mut MoveVector, &MoveSpeed, &ActionState<Action>), With<Player>>,
+    mut players: Query<(&mut MoveVector, &MoveSpeed, &ActionState<Action>), With<Player>>,
     cam: Query<&Transform, With<Camera3d>>,
-    player: Query<&Transform, With<Player>>,
 ) {
-    let (mut move_vec, move_speed, action_state) = query.single_mut().unwrap();
-    **move_vec = Vec3::ZERO;
-    let cam = cam.single().unwrap();
-    let player = player.single().unwrap();
-
-    let forward = Vec3::new(
-        player.translation.x - cam.translation.x,
-        0.0,
-        player.translation.z - cam.translation.z,
-    )
-    .normalize();
+    // the level may not have spawned the camera yet
+    let Ok(cam) = cam.single() else {
+        return;
+    };
+
+    // every player moves relative to the shared camera
+    let forward = cam.forward().horizontal().normalize();
     let right = forward.cross(Vec3::Y);
 
+    for (mut move_vec, move_speed, action_state) in players.iter_mut() {
+        move_player(&mut move_vec, **move_speed, action_state, forward, right);
+    }
+}
+
+/// Aim with the right stick, or for the first player by holding the mouse
+/// button and pointing at the ground
+pub fn aim_player(
+    mut players: Query<(
+        &Transform,
+        &PlayerSlot,
+        &InputMap<Action>,
+        &mut AimDirection,
+    )>,
+    gamepads: Query<&Gamepad>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    cam: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+) {
+    let Ok((camera, cam_transform)) = cam.single() else {
+        return;
+    };
+
+    // the stick is relative to the camera, like movement
+    let forward = cam_transform.forward().horizontal().normalize().xz();
+    let right = Vec2::new(-forward.y, forward.x);
+    let cursor = window.single().ok().and_then(Window::cursor_position);
+
+    for (transform, slot, input_map, mut aim) in players.iter_mut() {
+        let stick = input_map
+            .gamepad()
+            .and_then(|gamepad| gamepads.get(gamepad).ok())
+            .map(Gamepad::right_stick)
+            .filter(|stick| stick.length() >= AIM_DEADZONE)
+            .map(|stick| (right * stick.x + forward * stick.y).normalize());
+
+        let pointer = cursor
+            .filter(|_| **slot == 0 && mouse.pressed(AIM_MOUSE_BUTTON))
+            .and_then(|cursor| camera.viewport_to_world(cam_transform, cursor).ok())
+            .and_then(|ray| {
+                let distance =
+                    ray.intersect_plane(transform.translation, InfinitePlane3d::new(Vec3::Y))?;
+                (ray.get_point(distance) - transform.translation)
+                    .xz()
+                    .try_normalize()
+            });
+
+        let direction = stick.or(pointer);
+        if **aim != direction {
+            **aim = direction;
+        }
+    }
+}
+
+/// Queue up new presses of `BUFFERED_ACTIONS` and forget stale ones
+pub fn buffer_inputs(
+    time: Res<Time>,
+    mut players: Query<(&ActionState<Action>, &mut InputBuffer)>,
+) {
+    let now = time.elapsed_secs();
+    for (action_state, mut buffer) in players.iter_mut() {
+        buffer
+            .0
+            .retain(|(_, pressed_at)| now - pressed_at <= INPUT_BUFFER_SECONDS);
+        for action in BUFFERED_ACTIONS {
+            if action_state.just_pressed(&action) {
+                buffer.0.push_back((action, now));
+            }
+        }
+    }
+}
+
+/// Dash the way the player is moving, standing still doesn't dash. Players
+/// with stamina need enough of it for the dash
+pub fn start_dash(
+    time: Res<Time>,
+    mut players: Query<
+        (
+            &mut Dash,
+            &mut InputBuffer,
+            &MoveVector,
+            Option<&mut Stamina>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (mut dash, mut buffer, move_vec, stamina) in players.iter_mut() {
+        dash.cooldown = (dash.cooldown - time.delta_secs()).max(0.0);
+        dash.remaining = (dash.remaining - time.delta_secs()).max(0.0);
+
+        let Some(direction) = move_vec.xz().try_normalize() else {
+            continue;
+        };
+        if dash.cooldown > 0.0 || !buffer.take(Action::Dash) {
+            continue;
+        }
+        if stamina.is_some_and(|mut stamina| !stamina.try_spend(DASH_STAMINA)) {
+            continue;
+        }
+
+        *dash = Dash {
+            cooldown: DASH_COOLDOWN,
+            remaining: DASH_SECONDS,
+            direction,
+        };
+    }
+}
+
+fn move_player(
+    move_vec: &mut MoveVector,
+    move_speed: f32,
+    action_state: &ActionState<Action>,
+    forward: Vec3,
+    right: Vec3,
+) {
+    **move_vec = Vec3::ZERO;
+
     // handle pressing buttons
     if action_state.pressed(&Action::Left) {
         **move_vec -= right;
@@ -103,13 +393,26 @@ pub fn control_player(
         **move_vec -= forward;
     }
 
-    **move_vec = move_vec.normalize_or(Vec3::ZERO) * **move_speed;
+    **move_vec = move_vec.normalize_or(Vec3::ZERO) * move_speed;
     // dbg!(move_vec);
+
+    // a stick pushed partway moves partway to full speed, buttons win if
+    // both are used at once
+    let stick = action_state
+        .clamped_axis_pair(&Action::Move)
+        .clamp_length_max(1.0);
+    if **move_vec == Vec3::ZERO && stick != Vec2::ZERO {
+        **move_vec = (right * stick.x + forward * stick.y).normalize_or(Vec3::ZERO)
+            * stick.length()
+            * move_speed;
+    }
 }
 
+/// Keeps `GroundContacts` up to date, which of them are actually stood on is
+/// left to `update_ground_normals`
 pub fn check_collided_grounds(
     ground: Query<Entity, With<Ground>>,
-    mut collidee: Query<(Entity, &mut CollidedGrounds), Without<Ground>>,
+    mut collidee: Query<(Entity, &mut GroundContacts), Without<Ground>>,
     mut collision_events: EventReader<CollisionEvent>,
 ) {
     for collision_event in collision_events.read() {
@@ -135,11 +438,87 @@ pub fn check_collided_grounds(
     }
 }
 
-pub fn gravity_control(mut query: Query<(&mut GravityScale, &CollidedGrounds)>) {
-    for (mut gs, cg) in query.iter_mut() {
-        if cg.is_empty() {
+/// Read the contact normals off the grounds found by `check_collided_grounds`,
+/// keeping those the entity is on top of as its `CollidedGrounds`. Launched
+/// entities count as in the air
+pub fn update_ground_normals(
+    rapier_context: ReadRapierContext,
+    mut query: Query<(
+        Entity,
+        &GroundContacts,
+        &mut CollidedGrounds,
+        &mut GroundNormal,
+        Has<Launched>,
+    )>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+
+    for (entity, contacts, mut grounds, mut normal, launched) in query.iter_mut() {
+        if launched {
+            grounds.clear();
+            normal.set_if_neq(GroundNormal(None));
+            continue;
+        }
+
+        let mut standing_on = Vec::new();
+        let mut sum = Vec3::ZERO;
+        for &ground in contacts.iter() {
+            let Some(pair) = context.contact_pair(entity, ground) else {
+                continue;
+            };
+            let mut under = false;
+            for manifold in pair.manifolds() {
+                // points from the first collider to the second, which could
+                // be either of them, turned to point out of the ground
+                let contact = manifold.normal();
+                let contact = if pair.collider1() == Some(entity) {
+                    -contact
+                } else {
+                    contact
+                };
+                if contact.angle_between(Vec3::Y) > MAX_GROUND_ANGLE.to_radians() {
+                    continue;
+                }
+                sum += contact;
+                under = true;
+            }
+            if under {
+                standing_on.push(ground);
+            }
+        }
+
+        if **grounds != standing_on {
+            **grounds = standing_on;
+        }
+        normal.set_if_neq(GroundNormal(sum.try_normalize()));
+    }
+}
+
+/// Contacts further than this from straight up, in degrees, are walls or
+/// ceilings rather than ground. Steep slopes short of it are still ground,
+/// just not walkable
+const MAX_GROUND_ANGLE: f32 = 80.0;
+
+/// Ground too steep to walk on doesn't hold the entity up either, so it
+/// slides down it, pushed by `entities_try_to_move` as well. Neither does the
+/// ground something was just launched off
+pub fn gravity_control(
+    balance: Res<BalanceConfig>,
+    mut query: Query<(
+        &mut GravityScale,
+        &CollidedGrounds,
+        &GroundNormal,
+        &SlopeSettings,
+        Has<Launched>,
+    )>,
+) {
+    for (mut gs, cg, normal, slope, launched) in query.iter_mut() {
+        let too_steep = normal.is_some_and(|normal| !slope.walkable(normal));
+        if cg.is_empty() || too_steep || launched {
             // object is not grounded, no collided grounds exist
-            gs.0 = 30.0;
+            gs.0 = balance.air_gravity;
         } else {
             // object is grounded, collided grounds exist
             gs.0 = 0.0;
@@ -147,39 +526,363 @@ pub fn gravity_control(mut query: Query<(&mut GravityScale, &CollidedGrounds)>)
     }
 }
 
-pub fn fix_rotation(mut query: Query<(&mut Transform, &IntendedRotation)>) {
-    for (mut t, r) in query.iter_mut() {
-        t.rotation = **r;
+/// Swing `IntendedRotation` round to face the way the entity is moving,
+/// turning only about the vertical. Standing still keeps the last facing
+pub fn face_movement(
+    time: Res<Time>,
+    mut query: Query<(&mut IntendedRotation, &MoveVector, &FaceMovement)>,
+) {
+    for (mut rotation, move_vec, face) in query.iter_mut() {
+        let Some(direction) = move_vec.horizontal().try_normalize() else {
+            continue;
+        };
+
+        // models face down -Z
+        let target = Quat::from_rotation_y(f32::atan2(-direction.x, -direction.z));
+        let turned = rotation.rotate_towards(target, **face * time.delta_secs());
+        rotation.set_if_neq(IntendedRotation(turned));
+    }
+}
+
+/// Ease the tilt towards the ground's normal, or back upright in the air
+pub fn align_to_ground(time: Res<Time>, mut query: Query<(&mut AlignToGround, &GroundNormal)>) {
+    for (mut align, normal) in query.iter_mut() {
+        let target = normal.map_or(Quat::IDENTITY, |normal| {
+            Quat::from_rotation_arc(Vec3::Y, normal)
+        });
+        align.tilt = damped_lerp(align.tilt, target, align.rate, time.delta_secs());
     }
 }
 
-pub fn entities_try_to_move(mut query: Query<(&mut ExternalForce, &Velocity, &MoveVector)>) {
-    for (mut force, vel, move_vec) in query.iter_mut() {
-        // velocity.linvel.max_mag(move_vec);
-        let new_force = calc_force_diff(1.0, vel.linvel.xz(), move_vec.xz());
-        force.force = Vec3::new(new_force.x, force.force.y, new_force.y);
+/// The facing from `IntendedRotation`, tilted over by `AlignToGround` if
+/// the entity has it
+pub fn fix_rotation(mut query: Query<(&mut Transform, &IntendedRotation, Option<&AlignToGround>)>) {
+    for (mut t, r, align) in query.iter_mut() {
+        t.rotation = match align {
+            Some(align) => align.tilt * **r,
+            None => **r,
+        };
     }
 }
 
+/// Slower than this, movement turns instantly no matter the turn rate
+const TURN_MIN_SPEED: f32 = 1.0;
+
+/// Entities standing on something moving, like a platform or a conveyor,
+/// move relative to it so they're carried along rather than sliding off.
+/// What they stand on can also make them slower or more slippery
+///
+/// How quickly they get up to speed, stop and turn comes from their
+/// `MovementProfile`
+pub fn entities_try_to_move(
+    time: Res<Time>,
+    (moving, surfaces): (Query<&Velocity, With<Ground>>, Query<&SurfaceProperties>),
+    mut query: Query<(
+        &mut ExternalForce,
+        &Velocity,
+        &MoveVector,
+        Option<&Dash>,
+        Option<&MoveSpeed>,
+        (
+            Option<&ReadMassProperties>,
+            Option<&MovementProfile>,
+            Option<&AirControl>,
+        ),
+        (
+            Option<(&GroundNormal, &SlopeSettings)>,
+            Option<&CollidedGrounds>,
+        ),
+    )>,
+) {
+    for (
+        mut force,
+        vel,
+        move_vec,
+        dash,
+        move_speed,
+        (mass, profile, air_control),
+        (ground, standing_on),
+    ) in query.iter_mut()
+    {
+        let profile = profile.copied().unwrap_or_default();
+        let standing_on = standing_on.map_or(&[][..], |grounds| grounds.as_slice());
+        let surface = standing_on
+            .iter()
+            .find_map(|&ground| surfaces.get(ground).ok())
+            .copied()
+            .unwrap_or_default();
+        let carried = standing_on
+            .iter()
+            .find_map(|&ground| moving.get(ground).ok())
+            .map_or(Vec3::ZERO, |ground| ground.linvel)
+            + surface.conveyor;
+        let linvel = vel.linvel - carried;
+
+        // a move vector shorter than full speed is partial stick input
+        let dash = dash.filter(|dash| dash.is_dashing());
+        let (clamped_input, target) = match (dash, move_speed) {
+            (Some(dash), _) => (1.0, dash.direction * DASH_SPEED),
+            (None, Some(speed)) if **speed > 0.0 => (
+                (move_vec.xz().length() / **speed).clamp(0.0, 1.0),
+                move_vec.xz().normalize_or_zero() * **speed,
+            ),
+            (None, _) => (1.0, move_vec.xz()),
+        };
+
+        // swing round from the way it's already going rather than snapping
+        // to the new direction, dashes go straight where they're aimed
+        let current = linvel.xz();
+        let target = match current.try_normalize() {
+            Some(heading) if dash.is_none() && current.length() > TURN_MIN_SPEED => {
+                let max_turn = profile.turn_rate * time.delta_secs();
+                heading.rotate_towards(target.normalize_or(heading), max_turn) * target.length()
+            }
+            _ => target,
+        };
+        let mut target = Vec3::new(target.x, 0.0, target.y) * surface.speed;
+
+        let speeding_up = target.length() * clamped_input >= current.length();
+        let move_force = match speeding_up {
+            true => profile.acceleration,
+            false => profile.deceleration,
+        } * surface.acceleration;
+        let mass = mass.map(|mass| mass.mass);
+        let force_multiplier = step_gain(move_force, mass, time.delta_secs());
+
+        let mut slide = None;
+        match ground.and_then(|(normal, slope)| Some((normal.0?, slope))) {
+            // on the ground, move along it rather than into or off of it,
+            // keeping the same speed as on the flat. Only what's along the
+            // ground is pushed on, gravity and the contact deal with the rest
+            Some((normal, slope)) if slope.walkable(normal) => {
+                let along = target.project_onto_plane(normal).normalize_or_zero() * target.length();
+                force.force = calc_force_diff(
+                    clamped_input,
+                    linvel.project_onto_plane(normal),
+                    along,
+                    force_multiplier,
+                );
+                continue;
+            }
+            // too steep to climb, so nothing pushes up it and it slides back
+            // down, pushed along by more than gravity alone
+            Some((normal, slope)) => {
+                let downhill = normal.horizontal().normalize_or_zero();
+                target -= downhill * target.dot(downhill).min(0.0);
+                let down_slope = Vec3::NEG_Y.project_onto_plane(normal).normalize_or_zero();
+                slide = Some(down_slope * slope.slide_acceleration * mass.unwrap_or(1.0));
+            }
+            None => {}
+        }
+
+        // not on anything walkable, so in the air
+        let air_force = move_force * air_control.map_or(1.0, |control| **control);
+        let force_multiplier = step_gain(air_force, mass, time.delta_secs());
+        let new_force =
+            calc_force_diff(clamped_input, linvel.horizontal(), target, force_multiplier);
+        force.force = match slide {
+            Some(slide) => new_force + slide,
+            None => new_force.with_y(force.force.y),
+        };
+    }
+}
+
+/// How hard to push per unit of velocity missing, for a body of `mass` to
+/// close as much of the gap in a step of `dt` as pushing with `gain` the
+/// whole time would
+///
+/// Velocity eases towards the target at the same rate whatever the physics
+/// rate, and no step pushes past it, which would have light bodies
+/// overshoot further every step until they fly off
+fn step_gain(gain: f32, mass: Option<f32>, dt: f32) -> f32 {
+    return match mass {
+        // not weighed by physics yet
+        Some(mass) if mass <= 0.0 => 0.0,
+        Some(mass) if dt > 0.0 => mass / dt * (1.0 - (-gain / mass * dt).exp()),
+        _ => gain,
+    };
+}
+
 /// clamped_input is a 0.0-1.0 value representing the user's
 /// desired percentage of top speed to hold
 ///
-/// `current_velocity` is the current horizontal velocity
-fn calc_force_diff(clamped_input: f32, current_velocity: Vec2, target_velocity: Vec2) -> Vec2 {
+/// `current_velocity` is the part of the velocity being controlled, across
+/// the ground
+///
+/// `force_multiplier` scales how hard the difference is made up
+fn calc_force_diff(
+    clamped_input: f32,
+    current_velocity: Vec3,
+    target_velocity: Vec3,
+    force_multiplier: f32,
+) -> Vec3 {
     let target_speed = target_velocity * clamped_input;
     let diff_to_make_up = target_speed - current_velocity;
-    diff_to_make_up * 300.0
+    diff_to_make_up * force_multiplier
 }
 
+/// Radians per second the camera swings around while orbiting
+const CAMERA_ORBIT_SPEED: f32 = 2.0;
+
+/// Any player can swing the shared camera around, both ways at once cancel
+/// out
+pub fn orbit_camera(
+    time: Res<Time>,
+    players: Query<&ActionState<Action>, With<Player>>,
+    mut cam: Query<&mut CameraYaw, With<Camera3d>>,
+) {
+    let Ok(mut yaw) = cam.single_mut() else {
+        return;
+    };
+
+    let mut direction: f32 = 0.0;
+    for action_state in players.iter() {
+        if action_state.pressed(&Action::OrbitLeft) {
+            direction -= 1.0;
+        }
+        if action_state.pressed(&Action::OrbitRight) {
+            direction += 1.0;
+        }
+    }
+    if direction != 0.0 {
+        **yaw = (**yaw + direction.clamp(-1.0, 1.0) * CAMERA_ORBIT_SPEED * time.delta_secs())
+            .rem_euclid(TAU);
+    }
+}
+
+/// How much further the camera pulls back per unit players are spread apart
+const CAMERA_SPREAD_ZOOM: f32 = 1.2;
+/// Height above a player's origin the camera keeps a clear line to
+const CAMERA_SIGHT_HEIGHT: f32 = 1.0;
+/// Extra distance the camera comes in past whatever is in the way
+const CAMERA_SIGHT_MARGIN: f32 = 1.0;
+/// Closest the camera gets to the players, however much is in the way
+const MIN_CAMERA_DISTANCE: f32 = 6.0;
+/// How quickly the camera comes in when a player is hidden, and goes back
+/// out once they aren't, per second
+const CAMERA_PULL_IN_RATE: f32 = 12.0;
+const CAMERA_PULL_OUT_RATE: f32 = 3.0;
+
+/// How the camera frames the players, saved along with the other settings
+///
+/// Maps set how far out the camera sits with `CameraDistance`, `zoom`
+/// scales whatever that is
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Pitch down from straight overhead, in degrees
+    pub angle: f32,
+    pub zoom: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            angle: 30.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+fn load_camera_settings(save: Res<SaveData>, mut settings: ResMut<CameraSettings>) {
+    *settings = save.settings.camera;
+}
+
+fn save_camera_settings(settings: Res<CameraSettings>, mut save: ResMut<SaveData>) {
+    SaveData::update_setting(&mut save, |saved| &mut saved.camera, *settings);
+}
+
+/// Leaves the camera where it is instead of following the players, for
+/// flying it around freely
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct DetachedCamera;
+
 pub fn camera_lock(
-    mut cam: Query<(&mut Transform, &CameraDistance), (With<Camera3d>, Without<Player>)>,
-    player: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+    mut cam: Query<
+        (
+            &mut Transform,
+            &CameraDistance,
+            &CameraYaw,
+            &mut CameraPullIn,
+            Option<&mut CameraLookAhead>,
+        ),
+        (With<Camera3d>, Without<Player>, Without<DetachedCamera>),
+    >,
+    players: Query<(&Transform, Option<&Velocity>), With<Player>>,
+    settings: Res<CameraSettings>,
+    rapier_context: ReadRapierContext,
 ) {
-    let ((mut cam, dist), player) = (cam.single_mut().unwrap(), player.single().unwrap());
+    // the camera comes with the level, which may still be loading
+    let Ok((mut cam, dist, yaw, mut pull_in, look_ahead)) = cam.single_mut() else {
+        return;
+    };
+    let positions: Vec<Vec3> = players
+        .iter()
+        .map(|(player, _)| player.translation)
+        .collect();
+
+    // frame every player, pulling back as they spread apart
+    let center = positions.iter().sum::<Vec3>() / positions.len() as f32;
+    let spread = positions
+        .iter()
+        .map(|position| position.distance(center))
+        .fold(0.0, f32::max);
+    let dist = **dist * settings.zoom + spread * CAMERA_SPREAD_ZOOM;
+
+    // lead where they're all heading together, eased so it doesn't lurch
+    // about with every change of direction
+    let center = match look_ahead {
+        Some(mut look_ahead) => {
+            let heading = players
+                .iter()
+                .filter_map(|(_, velocity)| velocity)
+                .map(|velocity| velocity.linvel.horizontal())
+                .sum::<Vec3>()
+                / positions.len() as f32;
+            let target = (heading * look_ahead.lead).clamp_length_max(look_ahead.max_distance);
+            look_ahead.offset = damped_lerp(
+                look_ahead.offset,
+                target,
+                look_ahead.rate,
+                time.delta_secs(),
+            );
+            center + look_ahead.offset
+        }
+        None => center,
+    };
+
+    let angle = settings.angle.to_radians();
+    let offset = Quat::from_rotation_y(**yaw) * Vec3::new(sin(angle), cos(angle), sin(angle));
 
-    let x = **dist * sin(CAMERA_ANGLE);
-    let y = **dist * cos(CAMERA_ANGLE);
+    // come in past anything fixed standing between the camera and a player
+    let obstructed = rapier_context.single().ok().and_then(|context| {
+        let filter =
+            QueryFilter::new().groups(CollisionGroups::new(layers::PLAYER, layers::GROUND));
+        let eye = center + offset * dist;
+        positions
+            .iter()
+            .filter_map(|position| {
+                let sight = *position + Vec3::Y * CAMERA_SIGHT_HEIGHT;
+                let to_eye = eye - sight;
+                let (_, toi) = context.cast_ray(sight, to_eye, 1.0, true, filter)?;
+                Some((1.0 - toi) * to_eye.length() + CAMERA_SIGHT_MARGIN)
+            })
+            .reduce(f32::max)
+    });
+    let target = obstructed
+        .unwrap_or(0.0)
+        .min(dist - MIN_CAMERA_DISTANCE)
+        .max(0.0);
+    let rate = match target > **pull_in {
+        true => CAMERA_PULL_IN_RATE,
+        false => CAMERA_PULL_OUT_RATE,
+    };
+    **pull_in = damped_lerp(**pull_in, target, rate, time.delta_secs());
+    let dist = dist - **pull_in;
 
-    cam.translation = player.translation + Vec3::new(x, y, x);
-    *cam = cam.looking_at(player.translation, Vec3::Y);
+    cam.translation = center + offset * dist;
+    *cam = cam.looking_at(center, Vec3::Y);
 }
@@ -0,0 +1,151 @@
+use bevy::ecs::error::ignore;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::animation::{
+    AnimationController, AnimationFinished, AnimationIndices, AnimationSpeed, AnimationState,
+    AnimationTimer, Facing, SpriteScale,
+};
+use crate::combat::DeathEvent;
+use crate::pool::{Pooled, ReleaseCommandsExt};
+use crate::toon::ToonMaterial;
+use crate::{Enemy, GameplaySet};
+
+/// How long a corpse takes to shrink away once its death clip is over
+const CORPSE_FADE_SECONDS: f32 = 0.4;
+/// Longest a corpse waits on its death clip, in case it's never shown
+const CORPSE_MAX_CLIP_SECONDS: f32 = 3.0;
+
+pub struct CorpsesPlugin;
+
+impl Plugin for CorpsesPlugin {
+    fn build(&self, app: &mut App) {
+        // dead enemies linger until PostUpdate so anything reacting to
+        // `DeathEvent` during Update can still read their components
+        app.add_systems(PostUpdate, lay_out_corpses);
+        app.add_systems(
+            Update,
+            (finish_death_clips, fade_corpses)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// What's left of a dead enemy once everything that made it an enemy is
+/// stripped off, just its looks, playing out its death before it's released
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Corpse {
+    /// Running until the death clip finishes, or for the whole wait if
+    /// there's no clip to wait on
+    clip: Timer,
+    fade: Timer,
+    scale: Vec3,
+}
+
+/// What a corpse keeps, how it looks and what the pool needs back
+type CorpseRetained = (
+    (Pooled, Name),
+    (Transform, GlobalTransform),
+    (Visibility, InheritedVisibility, ViewVisibility),
+    (ChildOf, Children),
+    (
+        Mesh3d,
+        MeshMaterial3d<StandardMaterial>,
+        MeshMaterial3d<ToonMaterial>,
+    ),
+    (
+        Sprite,
+        SpriteScale,
+        Facing,
+        AnimationController,
+        AnimationIndices,
+        AnimationTimer,
+        AnimationSpeed,
+    ),
+    (RigidBody, Collider),
+    (
+        RapierRigidBodyHandle,
+        RapierColliderHandle,
+        RapierContextEntityLink,
+    ),
+);
+
+fn lay_out_corpses(
+    mut commands: Commands,
+    mut deaths: EventReader<DeathEvent>,
+    enemies: Query<(), With<Enemy>>,
+) {
+    for death in deaths.read() {
+        if enemies.contains(death.entity) {
+            commands
+                .entity(death.entity)
+                .queue_handled(lay_out_corpse, ignore);
+        }
+    }
+}
+
+/// The collider goes straight away so the body doesn't block anyone
+fn lay_out_corpse(mut entity: EntityWorldMut) {
+    // killed twice in one frame
+    if entity.contains::<Corpse>() {
+        return;
+    }
+
+    let has_clip = entity
+        .get::<AnimationController>()
+        .is_some_and(|controller| controller.clip(AnimationState::Die).is_some());
+    let clip_seconds = if has_clip {
+        CORPSE_MAX_CLIP_SECONDS
+    } else {
+        0.0
+    };
+    let scale = entity.get::<Transform>().map_or(Vec3::ONE, |t| t.scale);
+
+    entity.retain::<CorpseRetained>().insert((
+        Corpse {
+            clip: Timer::from_seconds(clip_seconds, TimerMode::Once),
+            fade: Timer::from_seconds(CORPSE_FADE_SECONDS, TimerMode::Once),
+            scale,
+        },
+        AnimationState::Die,
+        ColliderDisabled,
+        RigidBodyDisabled,
+    ));
+}
+
+fn finish_death_clips(
+    mut finished: EventReader<AnimationFinished>,
+    mut corpses: Query<(&mut Corpse, &AnimationController)>,
+) {
+    for event in finished.read() {
+        let Ok((mut corpse, controller)) = corpses.get_mut(event.entity) else {
+            continue;
+        };
+        // a clip that was already finishing as it died isn't the death clip
+        let death_clip = controller.clip(AnimationState::Die);
+        if death_clip.is_some_and(|clip| clip.name() == event.clip) {
+            let duration = corpse.clip.duration();
+            corpse.clip.set_elapsed(duration);
+        }
+    }
+}
+
+fn fade_corpses(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut corpses: Query<(Entity, &mut Corpse, &mut Transform)>,
+) {
+    for (entity, mut corpse, mut transform) in corpses.iter_mut() {
+        if !corpse.clip.tick(time.delta()).finished() {
+            continue;
+        }
+
+        if corpse.fade.tick(time.delta()).finished() {
+            commands.entity(entity).release();
+            continue;
+        }
+        transform.scale = corpse.scale * corpse.fade.fraction_remaining();
+    }
+}
@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::ui::{UiMaterial, UiMaterialPlugin};
+
+use crate::accessibility::AccessibilitySettings;
+use crate::combat::{DamageEvent, Health, apply_damage};
+use crate::{GameState, GameplaySet, Player};
+
+const VIGNETTE_SHADER_PATH: &str = "shaders/damage_vignette.wgsl";
+/// Seconds for a hit's flash to fade out
+const FLASH_SECONDS: f32 = 0.45;
+/// Vignette strength of a hit at full health, and how much stronger it gets
+/// as health runs out
+const FLASH_BASE: f32 = 0.4;
+const FLASH_LOW_HEALTH: f32 = 0.8;
+/// How much of the vignette also tints the middle of the screen
+const FLASH_FILL: f32 = 0.2;
+/// Vignette strength multiplier with reduced flashing on
+const REDUCED_FLASH: f32 = 0.35;
+
+pub struct DamageFeedbackPlugin;
+
+impl Plugin for DamageFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(UiMaterialPlugin::<DamageVignetteMaterial>::default());
+        app.add_systems(OnEnter(GameState::InGame), spawn_damage_vignette);
+        app.add_systems(
+            Update,
+            (
+                flash_on_player_damage.after(apply_damage),
+                fade_damage_vignette,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// A red vignette over the whole screen, darkest at the edges
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct DamageVignetteMaterial {
+    #[uniform(0)]
+    color: LinearRgba,
+    /// x is the strength of the edges, y of the flash over the whole screen
+    #[uniform(1)]
+    params: Vec4,
+}
+
+impl UiMaterial for DamageVignetteMaterial {
+    fn fragment_shader() -> ShaderRef {
+        return VIGNETTE_SHADER_PATH.into();
+    }
+}
+
+/// The flash of the last hit, eased out over `FLASH_SECONDS`
+#[derive(Component, Default)]
+struct DamageVignette {
+    /// Strength right as the hit landed
+    peak: f32,
+    /// Seconds since the hit
+    elapsed: f32,
+}
+
+fn spawn_damage_vignette(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<DamageVignetteMaterial>>,
+) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        MaterialNode(materials.add(DamageVignetteMaterial {
+            color: LinearRgba::rgb(0.8, 0.0, 0.0),
+            params: Vec4::ZERO,
+        })),
+        // under every menu, and never in the way of their buttons
+        GlobalZIndex(-1),
+        Pickable::IGNORE,
+        DamageVignette::default(),
+        Name::new("Damage Vignette"),
+        StateScoped(GameState::InGame),
+    ));
+}
+
+/// Hits flash harder the less health the player has left after them
+fn flash_on_player_damage(
+    accessibility: Res<AccessibilitySettings>,
+    mut damage: EventReader<DamageEvent>,
+    players: Query<&Health, With<Player>>,
+    mut vignette: Query<&mut DamageVignette>,
+) {
+    let Ok(mut vignette) = vignette.single_mut() else {
+        return;
+    };
+
+    for event in damage.read() {
+        if event.amount <= 0.0 {
+            continue;
+        }
+        let Ok(health) = players.get(event.target) else {
+            continue;
+        };
+
+        let missing = 1.0 - health.fraction().clamp(0.0, 1.0);
+        let mut peak = FLASH_BASE + FLASH_LOW_HEALTH * missing;
+        if accessibility.reduce_flashing {
+            peak *= REDUCED_FLASH;
+        }
+        // a weaker hit doesn't cut short a stronger flash still fading
+        let current = vignette.peak * ease_out(vignette.elapsed);
+        if peak >= current {
+            *vignette = DamageVignette { peak, elapsed: 0.0 };
+        }
+    }
+}
+
+fn fade_damage_vignette(
+    time: Res<Time>,
+    mut vignette: Query<(&mut DamageVignette, &MaterialNode<DamageVignetteMaterial>)>,
+    mut materials: ResMut<Assets<DamageVignetteMaterial>>,
+) {
+    let Ok((mut vignette, material)) = vignette.single_mut() else {
+        return;
+    };
+    if vignette.elapsed >= FLASH_SECONDS {
+        return;
+    }
+
+    vignette.elapsed += time.delta_secs();
+    let strength = vignette.peak * ease_out(vignette.elapsed);
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.params = Vec4::new(strength, strength * FLASH_FILL, 0.0, 0.0);
+    }
+}
+
+/// 1 right as the hit lands, falling off quickly and then easing into 0
+fn ease_out(elapsed: f32) -> f32 {
+    let remaining = 1.0 - (elapsed / FLASH_SECONDS).clamp(0.0, 1.0);
+    return remaining * remaining;
+}
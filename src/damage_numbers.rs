@@ -0,0 +1,333 @@
+//! Numbers floating up off everything that's hit, all drawn as one mesh
+//!
+//! A horde's worth of hits would be thousands of text entities, so numbers
+//! are plain data in `DamageNumbers` instead. Every frame they're written
+//! into a single mesh of camera facing quads, one per digit, textured from a
+//! digit atlas drawn at startup. One entity and one draw, however many are up
+
+use std::collections::VecDeque;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::view::NoFrustumCulling;
+use bevy::transform::TransformSystem;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::budget::EntityBudget;
+use crate::combat::{DamageEvent, apply_damage};
+use crate::{GameState, GameplaySet, MainCamera, Player};
+
+const NUMBER_SECONDS: f32 = 0.8;
+const RISE_SPEED: f32 = 2.0;
+/// How far above what was hit a number starts
+const NUMBER_HEIGHT: f32 = 1.5;
+const DIGIT_HEIGHT: f32 = 0.6;
+const CRIT_SCALE: f32 = 1.5;
+/// Numbers landing together are spread across this many lanes, so a target
+/// hit every frame doesn't stack them all on top of each other
+const LANES: u32 = 3;
+const LANE_WIDTH: f32 = 0.4;
+/// Pixels in each digit of the atlas
+const GLYPH_WIDTH: u32 = 8;
+const GLYPH_HEIGHT: u32 = 14;
+/// Which of the seven segments each digit lights up, from the top clockwise
+/// then the middle, like a digital clock
+const DIGIT_SEGMENTS: [u8; 10] = [
+    0b0111111, 0b0000110, 0b1011011, 0b1001111, 0b1100110, 0b1101101, 0b1111101, 0b0000111,
+    0b1111111, 0b1101111,
+];
+/// Where each segment sits in a glyph, as min and max pixel corners
+const SEGMENT_RECTS: [(UVec2, UVec2); 7] = [
+    (UVec2::new(1, 1), UVec2::new(7, 3)),
+    (UVec2::new(5, 1), UVec2::new(7, 8)),
+    (UVec2::new(5, 6), UVec2::new(7, 13)),
+    (UVec2::new(1, 11), UVec2::new(7, 13)),
+    (UVec2::new(1, 6), UVec2::new(3, 13)),
+    (UVec2::new(1, 1), UVec2::new(3, 8)),
+    (UVec2::new(1, 6), UVec2::new(7, 8)),
+];
+
+pub struct DamageNumbersPlugin;
+
+impl Plugin for DamageNumbersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DamageNumbers>();
+        app.add_systems(PreStartup, setup_damage_number_assets);
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            (clear_damage_numbers, spawn_damage_number_mesh),
+        );
+        app.add_systems(
+            Update,
+            add_damage_numbers.after(apply_damage).in_set(GameplaySet),
+        );
+        // after the camera has moved, so the quads face where it ends up
+        app.add_systems(
+            PostUpdate,
+            build_damage_number_mesh
+                .after(TransformSystem::TransformPropagate)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DamageNumber {
+    value: u32,
+    /// Where it started, it rises from here as it ages
+    position: Vec3,
+    age: f32,
+    color: LinearRgba,
+    scale: f32,
+    lane: f32,
+}
+
+/// Every damage number up right now, oldest first
+#[derive(Resource, Default, Debug)]
+pub struct DamageNumbers {
+    numbers: VecDeque<DamageNumber>,
+    /// Numbers added so far, for picking lanes
+    added: u32,
+}
+
+impl DamageNumbers {
+    pub fn len(&self) -> usize {
+        return self.numbers.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.numbers.is_empty();
+    }
+
+    /// Digits across every number up, so quads in the mesh
+    pub fn digits(&self) -> usize {
+        return self
+            .numbers
+            .iter()
+            .map(|number| digits(number.value).len())
+            .sum();
+    }
+}
+
+/// The one entity every damage number is drawn by
+#[derive(Component)]
+pub struct DamageNumberMesh;
+
+#[derive(Resource)]
+struct DamageNumberAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn digits(value: u32) -> Vec<usize> {
+    return value
+        .to_string()
+        .bytes()
+        .map(|digit| (digit - b'0') as usize)
+        .collect();
+}
+
+/// The ten digits side by side, white on clear so vertex colors tint them
+fn digit_atlas() -> Image {
+    let mut atlas = Image::new_fill(
+        Extent3d {
+            width: GLYPH_WIDTH * 10,
+            height: GLYPH_HEIGHT,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    for (digit, segments) in DIGIT_SEGMENTS.iter().enumerate() {
+        let origin = digit as u32 * GLYPH_WIDTH;
+        for (segment, (min, max)) in SEGMENT_RECTS.iter().enumerate() {
+            if segments & (1 << segment) == 0 {
+                continue;
+            }
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    let _ = atlas.set_color_at(origin + x, y, Color::WHITE);
+                }
+            }
+        }
+    }
+    return atlas;
+}
+
+fn setup_damage_number_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    commands.insert_resource(DamageNumberAssets {
+        // rebuilt every frame, so it has to stay readable on the CPU
+        mesh: meshes.add(
+            Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::default(),
+            )
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, Vec::<[f32; 2]>::new())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, Vec::<[f32; 4]>::new())
+            .with_inserted_indices(Indices::U32(Vec::new())),
+        ),
+        material: materials.add(StandardMaterial {
+            base_color_texture: Some(images.add(digit_atlas())),
+            unlit: true,
+            alpha_mode: AlphaMode::Blend,
+            cull_mode: None,
+            ..default()
+        }),
+    });
+}
+
+fn clear_damage_numbers(mut numbers: ResMut<DamageNumbers>) {
+    *numbers = DamageNumbers::default();
+}
+
+fn spawn_damage_number_mesh(mut commands: Commands, assets: Res<DamageNumberAssets>) {
+    commands.spawn((
+        Mesh3d(assets.mesh.clone()),
+        MeshMaterial3d(assets.material.clone()),
+        Transform::default(),
+        // the quads move every frame, the bounds worked out at spawn never
+        // cover them
+        NoFrustumCulling,
+        Visibility::Hidden,
+        DamageNumberMesh,
+        Name::new("Damage Numbers"),
+        StateScoped(GameState::InGame),
+    ));
+}
+
+fn add_damage_numbers(
+    mut damage: EventReader<DamageEvent>,
+    targets: Query<(&GlobalTransform, Has<Player>)>,
+    mut numbers: ResMut<DamageNumbers>,
+    (budget, accessibility): (Res<EntityBudget>, Res<AccessibilitySettings>),
+) {
+    let tints = accessibility.palette.damage_number_tints();
+    for event in damage.read() {
+        let value = event.amount.round();
+        if value < 1.0 {
+            continue;
+        }
+        let Ok((target, player)) = targets.get(event.target) else {
+            continue;
+        };
+
+        let color = match (player, event.crit) {
+            (true, _) => tints.player,
+            (false, true) => tints.crit,
+            (false, false) => tints.hit,
+        };
+        let lane = (numbers.added % LANES) as f32 - (LANES - 1) as f32 / 2.0;
+        numbers.added = numbers.added.wrapping_add(1);
+        numbers.numbers.push_back(DamageNumber {
+            value: value as u32,
+            position: target.translation() + Vec3::Y * NUMBER_HEIGHT,
+            age: 0.0,
+            color: color.to_linear(),
+            scale: if event.crit { CRIT_SCALE } else { 1.0 },
+            lane,
+        });
+    }
+
+    let over = numbers.len().saturating_sub(budget.damage_numbers);
+    numbers.numbers.drain(..over);
+}
+
+/// Age every number and write the ones still up into the mesh, a quad per
+/// digit facing the camera
+///
+/// Touching the mesh sends it to the GPU again, so it's left alone while
+/// there's nothing up and it's already empty
+fn build_damage_number_mesh(
+    time: Res<Time>,
+    mut numbers: ResMut<DamageNumbers>,
+    cameras: Query<&GlobalTransform, With<MainCamera>>,
+    assets: Res<DamageNumberAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut drawers: Query<&mut Visibility, With<DamageNumberMesh>>,
+    mut drawn: Local<bool>,
+) {
+    for number in numbers.numbers.iter_mut() {
+        number.age += time.delta_secs();
+    }
+    numbers.numbers.retain(|number| number.age < NUMBER_SECONDS);
+
+    for mut visibility in drawers.iter_mut() {
+        visibility.set_if_neq(match numbers.is_empty() {
+            true => Visibility::Hidden,
+            false => Visibility::Inherited,
+        });
+    }
+    if numbers.is_empty() && !*drawn {
+        return;
+    }
+    let Some(mesh) = meshes.get_mut(&assets.mesh) else {
+        return;
+    };
+    *drawn = !numbers.is_empty();
+
+    let (right, up) = cameras.single().map_or((Vec3::X, Vec3::Y), |camera| {
+        return (*camera.right(), *camera.up());
+    });
+    let quads = numbers.digits();
+    let mut positions = Vec::with_capacity(quads * 4);
+    let mut uvs = Vec::with_capacity(quads * 4);
+    let mut colors = Vec::with_capacity(quads * 4);
+    let mut indices = Vec::with_capacity(quads * 6);
+
+    let glyph_aspect = GLYPH_WIDTH as f32 / GLYPH_HEIGHT as f32;
+    for number in numbers.numbers.iter() {
+        let progress = number.age / NUMBER_SECONDS;
+        let height = DIGIT_HEIGHT * number.scale;
+        let width = height * glyph_aspect;
+        // fades out over the second half
+        let alpha = (2.0 - 2.0 * progress).min(1.0);
+        let color = [
+            number.color.red,
+            number.color.green,
+            number.color.blue,
+            alpha,
+        ];
+
+        let digits = digits(number.value);
+        let center = number.position + up * RISE_SPEED * number.age;
+        let start = center + right * (number.lane * LANE_WIDTH)
+            - right * width * (digits.len() as f32 - 1.0) / 2.0;
+        for (i, digit) in digits.into_iter().enumerate() {
+            let middle = start + right * width * i as f32;
+            let half_right = right * width / 2.0;
+            let half_up = up * height / 2.0;
+            let first = positions.len() as u32;
+            positions.extend([
+                (middle - half_right - half_up).to_array(),
+                (middle + half_right - half_up).to_array(),
+                (middle + half_right + half_up).to_array(),
+                (middle - half_right + half_up).to_array(),
+            ]);
+            let left = digit as f32 / 10.0;
+            let right_edge = (digit + 1) as f32 / 10.0;
+            uvs.extend([
+                [left, 1.0],
+                [right_edge, 1.0],
+                [right_edge, 0.0],
+                [left, 0.0],
+            ]);
+            colors.extend([color; 4]);
+            indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+    }
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.insert_indices(Indices::U32(indices));
+}
@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::GameplaySet;
+use crate::combat::{DamageEvent, DamageType, Faction, Health};
+use crate::physics::layers;
+use crate::weapons::WeaponKind;
+
+/// Zones deal their damage in chunks this far apart rather than every frame
+const ZONE_TICK_SECONDS: f32 = 0.25;
+
+pub struct DamageZonePlugin;
+
+impl Plugin for DamageZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_damage_zone_assets);
+        app.add_systems(
+            Update,
+            (tick_damage_zones, expire_damage_zones)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// A patch of ground left behind by an attack, hurting everything of the
+/// faction it `hits` standing in it until it runs out
+///
+/// Its size is its transform's scale, set by `damage_zone`
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct DamageZone {
+    pub dps: f32,
+    pub hits: Faction,
+    pub lifetime: Timer,
+    /// The player weapon that left it, for damage tracking
+    pub weapon: Option<WeaponKind>,
+    tick: Timer,
+}
+
+impl DamageZone {
+    pub fn new(dps: f32, seconds: f32, hits: Faction) -> Self {
+        return Self {
+            dps,
+            hits,
+            lifetime: Timer::from_seconds(seconds, TimerMode::Once),
+            weapon: None,
+            tick: Timer::from_seconds(ZONE_TICK_SECONDS, TimerMode::Repeating),
+        };
+    }
+
+    pub fn with_weapon(mut self, weapon: WeaponKind) -> Self {
+        self.weapon = Some(weapon);
+        return self;
+    }
+}
+
+#[derive(Resource)]
+pub struct DamageZoneAssets {
+    pub mesh: Handle<Mesh>,
+    /// Zones left by players, hitting enemies
+    pub player_material: Handle<StandardMaterial>,
+    /// Zones left by enemies, hitting players
+    pub enemy_material: Handle<StandardMaterial>,
+}
+
+fn setup_damage_zone_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut decal = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })
+    };
+
+    commands.insert_resource(DamageZoneAssets {
+        mesh: meshes.add(Cylinder::new(1.0, 0.02)),
+        player_material: decal(Color::srgba(1.0, 0.5, 0.1, 0.4)),
+        enemy_material: decal(Color::srgba(0.7, 0.2, 0.9, 0.4)),
+    });
+}
+
+/// A round zone of `radius` on the ground at `position`, coloured by who it
+/// hurts
+pub fn damage_zone(
+    assets: &DamageZoneAssets,
+    position: Vec3,
+    radius: f32,
+    zone: DamageZone,
+) -> impl Bundle {
+    let material = match zone.hits {
+        Faction::Enemy => assets.player_material.clone(),
+        Faction::Player => assets.enemy_material.clone(),
+    };
+
+    (
+        Mesh3d(assets.mesh.clone()),
+        MeshMaterial3d(material),
+        // the decal is a flat unit disc just above the ground, the collider
+        // a tall cylinder so standing bodies overlap it
+        Transform::from_translation(position.with_y(0.06))
+            .with_scale(Vec3::new(radius, 1.0, radius)),
+        Name::new("Damage Zone"),
+        zone,
+        Collider::cylinder(1.0, 1.0),
+        layers::hazard(),
+        Sensor,
+    )
+}
+
+fn tick_damage_zones(
+    time: Res<Time>,
+    rapier_context: ReadRapierContext,
+    mut zones: Query<(Entity, &mut DamageZone)>,
+    targets: Query<&Faction, With<Health>>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+
+    for (entity, mut zone) in zones.iter_mut() {
+        if !zone.tick.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let amount = zone.dps * ZONE_TICK_SECONDS;
+        for (a, b, intersecting) in context.intersection_pairs_with(entity) {
+            let other = if a == entity { b } else { a };
+            if !intersecting || targets.get(other) != Ok(&zone.hits) {
+                continue;
+            }
+
+            damage.write(DamageEvent {
+                target: other,
+                amount,
+                source: Some(entity),
+                crit: false,
+                weapon: zone.weapon,
+                kind: zone
+                    .weapon
+                    .map_or(DamageType::Physical, |weapon| weapon.damage_type()),
+            });
+        }
+    }
+}
+
+fn expire_damage_zones(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut zones: Query<(Entity, &mut DamageZone)>,
+) {
+    for (entity, mut zone) in zones.iter_mut() {
+        if zone.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use bevy_rapier3d::render::DebugRenderContext;
+
+use crate::ai::Separation;
+use crate::spawner::SPAWN_RING_RADIUS;
+use crate::{MoveVector, Player};
+
+/// Turns every debug visual on, or all of them off if any are showing
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+/// Arrows are drawn this many seconds of movement long
+const ARROW_SECONDS: f32 = 0.25;
+
+pub struct DebugVisualsPlugin;
+
+impl Plugin for DebugVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<DebugVisuals>();
+        app.init_resource::<DebugVisuals>();
+        app.add_systems(
+            Update,
+            (
+                toggle_debug_visuals,
+                sync_collider_wireframes.run_if(resource_changed::<DebugVisuals>),
+                draw_velocities.run_if(|visuals: Res<DebugVisuals>| visuals.velocities),
+                draw_spawn_rings.run_if(|visuals: Res<DebugVisuals>| visuals.spawn_rings),
+                draw_steering.run_if(|visuals: Res<DebugVisuals>| visuals.steering),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Which debug overlays are drawn, all off to begin with
+///
+/// Collider wireframes need the app to be set up with `debug_render`
+#[derive(Resource, Reflect, Debug, Clone, Copy, Default)]
+#[reflect(Resource)]
+pub struct DebugVisuals {
+    pub colliders: bool,
+    /// How fast every physics body is moving
+    pub velocities: bool,
+    /// Where around each player enemies appear
+    pub spawn_rings: bool,
+    /// Where each enemy's AI wants to go
+    pub steering: bool,
+}
+
+impl DebugVisuals {
+    fn any(&self) -> bool {
+        return self.colliders || self.velocities || self.spawn_rings || self.steering;
+    }
+}
+
+fn toggle_debug_visuals(keys: Res<ButtonInput<KeyCode>>, mut visuals: ResMut<DebugVisuals>) {
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    let on = !visuals.any();
+    *visuals = DebugVisuals {
+        colliders: on,
+        velocities: on,
+        spawn_rings: on,
+        steering: on,
+    };
+}
+
+fn sync_collider_wireframes(
+    visuals: Res<DebugVisuals>,
+    context: Option<ResMut<DebugRenderContext>>,
+) {
+    if let Some(mut context) = context {
+        context.enabled = visuals.colliders;
+    }
+}
+
+fn draw_velocities(bodies: Query<(&GlobalTransform, &Velocity)>, mut gizmos: Gizmos) {
+    for (transform, velocity) in bodies.iter() {
+        if velocity.linvel == Vec3::ZERO {
+            continue;
+        }
+        let start = transform.translation();
+        gizmos.arrow(
+            start,
+            start + velocity.linvel * ARROW_SECONDS,
+            Color::srgb(0.2, 0.6, 1.0),
+        );
+    }
+}
+
+fn draw_spawn_rings(players: Query<&Transform, With<Player>>, mut gizmos: Gizmos) {
+    for transform in players.iter() {
+        gizmos.circle(
+            Isometry3d::new(
+                transform.translation.with_y(0.1),
+                Quat::from_rotation_x(FRAC_PI_2),
+            ),
+            SPAWN_RING_RADIUS,
+            Color::srgb(1.0, 0.8, 0.1),
+        );
+    }
+}
+
+fn draw_steering(enemies: Query<(&Transform, &MoveVector), With<Separation>>, mut gizmos: Gizmos) {
+    for (transform, move_vec) in enemies.iter() {
+        if **move_vec == Vec3::ZERO {
+            continue;
+        }
+        let start = transform.translation;
+        gizmos.arrow(
+            start,
+            start + **move_vec * ARROW_SECONDS,
+            Color::srgb(0.3, 1.0, 0.3),
+        );
+    }
+}
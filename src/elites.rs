@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::accessibility::ColorPalette;
+use crate::animation::AnimationLibrary;
+use crate::combat::{DamageEvent, DamageType, DeathEvent, Health, apply_damage};
+use crate::highlight::Highlight;
+use crate::move_speed::MoveSpeedModifiers;
+use crate::pool::{Inactive, Pool};
+use crate::prefab_library::{PrefabLibrary, PrefabName, spawn_prefab};
+use crate::rng::GameRng;
+use crate::toon::ToonMaterial;
+use crate::{GameState, GameplaySet, MainCamera, Player};
+
+const FAST_SPEED_MULTIPLIER: f32 = 1.6;
+const TANKY_HEALTH_MULTIPLIER: f32 = 4.0;
+const ELITE_SCALE: f32 = 1.4;
+const EXPLOSION_RADIUS: f32 = 5.0;
+const EXPLOSION_DAMAGE: f32 = 20.0;
+/// Outline drawn around every elite
+pub const ELITE_HIGHLIGHT: Color = Color::srgb(1.0, 0.75, 0.1);
+const SPLIT_COUNT: usize = 3;
+/// Health of each split-off child relative to a fresh enemy of its kind
+const SPLIT_HEALTH_MULTIPLIER: f32 = 0.5;
+/// Size of the health bar over each elite's head, in UI pixels
+const HEALTH_BAR_SIZE: Vec2 = Vec2::new(40.0, 5.0);
+/// How far above an elite its health bar floats, in world units
+const HEALTH_BAR_HEIGHT: f32 = 2.5;
+
+pub struct ElitesPlugin;
+
+impl Plugin for ElitesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_elite_assets);
+        app.add_systems(
+            Update,
+            (
+                apply_elite_affixes,
+                explode_on_death.after(apply_damage),
+                split_on_death.after(apply_damage),
+                (sync_elite_health_bars, place_elite_health_bars).chain(),
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Marks an enemy as an elite, the affixes themselves are separate components
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Elite;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Fast;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Tanky;
+
+/// Damages players within `radius` when the entity dies
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct ExplodeOnDeath {
+    pub radius: f32,
+    pub damage: f32,
+}
+
+/// Splits into `count` weaker copies of itself on death
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct SplitOnDeath {
+    pub count: usize,
+}
+
+/// A small health bar kept over `elite`'s head
+#[derive(Component)]
+struct EliteHealthBar {
+    elite: Entity,
+}
+
+#[derive(Component)]
+struct EliteHealthFill;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Affix {
+    Fast,
+    Tanky,
+    Explosive,
+    Splitting,
+}
+
+#[derive(Resource)]
+pub struct EliteAssets {
+    pub material: Handle<ToonMaterial>,
+}
+
+fn setup_elite_assets(mut commands: Commands, mut materials: ResMut<Assets<ToonMaterial>>) {
+    commands.insert_resource(EliteAssets {
+        material: materials.add(ToonMaterial {
+            rim_color: Color::srgb(1.0, 0.9, 0.5),
+            rim_strength: 0.6,
+            ..ToonMaterial::from(ColorPalette::default().enemy_tints().elite)
+        }),
+    });
+}
+
+/// Turn the entity into an elite with one or two random affixes
+pub fn roll_elite(entity: &mut EntityCommands, rng: &mut impl Rng) {
+    let mut affixes = [
+        Affix::Fast,
+        Affix::Tanky,
+        Affix::Explosive,
+        Affix::Splitting,
+    ];
+    affixes.shuffle(rng);
+    let count = rng.random_range(1..=2);
+
+    entity.insert((Elite, Highlight(ELITE_HIGHLIGHT), Name::new("Elite")));
+    for affix in &affixes[..count] {
+        match affix {
+            Affix::Fast => entity.insert(Fast),
+            Affix::Tanky => entity.insert(Tanky),
+            Affix::Explosive => entity.insert(ExplodeOnDeath {
+                radius: EXPLOSION_RADIUS,
+                damage: EXPLOSION_DAMAGE,
+            }),
+            Affix::Splitting => entity.insert(SplitOnDeath { count: SPLIT_COUNT }),
+        };
+    }
+}
+
+fn apply_elite_affixes(
+    assets: Res<EliteAssets>,
+    mut elites: Query<
+        (
+            &mut Transform,
+            &mut MoveSpeedModifiers,
+            &mut Health,
+            Option<&mut MeshMaterial3d<ToonMaterial>>,
+            Has<Fast>,
+            Has<Tanky>,
+        ),
+        Added<Elite>,
+    >,
+) {
+    for (mut transform, mut speed_modifiers, mut health, material, fast, tanky) in elites.iter_mut()
+    {
+        transform.scale *= ELITE_SCALE;
+        if let Some(mut material) = material {
+            material.0 = assets.material.clone();
+        }
+
+        if fast {
+            speed_modifiers.insert("Fast", FAST_SPEED_MULTIPLIER, None);
+        }
+
+        if tanky {
+            health.max *= TANKY_HEALTH_MULTIPLIER;
+            health.current = health.max;
+        }
+    }
+}
+
+fn explode_on_death(
+    mut deaths: EventReader<DeathEvent>,
+    explosive: Query<(&Transform, &ExplodeOnDeath)>,
+    players: Query<(Entity, &Transform), With<Player>>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for death in deaths.read() {
+        let Ok((transform, explosion)) = explosive.get(death.entity) else {
+            continue;
+        };
+
+        for (player, player_transform) in players.iter() {
+            let dist = player_transform.translation.distance(transform.translation);
+            if dist <= explosion.radius {
+                damage.write(DamageEvent {
+                    target: player,
+                    amount: explosion.damage,
+                    source: Some(death.entity),
+                    crit: false,
+                    weapon: None,
+                    kind: DamageType::Fire,
+                });
+            }
+        }
+    }
+}
+
+fn split_on_death(
+    mut commands: Commands,
+    mut deaths: EventReader<DeathEvent>,
+    splitting: Query<(&Transform, &PrefabName, &SplitOnDeath)>,
+    (library, animations): (Res<PrefabLibrary>, Res<Assets<AnimationLibrary>>),
+    mut pool: ResMut<Pool>,
+    mut rng: ResMut<GameRng>,
+) {
+    for death in deaths.read() {
+        let Ok((transform, name, split)) = splitting.get(death.entity) else {
+            continue;
+        };
+
+        for i in 0..split.count {
+            let angle = std::f32::consts::TAU * i as f32 / split.count as f32;
+            let offset = Vec3::new(angle.cos(), 0.0, angle.sin()) * 1.5;
+            spawn_prefab(
+                &mut commands,
+                &mut pool,
+                (&library, &animations),
+                name,
+                transform.translation + offset,
+                SPLIT_HEALTH_MULTIPLIER,
+                &mut **rng,
+            );
+        }
+    }
+}
+
+/// Elites are pooled enemies, so bars go when the elite is released as well
+/// as when it's despawned
+fn sync_elite_health_bars(
+    mut commands: Commands,
+    bars: Query<(Entity, &EliteHealthBar)>,
+    elites: Query<Entity, (With<Elite>, Without<Inactive>)>,
+) {
+    for (bar, link) in bars.iter() {
+        if !elites.contains(link.elite) {
+            commands.entity(bar).despawn();
+        }
+    }
+
+    for elite in elites.iter() {
+        if bars.iter().any(|(_, link)| link.elite == elite) {
+            continue;
+        }
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(HEALTH_BAR_SIZE.x),
+                height: Val::Px(HEALTH_BAR_SIZE.y),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            Visibility::Hidden,
+            EliteHealthBar { elite },
+            Name::new("Elite Health Bar"),
+            StateScoped(GameState::InGame),
+            children![(
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(ELITE_HIGHLIGHT),
+                EliteHealthFill,
+            )],
+        ));
+    }
+}
+
+fn place_elite_health_bars(
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    elites: Query<(&GlobalTransform, &Health)>,
+    ui_scale: Option<Res<UiScale>>,
+    mut bars: Query<(&EliteHealthBar, &mut Node, &mut Visibility, &Children)>,
+    mut fills: Query<&mut Node, (With<EliteHealthFill>, Without<EliteHealthBar>)>,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let scale = ui_scale.map_or(1.0, |scale| scale.0);
+
+    for (link, mut node, mut visibility, children) in bars.iter_mut() {
+        let Ok((transform, health)) = elites.get(link.elite) else {
+            continue;
+        };
+        let head = transform.translation() + Vec3::Y * HEALTH_BAR_HEIGHT;
+        let Ok(point) = camera.world_to_viewport(camera_transform, head) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        let point = point / scale - HEALTH_BAR_SIZE / 2.0;
+        node.left = Val::Px(point.x);
+        node.top = Val::Px(point.y);
+        visibility.set_if_neq(Visibility::Inherited);
+        for child in children.iter() {
+            if let Ok(mut fill) = fills.get_mut(child) {
+                fill.width = Val::Percent(health.fraction() * 100.0);
+            }
+        }
+    }
+}
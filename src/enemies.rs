@@ -0,0 +1,150 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+use crate::accessibility::ColorPalette;
+use crate::animation::AnimationLibrary;
+use crate::combat::Faction;
+use crate::interpolation::InterpolatedTranslation;
+use crate::physics::layers;
+use crate::pool::Pool;
+use crate::prefab_library::{PrefabLibrary, spawn_prefab};
+use crate::rng::GameRng;
+use crate::toon::ToonMaterial;
+use crate::{Enemy, MoveVector, MovementProfile};
+
+/// Light on their feet, but committed enough to a direction that they can
+/// be sidestepped
+const ENEMY_MOVEMENT: MovementProfile = MovementProfile {
+    acceleration: 400.0,
+    deceleration: 400.0,
+    turn_rate: 8.0,
+};
+
+pub struct EnemiesPlugin;
+
+impl Plugin for EnemiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_enemy_assets);
+    }
+}
+
+/// The boss's mesh and material, every other enemy is a prefab in
+/// `assets/prefabs` with its own
+#[derive(Resource)]
+pub struct EnemyAssets {
+    pub boss_mesh: Handle<Mesh>,
+    pub boss_material: Handle<ToonMaterial>,
+}
+
+fn setup_enemy_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+) {
+    // recoloured once the accessibility settings load, see `apply_enemy_palette`
+    let tints = ColorPalette::default().enemy_tints();
+    commands.insert_resource(EnemyAssets {
+        boss_mesh: meshes.add(Capsule3d {
+            radius: 1.5,
+            half_length: 1.5,
+        }),
+        boss_material: materials.add(ToonMaterial::from(tints.boss)),
+    });
+}
+
+/// Scatter `count` of the enemy prefab called `prefab` around `center`,
+/// between `distance` away, the same way the director spawns them: pooled,
+/// from the run's `GameRng` and cleaned up with the run. For tools and
+/// examples outside the crate, which can't get at the pool themselves
+#[derive(Debug, Clone)]
+pub struct SpawnEnemies {
+    pub prefab: String,
+    pub count: usize,
+    pub center: Vec3,
+    pub distance: Range<f32>,
+}
+
+impl Command for SpawnEnemies {
+    fn apply(self, world: &mut World) {
+        if let Err(e) = world.run_system_cached_with(spawn_enemies, self) {
+            warn!("Couldn't spawn enemies: {e}");
+        }
+    }
+}
+
+fn spawn_enemies(
+    In(spawn): In<SpawnEnemies>,
+    mut commands: Commands,
+    mut pool: ResMut<Pool>,
+    (library, animations): (Res<PrefabLibrary>, Res<Assets<AnimationLibrary>>),
+    mut rng: ResMut<GameRng>,
+) {
+    for _ in 0..spawn.count {
+        let angle = rng.random_range(0.0..std::f32::consts::TAU);
+        let distance = rng.random_range(spawn.distance.clone());
+        let position = spawn.center + Vec3::new(angle.cos(), 0.0, angle.sin()) * distance;
+        let spawned = spawn_prefab(
+            &mut commands,
+            &mut pool,
+            (&library, &animations),
+            &spawn.prefab,
+            position,
+            1.0,
+            &mut **rng,
+        );
+        if spawned.is_none() {
+            warn!("No enemy prefab called {}", spawn.prefab);
+            return;
+        }
+    }
+}
+
+/// Spawn one of the enemy prefab called `prefab` straight into `world`, for
+/// tests that set up a fight by hand
+pub fn spawn_enemy(world: &mut World, prefab: &str, position: Vec3) -> Option<Entity> {
+    let entity = world
+        .run_system_cached_with(spawn_one_enemy, (prefab.to_string(), position))
+        .ok()
+        .flatten()?;
+    world.flush();
+    return Some(entity);
+}
+
+fn spawn_one_enemy(
+    In((prefab, position)): In<(String, Vec3)>,
+    mut commands: Commands,
+    mut pool: ResMut<Pool>,
+    (library, animations): (Res<PrefabLibrary>, Res<Assets<AnimationLibrary>>),
+    mut rng: ResMut<GameRng>,
+) -> Option<Entity> {
+    return spawn_prefab(
+        &mut commands,
+        &mut pool,
+        (&library, &animations),
+        &prefab,
+        position,
+        1.0,
+        &mut **rng,
+    );
+}
+
+/// Components every enemy body needs to move and be hit
+pub(crate) fn enemy_body(radius: f32, half_length: f32) -> impl Bundle {
+    (
+        Enemy,
+        Faction::Enemy,
+        MoveVector::default(),
+        RigidBody::Dynamic,
+        Velocity::default(),
+        ExternalForce::default(),
+        ReadMassProperties::default(),
+        Collider::capsule_y(half_length, radius),
+        layers::enemy(),
+        LockedAxes::ROTATION_LOCKED,
+        InterpolatedTranslation::default(),
+        ENEMY_MOVEMENT,
+    )
+}
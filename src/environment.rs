@@ -0,0 +1,125 @@
+use bevy::gltf::GltfExtras;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::Ground;
+use crate::physics::layers;
+
+/// Node name suffixes that request a collider
+const TRIMESH_SUFFIX: &str = "_trimesh";
+const CONVEX_SUFFIX: &str = "_convex";
+/// Node name suffix marking a collider as walkable ground
+const GROUND_SUFFIX: &str = "_ground";
+
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, generate_environment_colliders);
+    }
+}
+
+/// A GLTF scene used as level art
+///
+/// Meshes whose node is tagged get a fixed collider, either by a name
+/// suffix (`_trimesh`, `_convex`, with `_ground` for walkable surfaces) or
+/// by extras such as `{"collider": "trimesh", "ground": true}`
+#[derive(Component)]
+pub struct EnvironmentScene;
+
+/// The first scene of the GLTF at `path`
+pub fn environment_scene(asset_server: &AssetServer, path: &str) -> impl Bundle {
+    (
+        SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(path.to_string()))),
+        Name::new(format!("Environment {path}")),
+        EnvironmentScene,
+    )
+}
+
+/// Which collider a tagged node asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ColliderTag {
+    convex: bool,
+    ground: bool,
+}
+
+impl ColliderTag {
+    fn parse(name: Option<&Name>, extras: Option<&GltfExtras>) -> Option<Self> {
+        let name = name.map_or("", |name| name.as_str()).to_lowercase();
+        // extras are JSON, but only these few keys matter so skip a parser
+        let extras: String = extras
+            .map_or("", |extras| extras.value.as_str())
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+
+        let trimesh = name.contains(TRIMESH_SUFFIX) || extras.contains(r#""collider":"trimesh""#);
+        let convex = name.contains(CONVEX_SUFFIX) || extras.contains(r#""collider":"convex""#);
+        if !trimesh && !convex {
+            return None;
+        }
+
+        return Some(Self {
+            convex: convex && !trimesh,
+            ground: name.contains(GROUND_SUFFIX) || extras.contains(r#""ground":true"#),
+        });
+    }
+
+    fn shape(&self) -> ComputedColliderShape {
+        if self.convex {
+            return ComputedColliderShape::ConvexHull;
+        }
+        return ComputedColliderShape::TriMesh(TriMeshFlags::default());
+    }
+}
+
+/// GLTF puts names and extras on the node, and the mesh on a child of it,
+/// so check the mesh entity and then its parent for tags
+fn generate_environment_colliders(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    added: Query<(Entity, &Mesh3d, Option<&ChildOf>), (Added<Mesh3d>, Without<Collider>)>,
+    tags: Query<(Option<&Name>, Option<&GltfExtras>)>,
+    ancestors: Query<&ChildOf>,
+    scenes: Query<(), With<EnvironmentScene>>,
+) {
+    for (entity, mesh, parent) in added.iter() {
+        let in_environment = ancestors
+            .iter_ancestors(entity)
+            .any(|ancestor| scenes.contains(ancestor));
+        if !in_environment {
+            continue;
+        }
+
+        let tag = [Some(entity), parent.map(ChildOf::parent)]
+            .into_iter()
+            .flatten()
+            .find_map(|tagged| {
+                let (name, extras) = tags.get(tagged).ok()?;
+                ColliderTag::parse(name, extras)
+            });
+        let Some(tag) = tag else {
+            continue;
+        };
+
+        let collider = meshes
+            .get(&mesh.0)
+            .and_then(|mesh| Collider::from_bevy_mesh(mesh, &tag.shape()));
+        let Some(collider) = collider else {
+            warn!("could not build a collider for environment mesh {entity}");
+            continue;
+        };
+
+        let mut entity = commands.entity(entity);
+        entity.insert((RigidBody::Fixed, collider, layers::ground()));
+        if tag.ground {
+            entity.insert((
+                Ground,
+                Friction {
+                    coefficient: 0.0,
+                    ..default()
+                },
+            ));
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::combat::{DamageEvent, DeathEvent, apply_damage};
+use crate::pickups::PickupCollected;
+use crate::prefab_library::PrefabName;
+use crate::{Enemy, GameState};
+
+/// Oldest entries are dropped past this many
+const EVENT_LOG_CAPACITY: usize = 500;
+
+/// Recent gameplay events as lines of text, for the inspector's events tab
+pub struct EventLogPlugin;
+
+impl Plugin for EventLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EventLog>();
+        app.add_systems(
+            Update,
+            (
+                log_damage.after(apply_damage),
+                log_deaths,
+                log_spawns,
+                log_pickups,
+                log_state_changes,
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoggedKind {
+    Damage,
+    Death,
+    Spawn,
+    Pickup,
+    State,
+}
+
+impl LoggedKind {
+    pub const ALL: [LoggedKind; 5] = [
+        LoggedKind::Damage,
+        LoggedKind::Death,
+        LoggedKind::Spawn,
+        LoggedKind::Pickup,
+        LoggedKind::State,
+    ];
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    /// Seconds since startup
+    pub time: f32,
+    pub kind: LoggedKind,
+    /// Everything the event involves, to filter by
+    pub entities: Vec<Entity>,
+    pub text: String,
+}
+
+#[derive(Resource, Default)]
+pub struct EventLog {
+    pub events: VecDeque<LoggedEvent>,
+}
+
+impl EventLog {
+    fn push(&mut self, time: &Time, kind: LoggedKind, entities: Vec<Entity>, text: String) {
+        if self.events.len() == EVENT_LOG_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(LoggedEvent {
+            time: time.elapsed_secs(),
+            kind,
+            entities,
+            text,
+        });
+    }
+}
+
+/// The entity's name with its id, or just the id if it has no name or is
+/// already gone
+fn name_of(names: &Query<&Name>, entity: Entity) -> String {
+    return match names.get(entity) {
+        Ok(name) => format!("{name} {entity}"),
+        Err(_) => entity.to_string(),
+    };
+}
+
+/// Damage after `apply_damage`, so crits show as crits
+fn log_damage(
+    time: Res<Time>,
+    mut log: ResMut<EventLog>,
+    mut damage: EventReader<DamageEvent>,
+    names: Query<&Name>,
+) {
+    for event in damage.read() {
+        let target = name_of(&names, event.target);
+        let mut text = format!("{target} took {:.1}", event.amount);
+        let mut entities = vec![event.target];
+        if let Some(source) = event.source {
+            text += &format!(" from {}", name_of(&names, source));
+            entities.push(source);
+        }
+        if let Some(weapon) = event.weapon {
+            text += &format!(" ({weapon:?})");
+        }
+        if event.crit {
+            text += ", crit";
+        }
+        log.push(&time, LoggedKind::Damage, entities, text);
+    }
+}
+
+fn log_deaths(
+    time: Res<Time>,
+    mut log: ResMut<EventLog>,
+    mut deaths: EventReader<DeathEvent>,
+    names: Query<&Name>,
+) {
+    for death in deaths.read() {
+        let text = format!("{} died", name_of(&names, death.entity));
+        log.push(&time, LoggedKind::Death, vec![death.entity], text);
+    }
+}
+
+/// Enemies are pooled, but `PrefabName` is put back on every spawn, so this
+/// catches new and reused ones alike
+fn log_spawns(
+    time: Res<Time>,
+    mut log: ResMut<EventLog>,
+    enemies: Query<(Entity, &PrefabName, &Transform), (Added<PrefabName>, With<Enemy>)>,
+) {
+    for (entity, name, transform) in enemies.iter() {
+        let position = transform.translation;
+        let text = format!(
+            "{} {entity} spawned at {:.1}, {:.1}",
+            name.0, position.x, position.z
+        );
+        log.push(&time, LoggedKind::Spawn, vec![entity], text);
+    }
+}
+
+fn log_pickups(
+    time: Res<Time>,
+    mut log: ResMut<EventLog>,
+    mut collected: EventReader<PickupCollected>,
+    names: Query<&Name>,
+) {
+    for event in collected.read() {
+        let collector = name_of(&names, event.collector);
+        let text = format!("{collector} collected {:?}", event.pickup.kind);
+        log.push(&time, LoggedKind::Pickup, vec![event.collector], text);
+    }
+}
+
+fn log_state_changes(
+    time: Res<Time>,
+    mut log: ResMut<EventLog>,
+    mut transitions: EventReader<StateTransitionEvent<GameState>>,
+) {
+    for transition in transitions.read() {
+        let text = format!("{:?} -> {:?}", transition.exited, transition.entered);
+        log.push(&time, LoggedKind::State, Vec::new(), text);
+    }
+}
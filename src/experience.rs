@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::GameplaySet;
+use crate::combo::Combo;
+use crate::pickups::{PickupCollected, PickupKind};
+
+pub struct ExperiencePlugin;
+
+impl Plugin for ExperiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Experience>();
+        app.add_event::<LevelUp>();
+        app.add_systems(Update, gain_experience.in_set(GameplaySet));
+    }
+}
+
+/// Experience collected towards the next level
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Experience {
+    pub level: u32,
+    pub current: u32,
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self {
+            level: 1,
+            current: 0,
+        }
+    }
+}
+
+impl Experience {
+    /// Experience needed to go from `level` to the next one
+    pub fn required(level: u32) -> u32 {
+        return 5 + 10 * (level - 1);
+    }
+
+    pub fn fraction(&self) -> f32 {
+        return self.current as f32 / Self::required(self.level) as f32;
+    }
+
+    /// Add `amount`, returning each level reached along the way
+    pub fn gain(&mut self, amount: u32) -> Vec<u32> {
+        let mut levels = Vec::new();
+        self.current = self.current.saturating_add(amount);
+        while self.current >= Self::required(self.level) {
+            self.current -= Self::required(self.level);
+            self.level += 1;
+            levels.push(self.level);
+        }
+        return levels;
+    }
+}
+
+/// Sent once per level gained, so a big pickup can send several
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LevelUp {
+    pub entity: Entity,
+    pub level: u32,
+}
+
+fn gain_experience(
+    mut collected: EventReader<PickupCollected>,
+    mut query: Query<&mut Experience>,
+    mut level_ups: EventWriter<LevelUp>,
+    combo: Res<Combo>,
+) {
+    for event in collected.read() {
+        let PickupKind::Experience(amount) = event.pickup.kind else {
+            continue;
+        };
+
+        let Ok(mut experience) = query.get_mut(event.collector) else {
+            continue;
+        };
+
+        let amount = (amount as f32 * combo.multiplier()).round() as u32;
+        for level in experience.gain(amount) {
+            level_ups.write(LevelUp {
+                entity: event.collector,
+                level,
+            });
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Catches players who fall off the map, hurting them and putting them back
+//! on the last ground they stood on instead of letting them fall forever
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::combat::{DamageEvent, DamageType};
+use crate::{GameplaySet, GroundNormal, Player};
+
+/// Anyone below this has left the map for good
+const KILL_PLANE_Y: f32 = -30.0;
+/// Taken for every fall
+const FALL_DAMAGE: f32 = 15.0;
+/// Ground any steeper than this isn't somewhere to be put back on
+const MIN_SAFE_NORMAL_Y: f32 = 0.8;
+/// Dropped back in from a little above the ground, so they don't land
+/// inside it
+const RESPAWN_HEIGHT: f32 = 2.0;
+
+pub struct FallRecoveryPlugin;
+
+impl Plugin for FallRecoveryPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SafeGround>();
+        app.add_systems(
+            Update,
+            (record_safe_ground, recover_fallen_players)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Where the player last stood on flat enough ground
+#[derive(Component, Reflect, Deref, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct SafeGround(pub Vec3);
+
+fn record_safe_ground(
+    mut commands: Commands,
+    mut players: Query<(Entity, &Transform, &GroundNormal, Option<&mut SafeGround>), With<Player>>,
+) {
+    for (entity, transform, normal, safe_ground) in players.iter_mut() {
+        if !normal.is_some_and(|normal| normal.y >= MIN_SAFE_NORMAL_Y) {
+            continue;
+        }
+        match safe_ground {
+            Some(mut safe_ground) => safe_ground.0 = transform.translation,
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(SafeGround(transform.translation));
+            }
+        }
+    }
+}
+
+fn recover_fallen_players(
+    mut players: Query<(Entity, &mut Transform, &mut Velocity, Option<&SafeGround>), With<Player>>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for (entity, mut transform, mut velocity, safe_ground) in players.iter_mut() {
+        if transform.translation.y >= KILL_PLANE_Y {
+            continue;
+        }
+
+        // never touched the ground, the middle of the map is the best guess
+        let ground = safe_ground.map_or(Vec3::ZERO, |ground| **ground);
+        transform.translation = ground + Vec3::Y * RESPAWN_HEIGHT;
+        *velocity = Velocity::zero();
+
+        damage.write(DamageEvent {
+            target: entity,
+            amount: FALL_DAMAGE,
+            source: None,
+            crit: false,
+            weapon: None,
+            kind: DamageType::Physical,
+        });
+    }
+}
@@ -0,0 +1,219 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::abilities::Decoy;
+use crate::ai::chase_player;
+use crate::physics::layers;
+use crate::{GameplaySet, Player};
+
+const CELL_SIZE: f32 = 2.0;
+/// Cells from the center of the field to its edge, enough to reach past the
+/// spawn ring
+const HALF_CELLS: i32 = 28;
+const RECOMPUTE_SECONDS: f32 = 0.25;
+/// Anything fixed at this height blocks a cell, above the floor and the
+/// patches lying on it
+const PROBE_HEIGHT: f32 = 1.0;
+/// Path costs of a straight and a diagonal step
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+/// Closer than this to a target, chasers head straight for it rather than
+/// stepping from cell to cell
+const NEAR_COST: u32 = STRAIGHT_COST * 2;
+
+pub struct FlowFieldPlugin;
+
+impl Plugin for FlowFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlowField>();
+        app.add_systems(
+            Update,
+            update_flow_field.before(chase_player).in_set(GameplaySet),
+        );
+    }
+}
+
+/// Which way to walk from anywhere around the players to reach the nearest
+/// of them, or a decoy, going around anything fixed in the way
+///
+/// Shared by every chaser, so a horde of any size costs one search over the
+/// grid every `RECOMPUTE_SECONDS` rather than one each
+#[derive(Resource)]
+pub struct FlowField {
+    timer: Timer,
+    /// Corner of the cell at index 0
+    origin: Vec2,
+    /// Steps to the nearest target, `u32::MAX` where none can be reached
+    costs: Vec<u32>,
+    /// Unit direction towards the next cell on the way, zero on a target's
+    /// cell
+    directions: Vec<Vec2>,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        return Self {
+            timer: Timer::from_seconds(RECOMPUTE_SECONDS, TimerMode::Repeating),
+            origin: Vec2::ZERO,
+            costs: Vec::new(),
+            directions: Vec::new(),
+        };
+    }
+}
+
+impl FlowField {
+    const WIDTH: i32 = HALF_CELLS * 2 + 1;
+
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        let in_bounds =
+            cell.cmpge(IVec2::ZERO).all() && cell.cmplt(IVec2::splat(Self::WIDTH)).all();
+        if !in_bounds {
+            return None;
+        }
+        return Some((cell.y * Self::WIDTH + cell.x) as usize);
+    }
+
+    fn cell(&self, pos: Vec2) -> IVec2 {
+        return ((pos - self.origin) / CELL_SIZE).floor().as_ivec2();
+    }
+
+    fn center(&self, cell: IVec2) -> Vec2 {
+        return self.origin + (cell.as_vec2() + 0.5) * CELL_SIZE;
+    }
+
+    /// Which way to head from `pos` on the XZ plane. `None` off the field,
+    /// where no target can be reached, and right by a target, where heading
+    /// straight for it is better
+    pub fn direction(&self, pos: Vec2) -> Option<Vec2> {
+        let index = self.index(self.cell(pos))?;
+        let cost = *self.costs.get(index)?;
+        if cost <= NEAR_COST || cost == u32::MAX {
+            return None;
+        }
+        return Some(self.directions[index]).filter(|dir| *dir != Vec2::ZERO);
+    }
+}
+
+/// Re-centred on the players every time, snapped to the grid so the field
+/// doesn't shift under enemies as they move
+fn update_flow_field(
+    time: Res<Time>,
+    mut field: ResMut<FlowField>,
+    targets: Query<&Transform, Or<(With<Player>, With<Decoy>)>>,
+    players: Query<&Transform, With<Player>>,
+    rapier_context: ReadRapierContext,
+) {
+    if !field.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let count = players.iter().len();
+    if count == 0 {
+        field.costs.clear();
+        return;
+    }
+    let center = players.iter().map(|p| p.translation.xz()).sum::<Vec2>() / count as f32;
+    field.origin = ((center / CELL_SIZE).floor() - HALF_CELLS as f32) * CELL_SIZE;
+
+    let width = FlowField::WIDTH;
+    let cells = (width * width) as usize;
+    let context = rapier_context.single().ok();
+    let probe = Collider::cuboid(CELL_SIZE * 0.4, 0.5, CELL_SIZE * 0.4);
+    let filter = QueryFilter::only_fixed()
+        .exclude_sensors()
+        .groups(CollisionGroups::new(layers::ENEMY, layers::GROUND));
+    let blocked: Vec<bool> = (0..cells)
+        .map(|i| {
+            let Some(context) = &context else {
+                return false;
+            };
+            let cell = IVec2::new(i as i32 % width, i as i32 / width);
+            let pos = field.center(cell);
+            let mut hit = false;
+            context.intersections_with_shape(
+                Vec3::new(pos.x, PROBE_HEIGHT, pos.y),
+                Quat::IDENTITY,
+                &probe,
+                filter,
+                |_| {
+                    hit = true;
+                    return false;
+                },
+            );
+            return hit;
+        })
+        .collect();
+
+    let mut costs = vec![u32::MAX; cells];
+    let mut open = BinaryHeap::new();
+    for target in targets.iter() {
+        let Some(index) = field.index(field.cell(target.translation.xz())) else {
+            continue;
+        };
+        costs[index] = 0;
+        open.push(Reverse((0, index)));
+    }
+
+    let neighbours = |index: usize| {
+        let cell = IVec2::new(index as i32 % width, index as i32 / width);
+        let blocked = &blocked;
+        let walkable = move |cell: IVec2| {
+            let in_bounds = cell.cmpge(IVec2::ZERO).all() && cell.cmplt(IVec2::splat(width)).all();
+            return in_bounds && !blocked[(cell.y * width + cell.x) as usize];
+        };
+        [
+            IVec2::X,
+            IVec2::NEG_X,
+            IVec2::Y,
+            IVec2::NEG_Y,
+            IVec2::ONE,
+            IVec2::NEG_ONE,
+            IVec2::new(1, -1),
+            IVec2::new(-1, 1),
+        ]
+        .into_iter()
+        // no cutting corners past an obstacle
+        .filter(move |step| {
+            walkable(cell + *step)
+                && walkable(cell + IVec2::new(step.x, 0))
+                && walkable(cell + IVec2::new(0, step.y))
+        })
+        .map(move |step| {
+            let next = cell + step;
+            let cost = match step.x != 0 && step.y != 0 {
+                true => DIAGONAL_COST,
+                false => STRAIGHT_COST,
+            };
+            return ((next.y * width + next.x) as usize, step, cost);
+        })
+    };
+
+    while let Some(Reverse((cost, index))) = open.pop() {
+        if cost > costs[index] {
+            continue;
+        }
+        for (next, _, step_cost) in neighbours(index) {
+            let next_cost = cost + step_cost;
+            if next_cost < costs[next] {
+                costs[next] = next_cost;
+                open.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+
+    let directions = (0..cells)
+        .map(|index| {
+            if costs[index] == 0 || costs[index] == u32::MAX {
+                return Vec2::ZERO;
+            }
+            return neighbours(index)
+                .min_by_key(|(next, _, _)| costs[*next])
+                .map_or(Vec2::ZERO, |(_, step, _)| step.as_vec2().normalize());
+        })
+        .collect();
+
+    field.costs = costs;
+    field.directions = directions;
+}
@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+
+use crate::afterimage::Afterimage;
+use crate::boss::ArenaLock;
+use crate::combat::DeathEvent;
+use crate::corpses::Corpse;
+use crate::damage_zone::DamageZone;
+use crate::experience::Experience;
+use crate::hazards::HazardLifetime;
+use crate::pickups::Pickup;
+use crate::pool::ReleaseCommandsExt;
+use crate::projectile::Projectile;
+use crate::rng::{GameSeed, NextRunSeed};
+use crate::run_config::RunConfig;
+use crate::run_stats::RunStats;
+use crate::save::{RunRecord, SaveData};
+use crate::telegraph::Telegraph;
+use crate::transition::TransitionTo;
+use crate::{Enemy, GameState, GameplaySet, Player, PlayerSlot};
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerDied>();
+        app.add_event::<RunFinished>();
+        app.add_systems(
+            Update,
+            (detect_player_deaths, end_run_on_death)
+                .chain()
+                .in_set(GameplaySet),
+        );
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            (record_run, spawn_game_over_screen),
+        );
+        app.add_systems(
+            Update,
+            (return_to_menu, retry_seed).run_if(in_state(GameState::GameOver)),
+        );
+        app.add_systems(OnExit(GameState::GameOver), clean_up_run);
+    }
+}
+
+/// Retries the run without reaching for the mouse
+const RETRY_KEY: KeyCode = KeyCode::KeyR;
+
+#[derive(Component)]
+struct MainMenuButton;
+
+/// Starts another run straight away from the same seed
+#[derive(Component)]
+struct RetrySeedButton;
+
+/// A player's health ran out, sent before they are taken out of the run
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerDied {
+    pub player: Entity,
+    pub slot: usize,
+}
+
+/// A run ended and was added to the leaderboard
+#[derive(Event, Debug, Clone)]
+pub struct RunFinished(pub RunRecord);
+
+fn detect_player_deaths(
+    mut deaths: EventReader<DeathEvent>,
+    players: Query<&PlayerSlot, With<Player>>,
+    mut died: EventWriter<PlayerDied>,
+) {
+    for death in deaths.read() {
+        if let Ok(slot) = players.get(death.entity) {
+            died.write(PlayerDied {
+                player: death.entity,
+                slot: **slot,
+            });
+        }
+    }
+}
+
+/// The run goes on while any player is still standing, the others are
+/// taken out of it
+fn end_run_on_death(
+    mut commands: Commands,
+    mut died: EventReader<PlayerDied>,
+    players: Query<Entity, With<Player>>,
+    mut transition: EventWriter<TransitionTo>,
+) {
+    let dead: Vec<Entity> = died.read().map(|died| died.player).collect();
+    if dead.is_empty() {
+        return;
+    }
+
+    if players.iter().all(|player| dead.contains(&player)) {
+        transition.write(TransitionTo(GameState::GameOver));
+    } else {
+        for player in dead {
+            commands.entity(player).try_despawn();
+        }
+    }
+}
+
+/// Add the run to the leaderboard
+fn record_run(
+    stats: Res<RunStats>,
+    players: Query<&Experience, With<Player>>,
+    mut save: ResMut<SaveData>,
+    (seed, config): (Res<GameSeed>, Res<RunConfig>),
+    mut finished: EventWriter<RunFinished>,
+) {
+    let record = RunRecord {
+        survived_seconds: stats.survived_seconds,
+        level: players
+            .iter()
+            .map(|experience| experience.level)
+            .max()
+            .unwrap_or(1),
+        kills: stats.total_kills(),
+        gold: stats.gold_collected,
+        character: config.character().name.to_string(),
+        seed: Some(**seed),
+        difficulty: stats.difficulty,
+    };
+    save.record_run(record.clone());
+    finished.write(RunFinished(record));
+}
+
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    stats: Res<RunStats>,
+    seed: Res<GameSeed>,
+) {
+    time.pause();
+
+    let minutes = stats.survived_seconds as u32 / 60;
+    let seconds = stats.survived_seconds as u32 % 60;
+    let mut lines = vec![
+        format!("Survived {minutes}:{seconds:02}"),
+        format!("Difficulty: {}", stats.difficulty.name()),
+        format!("Kills: {}", stats.total_kills()),
+        format!("Damage dealt: {:.0}", stats.total_damage_dealt()),
+        format!("Damage taken: {:.0}", stats.damage_taken),
+        format!("Pickups collected: {}", stats.pickups_collected),
+        format!("Gold collected: {}", stats.gold_collected),
+        format!("Distance traveled: {:.0}", stats.distance_traveled),
+        format!("Seed: {}", **seed),
+    ];
+    for (weapon, weapon_stats) in stats.weapons_by_damage() {
+        lines.push(format!(
+            "  {}: {:.0} ({:.1} dps), {} hits, {:.0}% crits",
+            weapon.name(),
+            weapon_stats.damage,
+            weapon_stats.average_dps(stats.survived_seconds),
+            weapon_stats.hits,
+            weapon_stats.crit_rate() * 100.0,
+        ));
+    }
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Name::new("Game Over Screen"),
+            StateScoped(GameState::GameOver),
+        ))
+        .with_children(|screen| {
+            screen.spawn((Text::new("Game Over"), TextFont::from_font_size(56.0)));
+            for line in lines {
+                screen.spawn((Text::new(line), TextFont::from_font_size(20.0)));
+            }
+            screen.spawn((
+                Button,
+                Node {
+                    padding: UiRect::all(Val::Px(8.0)),
+                    margin: UiRect::top(Val::Px(16.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.9)),
+                RetrySeedButton,
+                children![(
+                    Text::new("Retry Same Seed (R)"),
+                    TextFont::from_font_size(28.0)
+                )],
+            ));
+            screen.spawn((
+                Button,
+                Node {
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.9)),
+                MainMenuButton,
+                children![(Text::new("Main Menu"), TextFont::from_font_size(28.0))],
+            ));
+        });
+}
+
+fn return_to_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<MainMenuButton>)>,
+    mut transition: EventWriter<TransitionTo>,
+) {
+    let clicked = buttons.iter().any(|i| *i == Interaction::Pressed);
+    if clicked || keys.just_pressed(KeyCode::Enter) {
+        transition.write(TransitionTo(GameState::MainMenu));
+    }
+}
+
+/// Straight back into a run, skipping the menus so the character and
+/// settings stay as they were
+fn retry_seed(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<RetrySeedButton>)>,
+    seed: Res<GameSeed>,
+    mut next_seed: ResMut<NextRunSeed>,
+    mut transition: EventWriter<TransitionTo>,
+) {
+    let clicked = buttons.iter().any(|i| *i == Interaction::Pressed);
+    if clicked || keys.just_pressed(RETRY_KEY) {
+        **next_seed = Some(**seed);
+        transition.write(TransitionTo(GameState::InGame));
+    }
+}
+
+/// Despawn everything left over from the run
+fn clean_up_run(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    leftovers: Query<
+        Entity,
+        Or<(
+            With<Player>,
+            With<Enemy>,
+            With<Corpse>,
+            With<Pickup>,
+            With<Projectile>,
+            With<HazardLifetime>,
+            With<DamageZone>,
+            With<Telegraph>,
+            With<Afterimage>,
+        )>,
+    >,
+) {
+    time.unpause();
+    commands.remove_resource::<ArenaLock>();
+    for entity in leftovers.iter() {
+        commands.entity(entity).release();
+    }
+}
@@ -0,0 +1,321 @@
+use std::time::{Duration, Instant};
+
+use bevy::pbr::PointLightShadowMap;
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, PresentMode, PrimaryWindow, VideoModeSelection, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::MainCamera;
+use crate::level::Sun;
+use crate::save::SaveData;
+
+/// Window sizes the settings cycle through, in logical pixels
+pub const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+
+pub struct GraphicsPlugin;
+
+impl Plugin for GraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GraphicsSettings>();
+        app.init_resource::<GraphicsSettings>();
+        app.add_systems(Startup, load_graphics_settings);
+        app.add_systems(
+            Update,
+            (
+                save_graphics_settings.run_if(resource_changed::<GraphicsSettings>),
+                apply_graphics_settings,
+                apply_window_settings.run_if(resource_changed::<GraphicsSettings>),
+            ),
+        );
+        app.add_systems(Last, limit_frame_rate);
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphicsQuality {
+    Low,
+    Medium,
+    #[default]
+    High,
+}
+
+impl GraphicsQuality {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraphicsQuality::Low => "Low",
+            GraphicsQuality::Medium => "Medium",
+            GraphicsQuality::High => "High",
+        }
+    }
+
+    /// The next preset up, wrapping back round to the lowest
+    pub fn next(&self) -> Self {
+        match self {
+            GraphicsQuality::Low => GraphicsQuality::Medium,
+            GraphicsQuality::Medium => GraphicsQuality::High,
+            GraphicsQuality::High => GraphicsQuality::Low,
+        }
+    }
+
+    fn shadows(&self) -> bool {
+        return *self != GraphicsQuality::Low;
+    }
+
+    /// Size of each face of the sun's shadow cube map, in texels
+    fn shadow_map_size(&self) -> usize {
+        match self {
+            GraphicsQuality::Low => 256,
+            GraphicsQuality::Medium => 512,
+            GraphicsQuality::High => 1024,
+        }
+    }
+
+    fn msaa(&self) -> Msaa {
+        match self {
+            GraphicsQuality::Low => Msaa::Off,
+            GraphicsQuality::Medium => Msaa::Sample2,
+            GraphicsQuality::High => Msaa::Sample4,
+        }
+    }
+
+    /// How far the sun reaches and how bright it is, relative to the level.
+    /// A shorter reach means fewer meshes lit by it
+    fn light_scale(&self) -> (f32, f32) {
+        match self {
+            GraphicsQuality::Low => (0.6, 0.8),
+            GraphicsQuality::Medium => (0.8, 0.9),
+            GraphicsQuality::High => (1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            DisplayMode::Borderless => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        }
+    }
+
+    /// Full screen modes take over whichever monitor the window is on
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+            DisplayMode::Fullscreen => {
+                WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+            }
+        }
+    }
+}
+
+/// Most frames drawn each second, on top of whatever vsync allows
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameCap {
+    Fps30,
+    Fps60,
+    Fps144,
+    #[default]
+    Uncapped,
+}
+
+impl FrameCap {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameCap::Fps30 => "30",
+            FrameCap::Fps60 => "60",
+            FrameCap::Fps144 => "144",
+            FrameCap::Uncapped => "Uncapped",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            FrameCap::Fps30 => FrameCap::Fps60,
+            FrameCap::Fps60 => FrameCap::Fps144,
+            FrameCap::Fps144 => FrameCap::Uncapped,
+            FrameCap::Uncapped => FrameCap::Fps30,
+        }
+    }
+
+    /// Shortest a frame may take
+    fn frame_time(&self) -> Option<Duration> {
+        let fps = match self {
+            FrameCap::Fps30 => 30.0,
+            FrameCap::Fps60 => 60.0,
+            FrameCap::Fps144 => 144.0,
+            FrameCap::Uncapped => return None,
+        };
+        return Some(Duration::from_secs_f64(1.0 / fps));
+    }
+}
+
+/// How good the game looks, traded off against how fast it runs, and how the
+/// window is shown
+///
+/// Applied to the camera and the sun as they spawn and to the window
+/// whenever it changes, and saved along with the other settings
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+#[reflect(Resource)]
+pub struct GraphicsSettings {
+    pub quality: GraphicsQuality,
+    pub display_mode: DisplayMode,
+    /// Size of the window when windowed, `None` keeps the size it opened at
+    pub resolution: Option<(u32, u32)>,
+    pub vsync: bool,
+    pub frame_cap: FrameCap,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            quality: GraphicsQuality::default(),
+            display_mode: DisplayMode::default(),
+            resolution: None,
+            vsync: true,
+            frame_cap: FrameCap::default(),
+        }
+    }
+}
+
+impl GraphicsSettings {
+    /// The next of `RESOLUTIONS`, wrapping back round to the smallest
+    pub fn next_resolution(&self) -> (u32, u32) {
+        let current = self
+            .resolution
+            .and_then(|resolution| RESOLUTIONS.iter().position(|r| *r == resolution));
+        return match current {
+            Some(i) => RESOLUTIONS[(i + 1) % RESOLUTIONS.len()],
+            None => RESOLUTIONS[0],
+        };
+    }
+}
+
+fn load_graphics_settings(save: Res<SaveData>, mut settings: ResMut<GraphicsSettings>) {
+    settings.quality = save.settings.graphics_quality;
+    settings.display_mode = save.settings.display_mode;
+    settings.resolution = save.settings.resolution;
+    settings.vsync = save.settings.vsync;
+    settings.frame_cap = save.settings.frame_cap;
+}
+
+fn save_graphics_settings(settings: Res<GraphicsSettings>, mut save: ResMut<SaveData>) {
+    SaveData::update_setting(
+        &mut save,
+        |saved| &mut saved.graphics_quality,
+        settings.quality,
+    );
+    SaveData::update_setting(
+        &mut save,
+        |saved| &mut saved.display_mode,
+        settings.display_mode,
+    );
+    SaveData::update_setting(
+        &mut save,
+        |saved| &mut saved.resolution,
+        settings.resolution,
+    );
+    SaveData::update_setting(&mut save, |saved| &mut saved.vsync, settings.vsync);
+    SaveData::update_setting(&mut save, |saved| &mut saved.frame_cap, settings.frame_cap);
+}
+
+/// Headless runs have no window, so there's nothing to apply
+fn apply_window_settings(
+    settings: Res<GraphicsSettings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+
+    let mode = settings.display_mode.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    if let Some((width, height)) = settings.resolution {
+        let size = Vec2::new(width as f32, height as f32);
+        if window.resolution.size() != size {
+            window.resolution.set(size.x, size.y);
+        }
+    }
+    let present_mode = match settings.vsync {
+        true => PresentMode::AutoVsync,
+        false => PresentMode::AutoNoVsync,
+    };
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+/// Sleeps off whatever is left of the frame once everything else has run.
+/// Headless runs step time by hand, so they're never held back
+fn limit_frame_rate(
+    settings: Res<GraphicsSettings>,
+    windows: Query<(), With<PrimaryWindow>>,
+    mut frame_start: Local<Option<Instant>>,
+) {
+    let now = Instant::now();
+    let Some(frame_time) = settings.frame_cap.frame_time() else {
+        *frame_start = Some(now);
+        return;
+    };
+    if windows.is_empty() {
+        return;
+    }
+
+    if let Some(start) = *frame_start {
+        let elapsed = now.duration_since(start);
+        if elapsed < frame_time {
+            std::thread::sleep(frame_time - elapsed);
+        }
+    }
+    *frame_start = Some(Instant::now());
+}
+
+/// The level respawns its camera and sun on reload, so new ones are set up
+/// too, not just when the settings change
+fn apply_graphics_settings(
+    mut commands: Commands,
+    settings: Res<GraphicsSettings>,
+    cameras: Query<Entity, With<MainCamera>>,
+    added_cameras: Query<(), Added<MainCamera>>,
+    mut suns: Query<(&Sun, &mut PointLight)>,
+    added_suns: Query<(), Added<Sun>>,
+) {
+    if !settings.is_changed() && added_cameras.is_empty() && added_suns.is_empty() {
+        return;
+    }
+    let quality = settings.quality;
+
+    commands.insert_resource(PointLightShadowMap {
+        size: quality.shadow_map_size(),
+    });
+
+    for camera in cameras.iter() {
+        commands.entity(camera).insert(quality.msaa());
+    }
+
+    let (range, intensity) = quality.light_scale();
+    for (sun, mut light) in suns.iter_mut() {
+        light.shadows_enabled = quality.shadows();
+        light.range = sun.range * range;
+        light.intensity = sun.intensity * intensity;
+    }
+}
@@ -0,0 +1,175 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::GameplaySet;
+use crate::combat::{DamageEvent, DamageType, Health};
+use crate::physics::layers;
+use crate::status::{ApplyStatus, StatusEffect};
+
+const HAZARD_TICK_SECONDS: f32 = 0.5;
+const SPIKE_DAMAGE: f32 = 6.0;
+/// Seconds for a hazard's glow to pulse once
+const PULSE_SECONDS: f32 = 1.5;
+
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_hazard_assets);
+        app.add_systems(
+            Update,
+            (tick_hazards, expire_hazards, pulse_hazard_materials).in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum HazardKind {
+    /// Damages whatever stands on it
+    Spikes,
+    /// Poisons and slows whatever stands in it
+    PoisonPool,
+}
+
+/// A sensor area affecting every entity with `Health` inside it, once per
+/// tick
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub tick: Timer,
+}
+
+/// Despawn the hazard when this finishes, for hazards left by attacks
+#[derive(Component, Reflect, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct HazardLifetime(pub Timer);
+
+#[derive(Resource)]
+pub struct HazardAssets {
+    pub mesh: Handle<Mesh>,
+    pub spike_material: Handle<StandardMaterial>,
+    pub poison_material: Handle<StandardMaterial>,
+}
+
+fn setup_hazard_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(HazardAssets {
+        mesh: meshes.add(Cylinder::new(1.0, 0.05)),
+        spike_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.5, 0.45, 0.4),
+            ..default()
+        }),
+        poison_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.3, 0.8, 0.2, 0.8),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+    });
+}
+
+/// A round hazard of `radius` on the ground at `position`
+pub fn hazard(assets: &HazardAssets, kind: HazardKind, position: Vec3, radius: f32) -> impl Bundle {
+    let material = match kind {
+        HazardKind::Spikes => assets.spike_material.clone(),
+        HazardKind::PoisonPool => assets.poison_material.clone(),
+    };
+
+    (
+        Mesh3d(assets.mesh.clone()),
+        MeshMaterial3d(material),
+        // the mesh is a unit disc, the collider a tall cylinder so standing
+        // bodies overlap it
+        Transform::from_translation(position + Vec3::Y * 0.1)
+            .with_scale(Vec3::new(radius, 1.0, radius)),
+        Name::new(format!("{kind:?}")),
+        Hazard {
+            kind,
+            tick: Timer::from_seconds(HAZARD_TICK_SECONDS, TimerMode::Repeating),
+        },
+        Collider::cylinder(1.0, 1.0),
+        layers::hazard(),
+        Sensor,
+    )
+}
+
+fn tick_hazards(
+    time: Res<Time>,
+    rapier_context: ReadRapierContext,
+    mut hazards: Query<(Entity, &mut Hazard)>,
+    targets: Query<(), With<Health>>,
+    mut damage: EventWriter<DamageEvent>,
+    mut status: EventWriter<ApplyStatus>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+
+    for (entity, mut hazard) in hazards.iter_mut() {
+        if !hazard.tick.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        for (a, b, intersecting) in context.intersection_pairs_with(entity) {
+            let other = if a == entity { b } else { a };
+            if !intersecting || !targets.contains(other) {
+                continue;
+            }
+
+            match hazard.kind {
+                HazardKind::Spikes => {
+                    damage.write(DamageEvent {
+                        target: other,
+                        amount: SPIKE_DAMAGE,
+                        source: Some(entity),
+                        crit: false,
+                        weapon: None,
+                        kind: DamageType::Physical,
+                    });
+                }
+                HazardKind::PoisonPool => {
+                    for effect in [StatusEffect::Poison, StatusEffect::Slow] {
+                        status.write(ApplyStatus {
+                            target: other,
+                            effect,
+                            stacks: 1,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn expire_hazards(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut HazardLifetime)>,
+) {
+    for (entity, mut lifetime) in query.iter_mut() {
+        if lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Every hazard of a kind shares its material, so pulse the material rather
+/// than each hazard
+fn pulse_hazard_materials(
+    time: Res<Time>,
+    assets: Res<HazardAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let pulse = (time.elapsed_secs() * TAU / PULSE_SECONDS).sin() * 0.5 + 0.5;
+    if let Some(material) = materials.get_mut(&assets.spike_material) {
+        material.emissive = LinearRgba::rgb(0.6, 0.1, 0.1) * pulse;
+    }
+    if let Some(material) = materials.get_mut(&assets.poison_material) {
+        material.emissive = LinearRgba::rgb(0.2, 1.0, 0.1) * pulse;
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::pbr::NotShadowCaster;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::Face;
+
+/// How much bigger than its mesh an outline is drawn
+const OUTLINE_SCALE: f32 = 1.15;
+
+pub struct HighlightPlugin;
+
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Highlight>();
+        app.init_resource::<Outlines>();
+        // outside of `GameplaySet` so outlines of entities cleaned up after
+        // a run are forgotten too
+        app.add_systems(Update, (remove_outlines, update_outlines).chain());
+    }
+}
+
+/// Draws a coloured outline around the entity's mesh, insert or remove it to
+/// toggle the outline
+#[derive(Component, Reflect, Deref, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Highlight(pub Color);
+
+/// The copy of a highlighted mesh drawing its outline
+///
+/// Drawn slightly bigger with only its back faces showing, so it peeks out
+/// around the edges of the mesh in front of it
+#[derive(Component)]
+struct Outline;
+
+/// The outline of every highlighted entity, and a material for each colour
+/// in use
+///
+/// Kept here rather than on the entity itself, since pooling strips
+/// everything off released entities before the outline can be cleaned up
+#[derive(Resource, Default)]
+struct Outlines {
+    outlines: HashMap<Entity, Entity>,
+    materials: HashMap<[u8; 4], Handle<StandardMaterial>>,
+}
+
+fn update_outlines(
+    mut commands: Commands,
+    mut outlines: ResMut<Outlines>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    highlighted: Query<(Entity, &Highlight, &Mesh3d), Changed<Highlight>>,
+) {
+    for (entity, highlight, mesh) in highlighted.iter() {
+        let material = outlines
+            .materials
+            .entry(highlight.to_srgba().to_u8_array())
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: **highlight,
+                    unlit: true,
+                    cull_mode: Some(Face::Front),
+                    ..default()
+                })
+            })
+            .clone();
+
+        if let Some(&outline) = outlines.outlines.get(&entity) {
+            // also reattaches outlines of pooled entities reused this frame
+            commands
+                .entity(outline)
+                .insert((MeshMaterial3d(material), ChildOf(entity)));
+            continue;
+        }
+
+        let outline = commands
+            .spawn((
+                Mesh3d(mesh.0.clone()),
+                MeshMaterial3d(material),
+                Transform::from_scale(Vec3::splat(OUTLINE_SCALE)),
+                NotShadowCaster,
+                Outline,
+                ChildOf(entity),
+            ))
+            .id();
+        outlines.outlines.insert(entity, outline);
+    }
+}
+
+fn remove_outlines(
+    mut commands: Commands,
+    mut outlines: ResMut<Outlines>,
+    mut removed: RemovedComponents<Highlight>,
+    highlighted: Query<(), With<Highlight>>,
+) {
+    for entity in removed.read() {
+        // highlighted again since, say by pooling reusing it
+        if highlighted.contains(entity) {
+            continue;
+        }
+        if let Some(outline) = outlines.outlines.remove(&entity) {
+            commands.entity(outline).try_despawn();
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::GameplaySet;
+use crate::boss::Boss;
+use crate::combat::{DamageEvent, apply_damage};
+use crate::time_scale::{MAX_TIME_SCALE, MIN_TIME_SCALE, TimeScale, apply_time_scale};
+
+/// How long the game freezes for a crit
+const CRIT_STOP: Duration = Duration::from_millis(50);
+/// How long the game freezes when a boss lands a hit
+const BOSS_HIT_STOP: Duration = Duration::from_millis(120);
+/// Real time the game runs between one stop and the next, so a flurry of
+/// crits reads as separate hits rather than one long freeze
+const STOP_GAP: Duration = Duration::from_millis(150);
+/// Stops waiting their turn, past this new ones are dropped
+const MAX_QUEUED: usize = 2;
+
+pub struct HitStopPlugin;
+
+impl Plugin for HitStopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HitStop>();
+        app.add_systems(
+            Update,
+            queue_hit_stops.after(apply_damage).in_set(GameplaySet),
+        );
+        app.add_systems(Update, run_hit_stop.after(apply_time_scale));
+    }
+}
+
+/// Brief freezes of virtual time that sell heavy hits, played one after
+/// another rather than added together
+#[derive(Resource, Default, Debug)]
+pub struct HitStop {
+    queue: VecDeque<Duration>,
+    /// Real time left in the current stop
+    remaining: Duration,
+    /// Real time left before the next stop can start
+    gap: Duration,
+}
+
+impl HitStop {
+    pub fn request(&mut self, duration: Duration) {
+        if self.queue.len() < MAX_QUEUED {
+            self.queue.push_back(duration);
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        return !self.remaining.is_zero();
+    }
+}
+
+/// However many heavy hits land in a frame, only the longest stop is queued
+fn queue_hit_stops(
+    mut damage: EventReader<DamageEvent>,
+    bosses: Query<(), With<Boss>>,
+    mut hit_stop: ResMut<HitStop>,
+) {
+    let longest = damage
+        .read()
+        .filter(|event| event.amount > 0.0)
+        .filter_map(|event| {
+            if event.source.is_some_and(|source| bosses.contains(source)) {
+                return Some(BOSS_HIT_STOP);
+            } else if event.crit {
+                return Some(CRIT_STOP);
+            }
+            return None;
+        })
+        .max();
+    if let Some(duration) = longest {
+        hit_stop.request(duration);
+    }
+}
+
+/// Timed on real time, since virtual time doesn't move during a stop
+fn run_hit_stop(
+    real: Res<Time<Real>>,
+    scale: Res<TimeScale>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let delta = real.delta();
+    if hit_stop.is_stopped() {
+        hit_stop.remaining = hit_stop.remaining.saturating_sub(delta);
+        if hit_stop.is_stopped() {
+            // the time scale may have been changed under us
+            time.set_relative_speed(0.0);
+        } else {
+            time.set_relative_speed(scale.speed.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE));
+            hit_stop.gap = STOP_GAP;
+        }
+        return;
+    }
+
+    hit_stop.gap = hit_stop.gap.saturating_sub(delta);
+    if !hit_stop.gap.is_zero() {
+        return;
+    }
+    if let Some(duration) = hit_stop.queue.pop_front() {
+        hit_stop.remaining = duration;
+        time.set_relative_speed(0.0);
+    }
+}
@@ -0,0 +1,90 @@
+//! Just enough of an HTTP client to talk to the leaderboard server
+//!
+//! Requests are blocking, run them on the `IoTaskPool`. Only plain `http://`
+//! is supported, and requests are sent as HTTP/1.0 so responses always come
+//! back whole rather than chunked
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use thiserror::Error;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum HttpError {
+    #[error("Unsupported URL: {0}")]
+    InvalidUrl(String),
+    #[error("Could not reach server: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed response from server")]
+    MalformedResponse,
+    #[error("Server responded with status {0}")]
+    Status(u16),
+}
+
+pub fn get(url: &str) -> Result<String, HttpError> {
+    return request("GET", url, None);
+}
+
+pub fn post(url: &str, body: &str) -> Result<String, HttpError> {
+    return request("POST", url, Some(body));
+}
+
+/// Send a request and return the body of a successful response
+fn request(method: &str, url: &str, body: Option<&str>) -> Result<String, HttpError> {
+    let (host, path) = split_url(url).ok_or_else(|| HttpError::InvalidUrl(url.to_string()))?;
+    let address = host
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| HttpError::InvalidUrl(url.to_string()))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    // sent in one go, `write!` can split it over several packets
+    let body = body.unwrap_or_default();
+    let request = format!(
+        "{method} {path} HTTP/1.0\r\nHost: {host}\r\nContent-Type: application/ron\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or(HttpError::MalformedResponse)?;
+    let status: u16 = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or(HttpError::MalformedResponse)?;
+    if !(200..300).contains(&status) {
+        return Err(HttpError::Status(status));
+    }
+
+    return Ok(body.to_string());
+}
+
+/// `http://host:port/path` into `host:port` and `/path`, port defaulting to 80
+fn split_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix("http://")?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return None;
+    }
+
+    let host = match host.contains(':') {
+        true => host.to_string(),
+        false => format!("{host}:80"),
+    };
+    return Some((host, path.to_string()));
+}
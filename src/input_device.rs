@@ -0,0 +1,140 @@
+//! Which device the game was last played with, so on screen prompts show the
+//! key or the button to press to match
+//!
+//! Anything showing a prompt reads `ActiveInputDevice` and picks its glyph
+//! with `ActiveInputDevice::glyph`. It follows whatever was touched last,
+//! unless the settings pin it to one device
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::save::SaveData;
+
+/// How far a stick has to be pushed to count as picking up the gamepad, so
+/// one resting a little off center doesn't take the prompts back
+const STICK_THRESHOLD: f32 = 0.5;
+
+pub struct InputDevicePlugin;
+
+impl Plugin for InputDevicePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveInputDevice>();
+        // menus show prompts too, so this runs in every state
+        app.add_systems(Update, detect_input_device);
+    }
+}
+
+/// The device prompts are shown for
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ActiveInputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+}
+
+impl ActiveInputDevice {
+    /// What to show for an action bound to `key` on the keyboard and
+    /// `button` on the gamepad
+    pub fn glyph(&self, key: KeyCode, button: GamepadButton) -> String {
+        match self {
+            ActiveInputDevice::Keyboard => return key_glyph(key),
+            ActiveInputDevice::Gamepad => return button_glyph(button).to_string(),
+        }
+    }
+}
+
+/// Which device prompts follow, picked in the main menu and saved with the
+/// other settings
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputDevicePreference {
+    /// Whichever was used last
+    #[default]
+    Auto,
+    Keyboard,
+    Gamepad,
+}
+
+impl InputDevicePreference {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputDevicePreference::Auto => "Auto",
+            InputDevicePreference::Keyboard => "Keyboard",
+            InputDevicePreference::Gamepad => "Gamepad",
+        }
+    }
+
+    /// The next one along, wrapping back round to auto
+    pub fn next(&self) -> Self {
+        match self {
+            InputDevicePreference::Auto => InputDevicePreference::Keyboard,
+            InputDevicePreference::Keyboard => InputDevicePreference::Gamepad,
+            InputDevicePreference::Gamepad => InputDevicePreference::Auto,
+        }
+    }
+}
+
+/// A key's name as it's printed on the keyboard, near enough
+pub fn key_glyph(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    let name = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Arrow"))
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name);
+    return name.to_string();
+}
+
+/// A button's name as it's printed on an Xbox style pad, where south is A
+pub fn button_glyph(button: GamepadButton) -> &'static str {
+    match button {
+        GamepadButton::South => return "A",
+        GamepadButton::East => return "B",
+        GamepadButton::West => return "X",
+        GamepadButton::North => return "Y",
+        GamepadButton::LeftTrigger => return "LB",
+        GamepadButton::RightTrigger => return "RB",
+        GamepadButton::LeftTrigger2 => return "LT",
+        GamepadButton::RightTrigger2 => return "RT",
+        GamepadButton::LeftThumb => return "LS",
+        GamepadButton::RightThumb => return "RS",
+        GamepadButton::DPadUp => return "D-Pad Up",
+        GamepadButton::DPadDown => return "D-Pad Down",
+        GamepadButton::DPadLeft => return "D-Pad Left",
+        GamepadButton::DPadRight => return "D-Pad Right",
+        GamepadButton::Start => return "Start",
+        GamepadButton::Select => return "Back",
+        _ => return "?",
+    }
+}
+
+fn detect_input_device(
+    save: Res<SaveData>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut active: ResMut<ActiveInputDevice>,
+) {
+    let used = match save.settings.input_device {
+        InputDevicePreference::Keyboard => Some(ActiveInputDevice::Keyboard),
+        InputDevicePreference::Gamepad => Some(ActiveInputDevice::Gamepad),
+        InputDevicePreference::Auto => {
+            let gamepad = gamepads.iter().any(|gamepad| {
+                return gamepad.get_just_pressed().next().is_some()
+                    || gamepad.left_stick().length() >= STICK_THRESHOLD
+                    || gamepad.right_stick().length() >= STICK_THRESHOLD;
+            });
+            let keyboard = keys.get_just_pressed().next().is_some()
+                || mouse.get_just_pressed().next().is_some();
+            // both in the same frame leaves it where it was
+            match (keyboard, gamepad) {
+                (true, false) => Some(ActiveInputDevice::Keyboard),
+                (false, true) => Some(ActiveInputDevice::Gamepad),
+                _ => None,
+            }
+        }
+    };
+
+    if let Some(used) = used {
+        active.set_if_neq(used);
+    }
+}
@@ -1,11 +1,17 @@
 use std::any::TypeId;
+use std::f32::consts::FRAC_PI_2;
 
 use bevy::{
     asset::{ReflectAsset, UntypedAssetId},
+    diagnostic::{
+        DiagnosticPath, DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
+    },
+    input::mouse::AccumulatedMouseMotion,
     math::{DQuat, DVec3},
     prelude::*,
     reflect::TypeRegistry,
     render::camera::{CameraProjection, Viewport},
+    time::TimeSystem,
     window::PrimaryWindow,
 };
 use bevy_egui::EguiGlobalSettings;
@@ -18,23 +24,93 @@ use bevy_inspector_egui::{
         ui_for_entities_shared_components, ui_for_entity_with_children,
     },
 };
+use bevy_rapier3d::plugin::ReadRapierContext;
+use bevy_rapier3d::prelude::QueryFilter;
 
 use bevy::render::view::RenderLayers;
 use egui_dock::{DockArea, DockState, NodeIndex, Style};
-use transform_gizmo_egui::{Gizmo, GizmoConfig, GizmoExt, GizmoOrientation};
+use leafwing_input_manager::prelude::ActionState;
+use transform_gizmo_egui::{
+    EnumSet, Gizmo, GizmoConfig, GizmoExt, GizmoMode, GizmoOrientation, TransformPivotPoint,
+};
+
+use crate::animation::AnimationLibrary;
+use crate::boss;
+use crate::combat::Armor;
+use crate::controls::{Action, DetachedCamera};
+use crate::elites::roll_elite;
+use crate::enemies::EnemyAssets;
+use crate::event_log::{EventLog, EventLogPlugin, LoggedKind};
+use crate::hazards::{HazardAssets, HazardKind, hazard};
+use crate::pickups::{Pickup, PickupAssets, PickupKind, Rarity, pickup_bundle};
+use crate::pool::Pool;
+use crate::prefab_library::{self, PrefabLibrary};
+use crate::profiling::{COLLIDER_COUNT, ProfilingPlugin, TIMED_SPANS};
+use crate::props::{PropAssets, spawn_random_prop};
+use crate::run_stats::RunStats;
+use crate::snapshot::{list_snapshots, load_snapshot, save_snapshot};
+use crate::stats::Stats;
+use crate::{GameState, MainCamera};
+
+/// Frame time the diagnostics graph is scaled to fit at least, 30 fps
+const FRAME_GRAPH_MIN_MS: f64 = 1000.0 / 30.0;
+/// Frame time of 60 fps, marked on the graph
+const FRAME_GRAPH_TARGET_MS: f64 = 1000.0 / 60.0;
+
+/// Units per second the fly camera moves
+const FLY_SPEED: f32 = 20.0;
+/// How much faster the fly camera goes while shift is held
+const FLY_BOOST: f32 = 3.0;
+/// Radians the fly camera turns per pixel the mouse moves
+const FLY_LOOK_SENSITIVITY: f32 = 0.003;
+/// Holding this turns the fly camera with the mouse
+const FLY_LOOK_BUTTON: MouseButton = MouseButton::Right;
+
+/// Shows and hides the inspector
+const TOGGLE_KEY: KeyCode = KeyCode::F1;
+
+/// Radius of hazards dropped from the spawner tab
+const PREFAB_HAZARD_RADIUS: f32 = 3.0;
 
-use crate::MainCamera;
+/// What the spawner tab offers, by the name it's listed under
+const PREFABS: &[(&str, Prefab)] = &[
+    ("Grunt", Prefab::Enemy("grunt")),
+    ("Witch", Prefab::Enemy("witch")),
+    ("Elite Grunt", Prefab::Elite("grunt")),
+    ("Elite Witch", Prefab::Elite("witch")),
+    ("Boss", Prefab::Boss),
+    ("Experience", Prefab::Pickup(PickupKind::Experience(5))),
+    ("Heal", Prefab::Pickup(PickupKind::Heal(20.0))),
+    ("Armor", Prefab::Pickup(PickupKind::Armor(25.0))),
+    ("Gold", Prefab::Pickup(PickupKind::Gold(10))),
+    ("Chest", Prefab::Pickup(PickupKind::Chest(3))),
+    ("Vacuum", Prefab::Pickup(PickupKind::Vacuum)),
+    ("Spikes", Prefab::Hazard(HazardKind::Spikes)),
+    ("Poison Pool", Prefab::Hazard(HazardKind::PoisonPool)),
+    ("Prop", Prefab::Prop),
+];
 
-pub struct Inspector;
+pub struct Inspector {
+    /// Whether it starts open, `TOGGLE_KEY` shows and hides it after
+    pub visible: bool,
+}
 
 impl Plugin for Inspector {
     fn build(&self, app: &mut App) {
         app.add_plugins((
             bevy_egui::EguiPlugin::default(),
             DefaultInspectorConfigPlugin,
+            ProfilingPlugin,
+            EventLogPlugin,
         ));
-        app.insert_resource(UiState::new());
+        app.insert_resource(UiState {
+            visible: self.visible,
+            ..UiState::new()
+        });
         app.add_systems(Startup, setup);
+        app.init_resource::<StepRequested>();
+        app.add_systems(First, step_simulation.after(TimeSystem));
+        app.add_systems(Update, (toggle_inspector, fly_camera));
         app.add_systems(EguiPrimaryContextPass, show_ui_system);
         app.add_systems(PostUpdate, set_camera_viewport.after(show_ui_system));
         app.register_type::<Option<Handle<Image>>>()
@@ -42,7 +118,16 @@ impl Plugin for Inspector {
     }
 }
 
+fn toggle_inspector(keys: Res<ButtonInput<KeyCode>>, mut ui_state: ResMut<UiState>) {
+    if keys.just_pressed(TOGGLE_KEY) {
+        ui_state.visible = !ui_state.visible;
+    }
+}
+
 fn show_ui_system(world: &mut World) {
+    if !world.resource::<UiState>().visible {
+        return;
+    }
     let Ok(egui_context) = world
         .query_filtered::<&mut EguiContext, With<PrimaryEguiContext>>()
         .single(world)
@@ -59,10 +144,24 @@ fn show_ui_system(world: &mut World) {
 // make camera only render to view not obstructed by UI
 fn set_camera_viewport(
     ui_state: Res<UiState>,
-    window: Single<&Window, With<PrimaryWindow>>,
-    mut cam: Single<&mut Camera, Without<PrimaryEguiContext>>,
-    egui_settings: Single<&EguiContextSettings>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut cam: Query<&mut Camera, Without<PrimaryEguiContext>>,
+    egui_settings: Query<&EguiContextSettings>,
 ) {
+    // the camera comes with the level and the window goes before anything
+    // else on exit, so any of them can be missing for a frame
+    let (Ok(window), Ok(mut cam), Ok(egui_settings)) =
+        (window.single(), cam.single_mut(), egui_settings.single())
+    else {
+        return;
+    };
+
+    // hidden, the game gets the whole window back
+    if !ui_state.visible {
+        cam.viewport = None;
+        return;
+    }
+
     let scale_factor = window.scale_factor() * egui_settings.scale_factor;
 
     let viewport_pos = ui_state.viewport_rect.left_top().to_vec2() * scale_factor;
@@ -94,13 +193,112 @@ enum InspectorSelection {
     Asset(TypeId, String, UntypedAssetId),
 }
 
+/// Something the spawner tab can drop into the world
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Prefab {
+    /// By the name of its prefab in `assets/prefabs`
+    Enemy(&'static str),
+    Elite(&'static str),
+    Boss,
+    Pickup(PickupKind),
+    Hazard(HazardKind),
+    /// A random crate, rock or tree
+    Prop,
+}
+
+/// What dragging the gizmo does to the selected entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GizmoTool {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+impl GizmoTool {
+    const ALL: [GizmoTool; 3] = [GizmoTool::Translate, GizmoTool::Rotate, GizmoTool::Scale];
+
+    fn name(&self) -> &'static str {
+        match self {
+            GizmoTool::Translate => "Move (W)",
+            GizmoTool::Rotate => "Rotate (E)",
+            GizmoTool::Scale => "Scale (R)",
+        }
+    }
+
+    fn key(&self) -> egui::Key {
+        match self {
+            GizmoTool::Translate => egui::Key::W,
+            GizmoTool::Rotate => egui::Key::E,
+            GizmoTool::Scale => egui::Key::R,
+        }
+    }
+
+    fn modes(&self) -> EnumSet<GizmoMode> {
+        match self {
+            GizmoTool::Translate => GizmoMode::all_translate(),
+            GizmoTool::Rotate => GizmoMode::all_rotate(),
+            GizmoTool::Scale => GizmoMode::all_scale(),
+        }
+    }
+}
+
+/// The gizmo's tool and what it snaps to, set from the game view's toolbar
+#[derive(Debug, Clone, Copy)]
+struct GizmoOptions {
+    tool: GizmoTool,
+    snapping: bool,
+    /// World units moves snap to
+    snap_distance: f32,
+    /// Degrees rotations snap to
+    snap_angle: f32,
+    /// Steps scaling snaps to
+    snap_scale: f32,
+}
+
+impl Default for GizmoOptions {
+    fn default() -> Self {
+        Self {
+            tool: GizmoTool::Translate,
+            snapping: false,
+            snap_distance: 1.0,
+            snap_angle: 15.0,
+            snap_scale: 0.25,
+        }
+    }
+}
+
 #[derive(Resource)]
 struct UiState {
+    visible: bool,
     state: DockState<EguiWindow>,
     viewport_rect: egui::Rect,
     selected_entities: SelectedEntities,
     selection: InspectorSelection,
     gizmo: Gizmo,
+    gizmo_options: GizmoOptions,
+    /// Spawned wherever the game view is clicked, while one is picked
+    prefab: Option<Prefab>,
+    event_filter: EventFilter,
+}
+
+/// Which of the logged events the events tab shows
+struct EventFilter {
+    hidden: Vec<LoggedKind>,
+    /// Only events involving the selected entities
+    selected_only: bool,
+    /// Only events whose text contains this, like an entity's name or id
+    text: String,
+}
+
+impl Default for EventFilter {
+    fn default() -> Self {
+        Self {
+            // damage is most of the log, so it starts hidden
+            hidden: vec![LoggedKind::Damage],
+            selected_only: false,
+            text: String::new(),
+        }
+    }
 }
 
 impl UiState {
@@ -110,15 +308,30 @@ impl UiState {
         let [game, _inspector] =
             tree.split_right(NodeIndex::root(), 0.75, vec![EguiWindow::Inspector]);
         let [game, _hierarchy] = tree.split_left(game, 0.2, vec![EguiWindow::Hierarchy]);
-        let [_game, _bottom] =
-            tree.split_below(game, 0.8, vec![EguiWindow::Resources, EguiWindow::Assets]);
+        let [_game, _bottom] = tree.split_below(
+            game,
+            0.8,
+            vec![
+                EguiWindow::Resources,
+                EguiWindow::Assets,
+                EguiWindow::Spawner,
+                EguiWindow::Snapshots,
+                EguiWindow::Diagnostics,
+                EguiWindow::Events,
+                EguiWindow::Weapons,
+            ],
+        );
 
         Self {
+            visible: true,
             state,
             selected_entities: SelectedEntities::default(),
             selection: InspectorSelection::Entities,
             viewport_rect: egui::Rect::NOTHING,
             gizmo: Gizmo::default(),
+            gizmo_options: GizmoOptions::default(),
+            prefab: None,
+            event_filter: EventFilter::default(),
         }
     }
 
@@ -129,6 +342,9 @@ impl UiState {
             selected_entities: &mut self.selected_entities,
             selection: &mut self.selection,
             gizmo: &mut self.gizmo,
+            gizmo_options: &mut self.gizmo_options,
+            prefab: &mut self.prefab,
+            event_filter: &mut self.event_filter,
         };
         DockArea::new(&mut self.state)
             .style(Style::from_egui(ctx.style().as_ref()))
@@ -142,6 +358,11 @@ enum EguiWindow {
     Hierarchy,
     Resources,
     Assets,
+    Spawner,
+    Snapshots,
+    Diagnostics,
+    Events,
+    Weapons,
     Inspector,
 }
 
@@ -151,6 +372,9 @@ struct TabViewer<'a> {
     selection: &'a mut InspectorSelection,
     viewport_rect: &'a mut egui::Rect,
     gizmo: &'a mut Gizmo,
+    gizmo_options: &'a mut GizmoOptions,
+    prefab: &'a mut Option<Prefab>,
+    event_filter: &'a mut EventFilter,
 }
 
 impl egui_dock::TabViewer for TabViewer<'_> {
@@ -164,7 +388,22 @@ impl egui_dock::TabViewer for TabViewer<'_> {
             EguiWindow::GameView => {
                 *self.viewport_rect = ui.clip_rect();
 
-                draw_gizmo(ui, self.gizmo, self.world, self.selected_entities);
+                let selecting = !self.selected_entities.is_empty();
+                let toolbar = game_view_toolbar(ui, self.gizmo_options, self.world, selecting);
+                draw_gizmo(
+                    ui,
+                    self.gizmo,
+                    self.gizmo_options,
+                    self.world,
+                    self.selected_entities,
+                );
+                if let Some(prefab) = *self.prefab {
+                    place_prefab(ui, self.world, prefab);
+                } else if !self.gizmo.is_focused()
+                    && pick_entity(ui, toolbar, self.world, self.selected_entities)
+                {
+                    *self.selection = InspectorSelection::Entities;
+                }
             }
             EguiWindow::Hierarchy => {
                 let selected = hierarchy_ui(self.world, ui, self.selected_entities);
@@ -174,9 +413,19 @@ impl egui_dock::TabViewer for TabViewer<'_> {
             }
             EguiWindow::Resources => select_resource(ui, &type_registry, self.selection),
             EguiWindow::Assets => select_asset(ui, &type_registry, self.world, self.selection),
+            EguiWindow::Spawner => select_prefab(ui, self.prefab),
+            EguiWindow::Snapshots => snapshots_ui(ui, self.world),
+            EguiWindow::Diagnostics => diagnostics_ui(ui, self.world),
+            EguiWindow::Events => {
+                events_ui(ui, self.world, self.event_filter, self.selected_entities)
+            }
+            EguiWindow::Weapons => weapons_ui(ui, self.world),
             EguiWindow::Inspector => match *self.selection {
                 InspectorSelection::Entities => match self.selected_entities.as_slice() {
-                    &[entity] => ui_for_entity_with_children(self.world, entity, ui),
+                    &[entity] => {
+                        stats_ui(ui, self.world, entity);
+                        ui_for_entity_with_children(self.world, entity, ui)
+                    }
                     entities => ui_for_entities_shared_components(self.world, entities, ui),
                 },
                 InspectorSelection::Resource(type_id, ref name) => {
@@ -212,48 +461,237 @@ impl egui_dock::TabViewer for TabViewer<'_> {
     }
 }
 
+/// Buttons over the top of the game view to pick the gizmo's tool and
+/// snapping, with W/E/R to switch tools while something is selected, and to
+/// fly the camera and pause the game
+///
+/// Those are also movement keys, so they only switch tools while the game
+/// view is hovered and the camera isn't flying. Returns where the toolbar is
+fn game_view_toolbar(
+    ui: &mut egui::Ui,
+    options: &mut GizmoOptions,
+    world: &mut World,
+    selecting: bool,
+) -> egui::Rect {
+    let mut flying = !world
+        .query_filtered::<(), (With<MainCamera>, With<DetachedCamera>)>()
+        .iter(world)
+        .is_empty();
+    let hovered = ui.rect_contains_pointer(ui.clip_rect());
+    if selecting && hovered && !flying && !ui.ctx().wants_keyboard_input() {
+        for tool in GizmoTool::ALL {
+            if ui.input(|input| input.key_pressed(tool.key())) {
+                options.tool = tool;
+            }
+        }
+    }
+
+    let toolbar = ui.horizontal(|ui| {
+        for tool in GizmoTool::ALL {
+            ui.selectable_value(&mut options.tool, tool, tool.name());
+        }
+        ui.separator();
+        ui.checkbox(&mut options.snapping, "Snap");
+        ui.add_enabled_ui(options.snapping, |ui| {
+            ui.add(
+                egui::DragValue::new(&mut options.snap_distance)
+                    .prefix("Grid: ")
+                    .speed(0.1)
+                    .range(0.01..=100.0),
+            );
+            ui.add(
+                egui::DragValue::new(&mut options.snap_angle)
+                    .prefix("Angle: ")
+                    .suffix("°")
+                    .range(1.0..=180.0),
+            );
+            ui.add(
+                egui::DragValue::new(&mut options.snap_scale)
+                    .prefix("Scale: ")
+                    .speed(0.01)
+                    .range(0.01..=10.0),
+            );
+        });
+        ui.separator();
+        if ui.toggle_value(&mut flying, "Fly Camera").clicked() {
+            set_fly_camera(world, flying);
+        }
+        ui.separator();
+        simulation_controls(ui, world);
+    });
+    return toolbar.response.rect;
+}
+
+/// Asks `step_simulation` to advance the paused game next frame
+#[derive(Resource, Default)]
+struct StepRequested(bool);
+
+/// Pause and play the game, and step through it a physics step at a time
+/// while paused
+///
+/// Physics runs on virtual time, so pausing that freezes it too. Menus
+/// pausing and unpausing the game show up here as well
+fn simulation_controls(ui: &mut egui::Ui, world: &mut World) {
+    let mut time = world.resource_mut::<Time<Virtual>>();
+    let paused = time.is_paused();
+    if ui.button(if paused { "Play" } else { "Pause" }).clicked() {
+        if paused {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+    if ui.add_enabled(paused, egui::Button::new("Step")).clicked() {
+        world.resource_mut::<StepRequested>().0 = true;
+    }
+}
+
+/// Moves paused virtual time on by exactly one physics step, so the fixed
+/// schedule runs once and everything else sees that much time go by
+fn step_simulation(
+    mut step: ResMut<StepRequested>,
+    fixed: Res<Time<Fixed>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time: ResMut<Time>,
+) {
+    if !std::mem::take(&mut step.0) || !virtual_time.is_paused() {
+        return;
+    }
+    virtual_time.advance_by(fixed.timestep());
+    *time = virtual_time.as_generic();
+}
+
+/// Detach the camera from the players to fly it around with WASD, or put it
+/// back following them
+///
+/// Players don't take input while it's flying, or WASD would move them too
+fn set_fly_camera(world: &mut World, flying: bool) {
+    let cameras: Vec<Entity> = world
+        .query_filtered::<Entity, With<MainCamera>>()
+        .iter(world)
+        .collect();
+    for camera in cameras {
+        if flying {
+            world.entity_mut(camera).insert(DetachedCamera);
+        } else {
+            world.entity_mut(camera).remove::<DetachedCamera>();
+        }
+    }
+
+    for mut action_state in world.query::<&mut ActionState<Action>>().iter_mut(world) {
+        if flying {
+            action_state.disable();
+        } else {
+            action_state.enable();
+        }
+    }
+}
+
+/// WASD to fly, Q and E to go down and up, and the right mouse button to
+/// look around. On real time so it keeps working while the game is paused
+fn fly_camera(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mut cameras: Query<&mut Transform, (With<MainCamera>, With<DetachedCamera>)>,
+) {
+    let Ok(mut transform) = cameras.single_mut() else {
+        return;
+    };
+
+    if mouse_buttons.pressed(FLY_LOOK_BUTTON) {
+        let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        let yaw = yaw - mouse_motion.delta.x * FLY_LOOK_SENSITIVITY;
+        let pitch = (pitch - mouse_motion.delta.y * FLY_LOOK_SENSITIVITY)
+            .clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+    }
+
+    let mut direction = Vec3::ZERO;
+    for (key, towards) in [
+        (KeyCode::KeyW, transform.forward().as_vec3()),
+        (KeyCode::KeyS, transform.back().as_vec3()),
+        (KeyCode::KeyA, transform.left().as_vec3()),
+        (KeyCode::KeyD, transform.right().as_vec3()),
+        (KeyCode::KeyQ, Vec3::NEG_Y),
+        (KeyCode::KeyE, Vec3::Y),
+    ] {
+        if keys.pressed(key) {
+            direction += towards;
+        }
+    }
+
+    let speed = match keys.pressed(KeyCode::ShiftLeft) {
+        true => FLY_SPEED * FLY_BOOST,
+        false => FLY_SPEED,
+    };
+    transform.translation += direction.normalize_or_zero() * speed * time.delta_secs();
+}
+
 #[allow(unused)]
 fn draw_gizmo(
     ui: &mut egui::Ui,
     gizmo: &mut Gizmo,
+    options: &GizmoOptions,
     world: &mut World,
     selected_entities: &SelectedEntities,
 ) {
-    let (cam_transform, projection) = world
+    let Ok((cam_transform, projection)) = world
         .query_filtered::<(&GlobalTransform, &Projection), With<MainCamera>>()
         .single(world)
-        .expect("Camera not found");
+    else {
+        return;
+    };
     let view_matrix = Mat4::from(cam_transform.affine().inverse());
     let projection_matrix = projection.get_clip_from_view();
 
-    if selected_entities.len() != 1 {
-        #[allow(clippy::needless_return)]
+    let selected: Vec<(Entity, Transform)> = selected_entities
+        .iter()
+        .filter_map(|entity| Some((entity, *world.get::<Transform>(entity)?)))
+        .collect();
+    if selected.is_empty() {
         return;
     }
 
-    for selected in selected_entities.iter() {
-        let Some(transform) = world.get::<Transform>(selected) else {
-            continue;
-        };
-        let model_matrix = transform.compute_matrix();
+    // a selection turns about its middle, and is only lined up with the
+    // entity itself when there's just the one
+    let orientation = if selected.len() == 1 {
+        GizmoOrientation::Local
+    } else {
+        GizmoOrientation::Global
+    };
+    gizmo.update_config(GizmoConfig {
+        view_matrix: view_matrix.as_dmat4().into(),
+        projection_matrix: projection_matrix.as_dmat4().into(),
+        orientation,
+        pivot_point: TransformPivotPoint::MedianPoint,
+        modes: options.tool.modes(),
+        snapping: options.snapping,
+        snap_distance: options.snap_distance,
+        snap_angle: options.snap_angle.to_radians(),
+        snap_scale: options.snap_scale,
+        ..Default::default()
+    });
 
-        gizmo.update_config(GizmoConfig {
-            view_matrix: view_matrix.as_dmat4().into(),
-            projection_matrix: projection_matrix.as_dmat4().into(),
-            orientation: GizmoOrientation::Local,
-            ..Default::default()
-        });
-        let transform = transform_gizmo_egui::math::Transform::from_scale_rotation_translation(
-            transform.scale.as_dvec3(),
-            transform.rotation.as_dquat(),
-            transform.translation.as_dvec3(),
-        );
-        let Some((result, transforms)) = gizmo.interact(ui, &[transform]) else {
+    let transforms: Vec<_> = selected
+        .iter()
+        .map(|(_, transform)| {
+            transform_gizmo_egui::math::Transform::from_scale_rotation_translation(
+                transform.scale.as_dvec3(),
+                transform.rotation.as_dquat(),
+                transform.translation.as_dvec3(),
+            )
+        })
+        .collect();
+    let Some((_, transforms)) = gizmo.interact(ui, &transforms) else {
+        return;
+    };
+
+    for ((entity, _), new) in selected.iter().zip(transforms) {
+        let Some(mut transform) = world.get_mut::<Transform>(*entity) else {
             continue;
         };
-        let new = transforms[0];
-
-        let mut transform = world.get_mut::<Transform>(selected).unwrap();
         *transform = Transform {
             translation: DVec3::from(new.translation).as_vec3(),
             rotation: DQuat::from_array(<[f64; 4]>::from(new.rotation)).as_quat(),
@@ -332,6 +770,367 @@ fn select_asset(
     }
 }
 
+/// The final value of each of an entity's stats, and how much damage its
+/// armor takes off, above its components
+fn stats_ui(ui: &mut egui::Ui, world: &World, entity: Entity) {
+    let Some(stats) = world.get::<Stats>(entity) else {
+        return;
+    };
+
+    egui::CollapsingHeader::new("Stats")
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new("stats").show(ui, |ui| {
+                for stat in stats.stats() {
+                    ui.label(format!("{stat:?}"));
+                    ui.label(format!("{:.2}", stats.get(stat)));
+                    ui.end_row();
+                }
+            });
+            if let Some(armor) = world.get::<Armor>(entity) {
+                ui.label(format!(
+                    "Damage reduction: {:.0}%",
+                    armor.reduction() * 100.0
+                ));
+            }
+        });
+    ui.separator();
+}
+
+/// Save the players, enemies and pickups to a file, or put back ones saved
+/// earlier
+fn snapshots_ui(ui: &mut egui::Ui, world: &mut World) {
+    if ui.button("Save Snapshot").clicked() {
+        match save_snapshot(world) {
+            Ok(path) => info!("Saved snapshot to {}", path.display()),
+            Err(e) => error!("{e}"),
+        }
+    }
+
+    ui.separator();
+    for path in list_snapshots() {
+        ui.horizontal(|ui| {
+            if ui.button("Load").clicked() {
+                match load_snapshot(world, &path) {
+                    Ok(()) => info!("Loaded snapshot {}", path.display()),
+                    Err(e) => error!("{e}"),
+                }
+            }
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            ui.label(name);
+        });
+    }
+}
+
+/// Frame rate, how much is alive and how long the bigger groups of systems
+/// take, to catch hordes getting slower
+fn diagnostics_ui(ui: &mut egui::Ui, world: &World) {
+    let diagnostics = world.resource::<DiagnosticsStore>();
+    let smoothed = |path: &DiagnosticPath| {
+        diagnostics
+            .get(path)
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .unwrap_or_default()
+    };
+
+    ui.label(format!(
+        "FPS: {:.0}",
+        smoothed(&FrameTimeDiagnosticsPlugin::FPS)
+    ));
+    ui.label(format!(
+        "Frame time: {:.2} ms",
+        smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+    ));
+    if let Some(frame_time) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME) {
+        let frame_times: Vec<f64> = frame_time.values().copied().collect();
+        frame_time_graph(ui, &frame_times);
+    }
+    ui.label(format!(
+        "Entities: {:.0}",
+        smoothed(&EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+    ));
+    ui.label(format!("Colliders: {:.0}", smoothed(&COLLIDER_COUNT)));
+
+    ui.separator();
+    egui::Grid::new("system timings").show(ui, |ui| {
+        for (name, path) in TIMED_SPANS {
+            ui.label(*name);
+            ui.label(format!("{:.2} ms", smoothed(path)));
+            ui.end_row();
+        }
+    });
+}
+
+/// How each weapon is doing this run, for balancing them against each other
+fn weapons_ui(ui: &mut egui::Ui, world: &World) {
+    let stats = world.resource::<RunStats>();
+    let now = stats.survived_seconds;
+    egui::Grid::new("weapon stats")
+        .striped(true)
+        .show(ui, |ui| {
+            for heading in ["Weapon", "Damage", "Hits", "Crits", "DPS", "Run DPS"] {
+                ui.strong(heading);
+            }
+            ui.end_row();
+
+            for (weapon, weapon_stats) in stats.weapons_by_damage() {
+                ui.label(weapon.name());
+                ui.label(format!("{:.0}", weapon_stats.damage));
+                ui.label(weapon_stats.hits.to_string());
+                ui.label(format!("{:.0}%", weapon_stats.crit_rate() * 100.0));
+                ui.label(format!("{:.1}", weapon_stats.recent_dps(now)));
+                ui.label(format!("{:.1}", weapon_stats.average_dps(now)));
+                ui.end_row();
+            }
+        });
+}
+
+/// The latest gameplay events, newest at the bottom, narrowed down by kind,
+/// the selected entities or some text
+fn events_ui(
+    ui: &mut egui::Ui,
+    world: &World,
+    filter: &mut EventFilter,
+    selected: &SelectedEntities,
+) {
+    ui.horizontal_wrapped(|ui| {
+        for kind in LoggedKind::ALL {
+            let mut shown = !filter.hidden.contains(&kind);
+            if ui.checkbox(&mut shown, format!("{kind:?}")).changed() {
+                filter.hidden.retain(|hidden| *hidden != kind);
+                if !shown {
+                    filter.hidden.push(kind);
+                }
+            }
+        }
+        ui.checkbox(&mut filter.selected_only, "Selected only");
+        ui.label("Filter");
+        ui.text_edit_singleline(&mut filter.text);
+    });
+    ui.separator();
+
+    let log = world.resource::<EventLog>();
+    let selected = selected.as_slice();
+    egui::ScrollArea::vertical()
+        .stick_to_bottom(true)
+        .auto_shrink(false)
+        .show(ui, |ui| {
+            let shown = log.events.iter().filter(|event| {
+                !filter.hidden.contains(&event.kind)
+                    && (!filter.selected_only
+                        || event
+                            .entities
+                            .iter()
+                            .any(|entity| selected.contains(entity)))
+                    && event.text.contains(filter.text.as_str())
+            });
+            for event in shown {
+                ui.label(format!(
+                    "{:>8.2}  {:?}  {}",
+                    event.time, event.kind, event.text
+                ));
+            }
+        });
+}
+
+/// The recent frame times as a line, with a line across at 60 fps
+fn frame_time_graph(ui: &mut egui::Ui, frame_times: &[f64]) {
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(ui.available_width(), 60.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    let max = frame_times
+        .iter()
+        .copied()
+        .fold(FRAME_GRAPH_MIN_MS, f64::max);
+    let height = |ms: f64| rect.bottom() - rect.height() * (ms / max) as f32;
+
+    let target = height(FRAME_GRAPH_TARGET_MS);
+    painter.hline(
+        rect.x_range(),
+        target,
+        egui::Stroke::new(1.0, ui.visuals().weak_text_color()),
+    );
+
+    let step = rect.width() / frame_times.len().saturating_sub(1).max(1) as f32;
+    let points = frame_times
+        .iter()
+        .enumerate()
+        .map(|(i, &ms)| egui::pos2(rect.left() + step * i as f32, height(ms)))
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN),
+    ));
+}
+
+fn select_prefab(ui: &mut egui::Ui, prefab: &mut Option<Prefab>) {
+    ui.label("Pick one, then click in the game view to spawn it");
+    for (name, candidate) in PREFABS {
+        let selected = *prefab == Some(*candidate);
+        if ui.selectable_label(selected, *name).clicked() {
+            // picking the same one again puts it away
+            *prefab = (!selected).then_some(*candidate);
+        }
+    }
+}
+
+/// The ray through the game view at `pointer`, from the camera out into the
+/// world
+fn cursor_ray(world: &mut World, pointer: egui::Pos2) -> Option<Ray3d> {
+    let egui_settings = world.query::<&EguiContextSettings>().single(world).ok()?;
+    // egui points are scaled on top of the window's logical pixels
+    let cursor = Vec2::new(pointer.x, pointer.y) * egui_settings.scale_factor;
+
+    let (camera, camera_transform) = world
+        .query_filtered::<(&Camera, &GlobalTransform), With<MainCamera>>()
+        .single(world)
+        .ok()?;
+    return camera.viewport_to_world(camera_transform, cursor).ok();
+}
+
+/// Select whatever collider is clicked in the game view, clicking nothing
+/// clears the selection. Holding ctrl adds to the selection instead
+///
+/// The gizmo and toolbar sit over the game view, so clicks on them are left
+/// alone. Returns whether the selection changed
+fn pick_entity(
+    ui: &mut egui::Ui,
+    toolbar: egui::Rect,
+    world: &mut World,
+    selected_entities: &mut SelectedEntities,
+) -> bool {
+    let (clicked, pointer, add) = ui.input(|input| {
+        (
+            input.pointer.primary_clicked(),
+            input.pointer.interact_pos(),
+            input.modifiers.ctrl,
+        )
+    });
+    let Some(pointer) = pointer.filter(|pointer| {
+        clicked && ui.clip_rect().contains(*pointer) && !toolbar.contains(*pointer)
+    }) else {
+        return false;
+    };
+    let Some(ray) = cursor_ray(world, pointer) else {
+        return false;
+    };
+
+    match world.run_system_cached_with(cast_pick_ray, ray) {
+        Ok(Some(entity)) => selected_entities.select_maybe_add(entity, add),
+        Ok(None) if !add => selected_entities.clear(),
+        Ok(None) => return false,
+        Err(e) => {
+            error!("Could not pick an entity: {e}");
+            return false;
+        }
+    }
+    return true;
+}
+
+fn cast_pick_ray(In(ray): In<Ray3d>, rapier_context: ReadRapierContext) -> Option<Entity> {
+    let context = rapier_context.single().ok()?;
+    let (entity, _) = context.cast_ray(
+        ray.origin,
+        *ray.direction,
+        f32::MAX,
+        true,
+        QueryFilter::default(),
+    )?;
+    return Some(entity);
+}
+
+/// Spawn `prefab` on the ground wherever the game view is clicked
+fn place_prefab(ui: &mut egui::Ui, world: &mut World, prefab: Prefab) {
+    let response = ui.interact(
+        ui.clip_rect(),
+        ui.id().with("place prefab"),
+        egui::Sense::click(),
+    );
+    let Some(pointer) = response
+        .interact_pointer_pos()
+        .filter(|_| response.clicked())
+    else {
+        return;
+    };
+
+    let Some(position) = cursor_ray(world, pointer).and_then(|ray| {
+        let distance = ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))?;
+        Some(ray.get_point(distance))
+    }) else {
+        return;
+    };
+
+    if let Err(e) = world.run_system_cached_with(spawn_prefab, (prefab, position)) {
+        error!("Could not spawn {prefab:?}: {e}");
+    }
+}
+
+fn spawn_prefab(
+    In((prefab, position)): In<(Prefab, Vec3)>,
+    mut commands: Commands,
+    mut pool: ResMut<Pool>,
+    (library, animations): (Res<PrefabLibrary>, Res<Assets<AnimationLibrary>>),
+    (enemies, pickups, hazards, props): (
+        Res<EnemyAssets>,
+        Res<PickupAssets>,
+        Res<HazardAssets>,
+        Res<PropAssets>,
+    ),
+) {
+    // a debugging tool, so leave the seeded rng to gameplay
+    let mut rng = rand::rng();
+    match prefab {
+        Prefab::Enemy(name) | Prefab::Elite(name) => {
+            let Some(enemy) = prefab_library::spawn_prefab(
+                &mut commands,
+                &mut pool,
+                (&library, &animations),
+                name,
+                position + Vec3::Y,
+                1.0,
+                &mut rng,
+            ) else {
+                warn!("No prefab called {name}");
+                return;
+            };
+            if matches!(prefab, Prefab::Elite(_)) {
+                roll_elite(&mut commands.entity(enemy), &mut rng);
+            }
+        }
+        Prefab::Boss => {
+            commands.spawn(boss::boss(&enemies, 1, position));
+        }
+        Prefab::Pickup(kind) => {
+            commands.spawn(pickup_bundle(
+                &pickups,
+                Pickup {
+                    kind,
+                    rarity: Rarity::Common,
+                },
+                position + Vec3::Y * 0.5,
+            ));
+        }
+        Prefab::Hazard(kind) => {
+            commands.spawn((
+                hazard(&hazards, kind, position, PREFAB_HAZARD_RADIUS),
+                StateScoped(GameState::InGame),
+            ));
+        }
+        Prefab::Prop => {
+            commands
+                .spawn((
+                    Transform::default(),
+                    Visibility::default(),
+                    Name::new("Spawned Prop"),
+                    StateScoped(GameState::InGame),
+                ))
+                .with_children(|parent| spawn_random_prop(parent, &props, position, &mut rng));
+        }
+    }
+}
+
 fn setup(
     mut commands: Commands,
     mut egui_global_settings: ResMut<EguiGlobalSettings>,
@@ -0,0 +1,193 @@
+//! Things in the world players walk up to and press interact on, rather than
+//! just touching
+//!
+//! Anything with `Interactable` prompts the players in range with its
+//! `prompt`, and sends `Interacted` when one of them presses
+//! `Action::Interact`. What that does is up to whoever reads the event, so
+//! chests, shrines and whatever comes next only need to listen for it
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::controls::Action;
+use crate::input_device::ActiveInputDevice;
+use crate::pool::Inactive;
+use crate::upgrades::{choose_upgrade, no_level_up_menu};
+use crate::{
+    GameState, GameplaySet, INTERACT_BUTTON, MainCamera, Player, PlayerSlot, interact_key,
+};
+
+/// How close a player has to be to interact, unless the `Interactable` says
+/// otherwise
+pub const INTERACT_RANGE: f32 = 3.0;
+/// How far above what it's for the prompt floats
+const PROMPT_HEIGHT: f32 = 1.5;
+const PROMPT_WIDTH: f32 = 240.0;
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Interactable>();
+        app.add_event::<Interacted>();
+        app.add_systems(
+            Update,
+            (
+                // the level up menu picks with the interact button too
+                interact.run_if(no_level_up_menu).before(choose_upgrade),
+                sync_prompts,
+                place_prompts,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Used by pressing `Action::Interact` within `range` of it
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Interactable {
+    /// What interacting does, shown next to the key or button to press
+    pub prompt: String,
+    pub range: f32,
+}
+
+impl Interactable {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        return Self {
+            prompt: prompt.into(),
+            range: INTERACT_RANGE,
+        };
+    }
+}
+
+/// `interactor` pressed interact on `target`
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Interacted {
+    pub interactor: Entity,
+    pub target: Entity,
+}
+
+/// The closest thing in range of `position` to interact with, if there is one
+fn nearest_interactable<'a>(
+    position: Vec3,
+    interactables: impl Iterator<Item = (Entity, &'a GlobalTransform, &'a Interactable)>,
+) -> Option<(Entity, Vec3, &'a Interactable)> {
+    return interactables
+        .map(|(entity, transform, interactable)| {
+            return (entity, transform.translation(), interactable);
+        })
+        .filter(|(_, target, interactable)| target.distance(position) <= interactable.range)
+        .min_by(|(_, a, _), (_, b, _)| a.distance(position).total_cmp(&b.distance(position)));
+}
+
+fn interact(
+    players: Query<(Entity, &Transform, &ActionState<Action>), With<Player>>,
+    interactables: Query<(Entity, &GlobalTransform, &Interactable), Without<Inactive>>,
+    mut interacted: EventWriter<Interacted>,
+) {
+    // two players pressing on the same thing together only use it once
+    let mut used = Vec::new();
+    for (interactor, transform, action_state) in players.iter() {
+        if !action_state.just_pressed(&Action::Interact) {
+            continue;
+        }
+        let nearest = nearest_interactable(transform.translation, interactables.iter());
+        let Some((target, _, _)) = nearest else {
+            continue;
+        };
+        if used.contains(&target) {
+            continue;
+        }
+
+        used.push(target);
+        interacted.write(Interacted { interactor, target });
+    }
+}
+
+/// The "press a key" text over whatever `player` can interact with, hidden
+/// while there's nothing in range
+#[derive(Component)]
+struct InteractPrompt {
+    player: Entity,
+}
+
+/// One prompt per player, spawned and despawned as players come and go
+fn sync_prompts(
+    mut commands: Commands,
+    prompts: Query<(Entity, &InteractPrompt)>,
+    players: Query<Entity, With<Player>>,
+) {
+    let mut without_prompt: Vec<Entity> = players.iter().collect();
+    for (entity, prompt) in prompts.iter() {
+        match without_prompt
+            .iter()
+            .position(|player| *player == prompt.player)
+        {
+            Some(i) => {
+                without_prompt.swap_remove(i);
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for player in without_prompt {
+        commands.spawn((
+            Text::default(),
+            TextFont::from_font_size(20.0),
+            TextColor(Color::WHITE),
+            TextLayout::new_with_justify(JustifyText::Center),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(PROMPT_WIDTH),
+                ..default()
+            },
+            Visibility::Hidden,
+            InteractPrompt { player },
+            Name::new("Interact Prompt"),
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+fn place_prompts(
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    players: Query<(&Transform, &PlayerSlot), With<Player>>,
+    interactables: Query<(Entity, &GlobalTransform, &Interactable), Without<Inactive>>,
+    ui_scale: Option<Res<UiScale>>,
+    device: Res<ActiveInputDevice>,
+    mut prompts: Query<(&InteractPrompt, &mut Text, &mut Node, &mut Visibility)>,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let scale = ui_scale.map_or(1.0, |scale| scale.0);
+
+    for (prompt, mut text, mut node, mut visibility) in prompts.iter_mut() {
+        let shown = players
+            .get(prompt.player)
+            .ok()
+            .and_then(|(transform, slot)| {
+                let (_, position, interactable) =
+                    nearest_interactable(transform.translation, interactables.iter())?;
+                let point = camera
+                    .world_to_viewport(camera_transform, position + Vec3::Y * PROMPT_HEIGHT)
+                    .ok()?;
+                return Some((point / scale, slot, interactable));
+            });
+        let Some((point, slot, interactable)) = shown else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        let glyph = device.glyph(interact_key(**slot), INTERACT_BUTTON);
+        let label = format!("[{glyph}] {}", interactable.prompt);
+        if text.0 != label {
+            text.0 = label;
+        }
+        node.left = Val::Px(point.x - PROMPT_WIDTH / 2.0);
+        node.top = Val::Px(point.y);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_rapier3d::prelude::PhysicsSet;
+
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            RunFixedMainLoop,
+            restore_physics_translation.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+        );
+        app.add_systems(
+            FixedUpdate,
+            record_physics_translation.after(PhysicsSet::Writeback),
+        );
+        app.add_systems(
+            PostUpdate,
+            interpolate_translation.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Smooth a physics body's rendered position between fixed steps
+///
+/// Physics only moves bodies at the fixed rate, so between steps the
+/// translation is blended from the previous step towards the latest one for
+/// rendering, then put back before the next step. Gameplay systems in
+/// `Update` always see the latest physics position
+#[derive(Component, Default)]
+pub struct InterpolatedTranslation {
+    previous: Option<Vec3>,
+    current: Option<Vec3>,
+}
+
+fn restore_physics_translation(mut query: Query<(&mut Transform, &InterpolatedTranslation)>) {
+    for (mut transform, interpolated) in query.iter_mut() {
+        if let Some(current) = interpolated.current {
+            transform.translation = current;
+        }
+    }
+}
+
+fn record_physics_translation(mut query: Query<(&Transform, &mut InterpolatedTranslation)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.previous = interpolated.current.or(Some(transform.translation));
+        interpolated.current = Some(transform.translation);
+    }
+}
+
+pub fn interpolate_translation(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &mut InterpolatedTranslation)>,
+) {
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        let (Some(previous), Some(current)) = (interpolated.previous, interpolated.current) else {
+            continue;
+        };
+
+        // moved outside of physics this frame, e.g. by the arena lock, so
+        // there's nothing to blend from
+        if transform.translation != current {
+            interpolated.previous = Some(transform.translation);
+            interpolated.current = Some(transform.translation);
+            continue;
+        }
+
+        transform.translation = previous.lerp(current, time.overstep_fraction());
+    }
+}
@@ -0,0 +1,332 @@
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task, block_on};
+
+use crate::GameState;
+use crate::cli::LaunchOptions;
+use crate::game_over::RunFinished;
+use crate::http::{self, HttpError};
+use crate::menu::BUTTON_COLOR;
+use crate::save::{RunRecord, SaveData};
+
+const PAGE_SIZE: usize = 5;
+/// Used when `--leaderboard` isn't given
+const ENDPOINT_ENV_VAR: &str = "BUNVIVOR_LEADERBOARD";
+/// How many of the best online runs to show
+const ONLINE_TOP: usize = 50;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LeaderboardView>();
+        app.init_resource::<OnlineLeaderboard>();
+        app.add_systems(Startup, configure_endpoint);
+        app.add_systems(
+            OnEnter(GameState::MainMenu),
+            (spawn_leaderboard, fetch_online_runs),
+        );
+        app.add_systems(
+            Update,
+            (
+                submit_runs,
+                poll_online_requests,
+                (leaderboard_buttons, update_leaderboard)
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu)),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The shared leaderboard server, shown in place of the local table
+/// whenever it can be reached
+///
+/// It takes finished runs as a RON `RunRecord` posted to `{endpoint}/runs`
+/// and lists the best ones from `{endpoint}/runs/top?count=N`
+#[derive(Resource, Default)]
+pub struct OnlineLeaderboard {
+    pub endpoint: Option<String>,
+    /// Best first, `None` until fetched or when offline
+    pub top: Option<Vec<RunRecord>>,
+    fetch: Option<Task<Result<Vec<RunRecord>, HttpError>>>,
+    submissions: Vec<Task<Result<String, HttpError>>>,
+}
+
+impl OnlineLeaderboard {
+    /// What the leaderboard shows, falling back to `local` while offline
+    pub fn runs<'a>(&'a self, local: &'a [RunRecord]) -> &'a [RunRecord] {
+        return self.top.as_deref().unwrap_or(local);
+    }
+}
+
+/// Which column the leaderboard is sorted by, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeaderboardSort {
+    #[default]
+    Time,
+    Level,
+    Kills,
+}
+
+impl LeaderboardSort {
+    fn next(self) -> Self {
+        match self {
+            LeaderboardSort::Time => LeaderboardSort::Level,
+            LeaderboardSort::Level => LeaderboardSort::Kills,
+            LeaderboardSort::Kills => LeaderboardSort::Time,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            LeaderboardSort::Time => "Time",
+            LeaderboardSort::Level => "Level",
+            LeaderboardSort::Kills => "Kills",
+        }
+    }
+
+    /// `runs` ordered best first, ties keep the longest run first
+    pub fn sorted<'a>(&self, runs: &'a [RunRecord]) -> Vec<&'a RunRecord> {
+        let mut sorted: Vec<&RunRecord> = runs.iter().collect();
+        sorted.sort_by(|a, b| {
+            let by_column = match self {
+                LeaderboardSort::Time => std::cmp::Ordering::Equal,
+                LeaderboardSort::Level => b.level.cmp(&a.level),
+                LeaderboardSort::Kills => b.kills.cmp(&a.kills),
+            };
+            by_column.then(b.survived_seconds.total_cmp(&a.survived_seconds))
+        });
+        return sorted;
+    }
+}
+
+/// How the main menu leaderboard is currently shown
+#[derive(Resource, Debug, Default)]
+pub struct LeaderboardView {
+    pub sort: LeaderboardSort,
+    pub page: usize,
+}
+
+#[derive(Component)]
+enum LeaderboardButton {
+    Sort,
+    PreviousPage,
+    NextPage,
+}
+
+#[derive(Component)]
+struct SortLabel;
+
+#[derive(Component)]
+struct PageLabel;
+
+/// The `n`th row of the current page
+#[derive(Component)]
+struct LeaderboardRow(usize);
+
+fn spawn_leaderboard(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(16.0),
+                right: Val::Px(16.0),
+                width: Val::Px(420.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            Name::new("Leaderboard"),
+            StateScoped(GameState::MainMenu),
+        ))
+        .with_children(|board| {
+            board.spawn((Text::new("High Scores"), TextFont::from_font_size(28.0)));
+            board.spawn((
+                small_button(),
+                LeaderboardButton::Sort,
+                children![(Text::new(""), TextFont::from_font_size(16.0), SortLabel)],
+            ));
+            for i in 0..PAGE_SIZE {
+                board.spawn((
+                    Text::new(""),
+                    TextFont::from_font_size(16.0),
+                    LeaderboardRow(i),
+                ));
+            }
+            board
+                .spawn(Node {
+                    column_gap: Val::Px(12.0),
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|pages| {
+                    pages.spawn((
+                        small_button(),
+                        LeaderboardButton::PreviousPage,
+                        children![(Text::new("<"), TextFont::from_font_size(16.0))],
+                    ));
+                    pages.spawn((Text::new(""), TextFont::from_font_size(16.0), PageLabel));
+                    pages.spawn((
+                        small_button(),
+                        LeaderboardButton::NextPage,
+                        children![(Text::new(">"), TextFont::from_font_size(16.0))],
+                    ));
+                });
+        });
+}
+
+fn small_button() -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+    )
+}
+
+fn page_count(runs: &[RunRecord]) -> usize {
+    return runs.len().div_ceil(PAGE_SIZE).max(1);
+}
+
+fn configure_endpoint(launch: Res<LaunchOptions>, mut online: ResMut<OnlineLeaderboard>) {
+    online.endpoint = launch
+        .leaderboard
+        .clone()
+        .or_else(|| std::env::var(ENDPOINT_ENV_VAR).ok())
+        .map(|endpoint| endpoint.trim_end_matches('/').to_string());
+}
+
+fn fetch_online_runs(mut online: ResMut<OnlineLeaderboard>) {
+    let Some(endpoint) = online.endpoint.clone() else {
+        return;
+    };
+    if online.fetch.is_some() {
+        return;
+    }
+
+    let url = format!("{endpoint}/runs/top?count={ONLINE_TOP}");
+    online.fetch = Some(IoTaskPool::get().spawn(async move {
+        let body = http::get(&url)?;
+        return ron::from_str(&body).map_err(|_| HttpError::MalformedResponse);
+    }));
+}
+
+fn submit_runs(mut finished: EventReader<RunFinished>, mut online: ResMut<OnlineLeaderboard>) {
+    for RunFinished(run) in finished.read() {
+        let Some(endpoint) = online.endpoint.clone() else {
+            continue;
+        };
+        let Ok(body) = ron::to_string(run) else {
+            continue;
+        };
+
+        let url = format!("{endpoint}/runs");
+        online
+            .submissions
+            .push(IoTaskPool::get().spawn(async move { http::post(&url, &body) }));
+    }
+}
+
+/// Requests finish in the background, failures just leave the local table
+/// showing
+fn poll_online_requests(mut online: ResMut<OnlineLeaderboard>) {
+    if let Some(task) = online.fetch.take_if(|task| task.is_finished()) {
+        match block_on(task) {
+            Ok(top) => online.top = Some(top),
+            Err(e) => warn!("could not fetch online leaderboard: {e}"),
+        }
+    }
+
+    let (finished, pending) = std::mem::take(&mut online.submissions)
+        .into_iter()
+        .partition::<Vec<_>, _>(|task| task.is_finished());
+    online.submissions = pending;
+    for task in finished {
+        if let Err(e) = block_on(task) {
+            warn!("could not submit run to online leaderboard: {e}");
+        }
+    }
+}
+
+fn leaderboard_buttons(
+    buttons: Query<(&Interaction, &LeaderboardButton), Changed<Interaction>>,
+    save: Res<SaveData>,
+    online: Res<OnlineLeaderboard>,
+    mut view: ResMut<LeaderboardView>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            LeaderboardButton::Sort => {
+                view.sort = view.sort.next();
+                view.page = 0;
+            }
+            LeaderboardButton::PreviousPage => view.page = view.page.saturating_sub(1),
+            LeaderboardButton::NextPage => {
+                view.page = (view.page + 1).min(page_count(online.runs(&save.best_runs)) - 1);
+            }
+        }
+    }
+}
+
+fn update_leaderboard(
+    save: Res<SaveData>,
+    online: Res<OnlineLeaderboard>,
+    view: Res<LeaderboardView>,
+    mut rows: Query<(&mut Text, &LeaderboardRow)>,
+    mut sort_label: Query<&mut Text, (With<SortLabel>, Without<LeaderboardRow>)>,
+    mut page_label: Query<
+        &mut Text,
+        (With<PageLabel>, Without<LeaderboardRow>, Without<SortLabel>),
+    >,
+    added: Query<(), Added<LeaderboardRow>>,
+) {
+    if !save.is_changed() && !online.is_changed() && !view.is_changed() && added.is_empty() {
+        return;
+    }
+
+    let runs = online.runs(&save.best_runs);
+    let pages = page_count(runs);
+    let page = view.page.min(pages - 1);
+    let sorted = view.sort.sorted(runs);
+
+    let source = match online.top {
+        Some(_) => "online",
+        None => "local",
+    };
+    for mut text in sort_label.iter_mut() {
+        text.0 = format!("Sort by: {} ({source})", view.sort.name());
+    }
+    for mut text in page_label.iter_mut() {
+        text.0 = format!("Page {}/{pages}", page + 1);
+    }
+
+    for (mut text, row) in rows.iter_mut() {
+        let rank = page * PAGE_SIZE + row.0;
+        text.0 = match sorted.get(rank) {
+            Some(run) => {
+                let seconds = run.survived_seconds as u32;
+                format!(
+                    "{}. {} ({}) - {}:{:02}, level {}, {} kills",
+                    rank + 1,
+                    run.character,
+                    run.difficulty.name(),
+                    seconds / 60,
+                    seconds % 60,
+                    run.level,
+                    run.kills
+                )
+            }
+            None if rank == 0 => "No runs yet".to_string(),
+            None => String::new(),
+        };
+    }
+}
@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::CameraLookAhead;
+use crate::boundary::BoundaryDefinition;
+use crate::environment::environment_scene;
+use crate::loading::GameAssets;
+use crate::physics::layers;
+use crate::prefabs::{CameraBundle, GroundBundle};
+use crate::ron_asset::RonAssetLoader;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Level>();
+        app.register_asset_loader(RonAssetLoader::<Level>::new(&["level.ron"]));
+        app.add_systems(Update, spawn_level);
+    }
+}
+
+/// Everything placed in a map by hand
+///
+/// Loaded from a `.level.ron` file and hot reloaded, respawning the level
+/// whenever the file changes
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct Level {
+    pub sun: SunDefinition,
+    pub camera: CameraDefinition,
+    /// Whether ground chunks are generated around the player
+    #[serde(default)]
+    pub terrain: bool,
+    /// Edge of the playable area, without one nothing stops anyone walking
+    /// off the map
+    #[serde(default)]
+    pub boundary: Option<BoundaryDefinition>,
+    #[serde(default)]
+    pub geometry: Vec<GeometryDefinition>,
+    /// Path of a GLTF scene to use as level art, see `EnvironmentScene`
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SunDefinition {
+    pub translation: Vec3,
+    pub intensity: f32,
+    pub range: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CameraDefinition {
+    /// Vertical field of view, in degrees
+    pub fov: f32,
+    pub distance: f32,
+    #[serde(default)]
+    pub look_ahead: Option<CameraLookAhead>,
+}
+
+/// A static box with a matching collider
+#[derive(Deserialize, Debug, Clone)]
+pub struct GeometryDefinition {
+    pub name: String,
+    pub size: Vec3,
+    pub translation: Vec3,
+    /// Euler angles in degrees, applied in XYZ order
+    #[serde(default)]
+    pub rotation: Vec3,
+    pub color: (f32, f32, f32),
+    /// Whether it can be stood on
+    #[serde(default)]
+    pub ground: bool,
+}
+
+/// The level the world is currently built from
+#[derive(Resource, Deref)]
+pub struct ActiveLevel(pub Level);
+
+/// The level's light, with its brightness and reach as the level sets them
+/// before graphics settings scale them
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Sun {
+    pub intensity: f32,
+    pub range: f32,
+}
+
+/// Despawned when the level is respawned
+#[derive(Component)]
+struct LevelEntity;
+
+fn spawn_level(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Level>>,
+    levels: Res<Assets<Level>>,
+    game_assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    mut assets: (ResMut<Assets<Mesh>>, ResMut<Assets<StandardMaterial>>),
+    spawned: Query<Entity, With<LevelEntity>>,
+) {
+    let (meshes, materials) = &mut assets;
+    let handle = &game_assets.level;
+    let reloaded = events
+        .read()
+        .any(|e| e.is_loaded_with_dependencies(handle) || e.is_modified(handle));
+    let Some(level) = levels.get(handle).filter(|_| reloaded) else {
+        return;
+    };
+
+    for entity in spawned.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    commands.spawn((
+        PointLight {
+            shadows_enabled: true,
+            intensity: level.sun.intensity,
+            range: level.sun.range,
+            shadow_depth_bias: 0.2,
+            ..default()
+        },
+        Transform::from_translation(level.sun.translation),
+        Sun {
+            intensity: level.sun.intensity,
+            range: level.sun.range,
+        },
+        Name::new("Sun"),
+        LevelEntity,
+    ));
+
+    let mut camera = commands.spawn((
+        CameraBundle::new(level.camera.fov, level.camera.distance),
+        LevelEntity,
+    ));
+    if let Some(look_ahead) = level.camera.look_ahead {
+        camera.insert(look_ahead);
+    }
+
+    for geometry in level.geometry.iter() {
+        let rotation = geometry.rotation * std::f32::consts::PI / 180.0;
+        let mut entity = commands.spawn((
+            Mesh3d(meshes.add(Cuboid::from_size(geometry.size))),
+            MeshMaterial3d(materials.add(Color::srgb(
+                geometry.color.0,
+                geometry.color.1,
+                geometry.color.2,
+            ))),
+            Transform::from_translation(geometry.translation).with_rotation(Quat::from_euler(
+                EulerRot::XYZ,
+                rotation.x,
+                rotation.y,
+                rotation.z,
+            )),
+            Name::new(geometry.name.clone()),
+            RigidBody::Fixed,
+            LevelEntity,
+        ));
+        let collider = Collider::cuboid(
+            geometry.size.x / 2.0,
+            geometry.size.y / 2.0,
+            geometry.size.z / 2.0,
+        );
+        if geometry.ground {
+            entity.insert(GroundBundle::new(collider));
+        } else {
+            entity.insert((collider, layers::ground()));
+        }
+    }
+
+    if let Some(path) = &level.environment {
+        commands.spawn((environment_scene(&asset_server, path), LevelEntity));
+    }
+
+    commands.insert_resource(ActiveLevel(level.clone()));
+}
@@ -1,51 +1,250 @@
 #![allow(clippy::type_complexity)]
 #![allow(unused)]
 
-use bevy::asset::RenderAssetUsages;
-use bevy::color::palettes::css::{SILVER, WHITE};
+use bevy::app::PluginGroupBuilder;
+use bevy::color::palettes::css::{PINK, WHITE};
+use bevy::log::{BoxedLayer, LogPlugin};
 use bevy::prelude::App as BevyApp;
 use bevy::prelude::*;
-use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+#[cfg(feature = "devtools")]
 use bevy_inspector_egui::InspectorOptions;
+#[cfg(feature = "devtools")]
 use bevy_inspector_egui::inspector_egui_impls::InspectorPrimitive;
+#[cfg(feature = "devtools")]
 use bevy_inspector_egui::prelude::ReflectInspectorOptions;
-use bevy_math::ops::{cos, sin};
 use bevy_rapier3d::prelude::*;
 use leafwing_input_manager::plugin::InputManagerPlugin;
-use leafwing_input_manager::prelude::InputMap;
+use leafwing_input_manager::prelude::{GamepadStick, InputMap};
 use rand::prelude::*;
+use serde::Deserialize;
 
-use crate::animation::SpriteScale;
+use crate::animation::{Facing, SpriteScale};
+use crate::balance::BalanceConfig;
+use crate::cli::LaunchOptions;
+use crate::combat::GodMode;
 use crate::controls::Action;
-
+use crate::move_speed::MoveSpeedModifiers;
+use crate::prefabs::PlayerConfig;
+use crate::run_config::RunConfig;
+use crate::save::SaveData;
+use crate::toon::ToonMaterial;
+
+mod abilities;
+mod accessibility;
+mod achievements;
+mod afterimage;
+pub mod ai;
 mod animation;
-mod controls;
+mod announcements;
+mod aura;
+mod balance;
+mod boss;
+mod boundary;
+mod budget;
+mod characters;
+mod chests;
+mod cinematic;
+pub mod cli;
+mod combat;
+mod combo;
 #[cfg(debug_assertions)]
+mod console;
+mod controls;
+mod corpses;
+mod damage_feedback;
+mod damage_numbers;
+mod damage_zone;
+mod debug_visuals;
+mod elites;
+pub mod enemies;
+mod environment;
+#[cfg(feature = "devtools")]
+mod event_log;
+mod experience;
+mod fall_recovery;
+mod flow_field;
+mod game_over;
+mod graphics;
+mod hazards;
+mod highlight;
+mod hit_stop;
+mod http;
+mod input_device;
+#[cfg(feature = "devtools")]
 mod inspector;
-
+mod interaction;
+mod interpolation;
+mod leaderboard;
+mod level;
+mod loading;
+pub mod math;
+mod menu;
+mod move_speed;
+mod music;
+#[cfg(feature = "net")]
+pub mod net;
+mod on_hit;
+mod particles;
+mod pets;
+pub mod physics;
+mod pickups;
+mod platforms;
+mod pool;
+mod prefab_library;
+mod prefabs;
+#[cfg(feature = "devtools")]
+mod profiling;
+mod projectile;
+mod props;
+mod reflection;
+mod replay;
+mod revive;
+mod rng;
+mod ron_asset;
+mod rumble;
+mod run_config;
+mod run_stats;
+mod save;
+mod sfx;
+mod shop;
+mod shrines;
+#[cfg(feature = "devtools")]
+mod snapshot;
+mod spatial;
+mod spawner;
+#[cfg(feature = "sprite_packing")]
+mod sprite_packing;
+mod stamina;
+mod stats;
+mod status;
+mod telegraph;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod threat_indicators;
+mod time_scale;
+mod toon;
+mod touch;
+mod transition;
+mod tutorial;
+mod unlocks;
+mod upgrades;
+mod weapons;
+mod world;
+
+/// Rate physics and the forces driving it are stepped at
+const PHYSICS_HZ: f64 = 64.0;
+/// Players that can share one screen
+pub const MAX_LOCAL_PLAYERS: usize = 2;
+/// Gap between players when a co-op run starts
+const PLAYER_SPACING: f32 = 4.0;
+/// Stick deflection ignored as drift before movement starts
+const MOVE_STICK_DEADZONE: f32 = 0.1;
+
+/// `BunvivorPlugins` in a bevy `App` of its own, see `BunvivorPlugins` to
+/// add the game to an existing one
 pub struct App {
     _app: BevyApp,
 }
 
+/// How the game starts up, for binaries and tests that need something other
+/// than the defaults
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub title: String,
+    /// Logical size of the window
+    pub resolution: Vec2,
+    pub vsync: bool,
+    /// Swap windowing and rendering for the bare minimum gameplay needs
+    pub headless: bool,
+    /// Allow drawing collider outlines, toggled by `DebugVisuals`
+    pub debug_render: bool,
+    /// Open the world inspector at startup, F1 toggles it later. Only
+    /// available with the `devtools` feature
+    pub inspector: bool,
+    /// Read and write the save file, otherwise progress only lives in memory
+    pub persist_save: bool,
+    /// Extra tracing layer for bevy's `LogPlugin`, for tools that read spans.
+    /// Headless apps have no logging at all unless this is set
+    pub log_layer: Option<fn(&mut BevyApp) -> Option<BoxedLayer>>,
+    pub launch: LaunchOptions,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            title: "Bunvivor".to_string(),
+            resolution: Vec2::new(1280.0, 720.0),
+            vsync: true,
+            headless: false,
+            debug_render: true,
+            inspector: true,
+            persist_save: true,
+            log_layer: None,
+            launch: LaunchOptions::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// No window, renderer, debug tools or save file
+    pub fn headless() -> Self {
+        return Self {
+            headless: true,
+            debug_render: false,
+            inspector: false,
+            persist_save: false,
+            ..default()
+        };
+    }
+}
+
 impl App {
     pub fn new() -> Self {
-        let mut app = BevyApp::new();
+        return Self::with_config(AppConfig::default());
+    }
 
-        app.add_plugins(SetupPlugin);
-        app.add_plugins(InputManagerPlugin::<crate::controls::Action>::default());
-        #[cfg(debug_assertions)]
-        app.add_plugins(crate::inspector::Inspector);
-        app.add_plugins(crate::controls::ControlsPlugin);
-        app.add_plugins(crate::animation::AnimationPlugin);
-        app.add_plugins(RapierPhysicsPlugin::<NoUserData>::default());
-        app.add_plugins(RapierDebugRenderPlugin::default());
+    /// The game without a window or renderer, for driving gameplay systems
+    /// from tests
+    ///
+    /// Nothing runs on its own, call `update` to step a frame. The save is
+    /// kept in memory so tests never touch the player's save file
+    pub fn new_headless() -> Self {
+        return Self::with_config(AppConfig::headless());
+    }
 
+    pub fn with_config(config: AppConfig) -> Self {
+        let mut app = BevyApp::new();
+        app.add_plugins(BunvivorPlugins { config });
         return Self { _app: app };
     }
 
+    pub fn add_plugins<M>(&mut self, plugins: impl bevy::app::Plugins<M>) -> &mut Self {
+        self._app.add_plugins(plugins);
+        return self;
+    }
+
     pub fn run(&mut self) {
         self._app.run();
     }
+
+    /// Run every schedule once
+    ///
+    /// The first call finishes plugin setup, which `run` would otherwise do
+    pub fn update(&mut self) {
+        if self._app.plugins_state() == bevy::app::PluginsState::Ready {
+            self._app.finish();
+            self._app.cleanup();
+        }
+        self._app.update();
+    }
+
+    pub fn world(&self) -> &World {
+        return self._app.world();
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        return self._app.world_mut();
+    }
 }
 
 impl Default for App {
@@ -54,50 +253,354 @@ impl Default for App {
     }
 }
 
-pub trait VecTools {
-    type Other;
-    fn max_mag(&mut self, other: &Self::Other);
+/// The whole game, for adding to a `bevy::App` of your own, which `App`
+/// wraps for the common case
+///
+/// `EnginePlugin` brings in bevy's own plugins. Disable it on the group if
+/// the app already has them:
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bunvivor::{AppConfig, BunvivorPlugins, EnginePlugin};
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugins(BunvivorPlugins::new(AppConfig::default()).build().disable::<EnginePlugin>())
+///     .run();
+/// ```
+pub struct BunvivorPlugins {
+    pub config: AppConfig,
 }
 
-impl VecTools for Vec3 {
-    type Other = Vec3;
+impl BunvivorPlugins {
+    pub fn new(config: AppConfig) -> Self {
+        return Self { config };
+    }
+}
 
-    fn max_mag(&mut self, other: &Self::Other) {
-        if self.x.abs() < other.x.abs() {
-            self.x = other.x;
+impl PluginGroup for BunvivorPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        let config = self.config;
+
+        // setup
+        let mut group = PluginGroupBuilder::start::<Self>()
+            .add(EnginePlugin {
+                config: config.clone(),
+            })
+            .add(SetupPlugin {
+                config: config.clone(),
+            })
+            .add(crate::rng::RngPlugin {
+                seed: config.launch.seed,
+            });
+        #[cfg(feature = "net")]
+        if let Some(role) = config.launch.net {
+            group = group.add(crate::net::NetPlugin { role });
         }
-
-        if self.y.abs() < other.y.abs() {
-            self.y = other.y;
+        group = group
+            .add(crate::loading::LoadingPlugin)
+            .add(crate::transition::TransitionPlugin)
+            .add(crate::balance::BalancePlugin)
+            .add(crate::reflection::ReflectionPlugin);
+
+        // controls
+        group = group.add(InputManagerPlugin::<crate::controls::Action>::default());
+        #[cfg(feature = "devtools")]
+        if !config.headless {
+            group = group.add(crate::inspector::Inspector {
+                visible: config.inspector,
+            });
+        }
+        group = group.add(crate::controls::ControlsPlugin);
+        #[cfg(debug_assertions)]
+        {
+            group = group.add(crate::console::ConsolePlugin);
+        }
+        group = group.add(crate::touch::TouchControlsPlugin);
+        group = group.add(crate::input_device::InputDevicePlugin);
+
+        // animation
+        group = group
+            .add(crate::toon::ToonPlugin)
+            .add(crate::animation::AnimationPlugin);
+        #[cfg(feature = "sprite_packing")]
+        {
+            group = group.add(crate::sprite_packing::SpritePackingPlugin);
         }
 
-        if self.z.abs() < other.z.abs() {
-            self.z = other.z;
+        // gameplay
+        group = group
+            .add(crate::ai::AiPlugin)
+            .add(crate::flow_field::FlowFieldPlugin)
+            .add(crate::combat::CombatPlugin)
+            .add(crate::corpses::CorpsesPlugin)
+            .add(crate::projectile::ProjectilePlugin)
+            .add(crate::pool::PoolPlugin)
+            .add(crate::budget::BudgetPlugin)
+            .add(crate::enemies::EnemiesPlugin)
+            .add(crate::pickups::PickupsPlugin)
+            .add(crate::interaction::InteractionPlugin)
+            .add(crate::prefab_library::PrefabLibraryPlugin)
+            .add(crate::run_config::RunConfigPlugin)
+            .add(crate::spawner::SpawnerPlugin)
+            .add(crate::boss::BossPlugin)
+            .add(crate::boundary::BoundaryPlugin)
+            .add(crate::fall_recovery::FallRecoveryPlugin)
+            .add(crate::elites::ElitesPlugin)
+            .add(crate::highlight::HighlightPlugin)
+            .add(crate::status::StatusPlugin)
+            .add(crate::on_hit::OnHitPlugin)
+            .add(crate::particles::ParticlesPlugin)
+            .add(crate::afterimage::AfterimagePlugin)
+            .add(crate::sfx::SfxPlugin)
+            .add(crate::music::MusicPlugin)
+            .add(crate::damage_feedback::DamageFeedbackPlugin)
+            .add(crate::damage_numbers::DamageNumbersPlugin)
+            .add(crate::stats::StatsPlugin)
+            .add(crate::move_speed::MoveSpeedPlugin)
+            .add(crate::stamina::StaminaPlugin)
+            .add(crate::abilities::AbilitiesPlugin)
+            .add(crate::revive::RevivePlugin)
+            .add(crate::rumble::RumblePlugin)
+            .add(crate::weapons::WeaponsPlugin)
+            .add(crate::aura::AuraPlugin)
+            .add(crate::pets::PetsPlugin)
+            .add(crate::experience::ExperiencePlugin)
+            .add(crate::combo::ComboPlugin)
+            .add(crate::upgrades::UpgradesPlugin)
+            .add(crate::chests::ChestsPlugin);
+        if config.persist_save {
+            group = group.add(crate::save::SavePlugin);
         }
+        group = group
+            .add(crate::shop::ShopPlugin)
+            .add(crate::graphics::GraphicsPlugin)
+            .add(crate::accessibility::AccessibilityPlugin)
+            .add(crate::menu::MainMenuPlugin)
+            .add(crate::characters::CharactersPlugin)
+            .add(crate::leaderboard::LeaderboardPlugin)
+            .add(crate::run_stats::RunStatsPlugin)
+            .add(crate::game_over::GameOverPlugin)
+            .add(crate::achievements::AchievementsPlugin)
+            .add(crate::tutorial::TutorialPlugin)
+            .add(crate::level::LevelPlugin)
+            .add(crate::environment::EnvironmentPlugin)
+            .add(crate::world::WorldPlugin)
+            .add(crate::props::PropsPlugin)
+            .add(crate::hazards::HazardsPlugin)
+            .add(crate::shrines::ShrinesPlugin)
+            .add(crate::damage_zone::DamageZonePlugin)
+            .add(crate::telegraph::TelegraphPlugin)
+            .add(crate::threat_indicators::ThreatIndicatorsPlugin)
+            .add(crate::announcements::AnnouncementsPlugin)
+            .add(crate::platforms::PlatformsPlugin)
+            .add(crate::replay::ReplayPlugin)
+            .add(crate::time_scale::TimeScalePlugin)
+            .add(crate::hit_stop::HitStopPlugin)
+            .add(crate::cinematic::CinematicPlugin);
+
+        // physics
+        group = group
+            .add(PhysicsStepPlugin)
+            .add(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
+            .add(crate::interpolation::InterpolationPlugin);
+        if config.debug_render && !config.headless {
+            group = group.add(RapierDebugRenderPlugin::default().disabled());
+        }
+        group = group.add(crate::debug_visuals::DebugVisualsPlugin);
+
+        return group;
+    }
+}
+
+/// Steps physics at `PHYSICS_HZ`, once per fixed update
+struct PhysicsStepPlugin;
+
+impl Plugin for PhysicsStepPlugin {
+    fn build(&self, app: &mut BevyApp) {
+        app.insert_resource(Time::<Fixed>::from_hz(PHYSICS_HZ));
+        app.insert_resource(TimestepMode::Fixed {
+            dt: 1.0 / PHYSICS_HZ as f32,
+            substeps: 1,
+        });
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct MainCamera;
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Player;
 
-#[derive(Component)]
+/// Which local player this is, starting from 0, deciding their controls and
+/// which gamepad they take
+#[derive(Component, Reflect, Deref, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct PlayerSlot(pub usize);
+
+/// How many players the next run starts with
+#[derive(Resource, Deref, DerefMut, Debug, Clone, Copy)]
+pub struct LocalPlayers(pub usize);
+
+impl Default for LocalPlayers {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Enemy;
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 pub struct Ground;
 
-#[derive(Component, Deref, DerefMut, Reflect)]
+/// The grounds the entity is standing on, out of everything it's touching.
+/// Walls and the undersides of things don't count, so brushing against the
+/// side of a ledge doesn't hold it up
+#[derive(Component, Deref, DerefMut, Reflect, Default, Debug)]
+#[reflect(Component)]
+#[require(GroundContacts, GroundNormal, SlopeSettings)]
 pub struct CollidedGrounds(Vec<Entity>);
 
+/// Every ground the entity is touching, whichever way it's touching it
+#[derive(Component, Deref, DerefMut, Reflect, Default, Debug)]
+#[reflect(Component)]
+pub struct GroundContacts(Vec<Entity>);
+
+/// Which way is up out of the ground the entity is standing on, averaged
+/// over everything in its `CollidedGrounds`. `None` while it's in the air
+#[derive(Component, Deref, DerefMut, Reflect, Default, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct GroundNormal(Option<Vec3>);
+
+/// Scales how hard the entity can steer while it isn't on walkable ground,
+/// less than 1 keeps jumps and knockback from being turned around mid-air
+#[derive(Component, Deref, DerefMut, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct AirControl(pub f32);
+
+impl Default for AirControl {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// How an entity gets up to speed, slows down and changes direction
+#[derive(Component, Reflect, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct MovementProfile {
+    /// How hard movement pushes towards a faster target velocity, per unit
+    /// of velocity missing
+    pub acceleration: f32,
+    /// The same, for slowing down or stopping
+    pub deceleration: f32,
+    /// Radians per second the direction of movement can swing round
+    pub turn_rate: f32,
+}
+
+impl Default for MovementProfile {
+    /// Snappy, turning on the spot
+    fn default() -> Self {
+        Self {
+            acceleration: 300.0,
+            deceleration: 300.0,
+            turn_rate: f32::INFINITY,
+        }
+    }
+}
+
+impl MovementProfile {
+    /// Speeding up, stopping and turning all `handling` times as quick
+    pub fn scaled(&self, handling: f32) -> Self {
+        Self {
+            acceleration: self.acceleration * handling,
+            deceleration: self.deceleration * handling,
+            turn_rate: self.turn_rate * handling,
+        }
+    }
+}
+
+/// How steep a slope the entity can walk up. Anything steeper can't be
+/// climbed and is slid back down, as if in the air
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct SlopeSettings {
+    /// In degrees from flat
+    pub max_angle: f32,
+    /// Pushes the entity down slopes steeper than `max_angle` on top of
+    /// gravity, so it can't cling to them
+    pub slide_acceleration: f32,
+}
+
+impl Default for SlopeSettings {
+    fn default() -> Self {
+        Self {
+            max_angle: 40.0,
+            slide_acceleration: 20.0,
+        }
+    }
+}
+
+impl SlopeSettings {
+    /// Whether ground facing out along `normal` is flat enough to stand on
+    pub fn walkable(&self, normal: Vec3) -> bool {
+        return normal.angle_between(Vec3::Y) <= self.max_angle.to_radians();
+    }
+}
+
 /// Describes the move speed of the player in terms of background tiles per second
-#[derive(Component, Deref, DerefMut)]
+///
+/// Worked out every frame from `BaseMoveSpeed` and any `MoveSpeedModifiers`,
+/// so change those rather than this
+#[derive(Component, Reflect, Deref, DerefMut, Default, PartialEq)]
+#[reflect(Component)]
 pub struct MoveSpeed(pub f32);
 
+/// Move speed before slows and boosts
+#[derive(Component, Deref, DerefMut, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+#[require(MoveSpeed, MoveSpeedModifiers)]
+pub struct BaseMoveSpeed(pub f32);
+
 #[derive(Component, Deref, DerefMut, Reflect, Debug, PartialEq, Default)]
+#[reflect(Component)]
 pub struct IntendedRotation(pub Quat);
 
+/// Turns the entity to face where it's moving, at this many radians per
+/// second
+#[derive(Component, Deref, DerefMut, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+#[require(IntendedRotation)]
+pub struct FaceMovement(pub f32);
+
+/// Tilts the entity over to stand square on sloped ground, eased in at
+/// `rate` so it doesn't snap on every bump
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+#[require(IntendedRotation, GroundNormal)]
+pub struct AlignToGround {
+    pub rate: f32,
+    /// How far it's currently tilted, on top of its `IntendedRotation`
+    pub tilt: Quat,
+}
+
+impl AlignToGround {
+    pub fn new(rate: f32) -> Self {
+        Self {
+            rate,
+            tilt: Quat::IDENTITY,
+        }
+    }
+}
+
 /// Describes the direction an entity is trying to move
-#[derive(Debug, Component, Deref, DerefMut, Reflect)]
+#[derive(Debug, Component, Deref, DerefMut, Reflect, PartialEq)]
+#[reflect(Component)]
+#[require(Facing)]
 pub struct MoveVector {
     pub vec: Vec3,
 }
@@ -114,190 +617,279 @@ impl AsRef<Vec3> for MoveVector {
     }
 }
 
-#[derive(Component, Deref, DerefMut)]
+#[derive(Component, Reflect, Deref, DerefMut)]
+#[reflect(Component)]
+#[require(CameraPullIn, CameraYaw)]
 pub struct CameraDistance(pub f32);
 
-pub struct SetupPlugin;
+/// How far the camera has been swung around the players, in radians
+#[derive(Component, Reflect, Deref, DerefMut, Default)]
+#[reflect(Component)]
+pub struct CameraYaw(pub f32);
+
+/// How far the camera is currently pulled in from its `CameraDistance` to
+/// see past something standing between it and a player
+#[derive(Component, Reflect, Deref, DerefMut, Default)]
+#[reflect(Component)]
+pub struct CameraPullIn(pub f32);
+
+/// Leads the camera ahead of where the players are heading, so more of what
+/// they're running into is on screen. Without it the camera stays centred
+#[derive(Component, Reflect, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct CameraLookAhead {
+    /// Seconds of the players' velocity to lead them by
+    pub lead: f32,
+    /// Furthest ahead it leads, in world units
+    pub max_distance: f32,
+    /// How quickly it swings round when they change direction
+    pub rate: f32,
+    /// How far ahead it's leading right now
+    #[serde(skip)]
+    pub offset: Vec3,
+}
+
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[states(scoped_entities)]
+pub enum GameState {
+    #[default]
+    Loading,
+    MainMenu,
+    CharacterSelect,
+    InGame,
+    GameOver,
+}
+
+/// Systems that only run during a run
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameplaySet;
+
+/// The order a frame of a run goes in. Spawning comes first, so nothing
+/// later in the frame finds an entity missing or only half set up, and the
+/// camera last, following wherever everything ended up
+///
+/// Part of `GameplaySet`. `Camera` runs in `PostUpdate`, the rest in `Update`
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    Spawn,
+    Control,
+    Camera,
+}
+
+/// Gameplay systems deciding what happens in a run, such as spawning and
+/// dealing damage, as opposed to just playing it out
+///
+/// Part of `GameplaySet`. Only the host runs these in online co-op
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthoritySet;
+
+pub struct SetupPlugin {
+    pub config: AppConfig,
+}
 
 impl Plugin for SetupPlugin {
     fn build(&self, app: &mut BevyApp) {
-        app.add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()));
-        app.add_systems(Startup, setup);
+        app.insert_resource(self.config.launch.clone());
+        app.insert_resource(LocalPlayers(
+            self.config
+                .launch
+                .players
+                .map_or(1, |players| players.clamp(1, MAX_LOCAL_PLAYERS)),
+        ));
+        app.register_type::<PlayerSlot>();
+        app.init_state::<GameState>();
+        app.configure_sets(Update, GameplaySet.run_if(in_state(GameState::InGame)));
+        app.configure_sets(FixedUpdate, GameplaySet.run_if(in_state(GameState::InGame)));
+        app.configure_sets(PostUpdate, GameplaySet.run_if(in_state(GameState::InGame)));
+        app.configure_sets(Update, AuthoritySet.in_set(GameplaySet));
+        app.configure_sets(
+            Update,
+            (GameSet::Spawn, GameSet::Control)
+                .chain()
+                .in_set(GameplaySet),
+        );
+        app.configure_sets(PostUpdate, GameSet::Camera.in_set(GameplaySet));
+        app.add_systems(OnEnter(GameState::InGame), spawn_players);
+        if !self.config.persist_save {
+            app.insert_resource(SaveData::default());
+        }
+    }
+}
+
+/// Bevy's own plugins, windowed or headless as `AppConfig` says. Leave it
+/// out of `BunvivorPlugins` when embedding in an app that has its own
+pub struct EnginePlugin {
+    pub config: AppConfig,
+}
+
+impl Plugin for EnginePlugin {
+    fn build(&self, app: &mut BevyApp) {
+        if self.config.headless {
+            add_headless_plugins(app);
+            if let Some(custom_layer) = self.config.log_layer {
+                app.add_plugins(LogPlugin {
+                    custom_layer,
+                    ..default()
+                });
+            }
+        } else {
+            let window = Window {
+                title: self.config.title.clone(),
+                resolution: self.config.resolution.into(),
+                present_mode: if self.config.vsync {
+                    bevy::window::PresentMode::AutoVsync
+                } else {
+                    bevy::window::PresentMode::AutoNoVsync
+                },
+                ..default()
+            };
+            let mut plugins =
+                DefaultPlugins
+                    .set(ImagePlugin::default_nearest())
+                    .set(WindowPlugin {
+                        primary_window: Some(window),
+                        ..default()
+                    });
+            if let Some(custom_layer) = self.config.log_layer {
+                plugins = plugins.set(LogPlugin {
+                    custom_layer,
+                    ..default()
+                });
+            }
+            app.add_plugins(plugins);
+        }
     }
 }
 
-fn setup(
+/// What `DefaultPlugins` provides that gameplay can't run without, minus
+/// the window and renderer
+fn add_headless_plugins(app: &mut BevyApp) {
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        bevy::input::InputPlugin,
+        bevy::state::app::StatesPlugin,
+        bevy::scene::ScenePlugin,
+        ImagePlugin::default_nearest(),
+    ));
+    // meshes and materials are still created and attached, just never drawn
+    app.init_asset::<Mesh>();
+    app.init_asset::<StandardMaterial>();
+    app.init_asset::<TextureAtlasLayout>();
+    app.init_asset::<Shader>();
+    // sounds are loaded too so tests catch broken ones, just never played
+    app.init_asset::<AudioSource>();
+    app.init_asset_loader::<bevy::audio::AudioLoader>();
+    app.add_plugins(bevy::gizmos::GizmoPlugin);
+}
+
+fn spawn_players(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+    (save, config): (Res<SaveData>, Res<RunConfig>),
+    balance: Res<BalanceConfig>,
+    launch: Res<LaunchOptions>,
+    local_players: Res<LocalPlayers>,
 ) {
-    // bun
-    commands
-        .spawn((
-            Mesh3d(meshes.add(Capsule3d {
-                radius: 1.0,
-                half_length: 1.0,
-            })),
-            MeshMaterial3d(materials.add(Color::from(WHITE))),
-            InputMap::new([
-                (Action::Left, KeyCode::ArrowLeft),
-                (Action::Right, KeyCode::ArrowRight),
-                (Action::Up, KeyCode::ArrowUp),
-                (Action::Down, KeyCode::ArrowDown),
-            ]),
-            MoveSpeed(23.6),
-            MoveVector::default(),
-            Player,
-            Transform::from_translation(Vec3::new(0.0, 2.1, 0.0)),
-            Name::new("Player"),
-            bevy_rapier3d::dynamics::Damping {
-                linear_damping: 0.0,
-                angular_damping: 6.5,
+    let character = config.character();
+    let mut stats = character.stats(&balance);
+    shop::apply_purchases(&mut stats, &save);
+    let mesh = meshes.add(Capsule3d {
+        radius: 1.0,
+        half_length: 1.0,
+    });
+
+    for slot in 0..**local_players {
+        // side by side, centred on the origin
+        let x = (slot as f32 - (**local_players - 1) as f32 / 2.0) * PLAYER_SPACING;
+        let color = if slot == 0 { WHITE } else { PINK };
+
+        // bun
+        let player = prefabs::spawn_player(
+            &mut commands,
+            PlayerConfig {
+                slot,
+                position: Vec3::new(x, 2.1, 0.0),
+                mesh: mesh.clone(),
+                material: materials.add(ToonMaterial::from(Color::from(color))),
+                stats: &stats,
+                weapon: character.weapon,
+                balance: &balance,
             },
-            RigidBody::Dynamic,
-            Velocity::default(),
-            ExternalForce::default(),
-            GravityScale(1.0),
-            Collider::capsule(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), 1.0),
-            IntendedRotation::default(),
-        ))
-        .insert(ActiveEvents::COLLISION_EVENTS)
-        .insert(LockedAxes::ROTATION_LOCKED)
-        .insert(CollidedGrounds(Vec::new()));
-
-    commands.spawn((
-        PointLight {
-            shadows_enabled: true,
-            intensity: 10_000_000.,
-            range: 80.0,
-            shadow_depth_bias: 0.2,
-            ..default()
-        },
-        Transform::from_xyz(16.0, 16.0, 16.0),
-        Name::new("Sun"),
-    ));
-
-    // base floor
-    commands.spawn((
-        Mesh3d(
-            meshes.add(
-                Plane3d::default()
-                    .mesh()
-                    .size(112.0, 112.0)
-                    .subdivisions(10),
-            ),
-        ),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color_texture: Some(images.add(uv_debug_texture())),
-            ..default()
-        })),
-        Collider::cuboid(66.0, 0.1, 66.0),
-        Friction {
-            coefficient: 0.0,
-            ..default()
-        },
-        RigidBody::Fixed,
-        Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
-        Name::new("Debug Floor"),
-        Ground,
-    ));
+        );
 
-    // ramp
-    commands.spawn((
-        Mesh3d(
-            meshes.add(
-                Plane3d::default()
-                    .mesh()
-                    .size(112.0, 112.0)
-                    .subdivisions(10),
-            ),
-        ),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color_texture: Some(images.add(uv_debug_texture())),
-            ..default()
-        })),
-        Collider::cuboid(66.0, 0.1, 66.0),
-        Friction {
-            coefficient: 0.0,
-            ..default()
-        },
-        RigidBody::Fixed,
-        Transform::from_translation(Vec3::new(-112.0, 66.0 * sin(30_f32.to_radians()), 0.0))
-            .with_rotation(Quat::from_rotation_z(-30_f32.to_radians())),
-        Name::new("Debug Floor"),
-        Ground,
-    ));
-
-    // second floor
-    commands.spawn((
-        Mesh3d(
-            meshes.add(
-                Plane3d::default()
-                    .mesh()
-                    .size(112.0, 112.0)
-                    .subdivisions(10),
-            ),
-        ),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color_texture: Some(images.add(uv_debug_texture())),
-            ..default()
-        })),
-        Collider::cuboid(66.0, 0.1, 66.0),
-        Friction {
-            coefficient: 0.0,
-            ..default()
-        },
-        RigidBody::Fixed,
-        Transform::from_translation(Vec3::new(
-            -138.0 + -112.0 * cos(30_f32.to_radians()),
-            112.0 * sin(30_f32.to_radians()) + 10.,
-            0.0,
-        )),
-        Name::new("Debug Floor"),
-        Ground,
-    ));
-
-    // spawn camera
-    commands.spawn((
-        Camera3d { ..default() },
-        Projection::Perspective(PerspectiveProjection {
-            fov: 35_f32.to_radians(),
-            ..default()
-        }),
-        Transform::from_xyz(0.0, 7., 14.0).looking_at(Vec3::new(0., 1., 0.), Vec3::Y),
-        CameraDistance(120.),
-        MainCamera,
-        Name::new("MainCamera"),
-    ));
+        if launch.god_mode {
+            commands.entity(player).insert(GodMode);
+        }
+    }
 }
 
-/// Creates a colorful test pattern
-fn uv_debug_texture() -> Image {
-    const TEXTURE_SIZE: usize = 8;
-
-    let mut palette: [u8; 32] = [
-        255, 102, 159, 255, 255, 159, 102, 255, 236, 255, 102, 255, 121, 255, 102, 255, 102, 255,
-        198, 255, 102, 198, 255, 255, 121, 102, 255, 255, 236, 102, 255, 255,
-    ];
-
-    let mut texture_data = [0; TEXTURE_SIZE * TEXTURE_SIZE * 4];
-    for y in 0..TEXTURE_SIZE {
-        let offset = TEXTURE_SIZE * y * 4;
-        texture_data[offset..(offset + TEXTURE_SIZE * 4)].copy_from_slice(&palette);
-        palette.rotate_right(4);
+/// The key each player presses to use whatever `Interactable` they're next
+/// to, picked to sit by the rest of their keys
+pub(crate) fn interact_key(slot: usize) -> KeyCode {
+    match slot {
+        0 => return KeyCode::Enter,
+        _ => return KeyCode::KeyG,
     }
+}
 
-    Image::new_fill(
-        Extent3d {
-            width: TEXTURE_SIZE as u32,
-            height: TEXTURE_SIZE as u32,
-            depth_or_array_layers: 1,
-        },
-        TextureDimension::D2,
-        &texture_data,
-        TextureFormat::Rgba8UnormSrgb,
-        RenderAssetUsages::RENDER_WORLD,
-    )
+/// Interact on every player's gamepad
+pub(crate) const INTERACT_BUTTON: GamepadButton = GamepadButton::East;
+
+/// Arrow keys for the first player and WASD for the second, each also takes
+/// the d-pad of the gamepad `assign_gamepads` gives them
+pub(crate) fn player_input_map(slot: usize) -> InputMap<Action> {
+    let keys = match slot {
+        0 => [
+            KeyCode::ArrowLeft,
+            KeyCode::ArrowRight,
+            KeyCode::ArrowUp,
+            KeyCode::ArrowDown,
+            KeyCode::Space,
+            KeyCode::ShiftRight,
+            KeyCode::Comma,
+            KeyCode::Period,
+        ],
+        _ => [
+            KeyCode::KeyA,
+            KeyCode::KeyD,
+            KeyCode::KeyW,
+            KeyCode::KeyS,
+            KeyCode::ShiftLeft,
+            KeyCode::ControlLeft,
+            KeyCode::KeyR,
+            KeyCode::KeyF,
+        ],
+    };
+
+    return InputMap::new([
+        (Action::Left, keys[0]),
+        (Action::Right, keys[1]),
+        (Action::Up, keys[2]),
+        (Action::Down, keys[3]),
+        (Action::Dash, keys[4]),
+        (Action::Sprint, keys[5]),
+        (Action::Ability1, keys[6]),
+        (Action::Ability2, keys[7]),
+        (Action::OrbitLeft, KeyCode::KeyQ),
+        (Action::OrbitRight, KeyCode::KeyE),
+        (Action::Interact, interact_key(slot)),
+    ])
+    .with(Action::Left, GamepadButton::DPadLeft)
+    .with(Action::Right, GamepadButton::DPadRight)
+    .with(Action::Up, GamepadButton::DPadUp)
+    .with(Action::Down, GamepadButton::DPadDown)
+    .with(Action::Dash, GamepadButton::South)
+    .with(Action::Sprint, GamepadButton::LeftThumb)
+    .with(Action::Ability1, GamepadButton::West)
+    .with(Action::Ability2, GamepadButton::North)
+    .with(Action::OrbitLeft, GamepadButton::LeftTrigger)
+    .with(Action::OrbitRight, GamepadButton::RightTrigger)
+    .with(Action::Interact, INTERACT_BUTTON)
+    .with_dual_axis(
+        Action::Move,
+        GamepadStick::LEFT.with_deadzone_symmetric(MOVE_STICK_DEADZONE),
+    );
 }
@@ -0,0 +1,176 @@
+use bevy::asset::io::file::FileAssetReader;
+use bevy::asset::{LoadedFolder, UntypedAssetId};
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::animation::AnimationLibrary;
+use crate::balance::BalanceConfig;
+use crate::cli::LaunchOptions;
+use crate::level::Level;
+use crate::spawner::{DifficultyCurve, RunTimeline};
+use crate::upgrades::UpgradePool;
+
+const SPRITESHEET_PATH: &str = "spritesheet.png";
+const ANIMATION_LIBRARY_PATH: &str = "animations.anim.ron";
+const DIFFICULTY_CURVE_PATH: &str = "difficulty.curve.ron";
+const UPGRADE_POOL_PATH: &str = "upgrades.upgrade.ron";
+const TIMELINE_PATH: &str = "run.timeline.ron";
+const LEVEL_PATH: &str = "arena.level.ron";
+const BALANCE_PATH: &str = "game.balance.ron";
+const PREFABS_FOLDER: &str = "prefabs";
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, load_game_assets);
+        app.add_systems(OnEnter(GameState::Loading), spawn_loading_screen);
+        app.add_systems(Update, track_loading.run_if(in_state(GameState::Loading)));
+    }
+}
+
+/// Strong handles to every file the game needs, so they stay loaded and
+/// gameplay never starts before they are
+#[derive(Resource)]
+pub struct GameAssets {
+    pub spritesheet: Handle<Image>,
+    pub animations: Handle<AnimationLibrary>,
+    pub difficulty_curve: Handle<DifficultyCurve>,
+    pub upgrades: Handle<UpgradePool>,
+    pub timeline: Handle<RunTimeline>,
+    pub level: Handle<Level>,
+    pub balance: Handle<BalanceConfig>,
+    /// Every `*.prefab.ron`, see `PrefabLibrary`
+    pub prefabs: Handle<LoadedFolder>,
+}
+
+impl GameAssets {
+    fn ids(&self) -> [UntypedAssetId; 8] {
+        [
+            self.spritesheet.id().untyped(),
+            self.animations.id().untyped(),
+            self.difficulty_curve.id().untyped(),
+            self.upgrades.id().untyped(),
+            self.timeline.id().untyped(),
+            self.level.id().untyped(),
+            self.balance.id().untyped(),
+            self.prefabs.id().untyped(),
+        ]
+    }
+}
+
+#[derive(Component)]
+struct LoadingBarFill;
+
+fn load_game_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    launch: Res<LaunchOptions>,
+) {
+    let level = launch.level.clone().unwrap_or(LEVEL_PATH.to_string());
+
+    commands.insert_resource(GameAssets {
+        spritesheet: asset_server.load(SPRITESHEET_PATH),
+        animations: asset_server.load(ANIMATION_LIBRARY_PATH),
+        difficulty_curve: asset_server.load(DIFFICULTY_CURVE_PATH),
+        upgrades: asset_server.load(UPGRADE_POOL_PATH),
+        timeline: asset_server.load(TIMELINE_PATH),
+        level: asset_server.load(level),
+        balance: asset_server.load(BALANCE_PATH),
+        prefabs: asset_server.load_folder(PREFABS_FOLDER),
+    });
+}
+
+/// Whether the file at `path` is there to load, for assets the game can do
+/// without, which would otherwise log an error each when they're missing
+pub fn optional_asset_exists(path: &str) -> bool {
+    return FileAssetReader::get_base_path()
+        .join("assets")
+        .join(path)
+        .exists();
+}
+
+/// The level brings the game camera, so the loading screen has its own
+fn spawn_loading_screen(mut commands: Commands) {
+    let camera = commands
+        .spawn((
+            Camera2d,
+            Camera {
+                order: 1,
+                ..default()
+            },
+            Name::new("Loading Camera"),
+            StateScoped(GameState::Loading),
+        ))
+        .id();
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(16.0),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+        Name::new("Loading Screen"),
+        UiTargetCamera(camera),
+        StateScoped(GameState::Loading),
+        children![
+            (Text::new("Loading"), TextFont::from_font_size(40.0)),
+            (
+                Node {
+                    width: Val::Px(400.0),
+                    height: Val::Px(16.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                children![(
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(1.0, 0.85, 0.1)),
+                    LoadingBarFill,
+                )],
+            ),
+        ],
+    ));
+}
+
+/// Fill the bar as assets finish, and move on to the menu once they all have,
+/// or straight into a run when launched with `--skip-menu`
+///
+/// Assets that failed count as finished so a broken file shows up as an
+/// error instead of a hang
+fn track_loading(
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    mut fill: Query<&mut Node, With<LoadingBarFill>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    launch: Res<LaunchOptions>,
+) {
+    let ids = assets.ids();
+    let done = ids
+        .iter()
+        .filter(|id| {
+            asset_server.is_loaded_with_dependencies(**id)
+                || asset_server.load_state(**id).is_failed()
+        })
+        .count();
+
+    for mut node in fill.iter_mut() {
+        node.width = Val::Percent(done as f32 / ids.len() as f32 * 100.0);
+    }
+
+    if done == ids.len() {
+        next_state.set(if launch.skip_menu {
+            GameState::InGame
+        } else {
+            GameState::MainMenu
+        });
+    }
+}
@@ -1,7 +1,20 @@
-use bunvivor::App;
+use bunvivor::cli::{CliError, USAGE};
+use bunvivor::{App, AppConfig};
 
 fn main() {
-    let mut app = App::new();
+    let config = match AppConfig::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(CliError::Help) => {
+            print!("{USAGE}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let mut app = App::with_config(config);
     app.run();
 }
 
@@ -0,0 +1,55 @@
+use bevy::math::StableInterpolate;
+use bevy::prelude::*;
+
+/// Helpers for the vector maths that movement, the camera and AI keep
+/// needing, mostly working on the ground (XZ) plane
+pub trait VecTools {
+    /// Flattened onto the ground, keeping only the X and Z parts
+    fn horizontal(self) -> Self;
+
+    /// With the part along `normal` taken out, so it lies in the plane
+    /// `normal` faces out of. `normal` doesn't need to be normalized
+    fn project_onto_plane(self, normal: Self) -> Self;
+
+    /// Moved towards `target` by at most `max_delta`, stopping on it rather
+    /// than overshooting
+    fn move_toward(self, target: Self, max_delta: f32) -> Self;
+
+    /// Signed angle in radians to turn from this to `other` over the ground,
+    /// positive going from X towards Z
+    fn angle_to_xz(self, other: Self) -> f32;
+}
+
+impl VecTools for Vec3 {
+    fn horizontal(self) -> Self {
+        return self.with_y(0.0);
+    }
+
+    fn project_onto_plane(self, normal: Self) -> Self {
+        let Some(normal) = normal.try_normalize() else {
+            return self;
+        };
+        return self - normal * self.dot(normal);
+    }
+
+    fn move_toward(self, target: Self, max_delta: f32) -> Self {
+        let offset = target - self;
+        let distance = offset.length();
+        if distance <= max_delta || distance == 0.0 {
+            return target;
+        }
+        return self + offset / distance * max_delta;
+    }
+
+    fn angle_to_xz(self, other: Self) -> f32 {
+        return self.xz().angle_to(other.xz());
+    }
+}
+
+/// Eases `from` towards `to` by the same amount each second whatever the
+/// framerate, the higher `rate` the faster it gets there
+///
+/// Unlike lerping by `rate * delta`, this never overshoots on long frames
+pub fn damped_lerp<T: StableInterpolate>(from: T, to: T, rate: f32, delta: f32) -> T {
+    return from.interpolate_stable(&to, 1.0 - (-rate * delta).exp());
+}
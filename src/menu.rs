@@ -0,0 +1,550 @@
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::accessibility::spawn_accessibility_options;
+use crate::graphics::GraphicsSettings;
+use crate::rng::NextRunSeed;
+use crate::save::SaveData;
+use crate::shop::SHOP_ITEMS;
+use crate::spawner::DifficultyCurve;
+use crate::transition::TransitionTo;
+use crate::{GameState, LocalPlayers, MAX_LOCAL_PLAYERS};
+
+pub const BUTTON_COLOR: Color = Color::srgba(0.1, 0.1, 0.15, 0.9);
+const BUTTON_HOVER_COLOR: Color = Color::srgba(0.2, 0.2, 0.3, 0.9);
+/// Width the high scores take up on the right of the main menu
+const LEADERBOARD_SPACE: f32 = 452.0;
+/// As many digits as a `u64` can have
+const MAX_SEED_DIGITS: usize = 20;
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SeedEntry>();
+        app.add_systems(OnEnter(GameState::MainMenu), spawn_main_menu);
+        app.add_systems(
+            Update,
+            (
+                main_menu_buttons,
+                type_seed,
+                highlight_buttons,
+                update_shop_labels,
+                update_players_label,
+                update_quality_label,
+                update_window_labels,
+                update_frame_labels,
+                update_rumble_label,
+                update_input_device_label,
+                update_difficulty_label,
+                update_seed_label,
+            )
+                .run_if(in_state(GameState::MainMenu)),
+        );
+    }
+}
+
+/// The seed typed into the main menu, for replaying a run someone shared.
+/// Kept between visits to the menu, so every run plays it until it's cleared
+#[derive(Resource, Debug)]
+struct SeedEntry {
+    digits: String,
+    /// Whether typing goes into it
+    editing: bool,
+}
+
+/// Starts out with the seed picked at launch, if there was one
+impl FromWorld for SeedEntry {
+    fn from_world(world: &mut World) -> Self {
+        let picked = world.get_resource::<NextRunSeed>().and_then(|next| **next);
+        return Self {
+            digits: picked.map(|seed| seed.to_string()).unwrap_or_default(),
+            editing: false,
+        };
+    }
+}
+
+impl SeedEntry {
+    fn seed(&self) -> Option<u64> {
+        return self.digits.parse().ok();
+    }
+}
+
+#[derive(Component)]
+enum MainMenuButton {
+    StartRun,
+    /// Cycle how many local players the run starts with
+    Players,
+    /// Cycle the graphics quality preset
+    Quality,
+    /// Cycle between windowed and full screen
+    DisplayMode,
+    /// Cycle the windowed size
+    Resolution,
+    /// Toggle vsync
+    VSync,
+    /// Cycle the frame rate cap
+    FrameCap,
+    /// Cycle how hard controllers rumble
+    Rumble,
+    /// Cycle which device prompts are shown for
+    InputDevice,
+    /// Cycle the difficulty preset
+    Difficulty,
+    /// Start or stop typing in a seed
+    Seed,
+    /// Index into `SHOP_ITEMS`
+    Buy(usize),
+}
+
+#[derive(Component)]
+struct GoldLabel;
+
+#[derive(Component)]
+struct PlayersLabel;
+
+#[derive(Component)]
+struct QualityLabel;
+
+#[derive(Component)]
+struct DisplayModeLabel;
+
+#[derive(Component)]
+struct ResolutionLabel;
+
+#[derive(Component)]
+struct VSyncLabel;
+
+#[derive(Component)]
+struct FrameCapLabel;
+
+#[derive(Component)]
+struct RumbleLabel;
+
+#[derive(Component)]
+struct InputDeviceLabel;
+
+#[derive(Component)]
+struct DifficultyLabel;
+
+#[derive(Component)]
+struct SeedLabel;
+
+#[derive(Component)]
+struct ShopItemLabel(usize);
+
+fn spawn_main_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                // centred in the space left of the high scores
+                padding: UiRect::right(Val::Px(LEADERBOARD_SPACE)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Name::new("Main Menu"),
+            StateScoped(GameState::MainMenu),
+        ))
+        .with_children(|menu| {
+            menu.spawn((Text::new("Bunvivor"), TextFont::from_font_size(64.0)));
+            menu.spawn(Node {
+                column_gap: Val::Px(24.0),
+                ..default()
+            })
+            .with_children(|columns| {
+                columns.spawn(column()).with_children(spawn_run_options);
+                columns.spawn(column()).with_children(spawn_settings);
+            });
+        });
+}
+
+fn column() -> Node {
+    return Node {
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(8.0),
+        ..default()
+    };
+}
+
+/// How the next run is set up, and the shop to spend gold in before it
+fn spawn_run_options(column: &mut ChildSpawnerCommands) {
+    column.spawn((
+        button(),
+        MainMenuButton::StartRun,
+        children![(Text::new("Start Run"), TextFont::from_font_size(28.0))],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::Players,
+        children![(Text::new(""), TextFont::from_font_size(20.0), PlayersLabel)],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::Difficulty,
+        children![(
+            Text::new(""),
+            TextFont::from_font_size(20.0),
+            DifficultyLabel
+        )],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::Seed,
+        children![(Text::new(""), TextFont::from_font_size(20.0), SeedLabel)],
+    ));
+
+    column.spawn((
+        Text::new(""),
+        TextFont::from_font_size(24.0),
+        TextColor(Color::srgb(1.0, 0.85, 0.1)),
+        GoldLabel,
+    ));
+    for i in 0..SHOP_ITEMS.len() {
+        column.spawn((
+            button(),
+            MainMenuButton::Buy(i),
+            children![(
+                Text::new(""),
+                TextFont::from_font_size(18.0),
+                ShopItemLabel(i)
+            )],
+        ));
+    }
+}
+
+fn spawn_settings(column: &mut ChildSpawnerCommands) {
+    column.spawn((Text::new("Settings"), TextFont::from_font_size(28.0)));
+    column.spawn((
+        button(),
+        MainMenuButton::Quality,
+        children![(Text::new(""), TextFont::from_font_size(20.0), QualityLabel)],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::DisplayMode,
+        children![(
+            Text::new(""),
+            TextFont::from_font_size(20.0),
+            DisplayModeLabel
+        )],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::Resolution,
+        children![(
+            Text::new(""),
+            TextFont::from_font_size(20.0),
+            ResolutionLabel
+        )],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::VSync,
+        children![(Text::new(""), TextFont::from_font_size(20.0), VSyncLabel)],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::FrameCap,
+        children![(Text::new(""), TextFont::from_font_size(20.0), FrameCapLabel)],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::Rumble,
+        children![(Text::new(""), TextFont::from_font_size(20.0), RumbleLabel)],
+    ));
+    column.spawn((
+        button(),
+        MainMenuButton::InputDevice,
+        children![(
+            Text::new(""),
+            TextFont::from_font_size(20.0),
+            InputDeviceLabel
+        )],
+    ));
+    spawn_accessibility_options(column);
+}
+
+pub fn button() -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(360.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+    )
+}
+
+fn main_menu_buttons(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Query<(&Interaction, &MainMenuButton), Changed<Interaction>>,
+    mut save: ResMut<SaveData>,
+    mut local_players: ResMut<LocalPlayers>,
+    (mut graphics, mut curve): (ResMut<GraphicsSettings>, ResMut<DifficultyCurve>),
+    (mut entry, mut next_seed): (ResMut<SeedEntry>, ResMut<NextRunSeed>),
+    mut transition: EventWriter<TransitionTo>,
+) {
+    if keys.just_pressed(KeyCode::Enter) {
+        **next_seed = entry.seed();
+        transition.write(TransitionTo(GameState::CharacterSelect));
+    }
+
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            MainMenuButton::StartRun => {
+                **next_seed = entry.seed();
+                transition.write(TransitionTo(GameState::CharacterSelect));
+            }
+            MainMenuButton::Players => {
+                **local_players = **local_players % MAX_LOCAL_PLAYERS + 1;
+            }
+            MainMenuButton::Quality => {
+                graphics.quality = graphics.quality.next();
+            }
+            MainMenuButton::DisplayMode => {
+                graphics.display_mode = graphics.display_mode.next();
+            }
+            MainMenuButton::Resolution => {
+                graphics.resolution = Some(graphics.next_resolution());
+            }
+            MainMenuButton::VSync => {
+                graphics.vsync = !graphics.vsync;
+            }
+            MainMenuButton::FrameCap => {
+                graphics.frame_cap = graphics.frame_cap.next();
+            }
+            MainMenuButton::Rumble => {
+                save.settings.rumble = save.settings.rumble.next();
+            }
+            MainMenuButton::InputDevice => {
+                save.settings.input_device = save.settings.input_device.next();
+            }
+            MainMenuButton::Difficulty => {
+                curve.preset = curve.preset.next();
+            }
+            MainMenuButton::Seed => {
+                entry.editing = !entry.editing;
+            }
+            MainMenuButton::Buy(i) => {
+                SHOP_ITEMS[*i].purchase(&mut save);
+            }
+        }
+    }
+}
+
+/// Digits only, anything else typed is ignored
+fn type_seed(mut entry: ResMut<SeedEntry>, mut keyboard: EventReader<KeyboardInput>) {
+    if !entry.editing {
+        keyboard.clear();
+        return;
+    }
+
+    for event in keyboard.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Backspace => {
+                entry.digits.pop();
+            }
+            Key::Escape => entry.editing = false,
+            Key::Character(text) => {
+                let digits = text.chars().filter(char::is_ascii_digit);
+                entry.digits.extend(digits);
+                entry.digits.truncate(MAX_SEED_DIGITS);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn highlight_buttons(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        color.0 = match interaction {
+            Interaction::None => BUTTON_COLOR,
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR,
+        };
+    }
+}
+
+fn update_shop_labels(
+    save: Res<SaveData>,
+    mut gold: Query<&mut Text, (With<GoldLabel>, Without<ShopItemLabel>)>,
+    mut items: Query<(&mut Text, &ShopItemLabel)>,
+    added: Query<(), Added<ShopItemLabel>>,
+) {
+    if !save.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in gold.iter_mut() {
+        text.0 = format!("Gold: {}", save.gold);
+    }
+
+    for (mut text, label) in items.iter_mut() {
+        let item = &SHOP_ITEMS[label.0];
+        let cost = match item.cost(&save) {
+            Some(cost) => format!("{cost}g"),
+            None => "max".to_string(),
+        };
+        text.0 = format!(
+            "{} {}/{} - {} ({cost})",
+            item.name,
+            item.level(&save),
+            item.max_level,
+            item.description
+        );
+    }
+}
+
+fn update_players_label(
+    local_players: Res<LocalPlayers>,
+    mut labels: Query<&mut Text, With<PlayersLabel>>,
+    added: Query<(), Added<PlayersLabel>>,
+) {
+    if !local_players.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in labels.iter_mut() {
+        text.0 = match **local_players {
+            1 => "1 Player".to_string(),
+            players => format!("{players} Players (Arrows / WASD)"),
+        };
+    }
+}
+
+fn update_quality_label(
+    graphics: Res<GraphicsSettings>,
+    mut labels: Query<&mut Text, With<QualityLabel>>,
+    added: Query<(), Added<QualityLabel>>,
+) {
+    if !graphics.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in labels.iter_mut() {
+        text.0 = format!("Graphics: {}", graphics.quality.name());
+    }
+}
+
+fn update_window_labels(
+    graphics: Res<GraphicsSettings>,
+    mut mode_labels: Query<&mut Text, (With<DisplayModeLabel>, Without<ResolutionLabel>)>,
+    mut resolution_labels: Query<&mut Text, With<ResolutionLabel>>,
+    added: Query<(), Or<(Added<DisplayModeLabel>, Added<ResolutionLabel>)>>,
+) {
+    if !graphics.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in mode_labels.iter_mut() {
+        text.0 = format!("Window: {}", graphics.display_mode.name());
+    }
+    let resolution = match graphics.resolution {
+        Some((width, height)) => format!("{width}x{height}"),
+        None => "Default".to_string(),
+    };
+    for mut text in resolution_labels.iter_mut() {
+        text.0 = format!("Resolution: {resolution}");
+    }
+}
+
+fn update_frame_labels(
+    graphics: Res<GraphicsSettings>,
+    mut vsync_labels: Query<&mut Text, (With<VSyncLabel>, Without<FrameCapLabel>)>,
+    mut cap_labels: Query<&mut Text, With<FrameCapLabel>>,
+    added: Query<(), Or<(Added<VSyncLabel>, Added<FrameCapLabel>)>>,
+) {
+    if !graphics.is_changed() && added.is_empty() {
+        return;
+    }
+
+    let vsync = if graphics.vsync { "On" } else { "Off" };
+    for mut text in vsync_labels.iter_mut() {
+        text.0 = format!("VSync: {vsync}");
+    }
+    for mut text in cap_labels.iter_mut() {
+        text.0 = format!("FPS Cap: {}", graphics.frame_cap.name());
+    }
+}
+
+fn update_rumble_label(
+    save: Res<SaveData>,
+    mut labels: Query<&mut Text, With<RumbleLabel>>,
+    added: Query<(), Added<RumbleLabel>>,
+) {
+    if !save.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in labels.iter_mut() {
+        text.0 = format!("Rumble: {}", save.settings.rumble.name());
+    }
+}
+
+fn update_input_device_label(
+    save: Res<SaveData>,
+    mut labels: Query<&mut Text, With<InputDeviceLabel>>,
+    added: Query<(), Added<InputDeviceLabel>>,
+) {
+    if !save.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in labels.iter_mut() {
+        text.0 = format!("Prompts: {}", save.settings.input_device.name());
+    }
+}
+
+fn update_difficulty_label(
+    curve: Res<DifficultyCurve>,
+    mut labels: Query<&mut Text, With<DifficultyLabel>>,
+    added: Query<(), Added<DifficultyLabel>>,
+) {
+    if !curve.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in labels.iter_mut() {
+        text.0 = format!("Difficulty: {}", curve.preset.name());
+    }
+}
+
+fn update_seed_label(
+    entry: Res<SeedEntry>,
+    mut labels: Query<&mut Text, With<SeedLabel>>,
+    added: Query<(), Added<SeedLabel>>,
+) {
+    if !entry.is_changed() && added.is_empty() {
+        return;
+    }
+
+    let cursor = if entry.editing { "_" } else { "" };
+    let seed = match (entry.digits.is_empty(), entry.seed()) {
+        (true, _) if !entry.editing => "Random".to_string(),
+        (false, None) => format!("{}{cursor} (too big, random)", entry.digits),
+        _ => format!("{}{cursor}", entry.digits),
+    };
+    for mut text in labels.iter_mut() {
+        text.0 = format!("Seed: {seed}");
+    }
+}
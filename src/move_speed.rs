@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+
+use crate::ai::{chase_player, keep_distance};
+use crate::controls::control_player;
+use crate::status::Slowed;
+use crate::{BaseMoveSpeed, GameplaySet, MoveSpeed};
+
+/// Source of the modifier `Slowed` stacks are turned into
+const SLOWED_SOURCE: &str = "Slowed";
+
+pub struct MoveSpeedPlugin;
+
+impl Plugin for MoveSpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<MoveSpeedModifiers>();
+        app.add_systems(
+            Update,
+            (sync_slows, apply_move_speed_modifiers)
+                .chain()
+                .before(control_player)
+                .before(chase_player)
+                .before(keep_distance)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Reflect, Debug, Clone)]
+pub struct MoveSpeedModifier {
+    /// What's changing the speed, e.g. a status effect or affix, so it can
+    /// be replaced or removed again
+    pub source: Cow<'static, str>,
+    pub multiplier: f32,
+    /// Runs out when this finishes, or lasts until removed if `None`
+    pub duration: Option<Timer>,
+}
+
+/// Everything currently speeding the entity up or slowing it down, multiplied
+/// together onto its `BaseMoveSpeed` to get its `MoveSpeed` every frame
+#[derive(Component, Reflect, Default, Debug, Clone)]
+#[reflect(Component)]
+pub struct MoveSpeedModifiers(Vec<MoveSpeedModifier>);
+
+impl MoveSpeedModifiers {
+    /// Add a modifier, replacing any other from the same `source`
+    pub fn insert(
+        &mut self,
+        source: impl Into<Cow<'static, str>>,
+        multiplier: f32,
+        seconds: Option<f32>,
+    ) {
+        let source = source.into();
+        self.remove(&source);
+        self.0.push(MoveSpeedModifier {
+            source,
+            multiplier,
+            duration: seconds.map(|seconds| Timer::from_seconds(seconds, TimerMode::Once)),
+        });
+    }
+
+    pub fn remove(&mut self, source: &str) {
+        self.0.retain(|modifier| modifier.source != source);
+    }
+
+    pub fn multiplier(&self) -> f32 {
+        return self.0.iter().map(|modifier| modifier.multiplier).product();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MoveSpeedModifier> {
+        return self.0.iter();
+    }
+}
+
+/// `Slowed` keeps its own stacks and timer, this keeps a modifier matching it
+fn sync_slows(
+    mut slowed: Query<(&Slowed, &mut MoveSpeedModifiers), Changed<Slowed>>,
+    mut removed: RemovedComponents<Slowed>,
+    mut modifiers: Query<&mut MoveSpeedModifiers, Without<Slowed>>,
+) {
+    for (slowed, mut modifiers) in slowed.iter_mut() {
+        let current = modifiers
+            .iter()
+            .find(|modifier| modifier.source == SLOWED_SOURCE)
+            .map(|modifier| modifier.multiplier);
+        if current != Some(slowed.multiplier()) {
+            modifiers.insert(SLOWED_SOURCE, slowed.multiplier(), None);
+        }
+    }
+
+    for entity in removed.read() {
+        if let Ok(mut modifiers) = modifiers.get_mut(entity) {
+            modifiers.remove(SLOWED_SOURCE);
+        }
+    }
+}
+
+pub fn apply_move_speed_modifiers(
+    time: Res<Time>,
+    mut query: Query<(&BaseMoveSpeed, &mut MoveSpeedModifiers, &mut MoveSpeed)>,
+) {
+    for (base, mut modifiers, mut move_speed) in query.iter_mut() {
+        let mut expired = false;
+        for duration in modifiers.0.iter_mut().filter_map(|m| m.duration.as_mut()) {
+            expired |= duration.tick(time.delta()).finished();
+        }
+        if expired {
+            modifiers.0.retain(|modifier| {
+                !modifier
+                    .duration
+                    .as_ref()
+                    .is_some_and(|duration| duration.finished())
+            });
+        }
+
+        move_speed.set_if_neq(MoveSpeed(**base * modifiers.multiplier()));
+    }
+}
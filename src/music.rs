@@ -0,0 +1,175 @@
+use bevy::audio::{GlobalVolume, Volume};
+use bevy::prelude::*;
+
+use crate::boss::Boss;
+use crate::loading::optional_asset_exists;
+use crate::spatial::{SpatialGrid, SpatialSet};
+use crate::{Enemy, GameState, GameplaySet, Player};
+
+/// How far from a player enemies count towards the music's intensity
+const NEARBY_RADIUS: f32 = 20.0;
+/// Nearby enemies it takes to bring each layer in fully
+const COMBAT_ENEMIES: f32 = 15.0;
+const FRENZY_ENEMIES: f32 = 60.0;
+/// Volume change per second as the layers crossfade
+const FADE_SPEED: f32 = 0.5;
+const MUSIC_VOLUME: f32 = 0.6;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Startup,
+            load_music.run_if(resource_exists::<Assets<AudioSource>>),
+        );
+        // headless runs load music all the same but have nothing to play it
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            start_music.run_if(resource_exists::<MusicAssets>.and(resource_exists::<GlobalVolume>)),
+        );
+        app.add_systems(
+            Update,
+            (music_intensity.after(SpatialSet), fade_stems)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Layers of the soundtrack, all playing in time and faded in over each
+/// other as the fight heats up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stem {
+    /// Always playing
+    Calm,
+    Combat,
+    /// Big hordes and bosses
+    Frenzy,
+}
+
+impl Stem {
+    const ALL: [Stem; 3] = [Stem::Calm, Stem::Combat, Stem::Frenzy];
+
+    fn path(&self) -> &'static str {
+        match self {
+            Stem::Calm => "music/calm.ogg",
+            Stem::Combat => "music/combat.ogg",
+            Stem::Frenzy => "music/frenzy.ogg",
+        }
+    }
+
+    /// How loud this layer should be at `intensity`, from 0 to 1
+    fn volume(&self, intensity: f32) -> f32 {
+        match self {
+            Stem::Calm => 1.0,
+            Stem::Combat => (intensity * 2.0).clamp(0.0, 1.0),
+            Stem::Frenzy => (intensity * 2.0 - 1.0).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Like sounds, music is left out of loading and a missing stem is never
+/// loaded and just stays quiet
+#[derive(Resource)]
+pub struct MusicAssets(Vec<(Stem, Handle<AudioSource>)>);
+
+impl MusicAssets {
+    pub fn handles(&self) -> impl Iterator<Item = &Handle<AudioSource>> {
+        return self.0.iter().map(|(_, handle)| handle);
+    }
+}
+
+/// How heated the fight is, from 0 with nobody around to 1 in a frenzy
+#[derive(Resource, Default, Debug)]
+struct MusicIntensity(f32);
+
+#[derive(Component)]
+struct StemPlayer {
+    stem: Stem,
+    volume: f32,
+}
+
+fn load_music(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let stems = Stem::ALL
+        .into_iter()
+        .filter(|stem| optional_asset_exists(stem.path()))
+        .map(|stem| (stem, asset_server.load(stem.path())))
+        .collect();
+    commands.insert_resource(MusicAssets(stems));
+    commands.init_resource::<MusicIntensity>();
+}
+
+/// Every stem starts together so they stay in time, the quiet ones muted.
+/// One that failed to load is left out rather than waited on forever
+fn start_music(mut commands: Commands, music: Res<MusicAssets>, asset_server: Res<AssetServer>) {
+    for (stem, handle) in music.0.iter() {
+        if asset_server.load_state(handle).is_failed() {
+            continue;
+        }
+        let volume = stem.volume(0.0);
+        commands.spawn((
+            AudioPlayer(handle.clone()),
+            PlaybackSettings::LOOP.with_volume(Volume::Linear(volume * MUSIC_VOLUME)),
+            StemPlayer {
+                stem: *stem,
+                volume,
+            },
+            Name::new(format!("Music {stem:?}")),
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+/// The most crowded player decides, and a boss always brings in everything
+fn music_intensity(
+    intensity: Option<ResMut<MusicIntensity>>,
+    players: Query<&Transform, With<Player>>,
+    enemies: Res<SpatialGrid<Enemy>>,
+    bosses: Query<(), With<Boss>>,
+) {
+    let Some(mut intensity) = intensity else {
+        return;
+    };
+
+    let nearby = players
+        .iter()
+        .map(|player| {
+            enemies
+                .within_radius(player.translation.xz(), NEARBY_RADIUS)
+                .count()
+        })
+        .max()
+        .unwrap_or(0) as f32;
+    let target = if bosses.is_empty() {
+        // halfway brings combat in fully, the rest of the way frenzy
+        if nearby <= COMBAT_ENEMIES {
+            nearby / COMBAT_ENEMIES * 0.5
+        } else {
+            0.5 + (nearby - COMBAT_ENEMIES) / (FRENZY_ENEMIES - COMBAT_ENEMIES) * 0.5
+        }
+    } else {
+        1.0
+    };
+    intensity.0 = target.clamp(0.0, 1.0);
+}
+
+fn fade_stems(
+    time: Res<Time>,
+    intensity: Option<Res<MusicIntensity>>,
+    mut stems: Query<(&mut StemPlayer, &mut AudioSink)>,
+) {
+    let Some(intensity) = intensity else {
+        return;
+    };
+
+    let step = FADE_SPEED * time.delta_secs();
+    for (mut player, mut sink) in stems.iter_mut() {
+        let target = player.stem.volume(intensity.0);
+        if player.volume == target {
+            continue;
+        }
+        player.volume += (target - player.volume).clamp(-step, step);
+        sink.set_volume(Volume::Linear(player.volume * MUSIC_VOLUME));
+    }
+}
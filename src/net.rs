@@ -0,0 +1,561 @@
+//! Online co-op, one host and one player joining over UDP
+//!
+//! The host runs the whole game as usual, with the second player driven by
+//! input sent from the other end. Everything that decides what happens in a
+//! run (`AuthoritySet`) only runs on the host, which sends back where players
+//! and enemies are and what got hurt every frame. The joining end only has to
+//! show that, so it starts from the host's seed to build the same terrain and
+//! keeps no state of its own that could drift
+//!
+//! Messages are RON, sent as is. Snapshots go out every frame so a lost one
+//! is simply replaced by the next, and anything the host stops mentioning is
+//! taken to be gone
+
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::RigidBody;
+use leafwing_input_manager::prelude::{ActionState, InputMap};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::animation::AnimationLibrary;
+use crate::boss::{self, Boss};
+use crate::combat::{DamageEvent, DamageType, GodMode};
+use crate::controls::Action;
+use crate::enemies::EnemyAssets;
+use crate::pool::{Pool, ReleaseCommandsExt};
+use crate::prefab_library::{PrefabLibrary, PrefabName, spawn_prefab};
+use crate::rng::{GameRng, GameSeed, NextRunSeed};
+use crate::transition::{ScreenFade, TransitionTo};
+use crate::weapons::Weapons;
+use crate::{
+    AuthoritySet, Enemy, GameState, GameplaySet, LocalPlayers, Player, PlayerSlot, player_input_map,
+};
+
+pub const DEFAULT_PORT: u16 = 7777;
+/// Slot the joining player takes on the host
+const REMOTE_SLOT: usize = 1;
+/// Keeps snapshots well under the largest datagram
+const ENEMIES_PER_MESSAGE: usize = 128;
+const MAX_MESSAGE_BYTES: usize = 65_507;
+/// How often the joining end asks to be let in until it is
+const HELLO_SECONDS: f32 = 0.5;
+/// Enemies the host hasn't mentioned for this long have died
+const STALE_SECONDS: f32 = 1.0;
+
+/// Which end of an online game this is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetRole {
+    Host { port: u16 },
+    Join { address: SocketAddr },
+}
+
+pub struct NetPlugin {
+    pub role: NetRole,
+}
+
+/// The port a host is listening on, which is only known once it's bound when
+/// hosting on port 0
+#[derive(Resource, Deref, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostPort(pub u16);
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        let socket = match open_socket(self.role) {
+            Ok(socket) => socket,
+            Err(e) => {
+                error!("{e}");
+                return;
+            }
+        };
+
+        // everyone online plays together on the host
+        app.insert_resource(LocalPlayers(2));
+        match self.role {
+            NetRole::Host { port } => {
+                let port = socket.local_addr().map_or(port, |address| address.port());
+                info!("hosting on port {port}");
+                app.insert_resource(HostPort(port));
+                app.insert_resource(NetHost {
+                    socket,
+                    client: None,
+                    next_id: 0,
+                });
+                app.add_systems(OnEnter(GameState::InGame), welcome_client);
+                app.add_systems(OnEnter(GameState::GameOver), announce_game_over);
+                app.add_systems(
+                    Update,
+                    (
+                        host_receive.before(GameplaySet),
+                        assign_net_ids.in_set(GameplaySet),
+                    ),
+                );
+                app.add_systems(PostUpdate, send_snapshot.in_set(GameplaySet));
+            }
+            NetRole::Join { address } => {
+                info!("joining {address}");
+                app.insert_resource(NetClient {
+                    socket,
+                    hello: Timer::from_seconds(HELLO_SECONDS, TimerMode::Repeating),
+                    entities: HashMap::new(),
+                });
+                app.configure_sets(Update, AuthoritySet.run_if(not(is_client)));
+                app.add_systems(
+                    Update,
+                    (
+                        say_hello.run_if(not(in_state(GameState::InGame))),
+                        client_receive,
+                        (take_over_players, send_input, despawn_stale).in_set(GameplaySet),
+                    )
+                        .chain(),
+                );
+            }
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum NetError {
+    #[error("Could not open network socket: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not serialize network message: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+fn open_socket(role: NetRole) -> Result<UdpSocket, NetError> {
+    let socket = match role {
+        NetRole::Host { port } => UdpSocket::bind(("0.0.0.0", port))?,
+        NetRole::Join { address } => {
+            let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+            socket.connect(address)?;
+            socket
+        }
+    };
+    socket.set_nonblocking(true)?;
+    return Ok(socket);
+}
+
+/// Identifies an enemy on both ends
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetId(pub u32);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+enum ClientMessage {
+    Hello,
+    Input {
+        pressed: Vec<Action>,
+        movement: Vec2,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+enum ServerMessage {
+    /// A run started that the client should join
+    Welcome {
+        seed: u64,
+    },
+    /// Where each player is by slot, `None` once they've died
+    Players(Vec<Option<Vec3>>),
+    /// Some of the enemies, there may be several of these per frame
+    Enemies(Vec<ReplicatedEnemy>),
+    Damage(Vec<ReplicatedDamage>),
+    GameOver,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ReplicatedEnemy {
+    id: NetId,
+    /// Which prefab it's spawned from, `None` for bosses
+    prefab: Option<String>,
+    position: Vec3,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+enum NetTarget {
+    Player(usize),
+    Enemy(NetId),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ReplicatedDamage {
+    target: NetTarget,
+    amount: f32,
+    crit: bool,
+    kind: DamageType,
+}
+
+#[derive(Resource)]
+struct NetHost {
+    socket: UdpSocket,
+    /// Whoever said hello last
+    client: Option<SocketAddr>,
+    next_id: u32,
+}
+
+impl NetHost {
+    fn send(&self, message: &ServerMessage) {
+        let Some(client) = self.client else {
+            return;
+        };
+        if let Err(e) = send_with(message, |bytes| self.socket.send_to(bytes, client)) {
+            warn!("{e}");
+        }
+    }
+}
+
+#[derive(Resource)]
+struct NetClient {
+    socket: UdpSocket,
+    hello: Timer,
+    entities: HashMap<NetId, Entity>,
+}
+
+impl NetClient {
+    fn send(&self, message: &ClientMessage) {
+        if let Err(e) = send_with(message, |bytes| self.socket.send(bytes)) {
+            warn!("{e}");
+        }
+    }
+}
+
+/// Not being able to send is only worth a warning, the next frame tries again
+fn send_with(
+    message: &impl Serialize,
+    send: impl Fn(&[u8]) -> std::io::Result<usize>,
+) -> Result<(), NetError> {
+    let text = ron::to_string(message)?;
+    match send(text.as_bytes()) {
+        Ok(_) => return Ok(()),
+        Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+        Err(e) => return Err(e.into()),
+    }
+}
+
+/// Every message waiting on the socket, skipping any that don't parse
+fn receive<T: for<'de> Deserialize<'de>>(socket: &UdpSocket) -> Vec<(T, SocketAddr)> {
+    let mut messages = Vec::new();
+    let mut buffer = vec![0; MAX_MESSAGE_BYTES];
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok((len, from)) => {
+                let parsed = std::str::from_utf8(&buffer[..len])
+                    .ok()
+                    .and_then(|text| ron::from_str(text).ok());
+                match parsed {
+                    Some(message) => messages.push((message, from)),
+                    None => warn!("ignoring malformed message from {from}"),
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            // a client going away shows up as a failed receive on some
+            // platforms, nothing to do but wait for them to come back
+            Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+            Err(e) => {
+                warn!("{e}");
+                break;
+            }
+        }
+    }
+    return messages;
+}
+
+fn is_client(client: Option<Res<NetClient>>) -> bool {
+    return client.is_some();
+}
+
+fn host_receive(
+    mut host: ResMut<NetHost>,
+    state: Res<State<GameState>>,
+    seed: Res<GameSeed>,
+    mut players: Query<(&PlayerSlot, &mut ActionState<Action>), With<Player>>,
+) {
+    for (message, from) in receive::<ClientMessage>(&host.socket) {
+        match message {
+            ClientMessage::Hello => {
+                if host.client != Some(from) {
+                    info!("{from} joined");
+                    host.client = Some(from);
+                }
+                if *state.get() == GameState::InGame {
+                    host.send(&ServerMessage::Welcome { seed: **seed });
+                }
+            }
+            ClientMessage::Input { pressed, movement } => {
+                if host.client != Some(from) {
+                    continue;
+                }
+                let remote = players.iter_mut().find(|(slot, _)| ***slot == REMOTE_SLOT);
+                if let Some((_, mut action_state)) = remote {
+                    for action in Action::ALL.iter() {
+                        if pressed.contains(action) {
+                            action_state.press(action);
+                        } else {
+                            action_state.release(action);
+                        }
+                    }
+                    action_state.set_axis_pair(&Action::Move, movement);
+                }
+            }
+        }
+    }
+}
+
+fn welcome_client(host: Res<NetHost>, seed: Res<GameSeed>) {
+    host.send(&ServerMessage::Welcome { seed: **seed });
+}
+
+fn announce_game_over(host: Res<NetHost>) {
+    host.send(&ServerMessage::GameOver);
+}
+
+fn assign_net_ids(
+    mut commands: Commands,
+    mut host: ResMut<NetHost>,
+    enemies: Query<Entity, (With<Enemy>, Without<NetId>)>,
+) {
+    for enemy in enemies.iter() {
+        commands.entity(enemy).insert(NetId(host.next_id));
+        host.next_id = host.next_id.wrapping_add(1);
+    }
+}
+
+fn send_snapshot(
+    host: Res<NetHost>,
+    mut damage: EventReader<DamageEvent>,
+    players: Query<(&PlayerSlot, &Transform), With<Player>>,
+    enemies: Query<(&NetId, &Transform, Option<&PrefabName>, Has<Boss>), With<Enemy>>,
+) {
+    if host.client.is_none() {
+        damage.clear();
+        return;
+    }
+
+    let mut positions = vec![None; 2];
+    for (slot, transform) in players.iter() {
+        if let Some(position) = positions.get_mut(**slot) {
+            *position = Some(transform.translation);
+        }
+    }
+    host.send(&ServerMessage::Players(positions));
+
+    let replicated: Vec<ReplicatedEnemy> = enemies
+        .iter()
+        .map(|(id, transform, name, is_boss)| ReplicatedEnemy {
+            id: *id,
+            prefab: if is_boss {
+                None
+            } else {
+                name.map(|name| name.0.clone())
+            },
+            position: transform.translation,
+        })
+        .collect();
+    for chunk in replicated.chunks(ENEMIES_PER_MESSAGE) {
+        host.send(&ServerMessage::Enemies(chunk.to_vec()));
+    }
+
+    let damage: Vec<ReplicatedDamage> = damage
+        .read()
+        .filter(|event| event.amount > 0.0)
+        .filter_map(|event| {
+            let target = match (players.get(event.target), enemies.get(event.target)) {
+                (Ok((slot, _)), _) => NetTarget::Player(**slot),
+                (_, Ok((id, ..))) => NetTarget::Enemy(*id),
+                _ => return None,
+            };
+            return Some(ReplicatedDamage {
+                target,
+                amount: event.amount,
+                crit: event.crit,
+                kind: event.kind,
+            });
+        })
+        .collect();
+    if !damage.is_empty() {
+        host.send(&ServerMessage::Damage(damage));
+    }
+}
+
+fn say_hello(time: Res<Time<Real>>, mut client: ResMut<NetClient>) {
+    if client.hello.tick(time.delta()).just_finished() {
+        client.send(&ClientMessage::Hello);
+    }
+}
+
+/// Where a replicated enemy was last heard of
+#[derive(Component)]
+struct LastSeen(f32);
+
+fn client_receive(
+    mut commands: Commands,
+    (time, mut client): (Res<Time<Real>>, ResMut<NetClient>),
+    (state, fade, mut transition): (
+        Res<State<GameState>>,
+        Res<ScreenFade>,
+        EventWriter<TransitionTo>,
+    ),
+    (mut next_seed, mut seed, mut rng): (ResMut<NextRunSeed>, ResMut<GameSeed>, ResMut<GameRng>),
+    (assets, library, animations, mut pool): (
+        Res<EnemyAssets>,
+        Res<PrefabLibrary>,
+        Res<Assets<AnimationLibrary>>,
+        ResMut<Pool>,
+    ),
+    mut damage: EventWriter<DamageEvent>,
+    mut bodies: Query<(
+        Entity,
+        &mut Transform,
+        Option<&PlayerSlot>,
+        Option<&mut LastSeen>,
+    )>,
+) {
+    let now = time.elapsed_secs();
+    for (message, _) in receive::<ServerMessage>(&client.socket) {
+        match message {
+            ServerMessage::Welcome { seed: new_seed } => {
+                // loading has to finish first, the host keeps welcoming
+                // until then, and through the fade into the run
+                if !fade.is_fading()
+                    && matches!(
+                        state.get(),
+                        GameState::MainMenu | GameState::CharacterSelect | GameState::GameOver
+                    )
+                {
+                    info!("joining run with seed {new_seed}");
+                    **next_seed = Some(new_seed);
+                    *seed = GameSeed(new_seed);
+                    *rng = GameRng::from_seed(new_seed);
+                    transition.write(TransitionTo(GameState::InGame));
+                }
+            }
+            ServerMessage::Players(positions) => {
+                for (entity, mut transform, slot, _) in bodies.iter_mut() {
+                    let Some(slot) = slot else {
+                        continue;
+                    };
+                    match positions.get(**slot) {
+                        Some(Some(position)) => transform.translation = *position,
+                        Some(None) => commands.entity(entity).try_despawn(),
+                        None => {}
+                    }
+                }
+            }
+            ServerMessage::Enemies(enemies) => {
+                if *state.get() != GameState::InGame {
+                    continue;
+                }
+                for enemy in enemies {
+                    if let Some(entity) = client.entities.get(&enemy.id).copied() {
+                        if let Ok((_, mut transform, _, Some(mut seen))) = bodies.get_mut(entity) {
+                            transform.translation = enemy.position;
+                            seen.0 = now;
+                        }
+                        continue;
+                    }
+
+                    let entity = match &enemy.prefab {
+                        Some(prefab) => spawn_prefab(
+                            &mut commands,
+                            &mut pool,
+                            (&library, &animations),
+                            prefab,
+                            enemy.position,
+                            1.0,
+                            &mut **rng,
+                        ),
+                        None => Some(commands.spawn(boss::boss(&assets, 1, enemy.position)).id()),
+                    };
+                    let Some(entity) = entity else {
+                        warn_once!("The host has enemies this client has no prefab for");
+                        continue;
+                    };
+                    commands.entity(entity).insert((
+                        enemy.id,
+                        LastSeen(now),
+                        RigidBody::KinematicPositionBased,
+                        GodMode,
+                    ));
+                    client.entities.insert(enemy.id, entity);
+                }
+            }
+            ServerMessage::Damage(events) => {
+                for event in events {
+                    let target = match event.target {
+                        NetTarget::Enemy(id) => client.entities.get(&id).copied(),
+                        NetTarget::Player(slot) => bodies
+                            .iter()
+                            .find(|(_, _, player_slot, _)| {
+                                player_slot.is_some_and(|player_slot| **player_slot == slot)
+                            })
+                            .map(|(entity, ..)| entity),
+                    };
+                    if let Some(target) = target {
+                        damage.write(DamageEvent {
+                            target,
+                            amount: event.amount,
+                            source: None,
+                            crit: event.crit,
+                            weapon: None,
+                            kind: event.kind,
+                        });
+                    }
+                }
+            }
+            ServerMessage::GameOver => {
+                transition.write(TransitionTo(GameState::GameOver));
+            }
+        }
+    }
+}
+
+/// Players only move where the host says they are, and the local one is
+/// steered with the arrow keys, whichever slot it has
+fn take_over_players(
+    mut commands: Commands,
+    players: Query<(Entity, &PlayerSlot), Added<PlayerSlot>>,
+) {
+    for (entity, slot) in players.iter() {
+        let input_map = if **slot == REMOTE_SLOT {
+            player_input_map(0)
+        } else {
+            InputMap::default()
+        };
+        commands
+            .entity(entity)
+            .insert((input_map, RigidBody::KinematicPositionBased, GodMode))
+            .remove::<Weapons>();
+    }
+}
+
+fn send_input(client: Res<NetClient>, players: Query<(&PlayerSlot, &ActionState<Action>)>) {
+    let Some((_, action_state)) = players.iter().find(|(slot, _)| ***slot == REMOTE_SLOT) else {
+        return;
+    };
+    client.send(&ClientMessage::Input {
+        pressed: action_state.get_pressed(),
+        movement: action_state.axis_pair(&Action::Move),
+    });
+}
+
+fn despawn_stale(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut client: ResMut<NetClient>,
+    enemies: Query<&LastSeen>,
+) {
+    let now = time.elapsed_secs();
+    client.entities.retain(|_, entity| {
+        let seen = enemies
+            .get(*entity)
+            .map_or(f32::NEG_INFINITY, |seen| seen.0);
+        if now - seen < STALE_SECONDS {
+            return true;
+        }
+        commands.entity(*entity).release();
+        return false;
+    });
+}
@@ -0,0 +1,106 @@
+//! Effects a hit triggers once its damage has been settled, granted by the
+//! stat sheet so passives can add them without any weapon knowing
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use rand::Rng;
+
+use crate::GameplaySet;
+use crate::combat::{DamageEvent, Health, apply_damage, attacker};
+use crate::platforms::Launched;
+use crate::projectile::Projectile;
+use crate::rng::GameRng;
+use crate::stats::{Stat, Stats};
+use crate::status::{ApplyStatus, StatusEffect};
+
+/// Stacks of burn a lucky hit puts on
+const ON_HIT_BURN_STACKS: u32 = 1;
+/// Sideways speed knocked back enemies are sent away at
+const KNOCKBACK_SPEED: f32 = 15.0;
+/// Just enough lift to get them off the ground, so they slide
+const KNOCKBACK_LIFT: f32 = 4.0;
+
+pub struct OnHitPlugin;
+
+impl Plugin for OnHitPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<OnHitEffects>();
+        app.add_systems(
+            Update,
+            apply_on_hit_effects.after(apply_damage).in_set(GameplaySet),
+        );
+    }
+}
+
+/// What this entity's hits do on top of their damage, derived from its
+/// `Stats` by `apply_stats`
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct OnHitEffects {
+    /// Fraction of damage dealt healed back
+    pub lifesteal: f32,
+    /// Chance, from 0 to 1, to set the target burning
+    pub burn_chance: f32,
+    /// Chance, from 0 to 1, to knock the target away
+    pub knockback_chance: f32,
+}
+
+impl OnHitEffects {
+    pub fn from_stats(stats: &Stats) -> Self {
+        Self {
+            lifesteal: stats.get(Stat::Lifesteal),
+            burn_chance: stats.get(Stat::BurnChance),
+            knockback_chance: stats.get(Stat::KnockbackChance),
+        }
+    }
+}
+
+fn apply_on_hit_effects(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut damage: EventReader<DamageEvent>,
+    projectiles: Query<&Projectile>,
+    mut attackers: Query<(&OnHitEffects, &Transform, &mut Health)>,
+    targets: Query<&Transform>,
+    mut status: EventWriter<ApplyStatus>,
+) {
+    for event in damage.read() {
+        // ignored hits, and burn ticks, which have no source, trigger nothing
+        if event.amount <= 0.0 {
+            continue;
+        }
+        let Some(attacker) = attacker(event, &projectiles) else {
+            continue;
+        };
+        let Ok((effects, transform, mut health)) = attackers.get_mut(attacker) else {
+            continue;
+        };
+
+        if effects.lifesteal > 0.0 && !health.is_dead() {
+            health.current = (health.current + event.amount * effects.lifesteal).min(health.max);
+        }
+
+        if rng.random_bool(effects.burn_chance.clamp(0.0, 1.0) as f64) {
+            status.write(ApplyStatus {
+                target: event.target,
+                effect: StatusEffect::Burn,
+                stacks: ON_HIT_BURN_STACKS,
+            });
+        }
+
+        if rng.random_bool(effects.knockback_chance.clamp(0.0, 1.0) as f64) {
+            let Ok(target) = targets.get(event.target) else {
+                continue;
+            };
+            let away = (target.translation - transform.translation)
+                .with_y(0.0)
+                .normalize_or(Vec3::X);
+            let knockback = away * KNOCKBACK_SPEED + Vec3::Y * KNOCKBACK_LIFT;
+            commands
+                .entity(event.target)
+                .entry::<Velocity>()
+                .and_modify(move |mut velocity| velocity.linvel = knockback);
+            commands.entity(event.target).insert(Launched::new());
+        }
+    }
+}
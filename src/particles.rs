@@ -0,0 +1,306 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::budget::{BudgetCategory, BudgetPriority, Budgeted, EntityBudget};
+use crate::combat::{DamageEvent, DeathEvent, apply_damage};
+use crate::controls::Dash;
+use crate::experience::LevelUp;
+use crate::{GameState, GameplaySet, Player};
+
+/// Seconds between puffs of a dash trail
+const DASH_TRAIL_INTERVAL: f32 = 0.02;
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EmitParticles>();
+        app.add_systems(PreStartup, setup_particle_assets);
+        app.add_systems(
+            Update,
+            (
+                (
+                    emit_hit_sparks.after(apply_damage),
+                    emit_death_poofs,
+                    emit_dash_trails,
+                    emit_level_up_bursts,
+                ),
+                spawn_particles,
+                update_particles,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleEffect {
+    /// A few quick sparks where something was hit
+    HitSpark,
+    /// A puff of smoke where an enemy died
+    DeathPoof,
+    /// Left behind a dashing player
+    DashTrail,
+    /// A ring of light bursting out of a player levelling up
+    LevelUpBurst,
+    /// A ring of dust kicked up by a ground slam
+    Shockwave,
+}
+
+/// How an effect's particles are thrown out and how long they last
+struct EffectSpec {
+    count: usize,
+    /// Starting speed, picked at random in this range
+    speed: (f32, f32),
+    /// How far up from flat particles can be thrown, 0 keeps them level
+    rise: f32,
+    lifetime: f32,
+    size: f32,
+    gravity: f32,
+}
+
+impl ParticleEffect {
+    fn spec(&self) -> EffectSpec {
+        match self {
+            ParticleEffect::HitSpark => EffectSpec {
+                count: 5,
+                speed: (6.0, 12.0),
+                rise: 1.0,
+                lifetime: 0.2,
+                size: 0.12,
+                gravity: 20.0,
+            },
+            ParticleEffect::DeathPoof => EffectSpec {
+                count: 10,
+                speed: (1.5, 3.5),
+                rise: 0.5,
+                lifetime: 0.5,
+                size: 0.4,
+                gravity: -1.0,
+            },
+            ParticleEffect::DashTrail => EffectSpec {
+                count: 1,
+                speed: (0.0, 0.5),
+                rise: 0.2,
+                lifetime: 0.3,
+                size: 0.3,
+                gravity: 0.0,
+            },
+            ParticleEffect::LevelUpBurst => EffectSpec {
+                count: 24,
+                speed: (8.0, 10.0),
+                rise: 0.0,
+                lifetime: 0.6,
+                size: 0.25,
+                gravity: -4.0,
+            },
+            ParticleEffect::Shockwave => EffectSpec {
+                count: 32,
+                speed: (12.0, 14.0),
+                rise: 0.1,
+                lifetime: 0.4,
+                size: 0.4,
+                gravity: 0.0,
+            },
+        }
+    }
+}
+
+/// Ask for a burst of `effect` at `position`
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EmitParticles {
+    pub effect: ParticleEffect,
+    pub position: Vec3,
+}
+
+/// A purely visual speck, moved by hand instead of by physics and shrunk
+/// away over its lifetime
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Particle {
+    velocity: Vec3,
+    gravity: f32,
+    size: f32,
+    lifetime: Timer,
+}
+
+#[derive(Resource)]
+struct ParticleAssets {
+    mesh: Handle<Mesh>,
+    spark_material: Handle<StandardMaterial>,
+    poof_material: Handle<StandardMaterial>,
+    trail_material: Handle<StandardMaterial>,
+    level_up_material: Handle<StandardMaterial>,
+    shockwave_material: Handle<StandardMaterial>,
+}
+
+impl ParticleAssets {
+    fn material(&self, effect: ParticleEffect) -> Handle<StandardMaterial> {
+        match effect {
+            ParticleEffect::HitSpark => self.spark_material.clone(),
+            ParticleEffect::DeathPoof => self.poof_material.clone(),
+            ParticleEffect::DashTrail => self.trail_material.clone(),
+            ParticleEffect::LevelUpBurst => self.level_up_material.clone(),
+            ParticleEffect::Shockwave => self.shockwave_material.clone(),
+        }
+    }
+}
+
+fn setup_particle_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut glowing = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color,
+            emissive: color.to_linear() * 4.0,
+            unlit: true,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        })
+    };
+
+    commands.insert_resource(ParticleAssets {
+        mesh: meshes.add(Sphere::new(0.5).mesh().ico(1).unwrap()),
+        spark_material: glowing(Color::srgb(1.0, 0.9, 0.5)),
+        poof_material: glowing(Color::srgba(0.8, 0.8, 0.85, 0.6)),
+        trail_material: glowing(Color::srgba(0.6, 0.8, 1.0, 0.5)),
+        level_up_material: glowing(Color::srgb(1.0, 0.8, 0.2)),
+        shockwave_material: glowing(Color::srgba(0.8, 0.65, 0.45, 0.7)),
+    });
+}
+
+fn emit_hit_sparks(
+    mut damage: EventReader<DamageEvent>,
+    targets: Query<&GlobalTransform>,
+    mut emit: EventWriter<EmitParticles>,
+) {
+    for event in damage.read() {
+        if event.amount <= 0.0 {
+            continue;
+        }
+        let Ok(target) = targets.get(event.target) else {
+            continue;
+        };
+        emit.write(EmitParticles {
+            effect: ParticleEffect::HitSpark,
+            position: target.translation(),
+        });
+    }
+}
+
+/// Players dying end the run, so only everything else poofs
+fn emit_death_poofs(
+    mut deaths: EventReader<DeathEvent>,
+    dying: Query<&GlobalTransform, Without<Player>>,
+    mut emit: EventWriter<EmitParticles>,
+) {
+    for death in deaths.read() {
+        let Ok(transform) = dying.get(death.entity) else {
+            continue;
+        };
+        emit.write(EmitParticles {
+            effect: ParticleEffect::DeathPoof,
+            position: transform.translation(),
+        });
+    }
+}
+
+fn emit_dash_trails(
+    time: Res<Time>,
+    mut since_puff: Local<f32>,
+    players: Query<(&Transform, &Dash), With<Player>>,
+    mut emit: EventWriter<EmitParticles>,
+) {
+    *since_puff += time.delta_secs();
+    if *since_puff < DASH_TRAIL_INTERVAL {
+        return;
+    }
+    *since_puff = 0.0;
+
+    for (transform, dash) in players.iter() {
+        if dash.is_dashing() {
+            emit.write(EmitParticles {
+                effect: ParticleEffect::DashTrail,
+                position: transform.translation,
+            });
+        }
+    }
+}
+
+fn emit_level_up_bursts(
+    mut level_ups: EventReader<LevelUp>,
+    players: Query<&Transform, With<Player>>,
+    mut emit: EventWriter<EmitParticles>,
+) {
+    for level_up in level_ups.read() {
+        let Ok(transform) = players.get(level_up.entity) else {
+            continue;
+        };
+        emit.write(EmitParticles {
+            effect: ParticleEffect::LevelUpBurst,
+            position: transform.translation,
+        });
+    }
+}
+
+/// Particles are cosmetic, so they roll on their own rng and leave the
+/// seeded one to gameplay, keeping replays and net games in step
+///
+/// Past the particle budget the oldest are reclaimed for the new ones, but
+/// no more than a whole budget's worth are spawned in a single frame
+fn spawn_particles(
+    mut commands: Commands,
+    mut events: EventReader<EmitParticles>,
+    assets: Res<ParticleAssets>,
+    budget: Res<EntityBudget>,
+) {
+    let mut rng = rand::rng();
+    let mut room = budget.particles;
+
+    for event in events.read() {
+        let spec = event.effect.spec();
+        for _ in 0..spec.count.min(room) {
+            let angle = rng.random_range(0.0..TAU);
+            let rise = rng.random_range(0.0..=spec.rise);
+            let direction = Vec3::new(angle.cos(), rise, angle.sin()).normalize();
+            let speed = rng.random_range(spec.speed.0..=spec.speed.1);
+
+            commands.spawn((
+                Mesh3d(assets.mesh.clone()),
+                MeshMaterial3d(assets.material(event.effect)),
+                Transform::from_translation(event.position).with_scale(Vec3::splat(spec.size)),
+                Particle {
+                    velocity: direction * speed,
+                    gravity: spec.gravity,
+                    size: spec.size,
+                    lifetime: Timer::from_seconds(spec.lifetime, TimerMode::Once),
+                },
+                Budgeted::new(BudgetCategory::Particle, BudgetPriority::Low),
+                StateScoped(GameState::InGame),
+            ));
+        }
+        room = room.saturating_sub(spec.count);
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform)>,
+) {
+    for (entity, mut particle, mut transform) in particles.iter_mut() {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        particle.velocity.y -= particle.gravity * time.delta_secs();
+        transform.translation += particle.velocity * time.delta_secs();
+        transform.scale = Vec3::splat(particle.size * particle.lifetime.fraction_remaining());
+    }
+}
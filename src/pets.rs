@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::combat::Faction;
+use crate::interpolation::InterpolatedTranslation;
+use crate::math::VecTools;
+use crate::physics::layers;
+use crate::pool::{Pool, PoolKey};
+use crate::projectile::{Projectile, ProjectileAssets, projectile_bundle};
+use crate::spatial::{SpatialGrid, SpatialSet};
+use crate::stats::Stats;
+use crate::toon::ToonMaterial;
+use crate::weapons::{WeaponKind, Weapons, fire_weapons};
+use crate::{
+    BaseMoveSpeed, Enemy, GameState, GameplaySet, MoveSpeed, MoveVector, MovementProfile, Player,
+};
+
+/// Pets stop following this close to their owner
+const FOLLOW_DISTANCE: f32 = 3.0;
+/// Pets further than this from their owner are brought straight back
+const LEASH_DISTANCE: f32 = 40.0;
+const PET_SPEED: f32 = 18.0;
+const PET_MOVEMENT: MovementProfile = MovementProfile {
+    acceleration: 400.0,
+    deceleration: 400.0,
+    turn_rate: 10.0,
+};
+/// How far a pet looks for enemies to attack
+const ATTACK_RANGE: f32 = 15.0;
+const BOLT_SPEED: f32 = 25.0;
+const BOLT_LIFETIME: f32 = 1.0;
+const PET_RADIUS: f32 = 0.4;
+
+pub struct PetsPlugin;
+
+impl Plugin for PetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_pet_assets);
+        app.add_systems(
+            Update,
+            (
+                sync_pets,
+                follow_owners,
+                pet_attacks.after(fire_weapons).after(SpatialSet),
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// A companion following `owner` around and attacking enemies near it
+///
+/// Comes with the pet weapon, attacking whenever the weapon comes off
+/// cooldown, so it's granted and levelled up like any other weapon
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Pet {
+    pub owner: Entity,
+}
+
+#[derive(Resource)]
+struct PetAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<ToonMaterial>,
+}
+
+fn setup_pet_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+) {
+    commands.insert_resource(PetAssets {
+        mesh: meshes.add(Sphere::new(PET_RADIUS)),
+        material: materials.add(ToonMaterial::from(Color::srgb(0.95, 0.9, 0.8))),
+    });
+}
+
+fn pet(assets: &PetAssets, owner: Entity, position: Vec3) -> impl Bundle {
+    (
+        Mesh3d(assets.mesh.clone()),
+        MeshMaterial3d(assets.material.clone()),
+        Transform::from_translation(position),
+        Name::new("Pet"),
+        Pet { owner },
+        (
+            MoveVector::default(),
+            BaseMoveSpeed(PET_SPEED),
+            PET_MOVEMENT,
+        ),
+        (
+            RigidBody::Dynamic,
+            Velocity::default(),
+            ExternalForce::default(),
+            ReadMassProperties::default(),
+            Collider::ball(PET_RADIUS),
+            layers::pet(),
+            LockedAxes::ROTATION_LOCKED,
+        ),
+        InterpolatedTranslation::default(),
+        StateScoped(GameState::InGame),
+    )
+}
+
+/// A pet for every player carrying the pet weapon, and none for anyone else
+fn sync_pets(
+    mut commands: Commands,
+    assets: Res<PetAssets>,
+    players: Query<(Entity, &Transform, &Weapons), With<Player>>,
+    pets: Query<(Entity, &Pet)>,
+) {
+    for (entity, pet) in pets.iter() {
+        let wanted = players
+            .get(pet.owner)
+            .is_ok_and(|(_, _, weapons)| weapons.get(WeaponKind::Pet).is_some());
+        if !wanted {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for (player, transform, weapons) in players.iter() {
+        if weapons.get(WeaponKind::Pet).is_none() || pets.iter().any(|(_, pet)| pet.owner == player)
+        {
+            continue;
+        }
+        let position = transform.translation + Vec3::X * FOLLOW_DISTANCE;
+        commands.spawn(pet(&assets, player, position));
+    }
+}
+
+/// Head for the owner until close enough, jumping back to them if left far
+/// behind
+fn follow_owners(
+    owners: Query<&Transform, With<Player>>,
+    mut pets: Query<(&Pet, &mut Transform, &MoveSpeed, &mut MoveVector), Without<Player>>,
+) {
+    for (pet, mut transform, move_speed, mut move_vec) in pets.iter_mut() {
+        let Ok(owner) = owners.get(pet.owner) else {
+            continue;
+        };
+
+        let to_owner = (owner.translation - transform.translation).horizontal();
+        let distance = to_owner.length();
+        if distance > LEASH_DISTANCE {
+            transform.translation = owner.translation + Vec3::X * FOLLOW_DISTANCE;
+            **move_vec = Vec3::ZERO;
+        } else if distance > FOLLOW_DISTANCE {
+            **move_vec = to_owner / distance * **move_speed;
+        } else {
+            **move_vec = Vec3::ZERO;
+        }
+    }
+}
+
+/// Fire at the nearest enemy in range each time the owner's pet weapon
+/// comes off cooldown
+fn pet_attacks(
+    mut commands: Commands,
+    (assets, mut pool): (Res<ProjectileAssets>, ResMut<Pool>),
+    pets: Query<(&Pet, &Transform)>,
+    owners: Query<(&Stats, &Weapons)>,
+    enemies: Res<SpatialGrid<Enemy>>,
+) {
+    for (pet, transform) in pets.iter() {
+        let Ok((stats, weapons)) = owners.get(pet.owner) else {
+            continue;
+        };
+        let Some(weapon) = weapons.get(WeaponKind::Pet).filter(|w| w.just_fired()) else {
+            continue;
+        };
+
+        let pos = transform.translation.xz();
+        let Some((_, enemy)) = enemies.nearest(pos, ATTACK_RANGE) else {
+            continue;
+        };
+        let dir = (enemy - pos).normalize_or(Vec2::X);
+        let dir = Vec3::new(dir.x, 0.0, dir.y);
+        pool.spawn(
+            &mut commands,
+            PoolKey::Projectile,
+            projectile_bundle(
+                assets.mesh.clone(),
+                assets.player_material.clone(),
+                transform.translation + dir * PET_RADIUS,
+                dir * BOLT_SPEED,
+                Projectile {
+                    damage: weapon.damage(stats),
+                    hits: Faction::Enemy,
+                    lifetime: Timer::from_seconds(BOLT_LIFETIME, TimerMode::Once),
+                    // crits with its owner's stats
+                    owner: Some(pet.owner),
+                    weapon: Some(WeaponKind::Pet),
+                    kind: WeaponKind::Pet.damage_type(),
+                },
+            ),
+        );
+    }
+}
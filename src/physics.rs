@@ -0,0 +1,61 @@
+/// Which colliders can touch which
+///
+/// Every collider is given one of these at spawn, anything left on the
+/// default groups collides with everything. Rapier only runs the narrow
+/// phase for pairs whose groups match both ways, so pairs nobody cares
+/// about (enemies crowding each other, projectiles crossing) cost nothing
+pub mod layers {
+    use bevy_rapier3d::prelude::{CollisionGroups, Group};
+
+    pub const PLAYER: Group = Group::GROUP_1;
+    pub const ENEMY: Group = Group::GROUP_2;
+    pub const PROJECTILE: Group = Group::GROUP_3;
+    pub const PICKUP: Group = Group::GROUP_4;
+    /// The floor, walls, ramps and props, anything fixed in the world
+    pub const GROUND: Group = Group::GROUP_5;
+    pub const HAZARD: Group = Group::GROUP_6;
+    /// Damaging fields around players, from aura weapons
+    pub const AURA: Group = Group::GROUP_7;
+    pub const PET: Group = Group::GROUP_8;
+
+    /// Players bump into each other, but walk through pickups, which are
+    /// collected by distance instead
+    pub fn player() -> CollisionGroups {
+        return CollisionGroups::new(PLAYER, PLAYER | ENEMY | PROJECTILE | GROUND | HAZARD);
+    }
+
+    /// Enemies don't touch each other, the separation steering in `ai`
+    /// keeps them apart
+    pub fn enemy() -> CollisionGroups {
+        return CollisionGroups::new(ENEMY, PLAYER | PROJECTILE | GROUND | HAZARD | AURA);
+    }
+
+    /// Projectiles pass through each other and over hazards
+    pub fn projectile() -> CollisionGroups {
+        return CollisionGroups::new(PROJECTILE, PLAYER | ENEMY | GROUND);
+    }
+
+    /// Pickups are collected through their spatial grid and have no collider
+    /// today, this is for any that need one to only ever meet players
+    pub fn pickup() -> CollisionGroups {
+        return CollisionGroups::new(PICKUP, PLAYER);
+    }
+
+    pub fn ground() -> CollisionGroups {
+        return CollisionGroups::new(GROUND, PLAYER | ENEMY | PROJECTILE | PET);
+    }
+
+    pub fn hazard() -> CollisionGroups {
+        return CollisionGroups::new(HAZARD, PLAYER | ENEMY);
+    }
+
+    /// Pets only stand on the ground, walking through everyone else
+    pub fn pet() -> CollisionGroups {
+        return CollisionGroups::new(PET, GROUND);
+    }
+
+    /// Auras only ever affect enemies
+    pub fn aura() -> CollisionGroups {
+        return CollisionGroups::new(AURA, ENEMY);
+    }
+}
@@ -0,0 +1,404 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::combat::{DeathEvent, Health, apply_damage};
+use crate::interaction::{Interactable, Interacted};
+use crate::rng::GameRng;
+use crate::run_config::RunConfig;
+use crate::spatial::{SpatialGrid, SpatialGridPlugin, SpatialSet};
+use crate::stats::{ModifierOp, Stat, StatModifier, Stats, TimedModifiers};
+use crate::{GameplaySet, Player};
+
+/// How close the player has to get to collect a pickup, unless they have a `PickupRadius`
+const PICKUP_RADIUS: f32 = 2.5;
+/// How long vacuumed experience takes to reach its collector
+const VACUUM_SECONDS: f32 = 2.0;
+const PICKUP_CELL_SIZE: f32 = 4.0;
+/// How long the armor from an armor pickup lasts
+const ARMOR_PICKUP_SECONDS: f32 = 10.0;
+/// Source of the modifier armor pickups grant
+const ARMOR_PICKUP_SOURCE: &str = "Armor Pickup";
+
+pub struct PickupsPlugin;
+
+impl Plugin for PickupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Pickup>();
+        app.register_type::<LootTable>();
+        app.add_event::<PickupCollected>();
+        app.add_plugins(SpatialGridPlugin::<Pickup>::new(PICKUP_CELL_SIZE));
+        app.add_systems(PreStartup, setup_pickup_assets);
+        app.add_systems(
+            Update,
+            (
+                make_pickups_interactable,
+                collect_pickups.after(SpatialSet),
+                collect_interacted_pickups,
+                start_vacuum.after(collect_pickups),
+                pull_vacuumed.after(start_vacuum),
+                apply_heal_pickups.after(collect_pickups),
+                apply_armor_pickups.after(collect_pickups),
+                // before the corpse is stripped of its loot table
+                drop_loot.after(apply_damage),
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Reflect, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    Experience(u32),
+    Heal(f32),
+    /// Grants this much armor for a while
+    Armor(f32),
+    Gold(u32),
+    /// Opens into this many upgrades
+    Chest(u32),
+    /// Pulls in all the experience on the map
+    Vacuum,
+}
+
+impl PickupKind {
+    /// Picked up by interacting with it rather than by walking over it, with
+    /// this prompt
+    pub fn interact_prompt(&self) -> Option<&'static str> {
+        match self {
+            PickupKind::Chest(_) => return Some("Open chest"),
+            _ => return None,
+        }
+    }
+}
+
+#[derive(Reflect, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rarity {
+    Common,
+    Rare,
+}
+
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub rarity: Rarity,
+}
+
+/// Pickups dropped on death, each rolled on its own
+///
+/// What an enemy drops on death, each one rolled on its own
+#[derive(Component, Reflect, Deref, Debug, Clone, Default)]
+#[reflect(Component)]
+pub struct LootTable(pub Vec<LootDrop>);
+
+#[derive(Reflect, Deserialize, Debug, Clone, Copy)]
+pub struct LootDrop {
+    pub kind: PickupKind,
+    pub rarity: Rarity,
+    /// From 0 to 1
+    pub chance: f64,
+}
+
+/// Experience on its way to `collector` from a vacuum, collected once it
+/// arrives rather than by walking over it
+#[derive(Component, Debug, Clone)]
+struct Vacuumed {
+    collector: Entity,
+    start: Vec3,
+    timer: Timer,
+}
+
+/// How close a collector has to get to collect a pickup
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct PickupRadius(pub f32);
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PickupCollected {
+    pub collector: Entity,
+    pub pickup: Pickup,
+}
+
+#[derive(Resource)]
+pub struct PickupAssets {
+    pub gem_mesh: Handle<Mesh>,
+    pub heal_mesh: Handle<Mesh>,
+    pub armor_mesh: Handle<Mesh>,
+    pub gold_mesh: Handle<Mesh>,
+    pub chest_mesh: Handle<Mesh>,
+    pub vacuum_mesh: Handle<Mesh>,
+    pub common_material: Handle<StandardMaterial>,
+    pub rare_material: Handle<StandardMaterial>,
+    pub heal_material: Handle<StandardMaterial>,
+    pub armor_material: Handle<StandardMaterial>,
+    pub gold_material: Handle<StandardMaterial>,
+    pub chest_material: Handle<StandardMaterial>,
+    pub vacuum_material: Handle<StandardMaterial>,
+}
+
+fn setup_pickup_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PickupAssets {
+        gem_mesh: meshes.add(Tetrahedron::default()),
+        heal_mesh: meshes.add(Sphere::new(0.4)),
+        armor_mesh: meshes.add(Cuboid::new(0.6, 0.6, 0.2)),
+        gold_mesh: meshes.add(Cylinder::new(0.4, 0.1)),
+        chest_mesh: meshes.add(Cuboid::new(1.2, 0.8, 0.8)),
+        vacuum_mesh: meshes.add(Torus::new(0.25, 0.5)),
+        common_material: materials.add(Color::srgb(0.2, 0.6, 1.0)),
+        rare_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.8, 0.2),
+            emissive: LinearRgba::rgb(2.0, 1.5, 0.3),
+            ..default()
+        }),
+        heal_material: materials.add(Color::srgb(1.0, 0.3, 0.4)),
+        armor_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.6, 0.65, 0.7),
+            metallic: 1.0,
+            perceptual_roughness: 0.4,
+            ..default()
+        }),
+        gold_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.85, 0.1),
+            metallic: 1.0,
+            perceptual_roughness: 0.3,
+            ..default()
+        }),
+        chest_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.55, 0.3, 0.1),
+            emissive: LinearRgba::rgb(0.6, 0.4, 0.05),
+            ..default()
+        }),
+        vacuum_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.3, 1.0, 0.8),
+            emissive: LinearRgba::rgb(0.5, 2.0, 1.5),
+            ..default()
+        }),
+    });
+}
+
+pub fn pickup_bundle(assets: &PickupAssets, pickup: Pickup, position: Vec3) -> impl Bundle {
+    let mesh = match pickup.kind {
+        PickupKind::Experience(_) => assets.gem_mesh.clone(),
+        PickupKind::Heal(_) => assets.heal_mesh.clone(),
+        PickupKind::Armor(_) => assets.armor_mesh.clone(),
+        PickupKind::Gold(_) => assets.gold_mesh.clone(),
+        PickupKind::Chest(_) => assets.chest_mesh.clone(),
+        PickupKind::Vacuum => assets.vacuum_mesh.clone(),
+    };
+    let material = match (pickup.kind, pickup.rarity) {
+        (PickupKind::Chest(_), _) => assets.chest_material.clone(),
+        (PickupKind::Vacuum, _) => assets.vacuum_material.clone(),
+        (_, Rarity::Rare) => assets.rare_material.clone(),
+        (PickupKind::Heal(_), _) => assets.heal_material.clone(),
+        (PickupKind::Armor(_), _) => assets.armor_material.clone(),
+        (PickupKind::Experience(_), _) => assets.common_material.clone(),
+        (PickupKind::Gold(_), _) => assets.gold_material.clone(),
+    };
+    let scale = match (pickup.kind, pickup.rarity) {
+        (PickupKind::Chest(_) | PickupKind::Vacuum, _) | (_, Rarity::Common) => 1.0,
+        (_, Rarity::Rare) => 2.0,
+    };
+
+    (
+        Mesh3d(mesh),
+        MeshMaterial3d(material),
+        Transform::from_translation(position).with_scale(Vec3::splat(scale)),
+        Name::new("Pickup"),
+        pickup,
+    )
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    players: Query<(Entity, &Transform, Option<&PickupRadius>), With<Player>>,
+    grid: Res<SpatialGrid<Pickup>>,
+    pickups: Query<&Pickup, Without<Vacuumed>>,
+    config: Res<RunConfig>,
+    mut collected: EventWriter<PickupCollected>,
+) {
+    // a pickup both players reach goes to whoever is checked first
+    let mut taken = Vec::new();
+    for (collector, transform, radius) in players.iter() {
+        let radius = radius.map_or(PICKUP_RADIUS, |radius| **radius);
+        for (entity, _) in grid.within_radius(transform.translation.xz(), radius) {
+            let Ok(pickup) = pickups.get(entity) else {
+                continue;
+            };
+            if taken.contains(&entity) || pickup.kind.interact_prompt().is_some() {
+                continue;
+            }
+
+            taken.push(entity);
+            // run modifiers pay out on everything collected
+            collected.write(PickupCollected {
+                collector,
+                pickup: Pickup {
+                    kind: config.reward(pickup.kind),
+                    ..*pickup
+                },
+            });
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// However a pickup was spawned, the ones that wait to be interacted with
+/// get their prompt
+fn make_pickups_interactable(
+    mut commands: Commands,
+    pickups: Query<(Entity, &Pickup), Added<Pickup>>,
+) {
+    for (entity, pickup) in pickups.iter() {
+        if let Some(prompt) = pickup.kind.interact_prompt() {
+            commands.entity(entity).insert(Interactable::new(prompt));
+        }
+    }
+}
+
+fn collect_interacted_pickups(
+    mut commands: Commands,
+    mut interacted: EventReader<Interacted>,
+    pickups: Query<&Pickup>,
+    config: Res<RunConfig>,
+    mut collected: EventWriter<PickupCollected>,
+) {
+    for event in interacted.read() {
+        let Ok(pickup) = pickups.get(event.target) else {
+            continue;
+        };
+
+        collected.write(PickupCollected {
+            collector: event.interactor,
+            pickup: Pickup {
+                kind: config.reward(pickup.kind),
+                ..*pickup
+            },
+        });
+        commands.entity(event.target).despawn();
+    }
+}
+
+fn drop_loot(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut deaths: EventReader<DeathEvent>,
+    enemies: Query<(&Transform, &LootTable)>,
+    assets: Res<PickupAssets>,
+) {
+    for death in deaths.read() {
+        let Ok((transform, loot)) = enemies.get(death.entity) else {
+            continue;
+        };
+
+        for (i, drop) in loot.iter().enumerate() {
+            if !rng.random_bool(drop.chance.clamp(0.0, 1.0)) {
+                continue;
+            }
+            // spread around the body so they don't stack
+            let offset = Quat::from_rotation_y(i as f32 * 2.4) * Vec3::X * i.min(1) as f32;
+            commands.spawn(pickup_bundle(
+                &assets,
+                Pickup {
+                    kind: drop.kind,
+                    rarity: drop.rarity,
+                },
+                transform.translation + offset,
+            ));
+        }
+    }
+}
+
+/// Sends every experience pickup on the map, however far, towards whoever
+/// collected the vacuum
+fn start_vacuum(
+    mut commands: Commands,
+    mut collected: EventReader<PickupCollected>,
+    pickups: Query<(Entity, &Pickup, &Transform), Without<Vacuumed>>,
+) {
+    for event in collected.read() {
+        if event.pickup.kind != PickupKind::Vacuum {
+            continue;
+        }
+
+        for (entity, pickup, transform) in pickups.iter() {
+            if !matches!(pickup.kind, PickupKind::Experience(_)) {
+                continue;
+            }
+            commands.entity(entity).insert(Vacuumed {
+                collector: event.collector,
+                start: transform.translation,
+                timer: Timer::from_seconds(VACUUM_SECONDS, TimerMode::Once),
+            });
+        }
+    }
+}
+
+/// Speeds up on the way in, so the whole map's worth lands together
+fn pull_vacuumed(
+    mut commands: Commands,
+    time: Res<Time>,
+    collectors: Query<&Transform, (With<Player>, Without<Vacuumed>)>,
+    mut pickups: Query<(Entity, &Pickup, &mut Transform, &mut Vacuumed)>,
+    config: Res<RunConfig>,
+    mut collected: EventWriter<PickupCollected>,
+) {
+    for (entity, pickup, mut transform, mut vacuumed) in pickups.iter_mut() {
+        // whoever called it in is gone, so leave it where it is
+        let Ok(target) = collectors.get(vacuumed.collector) else {
+            commands.entity(entity).remove::<Vacuumed>();
+            continue;
+        };
+
+        vacuumed.timer.tick(time.delta());
+        if vacuumed.timer.finished() {
+            collected.write(PickupCollected {
+                collector: vacuumed.collector,
+                pickup: Pickup {
+                    kind: config.reward(pickup.kind),
+                    ..*pickup
+                },
+            });
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let t = vacuumed.timer.fraction();
+        transform.translation = vacuumed.start.lerp(target.translation, t * t * t);
+    }
+}
+
+fn apply_heal_pickups(mut collected: EventReader<PickupCollected>, mut health: Query<&mut Health>) {
+    for event in collected.read() {
+        let PickupKind::Heal(amount) = event.pickup.kind else {
+            continue;
+        };
+
+        if let Ok(mut health) = health.get_mut(event.collector) {
+            health.current = (health.current + amount).min(health.max);
+        }
+    }
+}
+
+fn apply_armor_pickups(
+    mut collected: EventReader<PickupCollected>,
+    mut players: Query<(&mut Stats, &mut TimedModifiers)>,
+) {
+    for event in collected.read() {
+        let PickupKind::Armor(amount) = event.pickup.kind else {
+            continue;
+        };
+
+        if let Ok((mut stats, mut timed)) = players.get_mut(event.collector) {
+            let modifier = StatModifier {
+                source: ARMOR_PICKUP_SOURCE.into(),
+                stat: Stat::Armor,
+                op: ModifierOp::Add(amount),
+            };
+            timed.grant(&mut stats, modifier, ARMOR_PICKUP_SECONDS);
+        }
+    }
+}
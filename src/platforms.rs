@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::controls::update_ground_normals;
+use crate::prefabs::GroundBundle;
+use crate::{CollidedGrounds, GameplaySet, Ground};
+
+/// Size of a platform, it's a flat slab
+const PLATFORM_SIZE: Vec3 = Vec3::new(6.0, 0.5, 6.0);
+/// Close enough to a waypoint to head for the next one
+const ARRIVE_DISTANCE: f32 = 0.01;
+const PAD_RADIUS: f32 = 2.0;
+/// How long after a launch the ground is ignored, long enough to leave it
+const LAUNCH_SECONDS: f32 = 0.25;
+
+pub struct PlatformsPlugin;
+
+impl Plugin for PlatformsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<MovingPlatform>();
+        app.register_type::<BouncePad>();
+        app.register_type::<Launched>();
+        app.register_type::<SurfaceProperties>();
+        app.add_systems(PreStartup, setup_platform_assets);
+        // set before the physics step, like movement forces
+        app.add_systems(
+            FixedUpdate,
+            (move_platforms, (tick_launched, bounce).chain())
+                .before(update_ground_normals)
+                .before(PhysicsSet::SyncBackend)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Ground that travels between `waypoints` in order, looping back round to
+/// the first after the last
+///
+/// Anything standing on it is carried along by `entities_try_to_move`
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+#[require(RigidBody = RigidBody::KinematicVelocityBased, Velocity, Ground)]
+pub struct MovingPlatform {
+    /// Relative to the platform's parent, which shouldn't be rotated
+    pub waypoints: Vec<Vec3>,
+    /// Units per second
+    pub speed: f32,
+    /// Index of the waypoint it's heading for
+    pub next: usize,
+}
+
+impl MovingPlatform {
+    pub fn new(waypoints: Vec<Vec3>, speed: f32) -> Self {
+        return Self {
+            waypoints,
+            speed,
+            next: 0,
+        };
+    }
+}
+
+/// Ground that launches anything landing on it straight up at `speed`
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+#[require(Ground)]
+pub struct BouncePad {
+    pub speed: f32,
+}
+
+/// Thrown into the air, by a bounce pad or a knock-up attack
+///
+/// Until the timer is up the entity isn't held on the ground, so it keeps
+/// falling under air gravity instead of sticking to what it was launched
+/// off of
+#[derive(Component, Reflect, Deref, DerefMut, Debug, Clone)]
+#[reflect(Component)]
+pub struct Launched(pub Timer);
+
+impl Launched {
+    pub fn new() -> Self {
+        return Self(Timer::from_seconds(LAUNCH_SECONDS, TimerMode::Once));
+    }
+}
+
+impl Default for Launched {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// Throw `entity` straight up at `speed`, keeping its sideways velocity
+pub fn launch(commands: &mut Commands, entity: Entity, speed: f32) {
+    commands
+        .entity(entity)
+        .entry::<Velocity>()
+        .and_modify(move |mut velocity| velocity.linvel.y = speed);
+    commands.entity(entity).insert(Launched::new());
+}
+
+/// How ground changes the movement of whatever stands on it, for ice, mud
+/// and conveyor belts. Ground without it is plain
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+#[require(Ground)]
+pub struct SurfaceProperties {
+    /// Scales how hard movement pushes towards full speed, low is slippery
+    pub acceleration: f32,
+    /// Scales top speed
+    pub speed: f32,
+    /// Velocity anything standing on it is carried along at
+    pub conveyor: Vec3,
+}
+
+impl Default for SurfaceProperties {
+    fn default() -> Self {
+        return Self {
+            acceleration: 1.0,
+            speed: 1.0,
+            conveyor: Vec3::ZERO,
+        };
+    }
+}
+
+impl SurfaceProperties {
+    pub fn ice() -> Self {
+        return Self {
+            acceleration: 0.1,
+            speed: 1.2,
+            ..default()
+        };
+    }
+
+    pub fn mud() -> Self {
+        return Self {
+            acceleration: 0.6,
+            speed: 0.5,
+            ..default()
+        };
+    }
+
+    pub fn conveyor(velocity: Vec3) -> Self {
+        return Self {
+            conveyor: velocity,
+            ..default()
+        };
+    }
+}
+
+#[derive(Resource)]
+pub struct PlatformAssets {
+    pub platform_mesh: Handle<Mesh>,
+    pub platform_material: Handle<StandardMaterial>,
+    pub pad_mesh: Handle<Mesh>,
+    pub pad_material: Handle<StandardMaterial>,
+    /// A unit cube, scaled to the size of each patch
+    pub patch_mesh: Handle<Mesh>,
+    pub ice_material: Handle<StandardMaterial>,
+    pub mud_material: Handle<StandardMaterial>,
+    pub conveyor_material: Handle<StandardMaterial>,
+}
+
+fn setup_platform_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PlatformAssets {
+        platform_mesh: meshes.add(Cuboid::from_size(PLATFORM_SIZE)),
+        platform_material: materials.add(Color::srgb(0.45, 0.4, 0.5)),
+        pad_mesh: meshes.add(Cylinder::new(PAD_RADIUS, 0.3)),
+        pad_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.95, 0.5, 0.2),
+            emissive: LinearRgba::rgb(0.6, 0.25, 0.05),
+            ..default()
+        }),
+        patch_mesh: meshes.add(Cuboid::from_length(1.0)),
+        ice_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.75, 0.9, 1.0),
+            perceptual_roughness: 0.1,
+            ..default()
+        }),
+        mud_material: materials.add(Color::srgb(0.35, 0.25, 0.15)),
+        conveyor_material: materials.add(Color::srgb(0.3, 0.3, 0.35)),
+    });
+}
+
+/// A platform starting at the first of `waypoints`
+pub fn moving_platform(assets: &PlatformAssets, waypoints: Vec<Vec3>, speed: f32) -> impl Bundle {
+    let start = waypoints.first().copied().unwrap_or_default();
+    (
+        Mesh3d(assets.platform_mesh.clone()),
+        MeshMaterial3d(assets.platform_material.clone()),
+        Transform::from_translation(start),
+        Name::new("Moving Platform"),
+        MovingPlatform::new(waypoints, speed),
+        GroundBundle::new(Collider::cuboid(
+            PLATFORM_SIZE.x / 2.0,
+            PLATFORM_SIZE.y / 2.0,
+            PLATFORM_SIZE.z / 2.0,
+        )),
+    )
+}
+
+/// A bounce pad sitting on the ground at `position`
+pub fn bounce_pad(assets: &PlatformAssets, position: Vec3, speed: f32) -> impl Bundle {
+    (
+        Mesh3d(assets.pad_mesh.clone()),
+        MeshMaterial3d(assets.pad_material.clone()),
+        Transform::from_translation(position + Vec3::Y * 0.15),
+        Name::new("Bounce Pad"),
+        BouncePad { speed },
+        GroundBundle::new(Collider::cylinder(0.15, PAD_RADIUS)),
+    )
+}
+
+/// A flat patch of `surface` sitting on the ground at `position`, `size`
+/// across
+pub fn surface_patch(
+    assets: &PlatformAssets,
+    surface: SurfaceProperties,
+    position: Vec3,
+    size: Vec2,
+) -> impl Bundle {
+    let material = if surface.conveyor != Vec3::ZERO {
+        assets.conveyor_material.clone()
+    } else if surface.speed < 1.0 {
+        assets.mud_material.clone()
+    } else {
+        assets.ice_material.clone()
+    };
+
+    (
+        Mesh3d(assets.patch_mesh.clone()),
+        MeshMaterial3d(material),
+        Transform::from_translation(position + Vec3::Y * 0.05)
+            .with_scale(Vec3::new(size.x, 0.1, size.y)),
+        Name::new("Surface"),
+        surface,
+        // scaled along with the mesh
+        GroundBundle::new(Collider::cuboid(0.5, 0.5, 0.5)),
+    )
+}
+
+/// Head for the next waypoint, arriving on it exactly rather than
+/// overshooting
+fn move_platforms(
+    time: Res<Time>,
+    mut platforms: Query<(&Transform, &mut MovingPlatform, &mut Velocity)>,
+) {
+    let delta = time.delta_secs();
+    if delta <= 0.0 {
+        return;
+    }
+
+    for (transform, mut platform, mut velocity) in platforms.iter_mut() {
+        if platform.waypoints.is_empty() {
+            velocity.linvel = Vec3::ZERO;
+            continue;
+        }
+
+        // the path may have been edited shorter
+        platform.next %= platform.waypoints.len();
+        let mut to_waypoint = platform.waypoints[platform.next] - transform.translation;
+        if to_waypoint.length() <= ARRIVE_DISTANCE {
+            platform.next = (platform.next + 1) % platform.waypoints.len();
+            to_waypoint = platform.waypoints[platform.next] - transform.translation;
+        }
+        velocity.linvel = to_waypoint.clamp_length_max(platform.speed * delta) / delta;
+    }
+}
+
+fn tick_launched(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut launched: Query<(Entity, &mut Launched)>,
+) {
+    for (entity, mut launched) in launched.iter_mut() {
+        if launched.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Launched>();
+        }
+    }
+}
+
+/// Launch whatever is standing on a bounce pad, once per landing
+fn bounce(
+    mut commands: Commands,
+    pads: Query<&BouncePad>,
+    standing: Query<(Entity, &CollidedGrounds), Without<Launched>>,
+) {
+    for (entity, grounds) in standing.iter() {
+        let pad = grounds.iter().find_map(|&ground| pads.get(ground).ok());
+        if let Some(pad) = pad {
+            launch(&mut commands, entity, pad.speed);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::ecs::error::ignore;
+use bevy::ecs::system::EntityCommands;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::prefab_library::PrefabDefinition;
+use crate::toon::ToonMaterial;
+
+pub struct PoolPlugin;
+
+impl Plugin for PoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pool>();
+    }
+}
+
+/// Which entities can stand in for each other, pooled entities are only
+/// reused for the same key so they keep the same shape and mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PoolKey {
+    Prefab(AssetId<PrefabDefinition>),
+    Projectile,
+    Afterimage,
+}
+
+/// Marks an entity that goes back to the pool instead of being despawned
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Pooled(pub PoolKey);
+
+/// A pooled entity waiting to be reused, with its body and collider
+/// disabled and its mesh hidden
+#[derive(Component, Debug)]
+pub struct Inactive;
+
+/// What a released entity keeps, everything that is slow to create again.
+/// The rest is stripped so nothing from its last use carries over
+type Retained = (
+    Pooled,
+    (Transform, GlobalTransform),
+    (Visibility, InheritedVisibility, ViewVisibility),
+    (
+        Mesh3d,
+        MeshMaterial3d<StandardMaterial>,
+        MeshMaterial3d<ToonMaterial>,
+    ),
+    (RigidBody, Collider),
+    (
+        RapierRigidBodyHandle,
+        RapierColliderHandle,
+        RapierContextEntityLink,
+    ),
+);
+
+/// Spawning and despawning physics bodies is slow enough to hitch with
+/// hundreds of enemies and projectiles coming and going, so dead ones are
+/// kept around here and brought back for the next spawn
+#[derive(Resource, Default)]
+pub struct Pool {
+    free: HashMap<PoolKey, Vec<Entity>>,
+}
+
+impl Pool {
+    /// Reuse a released entity for `key` if there is one, otherwise spawn a
+    /// new one
+    pub fn spawn(&mut self, commands: &mut Commands, key: PoolKey, bundle: impl Bundle) -> Entity {
+        let Some(entity) = self.free.get_mut(&key).and_then(Vec::pop) else {
+            return commands.spawn((bundle, Pooled(key))).id();
+        };
+
+        commands
+            .entity(entity)
+            .remove::<(Inactive, RigidBodyDisabled, ColliderDisabled)>()
+            .insert((bundle, Visibility::Inherited));
+        return entity;
+    }
+
+    /// Entities waiting to be reused
+    pub fn len(&self) -> usize {
+        return self.free.values().map(Vec::len).sum();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+}
+
+pub trait ReleaseCommandsExt {
+    /// Return the entity to the `Pool` if it's `Pooled`, otherwise despawn
+    /// it. Like `try_despawn`, entities that are already gone are ignored
+    fn release(&mut self) -> &mut Self;
+}
+
+impl ReleaseCommandsExt for EntityCommands<'_> {
+    fn release(&mut self) -> &mut Self {
+        return self.queue_handled(release, ignore);
+    }
+}
+
+fn release(mut entity: EntityWorldMut) {
+    let Some(&Pooled(key)) = entity.get::<Pooled>() else {
+        entity.despawn();
+        return;
+    };
+    // released twice in one frame, say by two hits at once
+    if entity.contains::<Inactive>() {
+        return;
+    }
+
+    entity.retain::<Retained>().insert((
+        Inactive,
+        RigidBodyDisabled,
+        ColliderDisabled,
+        Visibility::Hidden,
+    ));
+    let id = entity.id();
+    entity.world_scope(|world| {
+        world
+            .resource_mut::<Pool>()
+            .free
+            .entry(key)
+            .or_default()
+            .push(id);
+    });
+}
@@ -0,0 +1,332 @@
+//! Enemies and pickups defined in `assets/prefabs/*.prefab.ron` rather than
+//! in code, so a new one is just a new file
+//!
+//! Every file in the folder is loaded before the menu, and turned into a
+//! `PrefabLibrary` entry once loading is done. Spawn them by name with
+//! `spawn_prefab`, which is how every regular enemy comes into the world
+
+use bevy::asset::LoadedFolder;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::accessibility::ColorPalette;
+use crate::ai::{ChasePlayer, KeepDistance, RangedAttack, Separation, varied_move_speed};
+use crate::animation::{AnimationController, AnimationLibrary, AnimationState};
+use crate::combat::{ContactDamage, DamageType, Health, Resistances};
+use crate::enemies::enemy_body;
+use crate::loading::GameAssets;
+use crate::pickups::{LootDrop, LootTable, Pickup, PickupKind, Rarity};
+use crate::pool::{Pool, PoolKey};
+use crate::ron_asset::RonAssetLoader;
+use crate::toon::ToonMaterial;
+
+pub struct PrefabLibraryPlugin;
+
+impl Plugin for PrefabLibraryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<PrefabDefinition>();
+        app.register_asset_loader(RonAssetLoader::<PrefabDefinition>::new(&["prefab.ron"]));
+        app.register_type::<PrefabName>();
+        app.init_resource::<PrefabLibrary>();
+        app.add_systems(OnExit(GameState::Loading), build_prefab_library);
+    }
+}
+
+/// One `*.prefab.ron` file
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct PrefabDefinition {
+    /// What it's spawned by, and its `Name`
+    pub name: String,
+    pub shape: PrefabShape,
+    pub color: (f32, f32, f32),
+    /// Colours to use instead of `color` under the colourblind palettes
+    #[serde(default)]
+    pub palette_colors: Vec<(ColorPalette, (f32, f32, f32))>,
+    #[serde(default)]
+    pub components: Vec<PrefabComponent>,
+}
+
+impl PrefabDefinition {
+    /// Its colour under `palette`
+    pub fn color(&self, palette: ColorPalette) -> Color {
+        let (r, g, b) = self
+            .palette_colors
+            .iter()
+            .find(|(candidate, _)| *candidate == palette)
+            .map_or(self.color, |(_, color)| *color);
+        return Color::srgb(r, g, b);
+    }
+
+    /// Enemies are pooled, everything else is spawned fresh
+    fn is_enemy(&self) -> bool {
+        return self
+            .components
+            .iter()
+            .any(|component| matches!(component, PrefabComponent::Enemy { .. }));
+    }
+}
+
+/// Used for both the mesh and, if it has one, the collider
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum PrefabShape {
+    Capsule { radius: f32, half_length: f32 },
+    Sphere { radius: f32 },
+    Cuboid { x: f32, y: f32, z: f32 },
+}
+
+impl PrefabShape {
+    fn mesh(&self) -> Mesh {
+        match *self {
+            PrefabShape::Capsule {
+                radius,
+                half_length,
+            } => return Capsule3d::new(radius, half_length * 2.0).into(),
+            PrefabShape::Sphere { radius } => return Sphere::new(radius).into(),
+            PrefabShape::Cuboid { x, y, z } => return Cuboid::new(x, y, z).into(),
+        }
+    }
+
+    fn collider(&self) -> Collider {
+        match *self {
+            PrefabShape::Capsule {
+                radius,
+                half_length,
+            } => return Collider::capsule_y(half_length, radius),
+            PrefabShape::Sphere { radius } => return Collider::ball(radius),
+            PrefabShape::Cuboid { x, y, z } => return Collider::cuboid(x / 2.0, y / 2.0, z / 2.0),
+        }
+    }
+}
+
+/// The building blocks a prefab is made from, each one maps onto one or
+/// more components
+#[derive(Deserialize, Debug, Clone)]
+pub enum PrefabComponent {
+    /// A physics body on the enemy side, chasing or keeping away is up to
+    /// the other components
+    Enemy {
+        health: f32,
+        move_speed: f32,
+    },
+    /// Seconds between hits default to `CONTACT_DAMAGE_INTERVAL`
+    ContactDamage {
+        amount: f32,
+        #[serde(default)]
+        interval: Option<f32>,
+    },
+    ChasePlayer,
+    KeepDistance {
+        distance: f32,
+        tolerance: f32,
+    },
+    RangedAttack {
+        range: f32,
+        cooldown: f32,
+        windup: f32,
+    },
+    /// Fraction of each damage type taken off, negative for a weakness
+    Resistances(Vec<(DamageType, f32)>),
+    /// Clips from the animation library, by name, for each state
+    Animations(Vec<(AnimationState, String)>),
+    /// Rolled on death
+    Drops(Vec<LootDrop>),
+    Pickup {
+        kind: PickupKind,
+        rarity: Rarity,
+    },
+}
+
+/// Which prefab an entity was spawned from
+#[derive(Component, Reflect, Deref, Debug, Clone)]
+#[reflect(Component)]
+pub struct PrefabName(pub String);
+
+/// A loaded prefab, with its mesh and material made once and shared by
+/// everything spawned from it
+pub struct LoadedPrefab {
+    pub id: AssetId<PrefabDefinition>,
+    pub definition: PrefabDefinition,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<ToonMaterial>,
+}
+
+/// Every prefab in `assets/prefabs`, by name
+#[derive(Resource, Default)]
+pub struct PrefabLibrary {
+    prefabs: HashMap<String, LoadedPrefab>,
+    /// Where prefab clips are looked up, at spawn so hot reloads apply
+    animations: Handle<AnimationLibrary>,
+}
+
+impl PrefabLibrary {
+    pub fn get(&self, name: &str) -> Option<&LoadedPrefab> {
+        return self.prefabs.get(name);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LoadedPrefab> {
+        return self.prefabs.values();
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        return self.prefabs.keys().map(String::as_str);
+    }
+}
+
+fn build_prefab_library(
+    mut library: ResMut<PrefabLibrary>,
+    assets: Res<GameAssets>,
+    folders: Res<Assets<LoadedFolder>>,
+    definitions: Res<Assets<PrefabDefinition>>,
+    animations: Res<Assets<AnimationLibrary>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+) {
+    let Some(folder) = folders.get(&assets.prefabs) else {
+        warn!("No prefabs folder loaded");
+        return;
+    };
+    let animations = animations.get(&assets.animations);
+
+    library.animations = assets.animations.clone();
+    library.prefabs.clear();
+    for handle in folder.handles.iter() {
+        let Ok(handle) = handle.clone().try_typed::<PrefabDefinition>() else {
+            continue;
+        };
+        let Some(definition) = definitions.get(&handle) else {
+            continue;
+        };
+
+        let mut definition = definition.clone();
+        // clips the library doesn't have would never play, so drop them here
+        // where it's obvious which prefab asked for them
+        for component in definition.components.iter_mut() {
+            if let PrefabComponent::Animations(clips) = component {
+                clips.retain(|(_, clip)| {
+                    let found = animations.is_some_and(|library| library.clip(clip).is_some());
+                    if !found {
+                        warn!("Prefab {} uses unknown clip {clip}", definition.name);
+                    }
+                    return found;
+                });
+            }
+        }
+
+        // recoloured for the palette in use by `apply_enemy_palette`
+        let loaded = LoadedPrefab {
+            id: handle.id(),
+            mesh: meshes.add(definition.shape.mesh()),
+            material: materials.add(ToonMaterial::from(definition.color(default()))),
+            definition,
+        };
+        if library
+            .prefabs
+            .insert(loaded.definition.name.clone(), loaded)
+            .is_some()
+        {
+            warn!("Two prefabs share a name, only the last one loaded is kept");
+        }
+    }
+}
+
+/// Spawn the prefab called `name`, or nothing if there isn't one
+///
+/// Enemies come out of the pool, with their health scaled by
+/// `health_multiplier`. Rolling them elite is up to the caller
+pub fn spawn_prefab(
+    commands: &mut Commands,
+    pool: &mut Pool,
+    (library, animations): (&PrefabLibrary, &Assets<AnimationLibrary>),
+    name: &str,
+    position: Vec3,
+    health_multiplier: f32,
+    rng: &mut impl Rng,
+) -> Option<Entity> {
+    let prefab = library.get(name)?;
+    let definition = &prefab.definition;
+    let animations = animations.get(&library.animations);
+
+    let bundle = (
+        Mesh3d(prefab.mesh.clone()),
+        MeshMaterial3d(prefab.material.clone()),
+        Transform::from_translation(position),
+        Name::new(definition.name.clone()),
+        PrefabName(definition.name.clone()),
+    );
+    let entity = if definition.is_enemy() {
+        pool.spawn(commands, PoolKey::Prefab(prefab.id), bundle)
+    } else {
+        commands.spawn(bundle).id()
+    };
+    let mut entity = commands.entity(entity);
+
+    for component in definition.components.iter() {
+        match component {
+            PrefabComponent::Enemy { health, move_speed } => {
+                entity.insert((
+                    enemy_body(0.5, 0.5),
+                    Health::new(*health * health_multiplier),
+                    Separation::default(),
+                    varied_move_speed(*move_speed, rng),
+                ));
+                // swap the default capsule for the prefab's own shape
+                entity.insert(definition.shape.collider());
+            }
+            PrefabComponent::ContactDamage { amount, interval } => {
+                entity.insert(match interval {
+                    Some(interval) => ContactDamage::with_interval(*amount, *interval),
+                    None => ContactDamage::new(*amount),
+                });
+            }
+            PrefabComponent::ChasePlayer => {
+                entity.insert(ChasePlayer);
+            }
+            PrefabComponent::KeepDistance {
+                distance,
+                tolerance,
+            } => {
+                entity.insert(KeepDistance {
+                    distance: *distance,
+                    tolerance: *tolerance,
+                });
+            }
+            PrefabComponent::RangedAttack {
+                range,
+                cooldown,
+                windup,
+            } => {
+                entity.insert(RangedAttack::new(*range, *cooldown, *windup));
+            }
+            PrefabComponent::Resistances(resistances) => {
+                entity.insert(Resistances(resistances.iter().copied().collect()));
+            }
+            PrefabComponent::Animations(clips) => {
+                let Some(animations) = animations else {
+                    continue;
+                };
+                let mut controller = AnimationController::new();
+                for (state, clip) in clips.iter() {
+                    if let Some(clip) = animations.clip(clip) {
+                        controller = controller.with_clip(*state, clip);
+                    }
+                }
+                entity.insert(controller);
+            }
+            PrefabComponent::Drops(drops) => {
+                entity.insert(LootTable(drops.clone()));
+            }
+            PrefabComponent::Pickup { kind, rarity } => {
+                entity.insert(Pickup {
+                    kind: *kind,
+                    rarity: *rarity,
+                });
+            }
+        }
+    }
+
+    return Some(entity.id());
+}
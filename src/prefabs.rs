@@ -0,0 +1,195 @@
+//! What the things spawned from more than one place are made of, so
+//! everywhere that spawns one gets the same thing
+//!
+//! Enemies are prefabs in `assets/prefabs`, spawned through `spawn_prefab`
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::prelude::InputMap;
+
+use crate::abilities::AbilitySlots;
+use crate::animation::AnimationController;
+use crate::balance::BalanceConfig;
+use crate::combat::{Faction, InvulnerableOnHit};
+use crate::controls::{Action, AimDirection, Dash, InputBuffer};
+use crate::experience::Experience;
+use crate::interpolation::InterpolatedTranslation;
+use crate::physics::layers;
+use crate::revive::Revives;
+use crate::stamina::Stamina;
+use crate::stats::{Stats, TimedModifiers};
+use crate::toon::ToonMaterial;
+use crate::upgrades::LevelUpCharges;
+use crate::weapons::{WeaponKind, Weapons};
+use crate::{
+    AirControl, AlignToGround, CameraDistance, CollidedGrounds, FaceMovement, Ground,
+    IntendedRotation, MainCamera, MoveVector, MovementProfile, Player, PlayerSlot,
+    player_input_map,
+};
+
+/// How quickly players tilt to match the slope they're on
+const GROUND_ALIGN_RATE: f32 = 10.0;
+/// Seconds a player can't be hurt again for after being hit
+const PLAYER_HIT_INVULNERABILITY: f32 = 0.75;
+
+/// A player's body and controls, everything that doesn't depend on who
+/// they're playing as
+#[derive(Bundle)]
+pub struct PlayerBundle {
+    pub player: Player,
+    pub slot: PlayerSlot,
+    pub name: Name,
+    pub transform: Transform,
+    pub input_map: InputMap<Action>,
+    pub move_vec: MoveVector,
+    pub aim: AimDirection,
+    pub dash: Dash,
+    pub input_buffer: InputBuffer,
+    pub abilities: AbilitySlots,
+    pub body: RigidBody,
+    pub collider: Collider,
+    pub layers: CollisionGroups,
+    pub active_events: ActiveEvents,
+    pub locked_axes: LockedAxes,
+    pub damping: Damping,
+    pub velocity: Velocity,
+    pub force: ExternalForce,
+    pub gravity: GravityScale,
+    pub mass: ReadMassProperties,
+    pub grounds: CollidedGrounds,
+    pub air_control: AirControl,
+    pub movement: MovementProfile,
+    pub face_movement: FaceMovement,
+    pub rotation: IntendedRotation,
+    pub align: AlignToGround,
+    pub interpolated: InterpolatedTranslation,
+    pub animation: AnimationController,
+    pub faction: Faction,
+    pub invulnerable_on_hit: InvulnerableOnHit,
+    pub level_up_charges: LevelUpCharges,
+    pub timed_modifiers: TimedModifiers,
+    pub revives: Revives,
+    pub experience: Experience,
+}
+
+impl PlayerBundle {
+    /// Local player `slot`, standing at `position`
+    pub fn new(slot: usize, position: Vec3, balance: &BalanceConfig) -> Self {
+        Self {
+            player: Player,
+            slot: PlayerSlot(slot),
+            name: Name::new(format!("Player {}", slot + 1)),
+            transform: Transform::from_translation(position),
+            input_map: player_input_map(slot),
+            move_vec: MoveVector::default(),
+            aim: AimDirection::default(),
+            dash: Dash::default(),
+            input_buffer: InputBuffer::default(),
+            abilities: AbilitySlots::default(),
+            body: RigidBody::Dynamic,
+            collider: Collider::capsule(Vec3::ZERO, Vec3::new(0.0, 2.0, 0.0), 1.0),
+            layers: layers::player(),
+            active_events: ActiveEvents::COLLISION_EVENTS,
+            locked_axes: LockedAxes::ROTATION_LOCKED,
+            damping: Damping {
+                linear_damping: 0.0,
+                angular_damping: 6.5,
+            },
+            velocity: Velocity::default(),
+            force: ExternalForce::default(),
+            gravity: GravityScale(1.0),
+            mass: ReadMassProperties::default(),
+            grounds: CollidedGrounds::default(),
+            air_control: AirControl(balance.air_control),
+            movement: balance.player_movement,
+            face_movement: FaceMovement(balance.player_turn_speed),
+            rotation: IntendedRotation::default(),
+            align: AlignToGround::new(GROUND_ALIGN_RATE),
+            interpolated: InterpolatedTranslation::default(),
+            animation: AnimationController::player(),
+            faction: Faction::Player,
+            invulnerable_on_hit: InvulnerableOnHit(PLAYER_HIT_INVULNERABILITY),
+            level_up_charges: LevelUpCharges::default(),
+            timed_modifiers: TimedModifiers::default(),
+            revives: Revives::default(),
+            experience: Experience::default(),
+        }
+    }
+}
+
+/// Who a spawned player is and how they look
+pub struct PlayerConfig<'a> {
+    pub slot: usize,
+    pub position: Vec3,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<ToonMaterial>,
+    /// After the character's modifiers and shop purchases
+    pub stats: &'a Stats,
+    pub weapon: WeaponKind,
+    pub balance: &'a BalanceConfig,
+}
+
+pub fn spawn_player(commands: &mut Commands, config: PlayerConfig) -> Entity {
+    return commands
+        .spawn((
+            PlayerBundle::new(config.slot, config.position, config.balance),
+            Mesh3d(config.mesh),
+            MeshMaterial3d(config.material),
+        ))
+        .insert((config.stats.derived(), Stamina::from_stats(config.stats)))
+        .insert((config.stats.clone(), Weapons::new([config.weapon])))
+        .id();
+}
+
+/// Something to stand on. Not a body of its own, so needs a `RigidBody`
+/// unless it's the child of one
+#[derive(Bundle)]
+pub struct GroundBundle {
+    pub ground: Ground,
+    pub collider: Collider,
+    pub layers: CollisionGroups,
+    /// None, so it doesn't drag on movement
+    pub friction: Friction,
+}
+
+impl GroundBundle {
+    pub fn new(collider: Collider) -> Self {
+        Self {
+            ground: Ground,
+            collider,
+            layers: layers::ground(),
+            friction: Friction {
+                coefficient: 0.0,
+                ..default()
+            },
+        }
+    }
+}
+
+/// The camera following the players
+#[derive(Bundle)]
+pub struct CameraBundle {
+    pub camera: Camera3d,
+    pub projection: Projection,
+    pub transform: Transform,
+    pub distance: CameraDistance,
+    pub main: MainCamera,
+    pub name: Name,
+}
+
+impl CameraBundle {
+    /// `fov` is vertical, in degrees
+    pub fn new(fov: f32, distance: f32) -> Self {
+        Self {
+            camera: Camera3d::default(),
+            projection: Projection::Perspective(PerspectiveProjection {
+                fov: fov.to_radians(),
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, 7.0, 14.0).looking_at(Vec3::Y, Vec3::Y),
+            distance: CameraDistance(distance),
+            main: MainCamera,
+            name: Name::new("MainCamera"),
+        }
+    }
+}
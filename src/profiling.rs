@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticPath, Diagnostics, EntityCountDiagnosticsPlugin,
+    FrameTimeDiagnosticsPlugin, RegisterDiagnostic,
+};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, PhysicsSet};
+
+use crate::GameplaySet;
+use crate::spatial::SpatialSet;
+
+pub const COLLIDER_COUNT: DiagnosticPath = DiagnosticPath::const_new("colliders");
+const GAMEPLAY_TIME: DiagnosticPath = DiagnosticPath::const_new("timings/gameplay");
+const SPATIAL_TIME: DiagnosticPath = DiagnosticPath::const_new("timings/spatial");
+const PHYSICS_TIME: DiagnosticPath = DiagnosticPath::const_new("timings/physics");
+
+/// The groups of systems timed every frame, by the name they're shown under
+pub const TIMED_SPANS: &[(&str, DiagnosticPath)] = &[
+    ("Gameplay", GAMEPLAY_TIME),
+    ("Spatial Grids", SPATIAL_TIME),
+    ("Physics", PHYSICS_TIME),
+];
+
+/// Frame times, entity and collider counts, and how long the bigger groups
+/// of systems take, for the inspector's diagnostics tab
+pub struct ProfilingPlugin;
+
+impl Plugin for ProfilingPlugin {
+    fn build(&self, app: &mut App) {
+        // the stress test example wants these too, in any build
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+        if !app.is_plugin_added::<EntityCountDiagnosticsPlugin>() {
+            app.add_plugins(EntityCountDiagnosticsPlugin);
+        }
+
+        app.register_diagnostic(Diagnostic::new(COLLIDER_COUNT));
+        app.init_resource::<SpanTimings>();
+        app.add_systems(Update, count_colliders);
+        app.add_systems(Last, flush_span_timings);
+
+        time_span(app, Update, GAMEPLAY_TIME, GameplaySet, GameplaySet);
+        time_span(app, Update, SPATIAL_TIME, SpatialSet, SpatialSet);
+        time_span(
+            app,
+            FixedUpdate,
+            PHYSICS_TIME,
+            PhysicsSet::SyncBackend,
+            PhysicsSet::Writeback,
+        );
+    }
+}
+
+/// When each span last started, and how long they've taken so far this
+/// frame. Spans in `FixedUpdate` can run more than once a frame
+#[derive(Resource, Default)]
+struct SpanTimings {
+    started: HashMap<DiagnosticPath, Instant>,
+    elapsed: HashMap<DiagnosticPath, Duration>,
+}
+
+/// Time from just before `first` starts to just after `last` is done
+///
+/// Systems outside of the span running alongside it get counted too, so this
+/// is the wall time the span holds up the frame for rather than exact
+/// per-system costs
+fn time_span<M1, M2>(
+    app: &mut App,
+    schedule: impl ScheduleLabel,
+    path: DiagnosticPath,
+    first: impl IntoSystemSet<M1>,
+    last: impl IntoSystemSet<M2>,
+) {
+    let schedule = schedule.intern();
+    app.register_diagnostic(Diagnostic::new(path.clone()).with_suffix("ms"));
+
+    let start = path.clone();
+    app.add_systems(
+        schedule,
+        (move |mut timings: ResMut<SpanTimings>| {
+            timings.started.insert(start.clone(), Instant::now());
+        })
+        .before(first),
+    );
+    app.add_systems(
+        schedule,
+        (move |mut timings: ResMut<SpanTimings>| {
+            let Some(started) = timings.started.remove(&path) else {
+                return;
+            };
+            *timings.elapsed.entry(path.clone()).or_default() += started.elapsed();
+        })
+        .after(last),
+    );
+}
+
+fn flush_span_timings(mut timings: ResMut<SpanTimings>, mut diagnostics: Diagnostics) {
+    for (_, path) in TIMED_SPANS {
+        let elapsed = timings.elapsed.remove(path).unwrap_or_default();
+        diagnostics.add_measurement(path, || elapsed.as_secs_f64() * 1000.0);
+    }
+}
+
+fn count_colliders(colliders: Query<(), With<Collider>>, mut diagnostics: Diagnostics) {
+    diagnostics.add_measurement(&COLLIDER_COUNT, || colliders.iter().len() as f64);
+}
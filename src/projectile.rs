@@ -0,0 +1,460 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::math::ops::sin;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::GameplaySet;
+use crate::budget::{BudgetCategory, BudgetPriority, Budgeted};
+use crate::combat::{DamageEvent, DamageType, Faction, Health};
+use crate::damage_zone::{DamageZone, DamageZoneAssets, damage_zone};
+use crate::math::VecTools;
+use crate::physics::layers;
+use crate::pool::{Inactive, Pool, PoolKey, ReleaseCommandsExt};
+use crate::props::{Piercing, Prop};
+use crate::status::{ApplyStatus, InflictsStatus};
+use crate::weapons::WeaponKind;
+
+/// Furthest a bouncing projectile looks for its next target
+const BOUNCE_RANGE: f32 = 12.0;
+/// Furthest a homing projectile looks for a new target once its own is gone
+const HOMING_RANGE: f32 = 30.0;
+/// Split projectiles each deal this much of the original's damage
+const SPLIT_DAMAGE: f32 = 0.5;
+const SPLIT_LIFETIME: f32 = 0.6;
+/// Angle between neighbouring split projectiles
+const SPLIT_SPREAD: f32 = 0.5;
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_projectile_assets);
+        app.add_systems(
+            Update,
+            (expire_projectiles, steer_homing, projectile_hits).in_set(GameplaySet),
+        );
+        // a velocity held for exactly one physics step stays on the path at
+        // any framerate
+        app.add_systems(
+            FixedUpdate,
+            follow_trajectories
+                .before(PhysicsSet::SyncBackend)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Projectile {
+    pub damage: f32,
+    /// The faction this projectile damages
+    pub hits: Faction,
+    pub lifetime: Timer,
+    /// Who fired it, their crit stats apply to its hits
+    pub owner: Option<Entity>,
+    /// The player weapon that fired it, for damage tracking
+    pub weapon: Option<WeaponKind>,
+    pub kind: DamageType,
+}
+
+/// Passes through this many more targets before it's used up
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Pierce(pub u32);
+
+/// Heads for the nearest other target after a hit, this many more times
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Bounce(pub u32);
+
+/// Bursts into `count` weaker projectiles fanning out from each hit
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct SplitOnHit {
+    pub count: u32,
+}
+
+/// Leaves a `DamageZone` where it hits, dealing `dps` for `seconds`
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct ZoneOnHit {
+    pub radius: f32,
+    pub dps: f32,
+    pub seconds: f32,
+}
+
+/// Split off of a hit on this entity, so it passes through it instead of
+/// hitting it again straight away
+#[derive(Component, Debug, Clone, Copy)]
+struct SplitFrom(Entity);
+
+/// Moves the projectile along a path over its lifetime, instead of in a
+/// straight line at its velocity
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+#[require(TrajectoryTime)]
+pub enum Trajectory {
+    /// Loops out as far as `reach` from `origin`, swinging out `width` to
+    /// one side on the way out and the other on the way back, and comes
+    /// home to whoever threw it, wherever they are by then
+    Boomerang {
+        origin: Vec3,
+        reach: Vec3,
+        width: f32,
+    },
+}
+
+impl Trajectory {
+    /// Where it should be `t` of the way through its lifetime, from 0 to 1.
+    /// `home` is where its owner is now, if they're still around
+    pub fn position(&self, t: f32, home: Option<Vec3>) -> Vec3 {
+        match *self {
+            Trajectory::Boomerang {
+                origin,
+                reach,
+                width,
+            } => {
+                let side = Vec3::new(-reach.z, 0.0, reach.x).normalize_or_zero() * width;
+                let home = home.unwrap_or(origin);
+                return origin.lerp(home, t) + reach * sin(PI * t) + side * sin(TAU * t);
+            }
+        }
+    }
+}
+
+/// Physics time a projectile has been on its `Trajectory`. Its lifetime is
+/// only ticked once a frame, which can be several physics steps
+#[derive(Component, Default)]
+struct TrajectoryTime(f32);
+
+/// Turn towards `target` by at most `turn_rate` radians per second
+///
+/// When the target dies, or there never was one, it picks the nearest living
+/// thing it `hits` instead
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Homing {
+    pub turn_rate: f32,
+    pub target: Option<Entity>,
+}
+
+#[derive(Resource)]
+pub struct ProjectileAssets {
+    pub mesh: Handle<Mesh>,
+    pub enemy_material: Handle<StandardMaterial>,
+    pub player_material: Handle<StandardMaterial>,
+}
+
+fn setup_projectile_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(ProjectileAssets {
+        mesh: meshes.add(Sphere::new(0.5)),
+        enemy_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.7, 0.2, 0.9),
+            emissive: LinearRgba::rgb(2.0, 0.4, 3.0),
+            ..default()
+        }),
+        player_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.5, 0.1),
+            emissive: LinearRgba::rgb(3.0, 1.2, 0.2),
+            ..default()
+        }),
+    });
+}
+
+/// A sensor ball moving at `velocity`, damaging the first `projectile.hits`
+/// entity it touches, or more with `Pierce` or `Bounce` added
+pub fn projectile_bundle(
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    origin: Vec3,
+    velocity: Vec3,
+    projectile: Projectile,
+) -> impl Bundle {
+    // shots at the player are the last to go, they're what the player is
+    // dodging
+    let priority = match projectile.hits {
+        Faction::Player => BudgetPriority::High,
+        Faction::Enemy => BudgetPriority::Normal,
+    };
+    (
+        Mesh3d(mesh),
+        MeshMaterial3d(material),
+        Transform::from_translation(origin),
+        Name::new("Projectile"),
+        projectile,
+        Budgeted::new(BudgetCategory::Projectile, priority),
+        RigidBody::KinematicVelocityBased,
+        Velocity::linear(velocity),
+        Collider::ball(0.5),
+        layers::projectile(),
+        Sensor,
+        ActiveEvents::COLLISION_EVENTS,
+        // props are fixed bodies, which kinematic bodies don't report by default
+        ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC,
+    )
+}
+
+fn expire_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Projectile)>,
+) {
+    for (entity, mut projectile) in query.iter_mut() {
+        if projectile.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).release();
+        }
+    }
+}
+
+/// Set the velocity that lands the projectile where its trajectory has it
+/// by the end of the physics step, like moving platforms do with their
+/// waypoints
+fn follow_trajectories(
+    time: Res<Time<Fixed>>,
+    owners: Query<&GlobalTransform>,
+    mut query: Query<(
+        &Projectile,
+        &Trajectory,
+        &Transform,
+        &mut TrajectoryTime,
+        &mut Velocity,
+    )>,
+) {
+    let delta = time.delta_secs();
+    if delta <= 0.0 {
+        return;
+    }
+
+    for (projectile, trajectory, transform, mut elapsed, mut velocity) in query.iter_mut() {
+        let lifetime = projectile.lifetime.duration().as_secs_f32();
+        if lifetime <= 0.0 {
+            continue;
+        }
+
+        elapsed.0 += delta;
+        let t = (elapsed.0 / lifetime).min(1.0);
+        let home = projectile
+            .owner
+            .and_then(|owner| owners.get(owner).ok())
+            .map(|owner| owner.translation());
+        velocity.linvel = (trajectory.position(t, home) - transform.translation) / delta;
+    }
+}
+
+fn steer_homing(
+    time: Res<Time>,
+    targets: Query<(Entity, &Faction, &Health, &GlobalTransform), Without<Inactive>>,
+    mut query: Query<(&Projectile, &Transform, &mut Homing, &mut Velocity)>,
+) {
+    for (projectile, transform, mut homing, mut velocity) in query.iter_mut() {
+        let alive = |target: Entity| {
+            targets
+                .get(target)
+                .ok()
+                .filter(|(_, _, health, _)| !health.is_dead())
+                .map(|(_, _, _, target)| target.translation())
+        };
+
+        let target = match homing.target.and_then(alive) {
+            Some(target) => target,
+            None => {
+                let nearest = targets
+                    .iter()
+                    .filter(|(_, faction, health, _)| {
+                        **faction == projectile.hits && !health.is_dead()
+                    })
+                    .map(|(entity, _, _, target)| (entity, target.translation()))
+                    .filter(|(_, target)| {
+                        target.distance_squared(transform.translation)
+                            <= HOMING_RANGE * HOMING_RANGE
+                    })
+                    .min_by(|(_, a), (_, b)| {
+                        let a = a.distance_squared(transform.translation);
+                        let b = b.distance_squared(transform.translation);
+                        a.total_cmp(&b)
+                    });
+                homing.target = nearest.map(|(entity, _)| entity);
+                let Some((_, target)) = nearest else {
+                    continue;
+                };
+                target
+            }
+        };
+
+        let desired = target - transform.translation;
+        if velocity.linvel.xz() == Vec2::ZERO || desired.xz() == Vec2::ZERO {
+            continue;
+        }
+
+        let max_turn = homing.turn_rate * time.delta_secs();
+        let turn = velocity
+            .linvel
+            .angle_to_xz(desired)
+            .clamp(-max_turn, max_turn);
+        let turned = Vec2::from_angle(turn).rotate(velocity.linvel.xz());
+        velocity.linvel.x = turned.x;
+        velocity.linvel.z = turned.y;
+    }
+}
+
+/// A hit with a target spends `Pierce` first, then `Bounce`, and only
+/// releases the projectile once neither is left. `SplitOnHit` splits on every
+/// hit regardless, as does `ZoneOnHit`
+fn projectile_hits(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionEvent>,
+    (assets, zone_assets, mut pool): (Res<ProjectileAssets>, Res<DamageZoneAssets>, ResMut<Pool>),
+    mut projectiles: Query<(
+        &Projectile,
+        &Transform,
+        &mut Velocity,
+        Option<&InflictsStatus>,
+        Has<Piercing>,
+        (
+            Option<&mut Pierce>,
+            Option<&mut Bounce>,
+            Option<&SplitOnHit>,
+            Option<&SplitFrom>,
+            Option<&ZoneOnHit>,
+        ),
+    )>,
+    targets: Query<(Entity, &Faction, &GlobalTransform), With<Health>>,
+    props: Query<Has<Health>, With<Prop>>,
+    (mut damage, mut status): (EventWriter<DamageEvent>, EventWriter<ApplyStatus>),
+) {
+    for collision in collisions.read() {
+        let CollisionEvent::Started(a, b, _) = collision else {
+            continue;
+        };
+
+        for (projectile_entity, other) in [(*a, *b), (*b, *a)] {
+            let Ok((
+                projectile,
+                transform,
+                mut velocity,
+                inflicts,
+                piercing,
+                (pierce, bounce, split, split_from, zone),
+            )) = projectiles.get_mut(projectile_entity)
+            else {
+                continue;
+            };
+
+            // the player's shots break props, nobody's get through them
+            if let Ok(breakable) = props.get(other) {
+                if breakable && projectile.hits == Faction::Enemy {
+                    damage.write(DamageEvent {
+                        target: other,
+                        amount: projectile.damage,
+                        source: Some(projectile_entity),
+                        crit: false,
+                        weapon: None,
+                        kind: projectile.kind,
+                    });
+                }
+                if !piercing {
+                    commands.entity(projectile_entity).release();
+                }
+                continue;
+            }
+
+            let Ok((_, faction, target)) = targets.get(other) else {
+                continue;
+            };
+
+            if *faction != projectile.hits || split_from.is_some_and(|from| from.0 == other) {
+                continue;
+            }
+
+            damage.write(DamageEvent {
+                target: other,
+                amount: projectile.damage,
+                source: Some(projectile_entity),
+                crit: false,
+                weapon: projectile.weapon,
+                kind: projectile.kind,
+            });
+            if let Some(inflicts) = inflicts {
+                status.write(ApplyStatus {
+                    target: other,
+                    effect: inflicts.effect,
+                    stacks: inflicts.stacks,
+                });
+            }
+
+            if let Some(zone) = zone {
+                let mut lingering = DamageZone::new(zone.dps, zone.seconds, projectile.hits);
+                if let Some(weapon) = projectile.weapon {
+                    lingering = lingering.with_weapon(weapon);
+                }
+                commands.spawn(damage_zone(
+                    &zone_assets,
+                    target.translation(),
+                    zone.radius,
+                    lingering,
+                ));
+            }
+
+            if let Some(split) = split {
+                let material = match projectile.hits {
+                    Faction::Enemy => assets.player_material.clone(),
+                    Faction::Player => assets.enemy_material.clone(),
+                };
+                let speed = velocity.linvel.length();
+                let forward = velocity.linvel.xz().normalize_or(Vec2::X);
+                let middle = (split.count as f32 - 1.0) / 2.0;
+                for i in 0..split.count {
+                    let dir = Vec2::from_angle((i as f32 - middle) * SPLIT_SPREAD).rotate(forward);
+                    let dir = Vec3::new(dir.x, 0.0, dir.y);
+                    let piece = pool.spawn(
+                        &mut commands,
+                        PoolKey::Projectile,
+                        projectile_bundle(
+                            assets.mesh.clone(),
+                            material.clone(),
+                            target.translation(),
+                            dir * speed,
+                            Projectile {
+                                damage: projectile.damage * SPLIT_DAMAGE,
+                                hits: projectile.hits,
+                                lifetime: Timer::from_seconds(SPLIT_LIFETIME, TimerMode::Once),
+                                owner: projectile.owner,
+                                weapon: projectile.weapon,
+                                kind: projectile.kind,
+                            },
+                        ),
+                    );
+                    commands.entity(piece).insert(SplitFrom(other));
+                }
+            }
+
+            if let Some(mut pierce) = pierce.filter(|pierce| pierce.0 > 0) {
+                pierce.0 -= 1;
+                continue;
+            }
+
+            let Some(mut bounce) = bounce.filter(|bounce| bounce.0 > 0) else {
+                commands.entity(projectile_entity).release();
+                continue;
+            };
+            let next = targets
+                .iter()
+                .filter(|(entity, faction, _)| *entity != other && **faction == projectile.hits)
+                .map(|(_, _, next)| (next.translation() - transform.translation).horizontal())
+                .filter(|to_next| to_next.length_squared() <= BOUNCE_RANGE * BOUNCE_RANGE)
+                .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+            let Some(to_next) = next else {
+                commands.entity(projectile_entity).release();
+                continue;
+            };
+            bounce.0 -= 1;
+            velocity.linvel = to_next.normalize_or_zero() * velocity.linvel.length();
+        }
+    }
+}
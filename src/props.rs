@@ -0,0 +1,215 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+use crate::combat::{DeathEvent, Health};
+use crate::physics::layers;
+use crate::pickups::{Pickup, PickupAssets, PickupKind, Rarity, pickup_bundle};
+use crate::rng::GameRng;
+use crate::run_config::RunConfig;
+use crate::toon::ToonMaterial;
+use crate::{GameplaySet, Player};
+
+const BREAK_SECONDS: f32 = 0.3;
+
+pub struct PropsPlugin;
+
+impl Plugin for PropsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_prop_assets);
+        app.add_systems(Update, (break_props, animate_breaking).in_set(GameplaySet));
+    }
+}
+
+/// Scenery that blocks movement and breaks when shot
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum Prop {
+    Crate,
+    Rock,
+    Tree,
+}
+
+impl Prop {
+    pub fn health(&self) -> f32 {
+        match self {
+            Prop::Crate => 10.0,
+            Prop::Rock => 60.0,
+            Prop::Tree => 30.0,
+        }
+    }
+
+    /// Chance, from 0 to 1, of dropping a pickup when broken
+    pub fn drop_chance(&self) -> f64 {
+        match self {
+            Prop::Crate => 0.35,
+            Prop::Rock => 0.05,
+            Prop::Tree => 0.1,
+        }
+    }
+}
+
+/// Projectiles with this pass through props instead of breaking on them
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Piercing;
+
+/// Shrinking and spinning away before despawning
+#[derive(Component)]
+struct Breaking {
+    timer: Timer,
+    scale: Vec3,
+}
+
+#[derive(Resource)]
+pub struct PropAssets {
+    pub crate_mesh: Handle<Mesh>,
+    pub crate_material: Handle<ToonMaterial>,
+    pub rock_mesh: Handle<Mesh>,
+    pub rock_material: Handle<ToonMaterial>,
+    pub trunk_mesh: Handle<Mesh>,
+    pub trunk_material: Handle<ToonMaterial>,
+    pub canopy_mesh: Handle<Mesh>,
+    pub canopy_material: Handle<ToonMaterial>,
+}
+
+fn setup_prop_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+) {
+    commands.insert_resource(PropAssets {
+        crate_mesh: meshes.add(Cuboid::from_length(1.5)),
+        crate_material: materials.add(ToonMaterial::from(Color::srgb(0.6, 0.4, 0.2))),
+        rock_mesh: meshes.add(Cuboid::from_length(1.0)),
+        rock_material: materials.add(ToonMaterial::from(Color::srgb(0.45, 0.43, 0.4))),
+        trunk_mesh: meshes.add(Cylinder::new(0.5, 4.0)),
+        trunk_material: materials.add(ToonMaterial::from(Color::srgb(0.4, 0.25, 0.15))),
+        canopy_mesh: meshes.add(Sphere::new(2.0)),
+        canopy_material: materials.add(ToonMaterial::from(Color::srgb(0.2, 0.45, 0.2))),
+    });
+}
+
+/// Spawn a random prop standing on the ground at `position`, relative to
+/// `parent`
+pub fn spawn_random_prop(
+    parent: &mut ChildSpawnerCommands,
+    assets: &PropAssets,
+    position: Vec3,
+    rng: &mut impl Rng,
+) {
+    let kind = match rng.random_range(0..3) {
+        0 => Prop::Crate,
+        1 => Prop::Rock,
+        _ => Prop::Tree,
+    };
+
+    let (mesh, material, scale, collider) = match kind {
+        Prop::Crate => (
+            assets.crate_mesh.clone(),
+            assets.crate_material.clone(),
+            Vec3::ONE,
+            Collider::cuboid(0.75, 0.75, 0.75),
+        ),
+        Prop::Rock => (
+            assets.rock_mesh.clone(),
+            assets.rock_material.clone(),
+            Vec3::new(
+                rng.random_range(1.0..3.0),
+                rng.random_range(1.0..2.5),
+                rng.random_range(1.0..3.0),
+            ),
+            Collider::cuboid(0.5, 0.5, 0.5),
+        ),
+        Prop::Tree => (
+            assets.trunk_mesh.clone(),
+            assets.trunk_material.clone(),
+            Vec3::ONE,
+            Collider::cylinder(2.0, 0.5),
+        ),
+    };
+    let height = match kind {
+        Prop::Crate => 1.5,
+        Prop::Rock => scale.y,
+        Prop::Tree => 4.0,
+    };
+
+    let mut prop = parent.spawn((
+        Mesh3d(mesh),
+        MeshMaterial3d(material),
+        Transform::from_translation(position + Vec3::Y * height / 2.0)
+            .with_rotation(Quat::from_rotation_y(rng.random_range(0.0..TAU)))
+            .with_scale(scale),
+        Name::new(format!("{kind:?}")),
+        kind,
+        Health::new(kind.health()),
+        collider,
+        layers::ground(),
+    ));
+    if kind == Prop::Tree {
+        prop.with_child((
+            Mesh3d(assets.canopy_mesh.clone()),
+            MeshMaterial3d(assets.canopy_material.clone()),
+            Transform::from_xyz(0.0, 2.5, 0.0),
+        ));
+    }
+}
+
+/// Swap broken props' colliders for the break animation and maybe drop loot
+fn break_props(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut deaths: EventReader<DeathEvent>,
+    props: Query<(&Prop, &GlobalTransform, &Transform)>,
+    (assets, config): (Res<PickupAssets>, Res<RunConfig>),
+) {
+    for death in deaths.read() {
+        let Ok((prop, global, transform)) = props.get(death.entity) else {
+            continue;
+        };
+
+        commands
+            .entity(death.entity)
+            .remove::<(Collider, Health)>()
+            .insert(Breaking {
+                timer: Timer::from_seconds(BREAK_SECONDS, TimerMode::Once),
+                scale: transform.scale,
+            });
+
+        if !rng.random_bool(prop.drop_chance()) {
+            continue;
+        }
+
+        let kind = match rng.random_range(0..4) {
+            0 if config.health_drops() => PickupKind::Heal(10.0),
+            1 => PickupKind::Gold(rng.random_range(1..=3)),
+            2 => PickupKind::Armor(25.0),
+            _ => PickupKind::Experience(5),
+        };
+        let pickup = Pickup {
+            kind,
+            rarity: Rarity::Common,
+        };
+        let position = global.translation().with_y(1.0);
+        commands.spawn(pickup_bundle(&assets, pickup, position));
+    }
+}
+
+fn animate_breaking(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut props: Query<(Entity, &mut Transform, &mut Breaking)>,
+) {
+    for (entity, mut transform, mut breaking) in props.iter_mut() {
+        if breaking.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let left = 1.0 - breaking.timer.fraction();
+        transform.scale = breaking.scale * left;
+        transform.rotate_y(TAU * 2.0 * time.delta_secs());
+    }
+}
@@ -0,0 +1,99 @@
+//! Registers gameplay components with the type registry, so the inspector
+//! can show and edit them
+//!
+//! Components whose plugin already registers them alongside its other setup
+//! stay there, this is for everything else. A new gameplay component should
+//! derive `Reflect` with `#[reflect(Component)]` and be added here
+
+use bevy::prelude::*;
+
+use crate::afterimage::Afterimage;
+use crate::ai::{ChasePlayer, KeepDistance, RangedAttack, Separation};
+use crate::animation::SpriteScale;
+use crate::aura::Aura;
+use crate::boss::{Boss, BossVolley};
+use crate::budget::Budgeted;
+use crate::combat::{GodMode, Invulnerable, InvulnerableOnHit};
+use crate::controls::DetachedCamera;
+use crate::corpses::Corpse;
+use crate::damage_zone::DamageZone;
+use crate::elites::{Elite, ExplodeOnDeath, Fast, SplitOnDeath, Tanky};
+use crate::hazards::{Hazard, HazardLifetime};
+use crate::level::Sun;
+use crate::particles::Particle;
+use crate::pets::Pet;
+use crate::pickups::PickupRadius;
+use crate::projectile::{Bounce, Homing, Pierce, Projectile, SplitOnHit, Trajectory, ZoneOnHit};
+use crate::props::{Piercing, Prop};
+use crate::status::InflictsStatus;
+use crate::telegraph::Telegraph;
+use crate::world::Chunk;
+use crate::{
+    BaseMoveSpeed, CameraDistance, CameraPullIn, CameraYaw, Enemy, Ground, IntendedRotation,
+    MainCamera, MoveSpeed, Player,
+};
+
+pub struct ReflectionPlugin;
+
+impl Plugin for ReflectionPlugin {
+    fn build(&self, app: &mut App) {
+        // players, enemies and moving
+        app.register_type::<Player>();
+        app.register_type::<Enemy>();
+        app.register_type::<Ground>();
+        app.register_type::<MoveSpeed>();
+        app.register_type::<BaseMoveSpeed>();
+        app.register_type::<IntendedRotation>();
+        app.register_type::<ChasePlayer>();
+        app.register_type::<KeepDistance>();
+        app.register_type::<RangedAttack>();
+        app.register_type::<Separation>();
+        app.register_type::<Boss>();
+        app.register_type::<BossVolley>();
+        app.register_type::<Elite>();
+        app.register_type::<Fast>();
+        app.register_type::<Tanky>();
+        app.register_type::<ExplodeOnDeath>();
+        app.register_type::<SplitOnDeath>();
+        app.register_type::<Pet>();
+        app.register_type::<Corpse>();
+
+        // camera
+        app.register_type::<MainCamera>();
+        app.register_type::<CameraDistance>();
+        app.register_type::<CameraYaw>();
+        app.register_type::<CameraPullIn>();
+        app.register_type::<DetachedCamera>();
+
+        // combat
+        app.register_type::<InvulnerableOnHit>();
+        app.register_type::<Invulnerable>();
+        app.register_type::<GodMode>();
+        app.register_type::<Aura>();
+        app.register_type::<Projectile>();
+        app.register_type::<Pierce>();
+        app.register_type::<Bounce>();
+        app.register_type::<SplitOnHit>();
+        app.register_type::<ZoneOnHit>();
+        app.register_type::<Trajectory>();
+        app.register_type::<Homing>();
+        app.register_type::<InflictsStatus>();
+        app.register_type::<DamageZone>();
+        app.register_type::<Telegraph>();
+        app.register_type::<Budgeted>();
+
+        // world
+        app.register_type::<Sun>();
+        app.register_type::<Chunk>();
+        app.register_type::<Prop>();
+        app.register_type::<Piercing>();
+        app.register_type::<Hazard>();
+        app.register_type::<HazardLifetime>();
+        app.register_type::<PickupRadius>();
+
+        // effects
+        app.register_type::<SpriteScale>();
+        app.register_type::<Particle>();
+        app.register_type::<Afterimage>();
+    }
+}
@@ -0,0 +1,296 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::{TimeSystem, TimeUpdateStrategy};
+use leafwing_input_manager::prelude::ActionState;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::cli::LaunchOptions;
+use crate::controls::{Action, AimDirection};
+use crate::rng::{GameRng, GameSeed, NextRunSeed};
+use crate::run_config::{RunConfig, RunModifier};
+use crate::spawner::{DifficultyCurve, DifficultyPreset};
+use crate::upgrades::{CHOICE_KEYS, OPTION_KEYS, UpgradeChosen};
+use crate::{GameState, GameplaySet, LocalPlayers, Player, PlayerSlot};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_replay);
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            (discard_fixed_overstep, restart_recording),
+        );
+        app.add_systems(
+            First,
+            play_back_frame_time
+                .before(TimeSystem)
+                .run_if(resource_exists::<ReplayPlayer>),
+        );
+        app.add_systems(
+            Update,
+            (
+                play_back_input
+                    .before(GameplaySet)
+                    .run_if(resource_exists::<ReplayPlayer>),
+                record_input
+                    .after(GameplaySet)
+                    .run_if(resource_exists::<ReplayRecorder>),
+            )
+                .run_if(in_state(GameState::InGame)),
+        );
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            write_recording.run_if(resource_exists::<ReplayRecorder>),
+        );
+        app.add_systems(
+            Last,
+            write_recording_on_exit.run_if(resource_exists::<ReplayRecorder>),
+        );
+    }
+}
+
+/// Everything needed to play a run back: its seed and, for every frame of
+/// it, how long the frame took and what the player was doing
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Replay {
+    pub seed: u64,
+    /// Local players the run was played with
+    #[serde(default = "one_player")]
+    pub players: usize,
+    /// Run modifiers the run was played with
+    #[serde(default)]
+    pub modifiers: Vec<RunModifier>,
+    /// Index into `CHARACTERS` of who was played
+    #[serde(default)]
+    pub character: usize,
+    #[serde(default)]
+    pub difficulty: DifficultyPreset,
+    pub frames: Vec<ReplayFrame>,
+}
+
+fn one_player() -> usize {
+    return 1;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayFrame {
+    /// Real time the frame took, played back exactly so physics takes the
+    /// same steps
+    pub delta: Duration,
+    /// What each player was pressing, by slot
+    pub pressed: Vec<Vec<Action>>,
+    /// How far each player pushed the movement stick, by slot
+    #[serde(default)]
+    pub movement: Vec<Vec2>,
+    /// Where each player was aiming, by slot
+    #[serde(default)]
+    pub aim: Vec<Option<Vec2>>,
+    /// Which level up choice or option was picked, if any
+    #[serde(default)]
+    pub upgrade: Option<usize>,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("Could not access replay file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse replay file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not serialize replay file: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+impl Replay {
+    pub fn load(path: &PathBuf) -> Result<Self, ReplayError> {
+        let text = std::fs::read_to_string(path)?;
+        return Ok(ron::from_str(&text)?);
+    }
+
+    pub fn write(&self, path: &PathBuf) -> Result<(), ReplayError> {
+        std::fs::write(path, ron::to_string(self)?)?;
+        return Ok(());
+    }
+}
+
+/// Records every frame of the current run, written out when it ends
+#[derive(Resource)]
+struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+/// Feeds a recorded run back in place of the player's input
+#[derive(Resource)]
+pub(crate) struct ReplayPlayer {
+    replay: Replay,
+    /// Next frame to play
+    frame: usize,
+}
+
+impl ReplayPlayer {
+    fn current(&self) -> Option<&ReplayFrame> {
+        return self.replay.frames.get(self.frame);
+    }
+}
+
+fn start_replay(
+    mut commands: Commands,
+    mut launch: ResMut<LaunchOptions>,
+    (mut local_players, mut config, mut curve): (
+        ResMut<LocalPlayers>,
+        ResMut<RunConfig>,
+        ResMut<DifficultyCurve>,
+    ),
+    (mut seed, mut rng, mut next_seed): (ResMut<GameSeed>, ResMut<GameRng>, ResMut<NextRunSeed>),
+) {
+    if let Some(path) = launch.record.clone() {
+        commands.insert_resource(ReplayRecorder {
+            path,
+            replay: Replay::default(),
+        });
+    }
+
+    let Some(path) = launch.replay.clone() else {
+        return;
+    };
+    match Replay::load(&path) {
+        Ok(replay) => {
+            info!("playing back {} frames", replay.frames.len());
+            // the replay only makes sense from the run it was recorded in
+            **next_seed = Some(replay.seed);
+            launch.skip_menu = true;
+            launch.players = Some(replay.players);
+            **local_players = replay.players;
+            config.modifiers = replay.modifiers.clone();
+            config.character = replay.character;
+            curve.preset = replay.difficulty;
+            // the world streamed in while loading is built from the seed too
+            *seed = GameSeed(replay.seed);
+            *rng = GameRng::from_seed(replay.seed);
+            commands.insert_resource(ReplayPlayer { replay, frame: 0 });
+        }
+        Err(e) => error!("{e}"),
+    }
+}
+
+/// Time left over from before the run would shift when physics steps
+/// happen, so runs always start from a clean fixed step
+fn discard_fixed_overstep(mut time: ResMut<Time<Fixed>>) {
+    let overstep = time.overstep();
+    time.discard_overstep(overstep);
+}
+
+fn restart_recording(
+    recorder: Option<ResMut<ReplayRecorder>>,
+    local_players: Res<LocalPlayers>,
+    (config, curve): (Res<RunConfig>, Res<DifficultyCurve>),
+) {
+    if let Some(mut recorder) = recorder {
+        recorder.replay.players = **local_players;
+        recorder.replay.modifiers = config.modifiers.clone();
+        recorder.replay.character = config.character;
+        recorder.replay.difficulty = curve.preset;
+        recorder.replay.frames.clear();
+    }
+}
+
+fn record_input(
+    time: Res<Time<Real>>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut upgrades: EventReader<UpgradeChosen>,
+    players: Query<(&PlayerSlot, &ActionState<Action>, &AimDirection), With<Player>>,
+) {
+    // players that died are kept in their slot with nothing pressed
+    let mut pressed = vec![Vec::new(); recorder.replay.players];
+    let mut movement = vec![Vec2::ZERO; recorder.replay.players];
+    let mut aim = vec![None; recorder.replay.players];
+    for (slot, action_state, direction) in players.iter() {
+        if let Some(actions) = pressed.get_mut(**slot) {
+            *actions = action_state.get_pressed();
+        }
+        if let Some(movement) = movement.get_mut(**slot) {
+            *movement = action_state.axis_pair(&Action::Move);
+        }
+        if let Some(aim) = aim.get_mut(**slot) {
+            *aim = **direction;
+        }
+    }
+    recorder.replay.frames.push(ReplayFrame {
+        delta: time.delta(),
+        pressed,
+        movement,
+        aim,
+        upgrade: upgrades.read().last().map(|chosen| chosen.index),
+    });
+}
+
+fn write_recording(mut recorder: ResMut<ReplayRecorder>, seed: Res<GameSeed>) {
+    recorder.replay.seed = **seed;
+    match recorder.replay.write(&recorder.path) {
+        Ok(()) => info!("replay written to {}", recorder.path.display()),
+        Err(e) => error!("{e}"),
+    }
+}
+
+/// Quitting mid run still keeps what was recorded
+fn write_recording_on_exit(
+    exit: EventReader<AppExit>,
+    state: Res<State<GameState>>,
+    recorder: ResMut<ReplayRecorder>,
+    seed: Res<GameSeed>,
+) {
+    if !exit.is_empty() && *state.get() == GameState::InGame {
+        write_recording(recorder, seed);
+    }
+}
+
+/// Frames before the run use the first frame's time, so the frame the run
+/// starts on takes as long as it did when recording
+fn play_back_frame_time(player: Res<ReplayPlayer>, mut strategy: ResMut<TimeUpdateStrategy>) {
+    *strategy = match player.current() {
+        Some(frame) => TimeUpdateStrategy::ManualDuration(frame.delta),
+        None => TimeUpdateStrategy::Automatic,
+    };
+}
+
+fn play_back_input(
+    mut commands: Commands,
+    mut replay: ResMut<ReplayPlayer>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut players: Query<(&PlayerSlot, &mut ActionState<Action>, &mut AimDirection), With<Player>>,
+) {
+    let Some(frame) = replay.current().cloned() else {
+        info!("replay finished");
+        commands.remove_resource::<ReplayPlayer>();
+        return;
+    };
+    replay.frame += 1;
+
+    for (slot, mut action_state, mut aim) in players.iter_mut() {
+        **aim = frame.aim.get(**slot).copied().flatten();
+        let movement = frame.movement.get(**slot).copied().unwrap_or_default();
+        action_state.set_axis_pair(&Action::Move, movement);
+        let pressed = frame.pressed.get(**slot);
+        for action in Action::ALL.iter() {
+            if pressed.is_some_and(|pressed| pressed.contains(action)) {
+                action_state.press(action);
+            } else {
+                action_state.release(action);
+            }
+        }
+    }
+
+    // clicked or not, upgrades are played back through their hotkeys
+    let mut menu_keys = CHOICE_KEYS.iter().chain(&OPTION_KEYS);
+    for key in menu_keys.clone() {
+        keys.release(*key);
+    }
+    if let Some(key) = frame.upgrade.and_then(|i| menu_keys.nth(i)) {
+        keys.press(*key);
+    }
+}
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+
+use crate::combat::{DamageEvent, DamageType, apply_damage};
+use crate::particles::{EmitParticles, ParticleEffect};
+use crate::platforms::launch;
+use crate::stats::{Stat, Stats};
+use crate::{Enemy, GameState, GameplaySet, Player, PlayerSlot};
+
+/// Fraction of max health a revive brings the player back with
+pub const REVIVE_HEALTH: f32 = 0.5;
+/// Seconds of invulnerability after a revive
+pub const REVIVE_INVULNERABILITY: f32 = 2.0;
+/// How far the shockwave of a revive clears enemies
+const SHOCKWAVE_RADIUS: f32 = 10.0;
+/// Enough to kill anything but a boss
+const SHOCKWAVE_DAMAGE: f32 = 1000.0;
+const SHOCKWAVE_KNOCK_UP: f32 = 30.0;
+const ICON_SIZE: f32 = 12.0;
+/// Where the revive icons start, just past the stamina bars
+const HUD_LEFT: f32 = 232.0;
+
+pub struct RevivePlugin;
+
+impl Plugin for RevivePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Revives>();
+        app.add_event::<Revived>();
+        app.add_systems(
+            Update,
+            (
+                apply_revive_stats.before(apply_damage),
+                revive_shockwaves.after(apply_damage),
+                update_revive_hud,
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Second winds left. Damage that would kill the player uses one up instead,
+/// see `apply_damage`
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Revives {
+    pub remaining: u8,
+    /// The `Revives` stat last time it was read, so raising it grants the
+    /// difference without refunding used ones
+    granted: u8,
+}
+
+impl Revives {
+    /// Use up a revive if there's one left
+    pub fn try_use(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        return true;
+    }
+}
+
+/// Sent when lethal damage used up a revive instead of killing its target
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Revived {
+    pub entity: Entity,
+}
+
+#[derive(Component)]
+struct ReviveHud {
+    player: Entity,
+}
+
+fn apply_revive_stats(mut query: Query<(&Stats, &mut Revives), Changed<Stats>>) {
+    for (stats, mut revives) in query.iter_mut() {
+        let granted = stats.get(Stat::Revives).clamp(0.0, u8::MAX as f32) as u8;
+        if granted == revives.granted {
+            continue;
+        }
+        let gained = granted.saturating_sub(revives.granted);
+        revives.remaining = revives.remaining.saturating_add(gained).min(granted);
+        revives.granted = granted;
+    }
+}
+
+/// Blow away and kill every enemy around a player who just came back
+fn revive_shockwaves(
+    mut commands: Commands,
+    mut revived: EventReader<Revived>,
+    players: Query<&Transform>,
+    enemies: Query<(Entity, &Transform), With<Enemy>>,
+    mut damage: EventWriter<DamageEvent>,
+    mut particles: EventWriter<EmitParticles>,
+) {
+    for event in revived.read() {
+        let Ok(transform) = players.get(event.entity) else {
+            continue;
+        };
+
+        for (enemy, enemy_transform) in enemies.iter() {
+            let distance_squared = enemy_transform
+                .translation
+                .distance_squared(transform.translation);
+            if distance_squared > SHOCKWAVE_RADIUS * SHOCKWAVE_RADIUS {
+                continue;
+            }
+            launch(&mut commands, enemy, SHOCKWAVE_KNOCK_UP);
+            damage.write(DamageEvent {
+                target: enemy,
+                amount: SHOCKWAVE_DAMAGE,
+                source: Some(event.entity),
+                crit: false,
+                weapon: None,
+                kind: DamageType::Arcane,
+            });
+        }
+        particles.write(EmitParticles {
+            effect: ParticleEffect::Shockwave,
+            position: transform.translation,
+        });
+    }
+}
+
+/// A heart for each revive left, beside each player's stamina bar
+fn update_revive_hud(
+    mut commands: Commands,
+    players: Query<(Entity, &PlayerSlot, &Revives), (With<Player>, Changed<Revives>)>,
+    huds: Query<(Entity, &ReviveHud)>,
+    all_players: Query<(), With<Player>>,
+) {
+    for (hud, link) in huds.iter() {
+        if !all_players.contains(link.player) {
+            commands.entity(hud).despawn();
+        }
+    }
+
+    for (player, slot, revives) in players.iter() {
+        let hud = match huds.iter().find(|(_, link)| link.player == player) {
+            Some((hud, _)) => {
+                commands.entity(hud).despawn_related::<Children>();
+                hud
+            }
+            None => commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Px(16.0 + **slot as f32 * (ICON_SIZE + 8.0)),
+                        left: Val::Px(HUD_LEFT),
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ReviveHud { player },
+                    Name::new(format!("Revives {}", **slot + 1)),
+                    StateScoped(GameState::InGame),
+                ))
+                .id(),
+        };
+
+        commands.entity(hud).with_children(|hud| {
+            for _ in 0..revives.remaining {
+                hud.spawn((
+                    Node {
+                        width: Val::Px(ICON_SIZE),
+                        height: Val::Px(ICON_SIZE),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(1.0, 0.3, 0.4)),
+                    BorderRadius::MAX,
+                ));
+            }
+        });
+    }
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::GameState;
+
+/// Overrides the random seed, for reproducing a run
+const SEED_ENV_VAR: &str = "BUNVIVOR_SEED";
+
+pub struct RngPlugin {
+    /// Takes priority over the environment variable
+    pub seed: Option<u64>,
+}
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let picked = picked_seed(self.seed);
+        let seed = picked.unwrap_or_else(rand::random);
+        app.insert_resource(GameSeed(seed));
+        app.insert_resource(GameRng::from_seed(seed));
+        // the menu starts out with it typed in, and runs that skip the
+        // menu play it straight away
+        app.insert_resource(NextRunSeed(picked));
+        app.add_systems(OnEnter(GameState::InGame), seed_run);
+    }
+}
+
+/// The seed `GameRng` was created from
+#[derive(Resource, Deref, Debug, Clone, Copy)]
+pub struct GameSeed(pub u64);
+
+/// A seed picked for the next run, at launch, from the main menu or by
+/// retrying one, used once and then cleared
+#[derive(Resource, Deref, DerefMut, Debug, Clone, Copy, Default)]
+pub struct NextRunSeed(pub Option<u64>);
+
+/// Seeded RNG for gameplay rolls, so a run can be replayed from its `GameSeed`
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(StdRng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        return Self(StdRng::seed_from_u64(seed));
+    }
+}
+
+/// `seed` if given, then the environment variable
+fn picked_seed(seed: Option<u64>) -> Option<u64> {
+    return seed.or_else(|| std::env::var(SEED_ENV_VAR).ok()?.parse().ok());
+}
+
+/// Every run gets a fresh seed, unless one was picked for it, so the same
+/// seed always plays out the same way no matter what came before it
+///
+/// The launch seed only picks the first run's, or fills in the menu's seed
+/// field, so typing another one or retrying a run still takes
+fn seed_run(mut next: ResMut<NextRunSeed>, mut seed: ResMut<GameSeed>, mut rng: ResMut<GameRng>) {
+    let new_seed = next.take().unwrap_or_else(rand::random);
+    info!("run seed: {new_seed}");
+    *seed = GameSeed(new_seed);
+    *rng = GameRng::from_seed(new_seed);
+}
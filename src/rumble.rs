@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::InputMap;
+use serde::{Deserialize, Serialize};
+
+use crate::boss::Boss;
+use crate::combat::{DamageEvent, apply_damage};
+use crate::controls::Action;
+use crate::experience::LevelUp;
+use crate::save::SaveData;
+use crate::{GameplaySet, Player};
+
+pub struct RumblePlugin;
+
+impl Plugin for RumblePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                rumble_on_damage.after(apply_damage),
+                rumble_on_boss,
+                rumble_on_level_up,
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// How hard controllers rumble, picked in the main menu and saved with the
+/// other settings
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RumbleStrength {
+    Off,
+    Low,
+    #[default]
+    High,
+}
+
+impl RumbleStrength {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RumbleStrength::Off => "Off",
+            RumbleStrength::Low => "Low",
+            RumbleStrength::High => "High",
+        }
+    }
+
+    /// The next one up, wrapping back round to off
+    pub fn next(&self) -> Self {
+        match self {
+            RumbleStrength::Off => RumbleStrength::Low,
+            RumbleStrength::Low => RumbleStrength::High,
+            RumbleStrength::High => RumbleStrength::Off,
+        }
+    }
+
+    fn scale(&self) -> f32 {
+        match self {
+            RumbleStrength::Off => 0.0,
+            RumbleStrength::Low => 0.5,
+            RumbleStrength::High => 1.0,
+        }
+    }
+}
+
+/// A single burst of rumble, before the strength setting scales it
+#[derive(Debug, Clone, Copy)]
+struct Pulse {
+    strong_motor: f32,
+    weak_motor: f32,
+    seconds: f32,
+}
+
+const DAMAGE_PULSE: Pulse = Pulse {
+    strong_motor: 0.4,
+    weak_motor: 0.6,
+    seconds: 0.15,
+};
+const BOSS_PULSE: Pulse = Pulse {
+    strong_motor: 1.0,
+    weak_motor: 1.0,
+    seconds: 0.8,
+};
+const LEVEL_UP_PULSE: Pulse = Pulse {
+    strong_motor: 0.0,
+    weak_motor: 0.3,
+    seconds: 0.08,
+};
+
+/// Rumble the gamepad a player is using, if they have one and rumble is on
+fn rumble(
+    requests: &mut EventWriter<GamepadRumbleRequest>,
+    save: &SaveData,
+    input_map: &InputMap<Action>,
+    pulse: Pulse,
+) {
+    let scale = save.settings.rumble.scale();
+    if scale <= 0.0 {
+        return;
+    }
+    // players without a gamepad are given the placeholder, see `assign_gamepads`
+    let Some(gamepad) = input_map.gamepad() else {
+        return;
+    };
+    if gamepad == Entity::PLACEHOLDER {
+        return;
+    }
+
+    requests.write(GamepadRumbleRequest::Add {
+        gamepad,
+        intensity: GamepadRumbleIntensity {
+            strong_motor: pulse.strong_motor * scale,
+            weak_motor: pulse.weak_motor * scale,
+        },
+        duration: Duration::from_secs_f32(pulse.seconds),
+    });
+}
+
+/// Only damage that got through, ignored hits leave a zero amount behind
+fn rumble_on_damage(
+    save: Res<SaveData>,
+    mut damage: EventReader<DamageEvent>,
+    players: Query<&InputMap<Action>, With<Player>>,
+    mut requests: EventWriter<GamepadRumbleRequest>,
+) {
+    for event in damage.read() {
+        if event.amount <= 0.0 {
+            continue;
+        }
+        let Ok(input_map) = players.get(event.target) else {
+            continue;
+        };
+        rumble(&mut requests, &save, input_map, DAMAGE_PULSE);
+    }
+}
+
+fn rumble_on_boss(
+    save: Res<SaveData>,
+    bosses: Query<(), Added<Boss>>,
+    players: Query<&InputMap<Action>, With<Player>>,
+    mut requests: EventWriter<GamepadRumbleRequest>,
+) {
+    if bosses.is_empty() {
+        return;
+    }
+    for input_map in players.iter() {
+        rumble(&mut requests, &save, input_map, BOSS_PULSE);
+    }
+}
+
+fn rumble_on_level_up(
+    save: Res<SaveData>,
+    mut level_ups: EventReader<LevelUp>,
+    players: Query<&InputMap<Action>, With<Player>>,
+    mut requests: EventWriter<GamepadRumbleRequest>,
+) {
+    for event in level_ups.read() {
+        let Ok(input_map) = players.get(event.entity) else {
+            continue;
+        };
+        rumble(&mut requests, &save, input_map, LEVEL_UP_PULSE);
+    }
+}
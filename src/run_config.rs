@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::characters::{CHARACTERS, Character};
+use crate::move_speed::MoveSpeedModifiers;
+use crate::pickups::PickupKind;
+use crate::prefab_library::PrefabName;
+use crate::spawner::DifficultyKey;
+use crate::{Enemy, GameplaySet};
+
+/// How much more often enemies spawn, and how many more can be alive, with
+/// `RunModifier::Horde`
+const HORDE_MULTIPLIER: f32 = 1.5;
+/// Enemy speed multiplier with `RunModifier::Frenzy`
+const FRENZY_SPEED: f32 = 1.3;
+/// Source of the speed modifier `RunModifier::Frenzy` gives enemies
+const FRENZY_SOURCE: &str = "Frenzy";
+
+pub struct RunConfigPlugin;
+
+impl Plugin for RunConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<RunConfig>();
+        app.init_resource::<RunConfig>();
+        app.add_systems(Update, speed_up_enemies.in_set(GameplaySet));
+    }
+}
+
+/// Optional ways to make a run harder, each paying out more gold and
+/// experience for it
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunModifier {
+    /// More enemies
+    Horde,
+    /// Faster enemies
+    Frenzy,
+    /// No health drops
+    Famine,
+}
+
+impl RunModifier {
+    pub const ALL: [RunModifier; 3] =
+        [RunModifier::Horde, RunModifier::Frenzy, RunModifier::Famine];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunModifier::Horde => "Horde",
+            RunModifier::Frenzy => "Frenzy",
+            RunModifier::Famine => "Famine",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            RunModifier::Horde => "More enemies",
+            RunModifier::Frenzy => "Faster enemies",
+            RunModifier::Famine => "No health drops",
+        }
+    }
+
+    /// Multiplier on gold and experience picked up while it's on
+    pub fn reward_multiplier(&self) -> f32 {
+        match self {
+            RunModifier::Horde => 1.25,
+            RunModifier::Frenzy => 1.2,
+            RunModifier::Famine => 1.15,
+        }
+    }
+}
+
+/// How the next run is set up, picked in the main menu before it starts
+#[derive(Resource, Reflect, Default, Debug, Clone)]
+#[reflect(Resource)]
+pub struct RunConfig {
+    /// Index into `CHARACTERS`
+    pub character: usize,
+    pub modifiers: Vec<RunModifier>,
+}
+
+impl RunConfig {
+    pub fn character(&self) -> &'static Character {
+        return CHARACTERS.get(self.character).unwrap_or(&CHARACTERS[0]);
+    }
+
+    pub fn has(&self, modifier: RunModifier) -> bool {
+        return self.modifiers.contains(&modifier);
+    }
+
+    pub fn toggle(&mut self, modifier: RunModifier) {
+        if self.has(modifier) {
+            self.modifiers.retain(|m| *m != modifier);
+        } else {
+            self.modifiers.push(modifier);
+        }
+    }
+
+    /// Every modifier's reward multiplier together
+    pub fn reward_multiplier(&self) -> f32 {
+        return self
+            .modifiers
+            .iter()
+            .map(|modifier| modifier.reward_multiplier())
+            .product();
+    }
+
+    /// `kind` with its gold or experience scaled by `reward_multiplier`
+    pub fn reward(&self, kind: PickupKind) -> PickupKind {
+        let scale = |amount: u32| (amount as f32 * self.reward_multiplier()).round() as u32;
+        match kind {
+            PickupKind::Experience(amount) => PickupKind::Experience(scale(amount)),
+            PickupKind::Gold(amount) => PickupKind::Gold(scale(amount)),
+            kind => kind,
+        }
+    }
+
+    /// `key` sampled from the difficulty curve, made harder by the modifiers
+    pub fn difficulty(&self, mut key: DifficultyKey) -> DifficultyKey {
+        if self.has(RunModifier::Horde) {
+            key.spawn_interval /= HORDE_MULTIPLIER;
+            key.max_alive = (key.max_alive as f32 * HORDE_MULTIPLIER).round() as usize;
+        }
+        return key;
+    }
+
+    /// Whether health pickups should drop at all
+    pub fn health_drops(&self) -> bool {
+        return !self.has(RunModifier::Famine);
+    }
+}
+
+/// Enemies are pooled, but `PrefabName` is put back on every spawn, so this
+/// catches new and reused ones alike
+fn speed_up_enemies(
+    config: Res<RunConfig>,
+    mut enemies: Query<&mut MoveSpeedModifiers, (Added<PrefabName>, With<Enemy>)>,
+) {
+    if !config.has(RunModifier::Frenzy) {
+        return;
+    }
+    for mut modifiers in enemies.iter_mut() {
+        modifiers.insert(FRENZY_SOURCE, FRENZY_SPEED, None);
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::boss::Boss;
+use crate::combat::{DamageEvent, DeathEvent, apply_damage};
+use crate::pickups::{PickupCollected, PickupKind};
+use crate::prefab_library::PrefabName;
+use crate::spawner::{DifficultyCurve, DifficultyPreset};
+use crate::weapons::WeaponKind;
+use crate::{Enemy, GameState, GameplaySet, Player};
+
+/// Seconds of hits a weapon's recent DPS is averaged over
+const DPS_WINDOW_SECONDS: f32 = 5.0;
+
+pub struct RunStatsPlugin;
+
+impl Plugin for RunStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<RunStats>();
+        app.register_type::<WeaponStats>();
+        app.init_resource::<RunStats>();
+        app.add_systems(OnEnter(GameState::InGame), reset_run_stats);
+        app.add_systems(
+            Update,
+            (
+                track_survival_time,
+                track_damage.after(apply_damage),
+                track_kills.after(apply_damage),
+                track_pickups,
+                track_distance,
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// What happened during the current (or last) run
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct RunStats {
+    pub difficulty: DifficultyPreset,
+    pub survived_seconds: f32,
+    pub weapons: HashMap<WeaponKind, WeaponStats>,
+    pub damage_taken: f32,
+    /// By prefab name
+    pub kills: HashMap<String, u32>,
+    pub boss_kills: u32,
+    pub pickups_collected: u32,
+    pub gold_collected: u32,
+    pub distance_traveled: f32,
+    /// Where each player was last frame
+    #[reflect(ignore)]
+    last_positions: HashMap<Entity, Vec3>,
+}
+
+impl RunStats {
+    pub fn total_kills(&self) -> u32 {
+        return self.kills.values().sum::<u32>() + self.boss_kills;
+    }
+
+    pub fn total_damage_dealt(&self) -> f32 {
+        return self.weapons.values().map(|weapon| weapon.damage).sum();
+    }
+
+    /// Every weapon that's dealt damage, most damage first
+    pub fn weapons_by_damage(&self) -> Vec<(WeaponKind, &WeaponStats)> {
+        let mut weapons: Vec<_> = self
+            .weapons
+            .iter()
+            .map(|(kind, stats)| (*kind, stats))
+            .collect();
+        weapons.sort_by(|a, b| b.1.damage.total_cmp(&a.1.damage));
+        return weapons;
+    }
+}
+
+/// How much one weapon has hit for over the run
+#[derive(Reflect, Debug, Default, Clone)]
+pub struct WeaponStats {
+    pub damage: f32,
+    pub hits: u32,
+    pub crits: u32,
+    /// When and for how much it hit within the last `DPS_WINDOW_SECONDS`,
+    /// by `RunStats::survived_seconds`
+    #[reflect(ignore)]
+    recent: VecDeque<(f32, f32)>,
+}
+
+impl WeaponStats {
+    /// Fraction of hits that crit
+    pub fn crit_rate(&self) -> f32 {
+        if self.hits == 0 {
+            return 0.0;
+        }
+        return self.crits as f32 / self.hits as f32;
+    }
+
+    /// Damage per second over a whole run lasting `seconds`
+    pub fn average_dps(&self, seconds: f32) -> f32 {
+        return self.damage / seconds.max(1.0);
+    }
+
+    /// Damage per second over the last `DPS_WINDOW_SECONDS` up to `now`
+    pub fn recent_dps(&self, now: f32) -> f32 {
+        let damage: f32 = self
+            .recent
+            .iter()
+            .filter(|(time, _)| *time > now - DPS_WINDOW_SECONDS)
+            .map(|(_, amount)| amount)
+            .sum();
+        return damage / DPS_WINDOW_SECONDS;
+    }
+
+    fn record_hit(&mut self, now: f32, amount: f32, crit: bool) {
+        self.damage += amount;
+        self.hits += 1;
+        if crit {
+            self.crits += 1;
+        }
+        self.recent.push_back((now, amount));
+        while self
+            .recent
+            .front()
+            .is_some_and(|(time, _)| *time <= now - DPS_WINDOW_SECONDS)
+        {
+            self.recent.pop_front();
+        }
+    }
+}
+
+fn reset_run_stats(mut commands: Commands, curve: Res<DifficultyCurve>) {
+    commands.insert_resource(RunStats {
+        difficulty: curve.preset,
+        ..default()
+    });
+}
+
+fn track_survival_time(time: Res<Time>, mut stats: ResMut<RunStats>) {
+    stats.survived_seconds += time.delta_secs();
+}
+
+fn track_damage(
+    mut damage: EventReader<DamageEvent>,
+    players: Query<(), With<Player>>,
+    enemies: Query<(), With<Enemy>>,
+    mut stats: ResMut<RunStats>,
+) {
+    for event in damage.read() {
+        if players.contains(event.target) {
+            stats.damage_taken += event.amount;
+        } else if let (Some(weapon), true) = (event.weapon, enemies.contains(event.target)) {
+            // ignored hits didn't land, so they don't count as hits either
+            if event.amount <= 0.0 {
+                continue;
+            }
+            let now = stats.survived_seconds;
+            let weapon = stats.weapons.entry(weapon).or_default();
+            weapon.record_hit(now, event.amount, event.crit);
+        }
+    }
+}
+
+fn track_kills(
+    mut deaths: EventReader<DeathEvent>,
+    enemies: Query<(Option<&PrefabName>, Has<Boss>), With<Enemy>>,
+    mut stats: ResMut<RunStats>,
+) {
+    for death in deaths.read() {
+        match enemies.get(death.entity) {
+            Ok((_, true)) => stats.boss_kills += 1,
+            Ok((Some(name), false)) => *stats.kills.entry(name.0.clone()).or_default() += 1,
+            _ => {}
+        }
+    }
+}
+
+fn track_pickups(mut collected: EventReader<PickupCollected>, mut stats: ResMut<RunStats>) {
+    for event in collected.read() {
+        stats.pickups_collected += 1;
+        if let PickupKind::Gold(amount) = event.pickup.kind {
+            stats.gold_collected += amount;
+        }
+    }
+}
+
+/// Distance is summed over every player
+fn track_distance(players: Query<(Entity, &Transform), With<Player>>, mut stats: ResMut<RunStats>) {
+    for (entity, transform) in players.iter() {
+        let position = transform.translation;
+        if let Some(last) = stats.last_positions.insert(entity, position) {
+            stats.distance_traveled += last.xz().distance(position.xz());
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::controls::CameraSettings;
+use crate::graphics::{DisplayMode, FrameCap, GraphicsQuality};
+use crate::input_device::InputDevicePreference;
+use crate::rumble::RumbleStrength;
+use crate::spawner::DifficultyPreset;
+
+/// Bump whenever `SaveData` changes shape, and teach `migrate` the old one
+pub const SAVE_VERSION: u32 = 3;
+const SAVE_FILE_NAME: &str = "save.ron";
+const APP_DIR_NAME: &str = "bunvivor";
+/// Where saves lived before they moved to the data dir
+const LEGACY_SAVE_PATH: &str = "save.ron";
+/// How many of the best runs are kept
+const BEST_RUNS: usize = 50;
+pub const DEFAULT_CHARACTER: &str = "Bun";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveData::load());
+        app.add_systems(Last, write_save.run_if(resource_changed::<SaveData>));
+    }
+}
+
+/// Everything that outlives a run, written back to disk whenever it changes
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    pub gold: u32,
+    /// Purchased level of each shop item, by name
+    pub shop: BTreeMap<String, u32>,
+    pub unlocks: BTreeSet<String>,
+    /// Ids of the tutorial prompts already shown
+    pub tutorials_seen: BTreeSet<String>,
+    pub settings: SettingsSnapshot,
+    /// Longest survived first
+    pub best_runs: Vec<RunRecord>,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            gold: 0,
+            shop: BTreeMap::new(),
+            unlocks: BTreeSet::new(),
+            tutorials_seen: BTreeSet::new(),
+            settings: SettingsSnapshot::default(),
+            best_runs: Vec::new(),
+        }
+    }
+}
+
+/// Settings as they were when last saved
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SettingsSnapshot {
+    pub master_volume: f32,
+    pub display_mode: DisplayMode,
+    /// Windowed size, `None` to keep whatever the window opened at
+    pub resolution: Option<(u32, u32)>,
+    pub vsync: bool,
+    pub frame_cap: FrameCap,
+    pub graphics_quality: GraphicsQuality,
+    pub rumble: RumbleStrength,
+    /// Which device prompts are shown for
+    pub input_device: InputDevicePreference,
+    pub accessibility: AccessibilitySettings,
+    pub camera: CameraSettings,
+}
+
+impl Default for SettingsSnapshot {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            display_mode: DisplayMode::default(),
+            resolution: None,
+            vsync: true,
+            frame_cap: FrameCap::default(),
+            graphics_quality: GraphicsQuality::default(),
+            rumble: RumbleStrength::default(),
+            input_device: InputDevicePreference::default(),
+            accessibility: AccessibilitySettings::default(),
+            camera: CameraSettings::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub survived_seconds: f32,
+    pub level: u32,
+    pub kills: u32,
+    pub gold: u32,
+    /// Who was played, runs from before characters were recorded count as
+    /// the default one
+    #[serde(default = "default_character")]
+    pub character: String,
+    /// What the run was seeded with, for replaying it with `--seed`
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Runs from before presets were recorded were played on normal
+    #[serde(default)]
+    pub difficulty: DifficultyPreset,
+}
+
+fn default_character() -> String {
+    return DEFAULT_CHARACTER.to_string();
+}
+
+/// The first save layout, from before saves were versioned
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SaveV1 {
+    gold: u32,
+    shop: BTreeMap<String, u32>,
+}
+
+impl From<SaveV1> for SaveData {
+    fn from(v1: SaveV1) -> Self {
+        Self {
+            gold: v1.gold,
+            shop: v1.shop,
+            ..default()
+        }
+    }
+}
+
+/// Just enough of any save to tell which layout it uses
+#[derive(Deserialize)]
+struct VersionProbe {
+    /// Unversioned saves are version 1
+    #[serde(default = "first_version")]
+    version: u32,
+}
+
+fn first_version() -> u32 {
+    return 1;
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("Could not access save file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse save file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not serialize save file: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Save file is version {0}, newer than this build understands")]
+    TooNew(u32),
+    #[error("No data directory for this platform")]
+    NoDataDir,
+}
+
+impl SaveData {
+    /// The platform's per-user data directory
+    fn dir() -> Result<PathBuf, SaveError> {
+        let env = |key| std::env::var_os(key).map(PathBuf::from);
+        let base = if cfg!(target_os = "windows") {
+            env("APPDATA")
+        } else if cfg!(target_os = "macos") {
+            env("HOME").map(|home| home.join("Library/Application Support"))
+        } else {
+            env("XDG_DATA_HOME").or_else(|| env("HOME").map(|home| home.join(".local/share")))
+        };
+        return base
+            .map(|base| base.join(APP_DIR_NAME))
+            .ok_or(SaveError::NoDataDir);
+    }
+
+    pub fn path() -> Result<PathBuf, SaveError> {
+        return Ok(Self::dir()?.join(SAVE_FILE_NAME));
+    }
+
+    /// Read the save file, starting fresh if there isn't a readable one
+    pub fn load() -> Self {
+        match Self::try_load() {
+            Ok(save) => save,
+            Err(SaveError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                error!("{e}, starting a fresh save");
+                Self::back_up_unreadable();
+                Self::default()
+            }
+        }
+    }
+
+    /// Move an unreadable save aside so the fresh one doesn't overwrite it
+    fn back_up_unreadable() {
+        let Ok(path) = Self::path() else {
+            return;
+        };
+
+        if path.exists() {
+            if let Err(e) = std::fs::rename(&path, path.with_extension("ron.bak")) {
+                error!("Could not back up unreadable save: {e}");
+            }
+        }
+    }
+
+    fn try_load() -> Result<Self, SaveError> {
+        let text = match std::fs::read_to_string(Self::path()?) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                std::fs::read_to_string(LEGACY_SAVE_PATH)?
+            }
+            result => result?,
+        };
+        return Self::migrate(&text);
+    }
+
+    /// Parse a save of any known version into the current layout
+    pub fn migrate(text: &str) -> Result<Self, SaveError> {
+        let probe: VersionProbe = ron::from_str(text)?;
+        let save = match probe.version {
+            1 => ron::from_str::<SaveV1>(text)?.into(),
+            // version 3 only added settings, which fall back to their defaults
+            2 | SAVE_VERSION => ron::from_str(text)?,
+            newer => return Err(SaveError::TooNew(newer)),
+        };
+        return Ok(Self {
+            version: SAVE_VERSION,
+            ..save
+        });
+    }
+
+    pub fn write(&self) -> Result<(), SaveError> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        // write then rename so a crash mid-write can't truncate the save
+        let tmp = path.with_extension("ron.tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(tmp, path)?;
+        return Ok(());
+    }
+
+    /// Keep `record` if it's among the best runs
+    pub fn record_run(&mut self, record: RunRecord) {
+        self.best_runs.push(record);
+        self.best_runs
+            .sort_by(|a, b| b.survived_seconds.total_cmp(&a.survived_seconds));
+        self.best_runs.truncate(BEST_RUNS);
+    }
+
+    /// Set the setting `field` picks out to `value`, only touching the save
+    /// when it differs, or loading the setting would write it straight back
+    pub fn update_setting<T: PartialEq>(
+        save: &mut ResMut<Self>,
+        field: impl FnOnce(&mut SettingsSnapshot) -> &mut T,
+        value: T,
+    ) {
+        save.reborrow()
+            .map_unchanged(|save| field(&mut save.settings))
+            .set_if_neq(value);
+    }
+}
+
+fn write_save(save: Res<SaveData>) {
+    if let Err(e) = save.write() {
+        error!("{e}");
+    }
+}
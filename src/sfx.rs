@@ -0,0 +1,343 @@
+use bevy::audio::{GlobalVolume, SpatialScale, Volume};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::animation::AnimationFrameEvent;
+use crate::combat::{DamageEvent, DeathEvent, apply_damage};
+use crate::experience::LevelUp;
+use crate::loading::optional_asset_exists;
+use crate::pickups::PickupCollected;
+use crate::platforms::SurfaceProperties;
+use crate::{CollidedGrounds, Enemy, GameplaySet, MainCamera, Player};
+
+/// Sounds playing at once, past this new ones are dropped
+const MAX_SOUNDS: usize = 24;
+/// Copies of the same sound playing at once, so one kind of sound can't
+/// take up every slot
+const MAX_SAME_SOUND: usize = 6;
+/// World units to audio units, lower makes far away sounds quieter
+const SPATIAL_SCALE: f32 = 0.15;
+/// Distance between the listener's ears, in world units
+const EAR_GAP: f32 = 4.0;
+/// Ground a player has to cover between footsteps, in world units, so
+/// running in place against a wall stays quiet
+const MIN_STRIDE: f32 = 0.5;
+
+pub struct SfxPlugin;
+
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>();
+        app.add_systems(
+            Startup,
+            load_sfx.run_if(resource_exists::<Assets<AudioSource>>),
+        );
+        app.add_systems(
+            Update,
+            (
+                enemy_death_sfx.after(apply_damage),
+                player_hurt_sfx.after(apply_damage),
+                level_up_sfx,
+                pickup_sfx,
+                attach_footsteps,
+                footstep_sfx,
+                animation_sfx,
+            )
+                .in_set(GameplaySet),
+        );
+        // headless runs load sounds all the same but have nothing to play them
+        app.add_systems(
+            Update,
+            (attach_listener, play_sfx)
+                .run_if(resource_exists::<SfxAssets>.and(resource_exists::<GlobalVolume>)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    EnemyDeath,
+    PlayerHurt,
+    LevelUp,
+    Pickup,
+    Footstep,
+    FootstepIce,
+    FootstepMud,
+    FootstepMetal,
+    WitchAttack,
+}
+
+impl Sfx {
+    const ALL: [Sfx; 9] = [
+        Sfx::EnemyDeath,
+        Sfx::PlayerHurt,
+        Sfx::LevelUp,
+        Sfx::Pickup,
+        Sfx::Footstep,
+        Sfx::FootstepIce,
+        Sfx::FootstepMud,
+        Sfx::FootstepMetal,
+        Sfx::WitchAttack,
+    ];
+
+    fn path(&self) -> &'static str {
+        match self {
+            Sfx::EnemyDeath => "sounds/enemy_death.ogg",
+            Sfx::PlayerHurt => "sounds/player_hurt.ogg",
+            Sfx::LevelUp => "sounds/level_up.ogg",
+            Sfx::Pickup => "sounds/pickup.ogg",
+            Sfx::Footstep => "sounds/footstep.ogg",
+            Sfx::FootstepIce => "sounds/footstep_ice.ogg",
+            Sfx::FootstepMud => "sounds/footstep_mud.ogg",
+            Sfx::FootstepMetal => "sounds/footstep_metal.ogg",
+            Sfx::WitchAttack => "sounds/witch_attack.ogg",
+        }
+    }
+
+    fn volume(&self) -> f32 {
+        match self {
+            Sfx::EnemyDeath => 0.5,
+            Sfx::PlayerHurt => 0.9,
+            Sfx::LevelUp => 0.8,
+            Sfx::Pickup => 0.4,
+            Sfx::Footstep | Sfx::FootstepIce | Sfx::FootstepMud | Sfx::FootstepMetal => 0.3,
+            Sfx::WitchAttack => 0.6,
+        }
+    }
+
+    /// The footstep for walking on `surface`, told apart the same way
+    /// surface patches pick their material
+    fn footstep(surface: &SurfaceProperties) -> Self {
+        if surface.conveyor != Vec3::ZERO {
+            return Sfx::FootstepMetal;
+        } else if surface.speed < 1.0 {
+            return Sfx::FootstepMud;
+        } else if surface.acceleration < 1.0 {
+            return Sfx::FootstepIce;
+        }
+        return Sfx::Footstep;
+    }
+}
+
+/// Play a sound, from `position` in the world if it has one, otherwise
+/// straight into both ears
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlaySfx {
+    pub sfx: Sfx,
+    pub position: Option<Vec3>,
+}
+
+/// Sounds aren't needed to play, so they aren't part of loading and a
+/// missing one is never loaded and just stays quiet
+#[derive(Resource)]
+pub struct SfxAssets(HashMap<Sfx, Handle<AudioSource>>);
+
+impl SfxAssets {
+    pub fn handles(&self) -> impl Iterator<Item = &Handle<AudioSource>> {
+        return self.0.values();
+    }
+}
+
+/// A sound that's playing, despawned along with its sink once it finishes
+#[derive(Component)]
+struct SfxInstance(Sfx);
+
+/// Ground a player has covered since their last footstep
+#[derive(Component, Default)]
+struct Footsteps {
+    travelled: f32,
+}
+
+fn load_sfx(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let sounds = Sfx::ALL
+        .into_iter()
+        .filter(|sfx| optional_asset_exists(sfx.path()))
+        .map(|sfx| (sfx, asset_server.load(sfx.path())))
+        .collect();
+    commands.insert_resource(SfxAssets(sounds));
+}
+
+/// The level respawns its camera on reload, so new ones get ears too
+fn attach_listener(mut commands: Commands, cameras: Query<Entity, Added<MainCamera>>) {
+    for camera in cameras.iter() {
+        commands
+            .entity(camera)
+            .insert(SpatialListener::new(EAR_GAP));
+    }
+}
+
+fn play_sfx(
+    mut commands: Commands,
+    mut requests: EventReader<PlaySfx>,
+    sounds: Res<SfxAssets>,
+    playing: Query<&SfxInstance>,
+) {
+    let mut counts: HashMap<Sfx, usize> = HashMap::new();
+    for instance in playing.iter() {
+        *counts.entry(instance.0).or_default() += 1;
+    }
+    let mut total = playing.iter().len();
+
+    for request in requests.read() {
+        let count = counts.entry(request.sfx).or_default();
+        if total >= MAX_SOUNDS || *count >= MAX_SAME_SOUND {
+            continue;
+        }
+        let Some(sound) = sounds.0.get(&request.sfx) else {
+            continue;
+        };
+        *count += 1;
+        total += 1;
+
+        let settings = PlaybackSettings::DESPAWN
+            .with_volume(Volume::Linear(request.sfx.volume()))
+            .with_spatial(request.position.is_some())
+            .with_spatial_scale(SpatialScale::new(SPATIAL_SCALE));
+        commands.spawn((
+            AudioPlayer(sound.clone()),
+            settings,
+            Transform::from_translation(request.position.unwrap_or_default()),
+            SfxInstance(request.sfx),
+            Name::new("Sfx"),
+        ));
+    }
+}
+
+fn enemy_death_sfx(
+    mut deaths: EventReader<DeathEvent>,
+    enemies: Query<&Transform, With<Enemy>>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for death in deaths.read() {
+        let Ok(transform) = enemies.get(death.entity) else {
+            continue;
+        };
+        sfx.write(PlaySfx {
+            sfx: Sfx::EnemyDeath,
+            position: Some(transform.translation),
+        });
+    }
+}
+
+/// Only damage that got through, ignored hits leave a zero amount behind
+fn player_hurt_sfx(
+    mut damage: EventReader<DamageEvent>,
+    players: Query<&Transform, With<Player>>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for event in damage.read() {
+        if event.amount <= 0.0 {
+            continue;
+        }
+        let Ok(transform) = players.get(event.target) else {
+            continue;
+        };
+        sfx.write(PlaySfx {
+            sfx: Sfx::PlayerHurt,
+            position: Some(transform.translation),
+        });
+    }
+}
+
+fn level_up_sfx(mut level_ups: EventReader<LevelUp>, mut sfx: EventWriter<PlaySfx>) {
+    for _ in level_ups.read() {
+        sfx.write(PlaySfx {
+            sfx: Sfx::LevelUp,
+            position: None,
+        });
+    }
+}
+
+fn pickup_sfx(
+    mut collected: EventReader<PickupCollected>,
+    players: Query<&Transform, With<Player>>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for event in collected.read() {
+        let Ok(transform) = players.get(event.collector) else {
+            continue;
+        };
+        sfx.write(PlaySfx {
+            sfx: Sfx::Pickup,
+            position: Some(transform.translation),
+        });
+    }
+}
+
+fn attach_footsteps(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for player in players.iter() {
+        commands.entity(player).insert(Footsteps::default());
+    }
+}
+
+/// Footsteps land on the run cycle's footfall frame, as long as the player
+/// got somewhere since the last one
+fn footstep_sfx(
+    time: Res<Time>,
+    mut frames: EventReader<AnimationFrameEvent>,
+    mut players: Query<
+        (
+            &Transform,
+            &Velocity,
+            Option<&CollidedGrounds>,
+            &mut Footsteps,
+        ),
+        With<Player>,
+    >,
+    surfaces: Query<&SurfaceProperties>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for (_, velocity, _, mut footsteps) in players.iter_mut() {
+        footsteps.travelled += velocity.linvel.xz().length() * time.delta_secs();
+    }
+
+    for event in frames.read() {
+        if (event.clip.as_ref(), event.frame) != ("player_run", 2) {
+            continue;
+        }
+        let Ok((transform, _, grounds, mut footsteps)) = players.get_mut(event.entity) else {
+            continue;
+        };
+        // no footsteps while running through the air
+        let Some(grounds) = grounds.filter(|grounds| !grounds.is_empty()) else {
+            continue;
+        };
+        if footsteps.travelled < MIN_STRIDE {
+            continue;
+        }
+        footsteps.travelled = 0.0;
+
+        let surface = grounds
+            .iter()
+            .find_map(|&ground| surfaces.get(ground).ok())
+            .copied()
+            .unwrap_or_default();
+        sfx.write(PlaySfx {
+            sfx: Sfx::footstep(&surface),
+            position: Some(transform.translation),
+        });
+    }
+}
+
+/// Witches are heard winding up before their projectile flies
+fn animation_sfx(
+    mut frames: EventReader<AnimationFrameEvent>,
+    enemies: Query<&Transform, With<Enemy>>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for event in frames.read() {
+        match (event.clip.as_ref(), event.frame) {
+            ("witch_attack", 10) => {
+                let Ok(transform) = enemies.get(event.entity) else {
+                    continue;
+                };
+                sfx.write(PlaySfx {
+                    sfx: Sfx::WitchAttack,
+                    position: Some(transform.translation),
+                });
+            }
+            _ => {}
+        }
+    }
+}
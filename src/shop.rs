@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+
+use crate::GameplaySet;
+use crate::pickups::{PickupCollected, PickupKind};
+use crate::save::SaveData;
+use crate::stats::{ModifierOp, Stat, StatModifier, Stats};
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, bank_gold.in_set(GameplaySet));
+    }
+}
+
+/// A permanent upgrade bought with gold between runs
+pub struct ShopItem {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub stat: Stat,
+    /// Applied once per purchased level
+    pub per_level: ModifierOp,
+    pub max_level: u32,
+    pub base_cost: u32,
+}
+
+pub const SHOP_ITEMS: &[ShopItem] = &[
+    ShopItem {
+        name: "Vitality",
+        description: "+10 max health",
+        stat: Stat::MaxHealth,
+        per_level: ModifierOp::Add(10.0),
+        max_level: 5,
+        base_cost: 20,
+    },
+    ShopItem {
+        name: "Swiftness",
+        description: "+5% move speed",
+        stat: Stat::MoveSpeed,
+        per_level: ModifierOp::Multiply(1.05),
+        max_level: 3,
+        base_cost: 30,
+    },
+    ShopItem {
+        name: "Might",
+        description: "+10% damage",
+        stat: Stat::Damage,
+        per_level: ModifierOp::Add(0.1),
+        max_level: 5,
+        base_cost: 25,
+    },
+    ShopItem {
+        name: "Magnetism",
+        description: "+0.5 pickup radius",
+        stat: Stat::PickupRadius,
+        per_level: ModifierOp::Add(0.5),
+        max_level: 4,
+        base_cost: 15,
+    },
+];
+
+impl ShopItem {
+    pub fn level(&self, save: &SaveData) -> u32 {
+        return save.shop.get(self.name).copied().unwrap_or(0);
+    }
+
+    /// Cost of the next level, `None` once maxed
+    pub fn cost(&self, save: &SaveData) -> Option<u32> {
+        let level = self.level(save);
+        if level >= self.max_level {
+            return None;
+        }
+        return Some(self.base_cost * (level + 1));
+    }
+
+    /// Spend gold on the next level, returns whether it was bought
+    pub fn purchase(&self, save: &mut SaveData) -> bool {
+        let Some(cost) = self.cost(save).filter(|cost| *cost <= save.gold) else {
+            return false;
+        };
+
+        save.gold -= cost;
+        *save.shop.entry(self.name.to_string()).or_default() += 1;
+        return true;
+    }
+}
+
+/// Add the modifiers for every purchased shop level
+pub fn apply_purchases(stats: &mut Stats, save: &SaveData) {
+    for item in SHOP_ITEMS {
+        for _ in 0..item.level(save) {
+            stats.add_modifier(StatModifier {
+                source: item.name.into(),
+                stat: item.stat,
+                op: item.per_level,
+            });
+        }
+    }
+}
+
+/// Collected gold goes straight into the persistent wallet
+fn bank_gold(mut collected: EventReader<PickupCollected>, mut save: ResMut<SaveData>) {
+    for event in collected.read() {
+        if let PickupKind::Gold(amount) = event.pickup.kind {
+            save.gold += amount;
+        }
+    }
+}
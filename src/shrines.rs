@@ -0,0 +1,241 @@
+//! Shrines scattered over generated terrain, each a challenge to take on.
+//! Interact with one and swarms come for whoever stands in the ring around
+//! it, hold the ring until time runs out for a chest
+//!
+//! The swarms come from the director, the same enemies at the same health the
+//! run is spawning at that point, only all sent at the shrine. Each shrine
+//! can be tried once a run, win or lose
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::animation::AnimationLibrary;
+use crate::announcements::Announcement;
+use crate::interaction::{Interactable, Interacted};
+use crate::pickups::{Pickup, PickupAssets, PickupKind, Rarity, pickup_bundle};
+use crate::pool::Pool;
+use crate::prefab_library::PrefabLibrary;
+use crate::rng::GameRng;
+use crate::spawner::{DifficultyCurve, WaveDirector, spawn_swarm};
+use crate::{AuthoritySet, GameSet, GameState, GameplaySet, Player};
+
+/// How long the ring has to be held
+const CHALLENGE_SECONDS: f32 = 30.0;
+const RING_RADIUS: f32 = 12.0;
+/// A swarm comes in this often while the challenge lasts, the first as it
+/// starts
+const WAVE_SECONDS: f32 = 10.0;
+const WAVE_SIZE: usize = 12;
+/// Swarms start this far from the shrine, outside the ring
+const WAVE_DISTANCE: f32 = 25.0;
+/// Upgrades in the chest for holding the ring
+const REWARD_CHEST_SIZE: u32 = 3;
+const RING_COLOR: Color = Color::srgb(0.7, 0.4, 1.0);
+
+pub struct ShrinesPlugin;
+
+impl Plugin for ShrinesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Shrine>();
+        app.register_type::<ShrineChallenge>();
+        app.init_resource::<SpentShrines>();
+        app.add_systems(PreStartup, setup_shrine_assets);
+        app.add_systems(OnEnter(GameState::InGame), reset_spent_shrines);
+        app.add_systems(
+            Update,
+            (
+                (start_challenges, run_challenges)
+                    .chain()
+                    .in_set(AuthoritySet)
+                    .in_set(GameSet::Spawn),
+                (hide_spent_shrines, draw_challenge_rings).in_set(GameplaySet),
+            ),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct ShrineAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+/// Starts a challenge when interacted with
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Shrine {
+    /// The chunk it was generated in, which is how it's remembered once
+    /// used, since the chunk makes a new one every time it streams back in
+    pub chunk: IVec2,
+}
+
+/// A shrine's challenge, while it's underway
+#[derive(Component, Reflect, Debug, Clone, Default)]
+#[reflect(Component)]
+pub struct ShrineChallenge {
+    /// Seconds the ring has been held for
+    pub elapsed: f32,
+    /// Swarms sent so far
+    pub waves: usize,
+}
+
+/// Chunks whose shrine has been used this run
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct SpentShrines(HashSet<IVec2>);
+
+fn setup_shrine_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(ShrineAssets {
+        mesh: meshes.add(Cylinder::new(0.6, 2.0)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.45, 0.3, 0.6),
+            emissive: LinearRgba::rgb(0.8, 0.4, 1.2),
+            ..default()
+        }),
+    });
+}
+
+/// A shrine standing on the ground at `position`, generated in `chunk`
+pub fn shrine(assets: &ShrineAssets, chunk: IVec2, position: Vec3) -> impl Bundle {
+    (
+        Mesh3d(assets.mesh.clone()),
+        MeshMaterial3d(assets.material.clone()),
+        Transform::from_translation(position + Vec3::Y),
+        Name::new("Shrine"),
+        Shrine { chunk },
+        Interactable::new("Start challenge"),
+    )
+}
+
+fn reset_spent_shrines(mut spent: ResMut<SpentShrines>) {
+    spent.clear();
+}
+
+/// A chunk streaming back in brings its shrine back, which stays used up
+fn hide_spent_shrines(
+    mut commands: Commands,
+    spent: Res<SpentShrines>,
+    shrines: Query<(Entity, &Shrine), (Added<Shrine>, Without<ShrineChallenge>)>,
+) {
+    for (entity, shrine) in shrines.iter() {
+        if spent.contains(&shrine.chunk) {
+            commands.entity(entity).remove::<Interactable>();
+        }
+    }
+}
+
+fn start_challenges(
+    mut commands: Commands,
+    mut interacted: EventReader<Interacted>,
+    shrines: Query<&Shrine, Without<ShrineChallenge>>,
+    mut spent: ResMut<SpentShrines>,
+    mut announcements: EventWriter<Announcement>,
+) {
+    for event in interacted.read() {
+        let Ok(shrine) = shrines.get(event.target) else {
+            continue;
+        };
+        if !spent.insert(shrine.chunk) {
+            continue;
+        }
+
+        commands
+            .entity(event.target)
+            .remove::<Interactable>()
+            .insert(ShrineChallenge::default());
+        announcements.write(Announcement {
+            text: "Hold the ring!".into(),
+        });
+    }
+}
+
+/// Send swarms at the shrine while anyone's in the ring, failing the
+/// challenge once nobody is and paying out a chest once time's up
+fn run_challenges(
+    mut commands: Commands,
+    (time, director, curve): (Res<Time>, Res<WaveDirector>, Res<DifficultyCurve>),
+    (library, animations, pickup_assets, mut pool): (
+        Res<PrefabLibrary>,
+        Res<Assets<AnimationLibrary>>,
+        Res<PickupAssets>,
+        ResMut<Pool>,
+    ),
+    mut shrines: Query<(Entity, &GlobalTransform, &mut ShrineChallenge)>,
+    players: Query<&Transform, With<Player>>,
+    mut rng: ResMut<GameRng>,
+    mut announcements: EventWriter<Announcement>,
+) {
+    for (entity, transform, mut challenge) in shrines.iter_mut() {
+        // the shrine stands on the ground, the ring goes round its foot
+        let center = transform.translation() - Vec3::Y;
+        let held = players.iter().any(|player| {
+            return (player.translation - center).xz().length() <= RING_RADIUS;
+        });
+        if !held {
+            commands.entity(entity).remove::<ShrineChallenge>();
+            announcements.write(Announcement {
+                text: "The shrine falls silent...".into(),
+            });
+            continue;
+        }
+
+        challenge.elapsed += time.delta_secs();
+        if challenge.elapsed >= CHALLENGE_SECONDS {
+            commands.entity(entity).remove::<ShrineChallenge>();
+            commands.spawn(pickup_bundle(
+                &pickup_assets,
+                Pickup {
+                    kind: PickupKind::Chest(REWARD_CHEST_SIZE),
+                    rarity: Rarity::Rare,
+                },
+                center + Vec3::new(0.0, 0.5, 2.0),
+            ));
+            announcements.write(Announcement {
+                text: "The shrine rewards you".into(),
+            });
+            continue;
+        }
+
+        if challenge.waves as f32 * WAVE_SECONDS > challenge.elapsed {
+            continue;
+        }
+        challenge.waves += 1;
+
+        let difficulty = curve.sample(director.minute());
+        let Some(prefab) = difficulty.enemy_mix.pick(&mut **rng) else {
+            continue;
+        };
+        let angle = rng.random_range(0.0..TAU);
+        spawn_swarm(
+            &mut commands,
+            &mut pool,
+            (&library, &animations),
+            (prefab, WAVE_SIZE),
+            center + Vec3::new(angle.cos(), 0.0, angle.sin()) * WAVE_DISTANCE,
+            difficulty.health_multiplier,
+            &mut **rng,
+        );
+    }
+}
+
+fn draw_challenge_rings(
+    shrines: Query<&GlobalTransform, With<ShrineChallenge>>,
+    mut gizmos: Gizmos,
+) {
+    for transform in shrines.iter() {
+        gizmos.circle(
+            Isometry3d::new(
+                transform.translation() - Vec3::Y * 0.9,
+                Quat::from_rotation_x(FRAC_PI_2),
+            ),
+            RING_RADIUS,
+            RING_COLOR,
+        );
+    }
+}
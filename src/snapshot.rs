@@ -0,0 +1,231 @@
+use std::path::{Path, PathBuf};
+
+use bevy::ecs::entity::EntityHashMap;
+use bevy::ecs::system::RegisteredSystemError;
+use bevy::prelude::*;
+use bevy::scene::ron;
+use bevy::scene::serde::SceneDeserializer;
+use bevy_rapier3d::prelude::Velocity;
+use serde::de::DeserializeSeed;
+use thiserror::Error;
+
+use crate::animation::AnimationLibrary;
+use crate::combat::Health;
+use crate::experience::Experience;
+use crate::pickups::{Pickup, PickupAssets, pickup_bundle};
+use crate::pool::{Pool, ReleaseCommandsExt};
+use crate::prefab_library::{PrefabLibrary, PrefabName, spawn_prefab};
+use crate::stats::Stats;
+use crate::weapons::Weapons;
+use crate::{Enemy, Player, PlayerSlot};
+
+/// Where snapshots are written, relative to the working directory
+pub const SNAPSHOT_DIR: &str = "snapshots";
+const SNAPSHOT_EXTENSION: &str = "scn.ron";
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Could not access snapshot: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not serialize snapshot: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Could not parse snapshot: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not restore snapshot: {0}")]
+    Restore(#[from] bevy::scene::SceneSpawnError),
+    #[error("Could not restore the snapshot's enemies and pickups: {0}")]
+    Field(#[from] RegisteredSystemError<FieldInput>),
+}
+
+/// The prefabs and pickups read out of a snapshot, for `restore_field`
+type FieldInput = In<(Vec<(String, Vec3, Option<Health>)>, Vec<(Pickup, Vec3)>)>;
+
+/// Write the players, enemies and pickups to a new file in `SNAPSHOT_DIR`
+///
+/// Only what makes up a situation is kept (where everything is, health,
+/// levels, stats and weapons) since meshes and physics bodies are rebuilt
+/// on load anyway
+pub fn save_snapshot(world: &mut World) -> Result<PathBuf, SnapshotError> {
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(With<Player>, With<Enemy>, With<Pickup>)>>()
+        .iter(world)
+        .collect();
+
+    let scene = DynamicSceneBuilder::from_world(world)
+        .deny_all()
+        .allow_component::<Transform>()
+        .allow_component::<Health>()
+        .allow_component::<PlayerSlot>()
+        .allow_component::<Experience>()
+        .allow_component::<Stats>()
+        .allow_component::<Weapons>()
+        .allow_component::<PrefabName>()
+        .allow_component::<Pickup>()
+        .extract_entities(entities.into_iter())
+        .build();
+
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let text = scene.serialize(&registry)?;
+
+    std::fs::create_dir_all(SNAPSHOT_DIR)?;
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let path = Path::new(SNAPSHOT_DIR).join(format!("snapshot-{seconds}.{SNAPSHOT_EXTENSION}"));
+    std::fs::write(&path, text)?;
+    return Ok(path);
+}
+
+/// Every snapshot in `SNAPSHOT_DIR`, newest first
+pub fn list_snapshots() -> Vec<PathBuf> {
+    let Ok(dir) = std::fs::read_dir(SNAPSHOT_DIR) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<PathBuf> = dir
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.to_string_lossy().ends_with(SNAPSHOT_EXTENSION))
+        .collect();
+    // named by time, so this sorts them by age
+    snapshots.sort();
+    snapshots.reverse();
+    return snapshots;
+}
+
+/// Put the world back the way `path` has it: players are moved and given
+/// back their health, levels and weapons, and every enemy and pickup is
+/// swapped for the ones in the snapshot
+///
+/// Elite affixes and statuses aren't kept, enemies come back plain, and
+/// bosses are left out altogether
+pub fn load_snapshot(world: &mut World, path: &Path) -> Result<(), SnapshotError> {
+    let text = std::fs::read_to_string(path)?;
+    let scene = {
+        let registry = world.resource::<AppTypeRegistry>().read();
+        let mut deserializer = ron::de::Deserializer::from_str(&text)?;
+        SceneDeserializer {
+            type_registry: &registry,
+        }
+        .deserialize(&mut deserializer)
+        .map_err(|e| deserializer.span_error(e))?
+    };
+
+    // the snapshot's entities come in bare, and are only read from to set up
+    // the real ones
+    let mut entity_map = EntityHashMap::default();
+    scene.write_to_world(world, &mut entity_map)?;
+
+    let mut prefabs = Vec::new();
+    let mut pickups = Vec::new();
+    for &restored in entity_map.values() {
+        let mut entity = world.entity_mut(restored);
+        let translation = entity
+            .get::<Transform>()
+            .map_or(Vec3::ZERO, |transform| transform.translation);
+
+        // prefab pickups are restored from their prefab, like enemies
+        if let Some(name) = entity.get::<PrefabName>() {
+            let health = entity.get::<Health>().copied();
+            prefabs.push((name.0.clone(), translation, health));
+        } else if let Some(&pickup) = entity.get::<Pickup>() {
+            pickups.push((pickup, translation));
+        } else if let Some(&slot) = entity.get::<PlayerSlot>() {
+            let health = entity.take::<Health>();
+            let experience = entity.take::<Experience>();
+            let stats = entity.take::<Stats>();
+            let weapons = entity.take::<Weapons>();
+            restore_player(
+                world,
+                slot,
+                translation,
+                (health, experience, stats, weapons),
+            );
+        }
+
+        world.entity_mut(restored).despawn();
+    }
+
+    world.run_system_cached_with(restore_field, (prefabs, pickups))?;
+    return Ok(());
+}
+
+fn restore_player(
+    world: &mut World,
+    slot: PlayerSlot,
+    translation: Vec3,
+    (health, experience, stats, weapons): (
+        Option<Health>,
+        Option<Experience>,
+        Option<Stats>,
+        Option<Weapons>,
+    ),
+) {
+    let player = world
+        .query_filtered::<(Entity, &PlayerSlot), With<Player>>()
+        .iter(world)
+        .find(|(_, live)| **live == slot)
+        .map(|(player, _)| player);
+    let Some(player) = player else {
+        warn!("Snapshot has player {} but this run doesn't", *slot + 1);
+        return;
+    };
+
+    let mut player = world.entity_mut(player);
+    if let Some(mut transform) = player.get_mut::<Transform>() {
+        transform.translation = translation;
+    }
+    if let Some(mut velocity) = player.get_mut::<Velocity>() {
+        *velocity = Velocity::zero();
+    }
+    if let Some(health) = health {
+        player.insert(health);
+    }
+    if let Some(experience) = experience {
+        player.insert(experience);
+    }
+    if let Some(stats) = stats {
+        player.insert(stats);
+    }
+    if let Some(weapons) = weapons {
+        player.insert(weapons);
+    }
+}
+
+/// Swap every enemy and pickup for the snapshot's
+fn restore_field(
+    In((prefabs, pickups)): FieldInput,
+    mut commands: Commands,
+    mut pool: ResMut<Pool>,
+    (library, animations): (Res<PrefabLibrary>, Res<Assets<AnimationLibrary>>),
+    pickup_assets: Res<PickupAssets>,
+    existing: Query<Entity, Or<(With<Enemy>, With<Pickup>)>>,
+) {
+    for entity in existing.iter() {
+        commands.entity(entity).release();
+    }
+
+    // what the enemies roll on spawn is overwritten by the snapshot, so it
+    // doesn't matter that it isn't the seeded rng
+    let mut rng = rand::rng();
+    for (name, translation, health) in prefabs {
+        let Some(entity) = spawn_prefab(
+            &mut commands,
+            &mut pool,
+            (&library, &animations),
+            &name,
+            translation,
+            1.0,
+            &mut rng,
+        ) else {
+            warn!("Snapshot has a {name} but there's no such prefab");
+            continue;
+        };
+        if let Some(health) = health {
+            commands.entity(entity).insert(health);
+        }
+    }
+
+    for (pickup, translation) in pickups {
+        commands.spawn(pickup_bundle(&pickup_assets, pickup, translation));
+    }
+}
@@ -0,0 +1,140 @@
+use std::marker::PhantomData;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::GameplaySet;
+
+/// Keeps a `SpatialGrid<T>` of every entity with `T`, rebuilt at the start of
+/// each frame of a run
+pub struct SpatialGridPlugin<T> {
+    /// Should be about the largest radius the grid is queried with
+    pub cell_size: f32,
+    pub marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> SpatialGridPlugin<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Component> Plugin for SpatialGridPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SpatialGrid::<T>::new(self.cell_size));
+        app.add_systems(
+            Update,
+            rebuild_grid::<T>.in_set(SpatialSet).in_set(GameplaySet),
+        );
+    }
+}
+
+/// Rebuilds every `SpatialGrid`, systems querying one should run after it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpatialSet;
+
+/// A uniform grid over the XZ plane bucketing entities with `T` by position
+///
+/// Neighbour queries only visit the cells overlapping the query radius,
+/// so proximity checks stay roughly linear in the number of entities
+#[derive(Resource)]
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> SpatialGrid<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        // keep the allocations of recently used cells around, the grid is
+        // rebuilt every frame and entities mostly stay in the same cells
+        self.cells.retain(|_, cell| !cell.is_empty());
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, pos: Vec2) {
+        let cell = self.cell_of(pos);
+        self.cells.entry(cell).or_default().push((entity, pos));
+    }
+
+    /// Every entity within `radius` of `pos`, along with its position
+    pub fn within_radius(&self, pos: Vec2, radius: f32) -> impl Iterator<Item = (Entity, Vec2)> {
+        let min = self.cell_of(pos - Vec2::splat(radius));
+        let max = self.cell_of(pos + Vec2::splat(radius));
+        let radius_sq = radius * radius;
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, other)| other.distance_squared(pos) <= radius_sq)
+    }
+
+    /// The entity closest to `pos` within `max_radius`, along with its
+    /// position
+    ///
+    /// Searches outwards ring by ring, stopping as soon as no further ring
+    /// could hold anything closer
+    pub fn nearest(&self, pos: Vec2, max_radius: f32) -> Option<(Entity, Vec2)> {
+        let center = self.cell_of(pos);
+        let max_ring = (max_radius / self.cell_size).ceil() as i32 + 1;
+        let mut best: Option<(Entity, Vec2, f32)> = None;
+
+        for ring in 0..=max_ring {
+            for cell in ring_cells(center, ring) {
+                for &(entity, other) in self.cells.get(&cell).into_iter().flatten() {
+                    let dist_sq = other.distance_squared(pos);
+                    if dist_sq <= max_radius * max_radius
+                        && best.is_none_or(|(_, _, best)| dist_sq < best)
+                    {
+                        best = Some((entity, other, dist_sq));
+                    }
+                }
+            }
+
+            // everything in the next ring is at least this far away
+            let reach = ring as f32 * self.cell_size;
+            if best.is_some_and(|(_, _, best)| best <= reach * reach) {
+                break;
+            }
+        }
+
+        return best.map(|(entity, other, _)| (entity, other));
+    }
+
+    fn cell_of(&self, pos: Vec2) -> IVec2 {
+        return (pos / self.cell_size).floor().as_ivec2();
+    }
+}
+
+/// The cells exactly `ring` steps out from `center`, the center itself for 0
+fn ring_cells(center: IVec2, ring: i32) -> impl Iterator<Item = IVec2> {
+    (-ring..=ring)
+        .flat_map(move |x| (-ring..=ring).map(move |y| IVec2::new(x, y)))
+        .filter(move |offset| offset.x.abs() == ring || offset.y.abs() == ring)
+        .map(move |offset| center + offset)
+}
+
+pub fn rebuild_grid<T: Component>(
+    mut grid: ResMut<SpatialGrid<T>>,
+    query: Query<(Entity, &Transform), With<T>>,
+) {
+    grid.clear();
+    for (entity, transform) in query.iter() {
+        grid.insert(entity, transform.translation.xz());
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::animation::AnimationLibrary;
+use crate::announcements::Announcement;
+use crate::balance::{BalanceConfig, StragglerPolicy};
+use crate::boss::{self, Boss};
+use crate::combat::{DamageTakenMultiplier, Health};
+use crate::elites::roll_elite;
+use crate::enemies::EnemyAssets;
+use crate::loading::GameAssets;
+use crate::pool::{Pool, ReleaseCommandsExt};
+use crate::prefab_library::{PrefabLibrary, spawn_prefab};
+use crate::rng::GameRng;
+use crate::ron_asset::RonAssetLoader;
+use crate::run_config::RunConfig;
+use crate::{AuthoritySet, Enemy, GameSet, GameState, GameplaySet, Player, PlayerSlot};
+
+/// Enemies appear on a ring this far from the player, just off screen
+pub const SPAWN_RING_RADIUS: f32 = 45.0;
+/// How far either side of a player's heading relocated stragglers land
+const STRAGGLER_SPREAD: f32 = std::f32::consts::FRAC_PI_4;
+/// How far from the middle of a swarm its enemies start
+const SWARM_RADIUS: f32 = 6.0;
+/// Shortest time between spawns, whatever the curve says
+const MIN_SPAWN_INTERVAL: f32 = 0.01;
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DifficultyCurve>();
+        app.register_asset_loader(RonAssetLoader::<DifficultyCurve>::new(&["curve.ron"]));
+        app.init_asset::<RunTimeline>();
+        app.register_asset_loader(RonAssetLoader::<RunTimeline>::new(&["timeline.ron"]));
+        app.register_type::<DifficultyCurve>();
+        app.init_resource::<DifficultyCurve>();
+        app.insert_resource(WaveDirector::default());
+        app.add_systems(OnEnter(GameState::InGame), reset_director);
+        app.add_systems(
+            Update,
+            (
+                // keeps syncing outside of runs so no reload events are missed
+                sync_difficulty_curve,
+                (tick_director, handle_stragglers, spawn_waves, run_timeline)
+                    .chain()
+                    .after(sync_difficulty_curve)
+                    .in_set(AuthoritySet)
+                    .in_set(GameSet::Spawn),
+                scale_damage_taken.in_set(GameplaySet),
+            ),
+        );
+    }
+}
+
+/// Decides when and where enemies enter the run
+#[derive(Resource)]
+pub struct WaveDirector {
+    /// Seconds since the run started
+    pub elapsed: f32,
+    pub spawn_timer: Timer,
+    /// Spawns owed for refunded stragglers, made on top of the timer
+    refunds: usize,
+    /// Bosses brought in by the timeline so far
+    bosses: usize,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            elapsed: 0.0,
+            spawn_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            refunds: 0,
+            bosses: 0,
+        }
+    }
+}
+
+impl WaveDirector {
+    pub fn minute(&self) -> f32 {
+        return self.elapsed / 60.0;
+    }
+}
+
+fn reset_director(mut director: ResMut<WaveDirector>) {
+    *director = WaveDirector::default();
+}
+
+/// How hard the run is at a given point in time
+///
+/// Loaded from `assets/difficulty.curve.ron` and hot reloaded, but also
+/// editable live from the inspector as a resource. Values are linearly
+/// interpolated between keys, and held past the first and last key
+#[derive(Asset, Resource, Reflect, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct DifficultyCurve {
+    pub keys: Vec<DifficultyKey>,
+    /// Picked in the main menu rather than loaded, and kept across reloads
+    #[serde(skip)]
+    pub preset: DifficultyPreset,
+}
+
+/// Scales the whole difficulty curve up or down, and the damage players take
+#[derive(Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl DifficultyPreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "Easy",
+            DifficultyPreset::Normal => "Normal",
+            DifficultyPreset::Hard => "Hard",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DifficultyPreset::Easy => DifficultyPreset::Normal,
+            DifficultyPreset::Normal => DifficultyPreset::Hard,
+            DifficultyPreset::Hard => DifficultyPreset::Easy,
+        }
+    }
+
+    /// Multiplier on enemy health
+    pub fn health_multiplier(&self) -> f32 {
+        match self {
+            DifficultyPreset::Easy => 0.75,
+            DifficultyPreset::Normal => 1.0,
+            DifficultyPreset::Hard => 1.4,
+        }
+    }
+
+    /// Multiplier on damage players take
+    pub fn damage_multiplier(&self) -> f32 {
+        match self {
+            DifficultyPreset::Easy => 0.6,
+            DifficultyPreset::Normal => 1.0,
+            DifficultyPreset::Hard => 1.5,
+        }
+    }
+
+    /// Multiplier on how often enemies spawn and how many can be alive
+    pub fn spawn_multiplier(&self) -> f32 {
+        match self {
+            DifficultyPreset::Easy => 0.75,
+            DifficultyPreset::Normal => 1.0,
+            DifficultyPreset::Hard => 1.3,
+        }
+    }
+}
+
+#[derive(Reflect, Deserialize, Debug, Clone, PartialEq)]
+pub struct DifficultyKey {
+    pub minute: f32,
+    /// Seconds between spawns
+    pub spawn_interval: f32,
+    pub max_alive: usize,
+    pub enemy_mix: EnemyMix,
+    pub health_multiplier: f32,
+    /// Chance, from 0 to 1, of a spawn rolling elite affixes
+    pub elite_chance: f32,
+}
+
+/// Relative odds of each enemy prefab, by name, being picked for a spawn
+///
+/// Ordered so the same seed picks the same enemies every run
+#[derive(Reflect, Deserialize, Debug, Clone, PartialEq, Deref)]
+#[serde(transparent)]
+pub struct EnemyMix(pub BTreeMap<String, f32>);
+
+impl EnemyMix {
+    /// Prefabs only one side has count as having no odds on the other
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut mix = BTreeMap::new();
+        for name in self.keys().chain(other.keys()) {
+            let from = self.get(name).copied().unwrap_or(0.0);
+            let to = other.get(name).copied().unwrap_or(0.0);
+            mix.insert(name.clone(), from.lerp(to, t));
+        }
+        return Self(mix);
+    }
+
+    /// The prefab to spawn, or `None` for an empty mix
+    pub fn pick(&self, rng: &mut impl Rng) -> Option<&str> {
+        let total: f32 = self.values().map(|odds| odds.max(0.0)).sum();
+        if total <= 0.0 {
+            return self.keys().next().map(String::as_str);
+        }
+
+        let mut roll = rng.random_range(0.0..total);
+        for (name, odds) in self.iter() {
+            roll -= odds.max(0.0);
+            if roll < 0.0 {
+                return Some(name.as_str());
+            }
+        }
+        // only reachable through rounding
+        return self.keys().next_back().map(String::as_str);
+    }
+}
+
+impl DifficultyKey {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            minute: self.minute.lerp(other.minute, t),
+            spawn_interval: self.spawn_interval.lerp(other.spawn_interval, t),
+            max_alive: (self.max_alive as f32)
+                .lerp(other.max_alive as f32, t)
+                .round() as usize,
+            enemy_mix: self.enemy_mix.lerp(&other.enemy_mix, t),
+            health_multiplier: self.health_multiplier.lerp(other.health_multiplier, t),
+            elite_chance: self.elite_chance.lerp(other.elite_chance, t),
+        }
+    }
+}
+
+impl DifficultyCurve {
+    /// The curve at `minute`, scaled by the preset
+    pub fn sample(&self, minute: f32) -> DifficultyKey {
+        let mut key = self.sample_keys(minute);
+        key.spawn_interval /= self.preset.spawn_multiplier();
+        key.max_alive = (key.max_alive as f32 * self.preset.spawn_multiplier()).round() as usize;
+        key.health_multiplier *= self.preset.health_multiplier();
+        return key;
+    }
+
+    fn sample_keys(&self, minute: f32) -> DifficultyKey {
+        let Some(first) = self.keys.first() else {
+            return DifficultyCurve::default().keys[0].clone();
+        };
+
+        let mut prev = first;
+        for key in self.keys.iter() {
+            if key.minute >= minute {
+                let span = key.minute - prev.minute;
+                if span <= 0.0 {
+                    return key.clone();
+                }
+                return prev.lerp(key, (minute - prev.minute) / span);
+            }
+            prev = key;
+        }
+
+        return prev.clone();
+    }
+}
+
+impl Default for DifficultyCurve {
+    fn default() -> Self {
+        Self {
+            keys: vec![DifficultyKey {
+                minute: 0.0,
+                spawn_interval: 0.5,
+                max_alive: 300,
+                enemy_mix: EnemyMix(BTreeMap::from([
+                    ("grunt".to_string(), 0.9),
+                    ("witch".to_string(), 0.1),
+                ])),
+                health_multiplier: 1.0,
+                elite_chance: 0.02,
+            }],
+            preset: DifficultyPreset::default(),
+        }
+    }
+}
+
+/// Authored beats played out on top of the difficulty curve, like a boss
+/// arriving or a swarm sweeping in at a set time
+///
+/// Loaded from `assets/run.timeline.ron`, read fresh every frame so edits
+/// apply to beats that haven't happened yet
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct RunTimeline {
+    pub beats: Vec<TimelineBeat>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimelineBeat {
+    /// Minute of the run the beat happens at
+    pub minute: f32,
+    pub event: TimelineEvent,
+    /// Shown across the screen as the beat happens
+    #[serde(default)]
+    pub announce: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub enum TimelineEvent {
+    /// Enemies evenly spaced on the spawn ring, closing in from every side
+    Ring { prefab: String, count: usize },
+    /// The next boss, each one tougher than the last
+    Boss,
+    /// A tight pack of enemies coming in from one side
+    Swarm { prefab: String, count: usize },
+}
+
+/// Copy the curve asset into the live resource whenever the file (re)loads
+fn sync_difficulty_curve(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<DifficultyCurve>>,
+    curves: Res<Assets<DifficultyCurve>>,
+    assets: Res<GameAssets>,
+    current: Res<DifficultyCurve>,
+) {
+    let handle = &assets.difficulty_curve;
+    let reloaded = events
+        .read()
+        .any(|e| e.is_loaded_with_dependencies(handle) || e.is_modified(handle));
+    if !reloaded {
+        return;
+    }
+
+    if let Some(curve) = curves.get(handle) {
+        commands.insert_resource(DifficultyCurve {
+            preset: current.preset,
+            ..curve.clone()
+        });
+    }
+}
+
+/// A random point on the spawn ring around `center`
+pub fn spawn_ring_position(center: Vec3, rng: &mut impl Rng) -> Vec3 {
+    let angle = rng.random_range(0.0..std::f32::consts::TAU);
+    return center + Vec3::new(angle.cos(), 0.0, angle.sin()) * SPAWN_RING_RADIUS;
+}
+
+/// A random point on the spawn ring around `center`, within
+/// `STRAGGLER_SPREAD` of `heading`
+fn spawn_ring_position_towards(center: Vec3, heading: Vec2, rng: &mut impl Rng) -> Vec3 {
+    let angle = heading.to_angle() + rng.random_range(-STRAGGLER_SPREAD..STRAGGLER_SPREAD);
+    return center + Vec3::new(angle.cos(), 0.0, angle.sin()) * SPAWN_RING_RADIUS;
+}
+
+fn tick_director(
+    time: Res<Time>,
+    (curve, config): (Res<DifficultyCurve>, Res<RunConfig>),
+    mut director: ResMut<WaveDirector>,
+) {
+    director.elapsed += time.delta_secs();
+
+    let difficulty = config.difficulty(curve.sample(director.minute()));
+    // the curve is edited by hand and live, so bad intervals are clamped, or
+    // keep the last good one, rather than panicking
+    let interval = difficulty.spawn_interval.max(MIN_SPAWN_INTERVAL);
+    let interval = Duration::try_from_secs_f32(interval).unwrap_or(director.spawn_timer.duration());
+    if director.spawn_timer.duration() != interval {
+        director.spawn_timer.set_duration(interval);
+    }
+    director.spawn_timer.tick(time.delta());
+}
+
+/// Deal with enemies too far from every player to ever catch up, they cost
+/// as much to simulate as any other but never get a hit in
+fn handle_stragglers(
+    mut commands: Commands,
+    balance: Res<BalanceConfig>,
+    mut director: ResMut<WaveDirector>,
+    players: Query<(&Transform, &Velocity), With<Player>>,
+    mut enemies: Query<
+        (Entity, &mut Transform, &mut Velocity),
+        (With<Enemy>, Without<Player>, Without<Boss>),
+    >,
+    mut rng: ResMut<GameRng>,
+) {
+    let radius_sq = balance.straggler_radius * balance.straggler_radius;
+    for (entity, mut transform, mut velocity) in enemies.iter_mut() {
+        let nearest = players.iter().min_by(|(a, _), (b, _)| {
+            let a = a.translation.distance_squared(transform.translation);
+            let b = b.translation.distance_squared(transform.translation);
+            a.total_cmp(&b)
+        });
+        let Some((player, player_velocity)) = nearest else {
+            return;
+        };
+        if player.translation.distance_squared(transform.translation) <= radius_sq {
+            continue;
+        }
+
+        match balance.straggler_policy {
+            StragglerPolicy::Relocate => {
+                // a player standing still has no ahead, anywhere around them will do
+                let heading = player_velocity
+                    .linvel
+                    .xz()
+                    .try_normalize()
+                    .unwrap_or_else(|| {
+                        Vec2::from_angle(rng.random_range(0.0..std::f32::consts::TAU))
+                    });
+                transform.translation =
+                    spawn_ring_position_towards(player.translation, heading, &mut **rng);
+                *velocity = Velocity::zero();
+            }
+            StragglerPolicy::Refund => {
+                commands.entity(entity).release();
+                director.refunds += 1;
+            }
+        }
+    }
+}
+
+fn spawn_waves(
+    mut commands: Commands,
+    mut director: ResMut<WaveDirector>,
+    (curve, config): (Res<DifficultyCurve>, Res<RunConfig>),
+    (library, animations, mut pool): (
+        Res<PrefabLibrary>,
+        Res<Assets<AnimationLibrary>>,
+        ResMut<Pool>,
+    ),
+    players: Query<(&Transform, &PlayerSlot), With<Player>>,
+    enemies: Query<(), With<Enemy>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let difficulty = config.difficulty(curve.sample(director.minute()));
+    let due = director.spawn_timer.just_finished() as usize + director.refunds;
+    let count = due.min(difficulty.max_alive.saturating_sub(enemies.iter().len()));
+
+    for _ in 0..count {
+        let Some(target) = spawn_target(&players, &mut **rng) else {
+            return;
+        };
+
+        let pos = spawn_ring_position(target, &mut **rng);
+        let Some(prefab) = difficulty.enemy_mix.pick(&mut **rng) else {
+            warn_once!("The enemy mix is empty, nothing to spawn");
+            return;
+        };
+        let Some(enemy) = spawn_prefab(
+            &mut commands,
+            &mut pool,
+            (&library, &animations),
+            prefab,
+            pos,
+            difficulty.health_multiplier,
+            &mut **rng,
+        ) else {
+            warn_once!("The enemy mix names {prefab}, which isn't a prefab");
+            continue;
+        };
+        // refunds are only paid off by spawns that happened, the rest wait
+        // for room under the cap
+        director.refunds = director.refunds.saturating_sub(1);
+
+        if rng.random::<f32>() < difficulty.elite_chance {
+            roll_elite(&mut commands.entity(enemy), &mut **rng);
+        }
+    }
+}
+
+/// Play out every beat whose time came up this frame, beats are spread
+/// across players in co-op like regular spawns
+fn run_timeline(
+    mut commands: Commands,
+    (time, mut director, curve): (Res<Time>, ResMut<WaveDirector>, Res<DifficultyCurve>),
+    (assets, timelines): (Res<GameAssets>, Res<Assets<RunTimeline>>),
+    (enemy_assets, library, animations, mut pool): (
+        Res<EnemyAssets>,
+        Res<PrefabLibrary>,
+        Res<Assets<AnimationLibrary>>,
+        ResMut<Pool>,
+    ),
+    players: Query<(&Transform, &PlayerSlot), With<Player>>,
+    mut rng: ResMut<GameRng>,
+    mut announcements: EventWriter<Announcement>,
+) {
+    let Some(timeline) = timelines.get(&assets.timeline) else {
+        return;
+    };
+
+    // beats between last frame and this one, going by time rather than an
+    // index so reordering the file mid-run doesn't replay or skip anything
+    let now = director.minute();
+    let before = (director.elapsed - time.delta_secs()) / 60.0;
+    let health = curve.sample(now).health_multiplier;
+    for beat in timeline
+        .beats
+        .iter()
+        .filter(|beat| beat.minute >= before && beat.minute < now)
+    {
+        if let Some(text) = &beat.announce {
+            announcements.write(Announcement { text: text.clone() });
+        }
+        let Some(target) = spawn_target(&players, &mut **rng) else {
+            return;
+        };
+
+        match &beat.event {
+            TimelineEvent::Ring { prefab, count } => {
+                let offset = rng.random_range(0.0..std::f32::consts::TAU);
+                for i in 0..*count {
+                    let angle = offset + std::f32::consts::TAU * i as f32 / *count as f32;
+                    let pos = target + Vec3::new(angle.cos(), 0.0, angle.sin()) * SPAWN_RING_RADIUS;
+                    let spawned = spawn_prefab(
+                        &mut commands,
+                        &mut pool,
+                        (&library, &animations),
+                        prefab,
+                        pos,
+                        health,
+                        &mut **rng,
+                    );
+                    if spawned.is_none() {
+                        warn_once!("The timeline names {prefab}, which isn't a prefab");
+                        break;
+                    }
+                }
+            }
+            TimelineEvent::Boss => {
+                director.bosses += 1;
+                let pos = spawn_ring_position(target, &mut **rng);
+                commands
+                    .spawn(boss::boss(&enemy_assets, director.bosses, pos))
+                    .insert(Health::new(boss::boss_health(director.bosses) * health));
+            }
+            TimelineEvent::Swarm { prefab, count } => {
+                let center = spawn_ring_position(target, &mut **rng);
+                spawn_swarm(
+                    &mut commands,
+                    &mut pool,
+                    (&library, &animations),
+                    (prefab, *count),
+                    center,
+                    health,
+                    &mut **rng,
+                );
+            }
+        }
+    }
+}
+
+/// A tight pack of `count` of the enemy prefab called `prefab` around
+/// `center`
+pub fn spawn_swarm(
+    commands: &mut Commands,
+    pool: &mut Pool,
+    prefabs: (&PrefabLibrary, &Assets<AnimationLibrary>),
+    (prefab, count): (&str, usize),
+    center: Vec3,
+    health_multiplier: f32,
+    rng: &mut impl Rng,
+) {
+    for _ in 0..count {
+        let angle = rng.random_range(0.0..std::f32::consts::TAU);
+        let distance = rng.random_range(0.0..SWARM_RADIUS);
+        let pos = center + Vec3::new(angle.cos(), 0.0, angle.sin()) * distance;
+        if spawn_prefab(commands, pool, prefabs, prefab, pos, health_multiplier, rng).is_none() {
+            warn_once!("No enemy prefab called {prefab}");
+            return;
+        }
+    }
+}
+
+/// Every hit on a player is scaled by the preset, whatever it came from
+fn scale_damage_taken(
+    mut commands: Commands,
+    curve: Res<DifficultyCurve>,
+    players: Query<(Entity, Option<&DamageTakenMultiplier>), With<Player>>,
+) {
+    let multiplier = curve.preset.damage_multiplier();
+    for (player, current) in players.iter() {
+        if current.map(|current| **current) != Some(multiplier) {
+            commands
+                .entity(player)
+                .insert(DamageTakenMultiplier(multiplier));
+        }
+    }
+}
+
+/// Which player to spawn around, picked at random in co-op so players who
+/// split up both have something to fight
+fn spawn_target(
+    players: &Query<(&Transform, &PlayerSlot), With<Player>>,
+    rng: &mut impl Rng,
+) -> Option<Vec3> {
+    let mut players: Vec<(usize, Vec3)> = players
+        .iter()
+        .map(|(transform, slot)| (**slot, transform.translation))
+        .collect();
+    // query order isn't stable between runs, slots are
+    players.sort_by_key(|(slot, _)| *slot);
+
+    let index = match players.len() {
+        0 => return None,
+        1 => 0,
+        len => rng.random_range(0..len),
+    };
+    return Some(players[index].1);
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use bevy::asset::{LoadedFolder, RenderAssetUsages};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::animation::{
+    AnimationIndices, AnimationLibrary, AnimationMode, AnimationTimer, ClipDefinition,
+};
+
+/// Loose frames live in a folder per clip under here, e.g.
+/// `sprites/bun_hop/0.png`, `sprites/bun_hop/1.png`
+const LOOSE_SPRITES_PATH: &str = "sprites";
+
+pub struct SpritePackingPlugin;
+
+impl Plugin for SpritePackingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, load_loose_sprites);
+        app.add_systems(Update, pack_loose_sprites);
+    }
+}
+
+#[derive(Resource)]
+struct LooseSprites(Handle<LoadedFolder>);
+
+/// Every loose frame packed into one sprite sheet, with a clip for each
+/// folder of frames
+///
+/// Frames are laid out on a grid of the largest frame's size, a clip's
+/// frames one after another in file name order, so each clip is a plain
+/// `first..=last` range of the atlas
+#[derive(Resource)]
+pub struct PackedSprites {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    /// Size of one grid cell in pixels
+    pub cell: UVec2,
+    /// Clips are named after their folders and loop until changed
+    pub library: AnimationLibrary,
+}
+
+impl PackedSprites {
+    /// Spawn a sprite playing one of the packed clips
+    pub fn sprite(&self, clip: &str, frame_seconds: f32) -> Option<impl Bundle> {
+        let clip: AnimationIndices = self.library.clip(clip)?;
+        let sprite = Sprite::from_atlas_image(
+            self.image.clone(),
+            TextureAtlas {
+                layout: self.layout.clone(),
+                index: clip.first(),
+            },
+        );
+        return Some((sprite, clip, AnimationTimer::from_seconds(frame_seconds)));
+    }
+}
+
+fn load_loose_sprites(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LooseSprites(asset_server.load_folder(LOOSE_SPRITES_PATH)));
+}
+
+/// The file name without its number, the number, then the whole path to
+/// break ties
+type FrameOrder = (String, u64, String);
+
+/// Frames sort by the number ending their file name, so `10.png` plays after
+/// `9.png` rather than after `1.png`
+fn frame_order(path: &Path) -> FrameOrder {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = stem[name.len()..].parse().unwrap_or(0);
+    return (name.to_string(), number, path.to_string_lossy().to_string());
+}
+
+/// Runs once the folder and every image in it has loaded
+fn pack_loose_sprites(
+    mut commands: Commands,
+    loose: Res<LooseSprites>,
+    mut events: EventReader<AssetEvent<LoadedFolder>>,
+    asset_server: Res<AssetServer>,
+    folders: Res<Assets<LoadedFolder>>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let loaded = events
+        .read()
+        .any(|event| event.is_loaded_with_dependencies(&loose.0));
+    if !loaded {
+        return;
+    }
+    let Some(folder) = folders.get(&loose.0) else {
+        return;
+    };
+
+    // clip name to its frames, both sorted so indices are the same every run
+    let mut clips: BTreeMap<String, BTreeMap<FrameOrder, Handle<Image>>> = BTreeMap::new();
+    for handle in folder.handles.iter() {
+        let Ok(image) = handle.clone().try_typed::<Image>() else {
+            continue;
+        };
+        let Some(path) = asset_server.get_path(image.id()) else {
+            continue;
+        };
+        let path = path.path();
+        let clip = path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .map(|name| name.to_string_lossy().to_string());
+        let Some(clip) = clip.filter(|clip| clip != LOOSE_SPRITES_PATH) else {
+            warn!("{} isn't in a clip folder, skipping it", path.display());
+            continue;
+        };
+        clips
+            .entry(clip)
+            .or_default()
+            .insert(frame_order(path), image);
+    }
+
+    let frames: Vec<&Image> = clips
+        .values()
+        .flat_map(|frames| frames.values())
+        .filter_map(|handle| images.get(handle))
+        .collect();
+    if frames.is_empty() {
+        return;
+    }
+    let cell = frames
+        .iter()
+        .fold(UVec2::ZERO, |cell, frame| cell.max(frame.size()));
+    let columns = (frames.len() as f32).sqrt().ceil() as u32;
+    let rows = (frames.len() as u32).div_ceil(columns);
+
+    let mut sheet = Image::new_fill(
+        Extent3d {
+            width: cell.x * columns,
+            height: cell.y * rows,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    for (i, frame) in frames.iter().enumerate() {
+        let origin = UVec2::new(i as u32 % columns, i as u32 / columns) * cell;
+        let size = frame.size();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let Ok(color) = frame.get_color_at(x, y) else {
+                    continue;
+                };
+                let _ = sheet.set_color_at(origin.x + x, origin.y + y, color);
+            }
+        }
+    }
+
+    let mut library = AnimationLibrary {
+        clips: HashMap::new(),
+    };
+    let mut first = 0;
+    for (name, frames) in clips.iter() {
+        // frames that failed to load were left out of the sheet too
+        let count = frames
+            .values()
+            .filter(|handle| images.contains(*handle))
+            .count();
+        if count == 0 {
+            continue;
+        }
+        library.clips.insert(
+            name.clone(),
+            ClipDefinition {
+                first,
+                last: first + count - 1,
+                mode: AnimationMode::Cycle,
+                fps: None,
+                frame_durations: Vec::new(),
+                frame_events: Vec::new(),
+            },
+        );
+        first += count;
+    }
+
+    info!(
+        "packed {} loose sprite frames into {} clips",
+        frames.len(),
+        library.clips.len()
+    );
+    commands.insert_resource(PackedSprites {
+        image: images.add(sheet),
+        layout: layouts.add(TextureAtlasLayout::from_grid(
+            cell, columns, rows, None, None,
+        )),
+        cell,
+        library,
+    });
+}
@@ -0,0 +1,225 @@
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::controls::{Action, control_player};
+use crate::move_speed::{MoveSpeedModifiers, apply_move_speed_modifiers};
+use crate::stats::{Stat, Stats};
+use crate::{GameState, GameplaySet, MoveVector, Player, PlayerSlot};
+
+/// Stamina a dash costs
+pub const DASH_STAMINA: f32 = 25.0;
+/// Stamina sprinting costs per second
+const SPRINT_STAMINA: f32 = 30.0;
+const SPRINT_SPEED_MULTIPLIER: f32 = 1.5;
+/// Source of the speed modifier sprinting adds
+const SPRINT_SOURCE: &str = "Sprint";
+/// Seconds after spending stamina before it starts coming back
+const REGEN_DELAY: f32 = 0.75;
+const BAR_WIDTH: f32 = 200.0;
+const BAR_HEIGHT: f32 = 12.0;
+
+pub struct StaminaPlugin;
+
+impl Plugin for StaminaPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Stamina>();
+        app.add_systems(
+            Update,
+            (
+                apply_stamina_stats,
+                sprint.before(apply_move_speed_modifiers),
+                regenerate_stamina,
+                spawn_stamina_bars,
+                update_stamina_bars,
+            )
+                .chain()
+                .before(control_player)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Spent on dashing and sprinting, and slowly comes back when not in use
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    /// Stamina regained per second
+    pub regen: f32,
+    /// Seconds until regen starts again
+    delay: f32,
+}
+
+impl Stamina {
+    pub fn new(max: f32, regen: f32) -> Self {
+        return Self {
+            current: max,
+            max,
+            regen,
+            delay: 0.0,
+        };
+    }
+
+    pub fn from_stats(stats: &Stats) -> Self {
+        return Self::new(stats.get(Stat::MaxStamina), stats.get(Stat::StaminaRegen));
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0.0 {
+            return 0.0;
+        }
+        return (self.current / self.max).clamp(0.0, 1.0);
+    }
+
+    /// Spend `amount` if there's that much left, holding off regen
+    pub fn try_spend(&mut self, amount: f32) -> bool {
+        if self.current < amount {
+            return false;
+        }
+        self.current -= amount;
+        self.delay = REGEN_DELAY;
+        return true;
+    }
+}
+
+#[derive(Component)]
+struct StaminaBar {
+    player: Entity,
+}
+
+#[derive(Component)]
+struct StaminaFill;
+
+/// Upgrades can raise max stamina and regen, topping up by however much the
+/// max went up
+fn apply_stamina_stats(mut query: Query<(&Stats, &mut Stamina), Changed<Stats>>) {
+    for (stats, mut stamina) in query.iter_mut() {
+        let max = stats.get(Stat::MaxStamina).max(0.0);
+        let gained = (max - stamina.max).max(0.0);
+        stamina.max = max;
+        stamina.current = (stamina.current + gained).min(max);
+        stamina.regen = stats.get(Stat::StaminaRegen);
+    }
+}
+
+/// Run faster while sprint is held, or from one press to the next with
+/// toggle sprint on, for as long as the stamina lasts
+fn sprint(
+    time: Res<Time>,
+    accessibility: Res<AccessibilitySettings>,
+    // players with sprint toggled on
+    mut toggled: Local<HashSet<Entity>>,
+    mut players: Query<
+        (
+            Entity,
+            &ActionState<Action>,
+            &MoveVector,
+            &mut Stamina,
+            &mut MoveSpeedModifiers,
+        ),
+        With<Player>,
+    >,
+) {
+    if !accessibility.toggle_sprint {
+        toggled.clear();
+    }
+
+    for (player, action_state, move_vec, mut stamina, mut modifiers) in players.iter_mut() {
+        let wants_sprint = if accessibility.toggle_sprint {
+            if action_state.just_pressed(&Action::Sprint) && !toggled.remove(&player) {
+                toggled.insert(player);
+            }
+            toggled.contains(&player)
+        } else {
+            action_state.pressed(&Action::Sprint)
+        };
+
+        let moving = **move_vec != Vec3::ZERO;
+        let cost = SPRINT_STAMINA * time.delta_secs();
+        let sprinting = wants_sprint && moving && stamina.try_spend(cost);
+        // running out of stamina turns a toggled sprint off
+        if wants_sprint && moving && !sprinting {
+            toggled.remove(&player);
+        }
+
+        let boosted = modifiers
+            .iter()
+            .any(|modifier| modifier.source == SPRINT_SOURCE);
+        if sprinting && !boosted {
+            modifiers.insert(SPRINT_SOURCE, SPRINT_SPEED_MULTIPLIER, None);
+        } else if !sprinting && boosted {
+            modifiers.remove(SPRINT_SOURCE);
+        }
+    }
+}
+
+fn regenerate_stamina(time: Res<Time>, mut query: Query<&mut Stamina>) {
+    for mut stamina in query.iter_mut() {
+        if stamina.delay > 0.0 {
+            stamina.delay = (stamina.delay - time.delta_secs()).max(0.0);
+            continue;
+        }
+        if stamina.current < stamina.max {
+            stamina.current =
+                (stamina.current + stamina.regen * time.delta_secs()).min(stamina.max);
+        }
+    }
+}
+
+/// A bar in the bottom left for each player, stacked up by slot
+fn spawn_stamina_bars(
+    mut commands: Commands,
+    players: Query<(Entity, &PlayerSlot), (With<Player>, Added<Stamina>)>,
+) {
+    for (player, slot) in players.iter() {
+        commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(16.0 + **slot as f32 * (BAR_HEIGHT + 8.0)),
+                    left: Val::Px(16.0),
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                StaminaBar { player },
+                Name::new(format!("Stamina Bar {}", **slot + 1)),
+                StateScoped(GameState::InGame),
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.4, 0.85, 0.3)),
+                    StaminaFill,
+                ));
+            });
+    }
+}
+
+fn update_stamina_bars(
+    mut commands: Commands,
+    bars: Query<(Entity, &StaminaBar, &Children)>,
+    players: Query<&Stamina>,
+    mut fills: Query<&mut Node, With<StaminaFill>>,
+) {
+    for (bar, link, children) in bars.iter() {
+        let Ok(stamina) = players.get(link.player) else {
+            commands.entity(bar).despawn();
+            continue;
+        };
+
+        for child in children.iter() {
+            if let Ok(mut node) = fills.get_mut(child) {
+                node.width = Val::Percent(stamina.fraction() * 100.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::balance::BalanceConfig;
+use crate::combat::{Armor, CritChance, CritMultiplier, DamageConversion, DamageType, Health};
+use crate::on_hit::OnHitEffects;
+use crate::pickups::PickupRadius;
+use crate::{BaseMoveSpeed, GameplaySet};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Stat>();
+        app.register_type::<Stats>();
+        app.register_type::<TimedModifiers>();
+        // PreUpdate so the derived components exist before anything in Update reads them
+        app.add_systems(PreUpdate, apply_stats);
+        app.add_systems(Update, expire_timed_modifiers.in_set(GameplaySet));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect, Deserialize)]
+pub enum Stat {
+    MaxHealth,
+    MoveSpeed,
+    /// Multiplier on weapon damage
+    Damage,
+    /// Multiplier on weapon cooldowns, lower is faster
+    Cooldown,
+    /// Multiplier on weapon area of effect
+    Area,
+    PickupRadius,
+    CritChance,
+    CritMultiplier,
+    /// Multiplier on how quickly movement speeds up, stops and turns
+    Handling,
+    MaxStamina,
+    /// Stamina regained per second
+    StaminaRegen,
+    /// Extra enemies player projectiles pass through, rounded down
+    Pierce,
+    /// Times player projectiles bounce on to another enemy, rounded down
+    Bounce,
+    /// Pieces player projectiles split into on a hit, rounded down
+    Split,
+    /// Radians per second player bolts turn towards enemies, none at 0
+    Homing,
+    /// Damage mitigation, see `Armor`
+    Armor,
+    /// Times lethal damage is survived, see `Revives`
+    Revives,
+    /// Fraction of physical damage dealt as fire instead, see `DamageConversion`
+    FireConversion,
+    ArcaneConversion,
+    PoisonConversion,
+    /// Fraction of damage dealt healed back, see `OnHitEffects`
+    Lifesteal,
+    /// Chance for a hit to set its target burning
+    BurnChance,
+    /// Chance for a hit to knock its target away
+    KnockbackChance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Deserialize)]
+pub enum ModifierOp {
+    Add(f32),
+    Multiply(f32),
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Deserialize)]
+pub struct StatModifier {
+    /// What granted the modifier, e.g. an upgrade or curse name, so it can be removed again
+    pub source: Cow<'static, str>,
+    pub stat: Stat,
+    pub op: ModifierOp,
+}
+
+/// Base stats plus the modifiers stacked on top of them
+///
+/// A stat's value is `(base + adds) * multiplies`. Modifiers are kept sorted
+/// so the result doesn't depend on the order they were granted in
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Stats {
+    base: Vec<(Stat, f32)>,
+    modifiers: Vec<StatModifier>,
+}
+
+impl Stats {
+    pub fn new(base: impl IntoIterator<Item = (Stat, f32)>) -> Self {
+        let mut stats = Self {
+            base: Vec::new(),
+            modifiers: Vec::new(),
+        };
+        for (stat, value) in base {
+            stats.set_base(stat, value);
+        }
+        return stats;
+    }
+
+    pub fn player(balance: &BalanceConfig) -> Self {
+        return Self::new([
+            (Stat::MaxHealth, 100.0),
+            (Stat::MoveSpeed, balance.player_move_speed),
+            (Stat::Damage, 1.0),
+            (Stat::Cooldown, 1.0),
+            (Stat::Area, 1.0),
+            (Stat::PickupRadius, 2.5),
+            (Stat::CritChance, 0.05),
+            (Stat::CritMultiplier, 2.0),
+            (Stat::Handling, 1.0),
+            (Stat::MaxStamina, 100.0),
+            (Stat::StaminaRegen, 25.0),
+        ]);
+    }
+
+    pub fn base(&self, stat: Stat) -> f32 {
+        return self
+            .base
+            .iter()
+            .find(|(s, _)| *s == stat)
+            .map_or(0.0, |(_, value)| *value);
+    }
+
+    pub fn set_base(&mut self, stat: Stat, value: f32) {
+        match self.base.iter_mut().find(|(s, _)| *s == stat) {
+            Some((_, base)) => *base = value,
+            None => self.base.push((stat, value)),
+        }
+    }
+
+    pub fn add_modifier(&mut self, modifier: StatModifier) {
+        let index = self
+            .modifiers
+            .partition_point(|m| (&m.source, m.stat) <= (&modifier.source, modifier.stat));
+        self.modifiers.insert(index, modifier);
+    }
+
+    /// Remove every modifier granted by `source`
+    pub fn remove_source(&mut self, source: &str) {
+        self.modifiers.retain(|m| m.source != source);
+    }
+
+    /// Whether any modifier was granted by `source`
+    pub fn has_source(&self, source: &str) -> bool {
+        return self.modifiers.iter().any(|m| m.source == source);
+    }
+
+    pub fn modifiers(&self) -> &[StatModifier] {
+        return &self.modifiers;
+    }
+
+    /// Every stat with a base value or a modifier, in order
+    pub fn stats(&self) -> Vec<Stat> {
+        let mut stats: Vec<Stat> = self
+            .base
+            .iter()
+            .map(|(stat, _)| *stat)
+            .chain(self.modifiers.iter().map(|m| m.stat))
+            .collect();
+        stats.sort();
+        stats.dedup();
+        return stats;
+    }
+
+    pub fn get(&self, stat: Stat) -> f32 {
+        let mut add = 0.0;
+        let mut multiply = 1.0;
+        for modifier in self.modifiers.iter().filter(|m| m.stat == stat) {
+            match modifier.op {
+                ModifierOp::Add(value) => add += value,
+                ModifierOp::Multiply(value) => multiply *= value,
+            }
+        }
+        return (self.base(stat) + add) * multiply;
+    }
+
+    /// The components `apply_stats` derives from these stats, for spawning
+    /// an entity with them already in place
+    pub fn derived(&self) -> impl Bundle {
+        (
+            BaseMoveSpeed(self.get(Stat::MoveSpeed)),
+            PickupRadius(self.get(Stat::PickupRadius)),
+            CritChance(self.get(Stat::CritChance)),
+            CritMultiplier(self.get(Stat::CritMultiplier)),
+            Armor(self.get(Stat::Armor)),
+            self.conversion(),
+            OnHitEffects::from_stats(self),
+            Health::new(self.get(Stat::MaxHealth).max(1.0)),
+        )
+    }
+
+    fn conversion(&self) -> DamageConversion {
+        let conversions = [
+            (DamageType::Fire, Stat::FireConversion),
+            (DamageType::Arcane, Stat::ArcaneConversion),
+            (DamageType::Poison, Stat::PoisonConversion),
+        ];
+        return DamageConversion(
+            conversions
+                .into_iter()
+                .map(|(kind, stat)| (kind, self.get(stat)))
+                .filter(|(_, fraction)| *fraction > 0.0)
+                .collect(),
+        );
+    }
+}
+
+/// Sources of modifiers on the entity's `Stats` that come off again when
+/// their timer runs out, e.g. from a pickup
+#[derive(Component, Reflect, Default, Debug, Clone)]
+#[reflect(Component)]
+pub struct TimedModifiers(Vec<(Cow<'static, str>, Timer)>);
+
+impl TimedModifiers {
+    /// Add `modifier` to `stats` for `seconds`. Granting one from the same
+    /// source again replaces it and starts its timer over
+    pub fn grant(&mut self, stats: &mut Stats, modifier: StatModifier, seconds: f32) {
+        stats.remove_source(&modifier.source);
+        self.0.retain(|(source, _)| *source != modifier.source);
+        self.0.push((
+            modifier.source.clone(),
+            Timer::from_seconds(seconds, TimerMode::Once),
+        ));
+        stats.add_modifier(modifier);
+    }
+}
+
+fn expire_timed_modifiers(time: Res<Time>, mut query: Query<(&mut TimedModifiers, &mut Stats)>) {
+    for (mut timed, mut stats) in query.iter_mut() {
+        if timed.0.is_empty() {
+            continue;
+        }
+        for (source, timer) in timed.0.iter_mut() {
+            if timer.tick(time.delta()).just_finished() {
+                stats.remove_source(source);
+            }
+        }
+        timed.0.retain(|(_, timer)| !timer.finished());
+    }
+}
+
+/// Write the final stat values into the components the rest of the game reads
+fn apply_stats(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Stats, Option<&mut Health>), Changed<Stats>>,
+) {
+    for (entity, stats, health) in query.iter_mut() {
+        let Some(mut health) = health else {
+            commands.entity(entity).insert(stats.derived());
+            continue;
+        };
+
+        commands.entity(entity).insert((
+            BaseMoveSpeed(stats.get(Stat::MoveSpeed)),
+            PickupRadius(stats.get(Stat::PickupRadius)),
+            CritChance(stats.get(Stat::CritChance)),
+            CritMultiplier(stats.get(Stat::CritMultiplier)),
+            Armor(stats.get(Stat::Armor)),
+            stats.conversion(),
+            OnHitEffects::from_stats(stats),
+        ));
+
+        // gaining max health heals by the same amount, losing it only clamps
+        let max_health = stats.get(Stat::MaxHealth).max(1.0);
+        let gained = (max_health - health.max).max(0.0);
+        health.max = max_health;
+        health.current = (health.current + gained).min(max_health);
+    }
+}
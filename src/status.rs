@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+
+use crate::GameplaySet;
+use crate::combat::{DamageEvent, DamageType};
+
+/// How often damage over time effects deal their damage
+const DOT_TICK_SECONDS: f32 = 0.5;
+const BURN_DAMAGE_PER_STACK: f32 = 4.0;
+const BURN_SECONDS: f32 = 3.0;
+const BURN_MAX_STACKS: u32 = 5;
+const POISON_DAMAGE_PER_STACK: f32 = 1.5;
+const POISON_SECONDS: f32 = 6.0;
+const POISON_MAX_STACKS: usize = 10;
+const SLOW_PER_STACK: f32 = 0.2;
+const SLOW_SECONDS: f32 = 2.0;
+const SLOW_MAX_STACKS: u32 = 3;
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Burning>();
+        app.register_type::<Slowed>();
+        app.register_type::<Poisoned>();
+        app.add_event::<ApplyStatus>();
+        app.add_systems(
+            Update,
+            (apply_status, tick_burning, tick_poisoned, tick_slowed).in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum StatusEffect {
+    Burn,
+    Slow,
+    Poison,
+}
+
+/// Request to put `stacks` of `effect` on `target`
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ApplyStatus {
+    pub target: Entity,
+    pub effect: StatusEffect,
+    pub stacks: u32,
+}
+
+/// Projectiles carrying this apply the status effect to whatever they hit
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct InflictsStatus {
+    pub effect: StatusEffect,
+    pub stacks: u32,
+}
+
+/// Deals damage per stack every tick, reapplying adds stacks and refreshes the duration
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Burning {
+    pub stacks: u32,
+    pub duration: Timer,
+    tick: Timer,
+}
+
+/// Every stack runs out on its own timer
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Poisoned {
+    pub stacks: Vec<Timer>,
+    tick: Timer,
+}
+
+/// Scales move speed down per stack, reapplying adds stacks and refreshes the duration
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Slowed {
+    pub stacks: u32,
+    pub duration: Timer,
+}
+
+impl Slowed {
+    pub fn multiplier(&self) -> f32 {
+        return (1.0 - SLOW_PER_STACK * self.stacks as f32).max(0.0);
+    }
+}
+
+fn dot_tick() -> Timer {
+    return Timer::from_seconds(DOT_TICK_SECONDS, TimerMode::Repeating);
+}
+
+fn apply_status(
+    mut commands: Commands,
+    mut events: EventReader<ApplyStatus>,
+    mut query: Query<(
+        Option<&mut Burning>,
+        Option<&mut Poisoned>,
+        Option<&mut Slowed>,
+    )>,
+) {
+    for event in events.read() {
+        let Ok((burning, poisoned, slowed)) = query.get_mut(event.target) else {
+            continue;
+        };
+
+        // effects inserted earlier this frame aren't visible yet, so a second
+        // application in the same frame overwrites rather than stacks
+        match event.effect {
+            StatusEffect::Burn => match burning {
+                Some(mut burning) => {
+                    burning.stacks = (burning.stacks + event.stacks).min(BURN_MAX_STACKS);
+                    burning.duration.reset();
+                }
+                None => {
+                    commands.entity(event.target).insert(Burning {
+                        stacks: event.stacks.min(BURN_MAX_STACKS),
+                        duration: Timer::from_seconds(BURN_SECONDS, TimerMode::Once),
+                        tick: dot_tick(),
+                    });
+                }
+            },
+            StatusEffect::Poison => {
+                let new_stacks =
+                    (0..event.stacks).map(|_| Timer::from_seconds(POISON_SECONDS, TimerMode::Once));
+                match poisoned {
+                    Some(mut poisoned) => {
+                        poisoned.stacks.extend(new_stacks);
+                        let excess = poisoned.stacks.len().saturating_sub(POISON_MAX_STACKS);
+                        // drop the oldest stacks first
+                        poisoned.stacks.drain(..excess);
+                    }
+                    None => {
+                        commands.entity(event.target).insert(Poisoned {
+                            stacks: new_stacks.take(POISON_MAX_STACKS).collect(),
+                            tick: dot_tick(),
+                        });
+                    }
+                }
+            }
+            StatusEffect::Slow => match slowed {
+                Some(mut slowed) => {
+                    slowed.stacks = (slowed.stacks + event.stacks).min(SLOW_MAX_STACKS);
+                    slowed.duration.reset();
+                }
+                None => {
+                    commands.entity(event.target).insert(Slowed {
+                        stacks: event.stacks.min(SLOW_MAX_STACKS),
+                        duration: Timer::from_seconds(SLOW_SECONDS, TimerMode::Once),
+                    });
+                }
+            },
+        }
+    }
+}
+
+fn tick_burning(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Burning)>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for (entity, mut burning) in query.iter_mut() {
+        if burning.tick.tick(time.delta()).just_finished() {
+            damage.write(DamageEvent {
+                target: entity,
+                amount: BURN_DAMAGE_PER_STACK * DOT_TICK_SECONDS * burning.stacks as f32,
+                source: None,
+                crit: false,
+                weapon: None,
+                kind: DamageType::Fire,
+            });
+        }
+
+        if burning.duration.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Burning>();
+        }
+    }
+}
+
+fn tick_poisoned(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Poisoned)>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for (entity, mut poisoned) in query.iter_mut() {
+        if poisoned.tick.tick(time.delta()).just_finished() {
+            damage.write(DamageEvent {
+                target: entity,
+                amount: POISON_DAMAGE_PER_STACK * DOT_TICK_SECONDS * poisoned.stacks.len() as f32,
+                source: None,
+                crit: false,
+                weapon: None,
+                kind: DamageType::Poison,
+            });
+        }
+
+        for stack in poisoned.stacks.iter_mut() {
+            stack.tick(time.delta());
+        }
+        poisoned.stacks.retain(|stack| !stack.finished());
+        if poisoned.stacks.is_empty() {
+            commands.entity(entity).remove::<Poisoned>();
+        }
+    }
+}
+
+fn tick_slowed(mut commands: Commands, time: Res<Time>, mut query: Query<(Entity, &mut Slowed)>) {
+    for (entity, mut slowed) in query.iter_mut() {
+        if slowed.duration.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Slowed>();
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+
+use crate::GameplaySet;
+
+/// Height above the ground telegraphs are drawn at, under damage zones
+const TELEGRAPH_HEIGHT: f32 = 0.04;
+
+pub struct TelegraphPlugin;
+
+impl Plugin for TelegraphPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, setup_telegraph_assets);
+        app.add_systems(
+            Update,
+            (tick_telegraphs, grow_telegraphs, expire_telegraphs)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// The ground an attack is about to hit
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum TelegraphShape {
+    Circle {
+        radius: f32,
+    },
+    /// Straight out along the telegraph's forward, from its origin
+    Line {
+        length: f32,
+        width: f32,
+    },
+}
+
+/// A warning on the ground for an attack's wind up, filling in until the
+/// attack lands so it can be dodged
+///
+/// Attacks should spawn one with their own wind up and reach, so what's
+/// shown is always what's coming
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Telegraph {
+    pub shape: TelegraphShape,
+    pub windup: Timer,
+}
+
+impl Telegraph {
+    /// How far through the wind up it is, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        return self.windup.fraction();
+    }
+}
+
+/// The part of a telegraph that fills in over its wind up
+#[derive(Component)]
+struct TelegraphFill;
+
+#[derive(Resource)]
+pub struct TelegraphAssets {
+    disc: Handle<Mesh>,
+    rectangle: Handle<Mesh>,
+    outline_material: Handle<StandardMaterial>,
+    fill_material: Handle<StandardMaterial>,
+}
+
+fn setup_telegraph_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut decal = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })
+    };
+
+    commands.insert_resource(TelegraphAssets {
+        disc: meshes.add(Cylinder::new(1.0, 0.01)),
+        rectangle: meshes.add(Plane3d::new(Vec3::Y, Vec2::splat(0.5))),
+        outline_material: decal(Color::srgba(1.0, 0.15, 0.1, 0.15)),
+        fill_material: decal(Color::srgba(1.0, 0.15, 0.1, 0.4)),
+    });
+}
+
+/// A telegraph of `shape` lasting `seconds`, on the ground at `position`
+/// facing `forward`
+pub fn telegraph(
+    assets: &TelegraphAssets,
+    shape: TelegraphShape,
+    seconds: f32,
+    position: Vec3,
+    forward: Vec3,
+) -> impl Bundle {
+    let mesh = match shape {
+        TelegraphShape::Circle { .. } => assets.disc.clone(),
+        TelegraphShape::Line { .. } => assets.rectangle.clone(),
+    };
+    let position = position.with_y(TELEGRAPH_HEIGHT);
+
+    (
+        Transform::from_translation(position).looking_to(forward.with_y(0.0), Vec3::Y),
+        Visibility::default(),
+        Name::new("Telegraph"),
+        Telegraph {
+            shape,
+            windup: Timer::from_seconds(seconds, TimerMode::Once),
+        },
+        children![
+            (
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(assets.outline_material.clone()),
+                shape_transform(shape, 1.0),
+            ),
+            (
+                Mesh3d(mesh),
+                MeshMaterial3d(assets.fill_material.clone()),
+                shape_transform(shape, 0.0).with_translation(Vec3::Y * 0.01),
+                TelegraphFill,
+            ),
+        ],
+    )
+}
+
+/// Scales the unit disc or square to `progress` of the shape, lines growing
+/// out from their origin
+fn shape_transform(shape: TelegraphShape, progress: f32) -> Transform {
+    match shape {
+        TelegraphShape::Circle { radius } => {
+            let radius = radius * progress;
+            return Transform::from_scale(Vec3::new(radius, 1.0, radius));
+        }
+        TelegraphShape::Line { length, width } => {
+            let length = length * progress;
+            return Transform::from_translation(Vec3::NEG_Z * length / 2.0)
+                .with_scale(Vec3::new(width, 1.0, length));
+        }
+    }
+}
+
+pub fn tick_telegraphs(time: Res<Time>, mut telegraphs: Query<&mut Telegraph>) {
+    for mut telegraph in telegraphs.iter_mut() {
+        telegraph.windup.tick(time.delta());
+    }
+}
+
+fn grow_telegraphs(
+    telegraphs: Query<(&Telegraph, &Children)>,
+    mut fills: Query<&mut Transform, With<TelegraphFill>>,
+) {
+    for (telegraph, children) in telegraphs.iter() {
+        for child in children.iter() {
+            let Ok(mut transform) = fills.get_mut(child) else {
+                continue;
+            };
+            let height = transform.translation.y;
+            *transform = shape_transform(telegraph.shape, telegraph.progress());
+            transform.translation.y = height;
+        }
+    }
+}
+
+/// Whatever was telegraphed lands as this runs, so systems landing attacks
+/// from telegraphs should run between `tick_telegraphs` and this
+pub fn expire_telegraphs(mut commands: Commands, telegraphs: Query<(Entity, &Telegraph)>) {
+    for (entity, telegraph) in telegraphs.iter() {
+        if telegraph.windup.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
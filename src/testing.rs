@@ -0,0 +1,230 @@
+//! A headless run driven a fixed step at a time, for testing gameplay
+//! systems from outside the crate, and the hot systems on their own for
+//! benchmarking
+//!
+//! `TestWorld` is the whole game without a window, the same as
+//! `App::new_headless`, already through loading and into a run. Every
+//! `advance` steps exactly one physics tick, so forces and velocities can be
+//! checked step by step. The gameplay types tests need to set things up and
+//! check on them are re-exported here, the modules they live in being
+//! private
+
+use std::time::Duration;
+
+use bevy::ecs::component::Mutable;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy_rapier3d::plugin::TimestepMode;
+
+use crate::animation::AnimationLibrary;
+use crate::pool::Pool;
+use crate::prefab_library::{PrefabLibrary, spawn_prefab};
+use crate::{App, GameState, Player};
+
+pub use crate::animation::Facing;
+pub use crate::boss::{Boss, BossPhase};
+pub use crate::budget::EntityBudget;
+pub use crate::combat::{
+    Armor, ContactDamage, DamageConversion, DamageEvent, DamageType, DeathEvent, Faction, Health,
+    Resistances,
+};
+pub use crate::damage_numbers::{DamageNumberMesh, DamageNumbers};
+pub use crate::input_device::{ActiveInputDevice, InputDevicePreference};
+pub use crate::interaction::{Interactable, Interacted};
+pub use crate::music::MusicAssets;
+pub use crate::on_hit::OnHitEffects;
+pub use crate::particles::{EmitParticles, Particle, ParticleEffect};
+pub use crate::pickups::{Pickup, PickupKind, Rarity};
+pub use crate::pool::Inactive;
+pub use crate::rng::{GameSeed, NextRunSeed};
+pub use crate::run_stats::RunStats;
+pub use crate::save::SaveData;
+pub use crate::sfx::{PlaySfx, Sfx, SfxAssets};
+pub use crate::shrines::{Shrine, ShrineChallenge};
+pub use crate::transition::TransitionTo;
+pub use crate::weapons::WeaponKind;
+
+// the systems `benches/hot_systems.rs` times on their own, outside of a run
+pub use crate::animation::{
+    AnimationFinished, AnimationFrameEvent, AnimationIndices, AnimationMode, AnimationSpeed,
+    AnimationTimer, animate_sprites,
+};
+pub use crate::controls::entities_try_to_move;
+pub use crate::spatial::{SpatialGrid, rebuild_grid};
+
+/// Loading finishes asynchronously, so give it plenty of frames
+const MAX_LOADING_FRAMES: usize = 600;
+
+pub struct TestWorld {
+    app: App,
+}
+
+impl TestWorld {
+    /// A run with a single player, past loading and the menus
+    pub fn new() -> Self {
+        let mut app = App::new_headless();
+        // one frame per physics tick, so each update runs `FixedUpdate` once
+        let tick = app.world().resource::<Time<Fixed>>().timestep();
+        app.world_mut()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(tick));
+
+        let mut world = Self { app };
+        world.finish_loading();
+        world.set_state(GameState::InGame);
+        world.advance(1);
+        return world;
+    }
+
+    fn finish_loading(&mut self) {
+        for _ in 0..MAX_LOADING_FRAMES {
+            self.app.update();
+            if self.state() != GameState::Loading {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("assets never finished loading");
+    }
+
+    pub fn state(&self) -> GameState {
+        return *self.app.world().resource::<State<GameState>>().get();
+    }
+
+    /// Takes effect on the next `advance`
+    pub fn set_state(&mut self, state: GameState) {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(state);
+    }
+
+    /// Step `ticks` frames, each one physics tick long
+    pub fn advance(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    /// Step physics, and so frames, at `hz` from now on
+    pub fn set_physics_hz(&mut self, hz: f64) {
+        let world = self.app.world_mut();
+        world.insert_resource(Time::<Fixed>::from_hz(hz));
+        world.insert_resource(TimestepMode::Fixed {
+            dt: 1.0 / hz as f32,
+            substeps: 1,
+        });
+        let tick = world.resource::<Time<Fixed>>().timestep();
+        world.insert_resource(TimeUpdateStrategy::ManualDuration(tick));
+    }
+
+    /// Seconds `ticks` physics ticks take
+    pub fn seconds(&self, ticks: usize) -> f32 {
+        let tick = self.app.world().resource::<Time<Fixed>>().timestep();
+        return tick.as_secs_f32() * ticks as f32;
+    }
+
+    pub fn spawn(&mut self, bundle: impl Bundle) -> Entity {
+        return self.app.world_mut().spawn(bundle).id();
+    }
+
+    /// Spawn one of the prefabs in `assets/prefabs`, panicking if there's
+    /// no such prefab
+    pub fn spawn_prefab(&mut self, name: &str, position: Vec3) -> Entity {
+        let entity = self
+            .app
+            .world_mut()
+            .run_system_cached_with(spawn_named_prefab, (name.to_string(), position))
+            .expect("the prefab library isn't set up");
+        return entity.unwrap_or_else(|| panic!("there's no prefab called {name}"));
+    }
+
+    pub fn send_event<E: Event>(&mut self, event: E) {
+        self.app.world_mut().send_event(event);
+    }
+
+    /// The first player
+    pub fn player(&mut self) -> Entity {
+        let world = self.app.world_mut();
+        return world
+            .query_filtered::<Entity, With<Player>>()
+            .iter(world)
+            .next()
+            .expect("the run has no player");
+    }
+
+    /// `entity`'s `C`, panicking with its name if it doesn't have one
+    pub fn get<C: Component>(&self, entity: Entity) -> &C {
+        let world = self.app.world();
+        return world.get::<C>(entity).unwrap_or_else(|| {
+            panic!(
+                "{} has no {}",
+                self.name(entity),
+                std::any::type_name::<C>()
+            )
+        });
+    }
+
+    pub fn get_mut<C: Component<Mutability = Mutable>>(&mut self, entity: Entity) -> Mut<'_, C> {
+        let name = self.name(entity);
+        return self
+            .app
+            .world_mut()
+            .get_mut::<C>(entity)
+            .unwrap_or_else(|| panic!("{name} has no {}", std::any::type_name::<C>()));
+    }
+
+    /// Panics with `entity`'s `C` if `check` fails on it
+    #[track_caller]
+    pub fn assert_component<C: Component + std::fmt::Debug>(
+        &self,
+        entity: Entity,
+        check: impl FnOnce(&C) -> bool,
+    ) {
+        let component = self.get::<C>(entity);
+        assert!(
+            check(component),
+            "{} failed the check with {component:?}",
+            self.name(entity)
+        );
+    }
+
+    /// Its name if it has one, for messages
+    pub fn name(&self, entity: Entity) -> String {
+        return match self.app.world().get::<Name>(entity) {
+            Some(name) => format!("{name} {entity}"),
+            None => entity.to_string(),
+        };
+    }
+
+    pub fn world(&self) -> &World {
+        return self.app.world();
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        return self.app.world_mut();
+    }
+}
+
+fn spawn_named_prefab(
+    In((name, position)): In<(String, Vec3)>,
+    mut commands: Commands,
+    mut pool: ResMut<Pool>,
+    library: Res<PrefabLibrary>,
+    animations: Res<Assets<AnimationLibrary>>,
+) -> Option<Entity> {
+    return spawn_prefab(
+        &mut commands,
+        &mut pool,
+        (&library, &animations),
+        &name,
+        position,
+        1.0,
+        &mut rand::rng(),
+    );
+}
+
+impl Default for TestWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::boss::Boss;
+use crate::elites::Elite;
+use crate::pickups::{Pickup, PickupKind};
+use crate::pool::Inactive;
+use crate::{GameState, GameplaySet, MainCamera};
+
+const INDICATOR_SIZE: f32 = 22.0;
+/// Gap kept between indicators and the edge of the screen
+const EDGE_MARGIN: f32 = 28.0;
+
+pub struct ThreatIndicatorsPlugin;
+
+impl Plugin for ThreatIndicatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (sync_indicators, place_indicators)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Things worth knowing about while they're off screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndicatorKind {
+    Elite,
+    Boss,
+    Chest,
+}
+
+impl IndicatorKind {
+    fn color(&self) -> Color {
+        match self {
+            IndicatorKind::Elite => Color::srgb(1.0, 0.75, 0.1),
+            IndicatorKind::Boss => Color::srgb(0.9, 0.1, 0.1),
+            IndicatorKind::Chest => Color::srgb(0.4, 0.8, 1.0),
+        }
+    }
+}
+
+/// A pin on the edge of the screen pointing towards `target`, hidden while
+/// the target is in view
+#[derive(Component)]
+struct ThreatIndicator {
+    target: Entity,
+}
+
+/// One indicator per target, spawned and despawned as targets come and go
+fn sync_indicators(
+    mut commands: Commands,
+    indicators: Query<(Entity, &ThreatIndicator)>,
+    elites: Query<Entity, (With<Elite>, Without<Inactive>)>,
+    bosses: Query<Entity, With<Boss>>,
+    pickups: Query<(Entity, &Pickup), Without<Inactive>>,
+) {
+    let mut targets: HashMap<Entity, IndicatorKind> = HashMap::new();
+    targets.extend(elites.iter().map(|entity| (entity, IndicatorKind::Elite)));
+    targets.extend(bosses.iter().map(|entity| (entity, IndicatorKind::Boss)));
+    targets.extend(
+        pickups
+            .iter()
+            .filter(|(_, pickup)| matches!(pickup.kind, PickupKind::Chest(_)))
+            .map(|(entity, _)| (entity, IndicatorKind::Chest)),
+    );
+
+    for (indicator, link) in indicators.iter() {
+        if targets.remove(&link.target).is_none() {
+            commands.entity(indicator).despawn();
+        }
+    }
+
+    for (target, kind) in targets {
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(INDICATOR_SIZE),
+                height: Val::Px(INDICATOR_SIZE),
+                ..default()
+            },
+            // the one square corner is the point
+            BorderRadius {
+                top_left: Val::Px(0.0),
+                ..BorderRadius::MAX
+            },
+            BackgroundColor(kind.color()),
+            Visibility::Hidden,
+            ThreatIndicator { target },
+            Name::new(format!("{kind:?} Indicator")),
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+/// Pin each indicator to the screen edge where the line from the middle of
+/// the screen to its target leaves it
+fn place_indicators(
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    targets: Query<&GlobalTransform>,
+    ui_scale: Option<Res<UiScale>>,
+    mut indicators: Query<(&ThreatIndicator, &mut Node, &mut Transform, &mut Visibility)>,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+    let scale = ui_scale.map_or(1.0, |scale| scale.0);
+    let half = viewport / scale / 2.0;
+    let inset = (half - EDGE_MARGIN).max(Vec2::ONE);
+    let to_camera = camera_transform.affine().inverse();
+
+    for (indicator, mut node, mut transform, mut visibility) in indicators.iter_mut() {
+        let Ok(target) = targets.get(indicator.target) else {
+            continue;
+        };
+        let position = target.translation();
+
+        let on_screen = camera
+            .world_to_viewport(camera_transform, position)
+            .ok()
+            .map(|point| point / scale - half);
+        if on_screen.is_some_and(|offset| offset.abs().cmple(half).all()) {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        // behind the camera there's no projection, but which way it lies
+        // across the screen still points the right way
+        let local = to_camera.transform_point3(position);
+        let dir = on_screen
+            .unwrap_or(Vec2::new(local.x, -local.y))
+            .normalize_or(Vec2::Y);
+
+        let reach = (inset / dir.abs().max(Vec2::splat(f32::EPSILON))).min_element();
+        let center = half + dir * reach;
+        node.left = Val::Px(center.x - INDICATOR_SIZE / 2.0);
+        node.top = Val::Px(center.y - INDICATOR_SIZE / 2.0);
+        // the square top left corner faces up and to the left unrotated
+        let angle = Vec2::new(-1.0, -1.0).angle_to(dir);
+        transform.rotation = Quat::from_rotation_z(angle);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
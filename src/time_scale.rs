@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+#[cfg(feature = "devtools")]
+use bevy_inspector_egui::InspectorOptions;
+#[cfg(feature = "devtools")]
+use bevy_inspector_egui::prelude::ReflectInspectorOptions;
+
+pub const MIN_TIME_SCALE: f32 = 0.25;
+pub const MAX_TIME_SCALE: f32 = 4.0;
+/// Speeds the bindings step through
+const TIME_SCALE_STEPS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+const SLOWER_KEY: KeyCode = KeyCode::BracketLeft;
+const FASTER_KEY: KeyCode = KeyCode::BracketRight;
+const RESET_KEY: KeyCode = KeyCode::Backslash;
+
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<TimeScale>();
+        app.init_resource::<TimeScale>();
+        app.add_systems(
+            Update,
+            (
+                change_time_scale,
+                apply_time_scale.run_if(resource_changed::<TimeScale>),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// How fast the game runs compared to real time, for slow motion effects and
+/// for debugging animation and physics
+///
+/// Physics steps on virtual time too, so it slows down and speeds up along
+/// with everything else while each step stays the same length
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+#[cfg_attr(feature = "devtools", derive(InspectorOptions))]
+#[reflect(Resource)]
+#[cfg_attr(feature = "devtools", reflect(InspectorOptions))]
+pub struct TimeScale {
+    #[cfg_attr(feature = "devtools", inspector(min = 0.25, max = 4.0))]
+    pub speed: f32,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self { speed: 1.0 }
+    }
+}
+
+/// Step through `TIME_SCALE_STEPS` with the bracket keys
+fn change_time_scale(keys: Res<ButtonInput<KeyCode>>, mut scale: ResMut<TimeScale>) {
+    if keys.just_pressed(RESET_KEY) {
+        scale.speed = 1.0;
+    } else if keys.just_pressed(SLOWER_KEY) {
+        scale.speed = TIME_SCALE_STEPS
+            .iter()
+            .rev()
+            .find(|step| **step < scale.speed)
+            .copied()
+            .unwrap_or(MIN_TIME_SCALE);
+    } else if keys.just_pressed(FASTER_KEY) {
+        scale.speed = TIME_SCALE_STEPS
+            .iter()
+            .find(|step| **step > scale.speed)
+            .copied()
+            .unwrap_or(MAX_TIME_SCALE);
+    }
+}
+
+pub fn apply_time_scale(scale: Res<TimeScale>, mut time: ResMut<Time<Virtual>>) {
+    let speed = scale.speed.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+    info!("time scale: {speed}x");
+    time.set_relative_speed(speed);
+}
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+
+const TOON_SHADER_PATH: &str = "shaders/toon.wgsl";
+
+pub struct ToonPlugin;
+
+impl Plugin for ToonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<ToonMaterial>::default());
+        app.register_type::<ToonMaterial>();
+        app.register_asset_reflect::<ToonMaterial>();
+    }
+}
+
+/// Cel shading for characters and props: lighting stepped into flat bands
+/// between `shadow_color` and `color`, with a hard rim of light around the
+/// edges
+///
+/// Editable live from the inspector's asset list
+#[derive(Asset, Reflect, AsBindGroup, Debug, Clone)]
+#[uniform(0, ToonMaterialUniform)]
+pub struct ToonMaterial {
+    pub color: Color,
+    /// Colour of the side facing away from the sun
+    pub shadow_color: Color,
+    pub rim_color: Color,
+    /// Steps of light between fully shaded and fully lit
+    pub bands: u32,
+    /// Higher keeps the rim closer to the edges
+    pub rim_power: f32,
+    pub rim_strength: f32,
+}
+
+impl From<Color> for ToonMaterial {
+    /// A material of `color` shaded towards a darker, cooler version of it
+    fn from(color: Color) -> Self {
+        let shadow = color.to_linear() * 0.35 + LinearRgba::rgb(0.0, 0.01, 0.04);
+        return Self {
+            color,
+            shadow_color: shadow.into(),
+            rim_color: Color::WHITE,
+            bands: 3,
+            rim_power: 3.0,
+            rim_strength: 0.25,
+        };
+    }
+}
+
+impl Material for ToonMaterial {
+    fn fragment_shader() -> ShaderRef {
+        return TOON_SHADER_PATH.into();
+    }
+}
+
+/// What the shader sees of a `ToonMaterial`
+#[derive(ShaderType)]
+pub struct ToonMaterialUniform {
+    color: Vec4,
+    shadow_color: Vec4,
+    rim_color: Vec4,
+    bands: u32,
+    rim_power: f32,
+    rim_strength: f32,
+}
+
+impl AsBindGroupShaderType<ToonMaterialUniform> for ToonMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<GpuImage>) -> ToonMaterialUniform {
+        return ToonMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            shadow_color: self.shadow_color.to_linear().to_vec4(),
+            rim_color: self.rim_color.to_linear().to_vec4(),
+            bands: self.bands,
+            rim_power: self.rim_power,
+            rim_strength: self.rim_strength,
+        };
+    }
+}
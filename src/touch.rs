@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::controls::{Action, control_player};
+use crate::{GameState, GameplaySet, Player, PlayerSlot};
+
+/// Touches starting left of this fraction of the screen grab the stick
+const STICK_ZONE: f32 = 0.5;
+/// How far, in pixels, the stick can be pulled from where it was grabbed
+const STICK_RADIUS: f32 = 60.0;
+const STICK_DEADZONE: f32 = 12.0;
+const KNOB_RADIUS: f32 = 24.0;
+
+pub struct TouchControlsPlugin;
+
+impl Plugin for TouchControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VirtualStick>();
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            (reset_virtual_stick, spawn_virtual_stick),
+        );
+        app.add_systems(
+            Update,
+            (read_touch_controls, draw_virtual_stick)
+                .chain()
+                .before(control_player)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// An on screen stick for the first player, grabbed by touching anywhere on
+/// the left of the screen and moved by dragging from there
+///
+/// Presses the same actions as the keyboard and gamepad, so gameplay can't
+/// tell the difference. Touches anywhere else dash
+#[derive(Resource, Default, Debug)]
+pub struct VirtualStick {
+    /// The touch holding the stick, if any
+    touch: Option<u64>,
+    /// Where that touch started, in window coordinates
+    origin: Vec2,
+    /// How far it has been dragged, clamped to `STICK_RADIUS`
+    offset: Vec2,
+}
+
+#[derive(Component)]
+struct StickBase;
+
+#[derive(Component)]
+struct StickKnob;
+
+fn reset_virtual_stick(mut stick: ResMut<VirtualStick>) {
+    *stick = VirtualStick::default();
+}
+
+fn spawn_virtual_stick(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(STICK_RADIUS * 2.0),
+                height: Val::Px(STICK_RADIUS * 2.0),
+                ..default()
+            },
+            BorderRadius::MAX,
+            BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.15)),
+            Visibility::Hidden,
+            StickBase,
+            Name::new("Virtual Stick"),
+            StateScoped(GameState::InGame),
+        ))
+        .with_child((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(KNOB_RADIUS * 2.0),
+                height: Val::Px(KNOB_RADIUS * 2.0),
+                ..default()
+            },
+            BorderRadius::MAX,
+            BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.4)),
+            StickKnob,
+        ));
+}
+
+fn read_touch_controls(
+    touches: Res<Touches>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut stick: ResMut<VirtualStick>,
+    mut players: Query<(&PlayerSlot, &mut ActionState<Action>), With<Player>>,
+) {
+    let Ok(window) = window.single() else {
+        return;
+    };
+
+    if stick.touch.is_none() {
+        let grabbed = touches
+            .iter_just_pressed()
+            .find(|touch| touch.position().x < window.width() * STICK_ZONE);
+        if let Some(touch) = grabbed {
+            stick.touch = Some(touch.id());
+            stick.origin = touch.start_position();
+        }
+    }
+    let dash = touches
+        .iter_just_pressed()
+        .any(|touch| Some(touch.id()) != stick.touch);
+
+    let held = stick.touch.and_then(|id| touches.get_pressed(id));
+    let released = stick.touch.is_some() && held.is_none();
+    match held {
+        Some(touch) => {
+            stick.offset = (touch.position() - stick.origin).clamp_length_max(STICK_RADIUS)
+        }
+        None if released => *stick = VirtualStick::default(),
+        None => {}
+    }
+
+    let Some((_, mut action_state)) = players.iter_mut().find(|(slot, _)| ***slot == 0) else {
+        return;
+    };
+
+    // only touch the axis while the stick is in use, so a gamepad keeps
+    // working alongside it. Pulled partway it moves partway, like a real stick
+    if held.is_some() || released {
+        let axis = match stick.offset.length() >= STICK_DEADZONE {
+            // window coordinates grow downwards
+            true => Vec2::new(stick.offset.x, -stick.offset.y) / STICK_RADIUS,
+            false => Vec2::ZERO,
+        };
+        action_state.set_axis_pair(&Action::Move, axis);
+    }
+
+    if dash {
+        action_state.press(&Action::Dash);
+    }
+}
+
+fn draw_virtual_stick(
+    stick: Res<VirtualStick>,
+    mut base: Query<(&mut Node, &mut Visibility), (With<StickBase>, Without<StickKnob>)>,
+    mut knob: Query<&mut Node, With<StickKnob>>,
+) {
+    if !stick.is_changed() {
+        return;
+    }
+    let (Ok((mut base, mut visibility)), Ok(mut knob)) = (base.single_mut(), knob.single_mut())
+    else {
+        return;
+    };
+
+    if stick.touch.is_none() {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    *visibility = Visibility::Inherited;
+    base.left = Val::Px(stick.origin.x - STICK_RADIUS);
+    base.top = Val::Px(stick.origin.y - STICK_RADIUS);
+    knob.left = Val::Px(STICK_RADIUS - KNOB_RADIUS + stick.offset.x);
+    knob.top = Val::Px(STICK_RADIUS - KNOB_RADIUS + stick.offset.y);
+}
@@ -0,0 +1,129 @@
+//! Fades to black and back over state changes, so the screen never cuts
+//! straight from one state to the next
+//!
+//! Send `TransitionTo` rather than setting `NextState<GameState>` directly.
+//! The state changes once the screen is fully black
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::GameState;
+
+/// Seconds to fade out, and again to fade back in
+const FADE_SECONDS: f32 = 0.25;
+/// Above every other piece of UI, the level up menu and console included
+const FADE_Z_INDEX: i32 = 1000;
+
+pub struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransitionTo>();
+        app.init_resource::<ScreenFade>();
+        app.add_systems(Startup, spawn_fade_overlay);
+        app.add_systems(Update, (start_transitions, run_transitions).chain());
+    }
+}
+
+/// Fade out, switch to the given state, then fade back in
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitionTo(pub GameState);
+
+/// Where the fade is up to
+#[derive(Resource, Default, Debug)]
+pub enum ScreenFade {
+    #[default]
+    Clear,
+    Out {
+        to: GameState,
+        timer: Timer,
+    },
+    In(Timer),
+}
+
+impl ScreenFade {
+    /// How black the screen is, from 0 to 1
+    pub fn opacity(&self) -> f32 {
+        let t = match self {
+            ScreenFade::Clear => return 0.0,
+            ScreenFade::Out { timer, .. } => timer.fraction(),
+            ScreenFade::In(timer) => timer.fraction_remaining(),
+        };
+        return t * t * (3.0 - 2.0 * t);
+    }
+
+    pub fn is_fading(&self) -> bool {
+        return !matches!(self, ScreenFade::Clear);
+    }
+}
+
+#[derive(Component)]
+struct FadeOverlay;
+
+fn spawn_fade_overlay(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+        GlobalZIndex(FADE_Z_INDEX),
+        // clicks go through to whatever is underneath
+        Pickable::IGNORE,
+        Name::new("Screen Fade"),
+        FadeOverlay,
+    ));
+}
+
+/// Only the first request of a fade counts, presses repeated while the
+/// screen goes dark shouldn't send it somewhere else
+fn start_transitions(mut requests: EventReader<TransitionTo>, mut fade: ResMut<ScreenFade>) {
+    for request in requests.read() {
+        if matches!(*fade, ScreenFade::Out { .. }) {
+            continue;
+        }
+
+        // turning back around partway through fading in starts from as
+        // dark as it already is
+        let elapsed = match &*fade {
+            ScreenFade::In(timer) => timer.remaining_secs(),
+            _ => 0.0,
+        };
+        let mut timer = Timer::from_seconds(FADE_SECONDS, TimerMode::Once);
+        timer.set_elapsed(Duration::from_secs_f32(elapsed));
+        *fade = ScreenFade::Out {
+            to: request.0,
+            timer,
+        };
+    }
+}
+
+/// Real time, so a paused game still fades
+fn run_transitions(
+    time: Res<Time<Real>>,
+    mut fade: ResMut<ScreenFade>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut overlay: Query<&mut BackgroundColor, With<FadeOverlay>>,
+) {
+    match &mut *fade {
+        ScreenFade::Clear => {}
+        ScreenFade::Out { to, timer } => {
+            if timer.tick(time.delta()).finished() {
+                next_state.set(*to);
+                *fade = ScreenFade::In(Timer::from_seconds(FADE_SECONDS, TimerMode::Once));
+            }
+        }
+        ScreenFade::In(timer) => {
+            if timer.tick(time.delta()).finished() {
+                *fade = ScreenFade::Clear;
+            }
+        }
+    }
+
+    for mut background in overlay.iter_mut() {
+        background.0 = Color::BLACK.with_alpha(fade.opacity());
+    }
+}
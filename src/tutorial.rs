@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+
+use crate::input_device::ActiveInputDevice;
+use crate::pickups::{Pickup, PickupCollected, PickupKind};
+use crate::save::SaveData;
+use crate::upgrades::{CHOICE_BUTTONS, CHOICE_KEYS, LevelUpMenu};
+use crate::{GameState, GameplaySet, MoveVector, Player};
+
+/// Real seconds a prompt stays up if what it asks for never happens
+const PROMPT_SECONDS: f32 = 8.0;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (show_prompts, update_prompt_text, dismiss_prompts)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Onboarding shown the first time each situation comes up, and never again
+/// once seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TutorialPrompt {
+    Move,
+    CollectExperience,
+    ChooseUpgrade,
+}
+
+impl TutorialPrompt {
+    /// Ones shown first win when more than one comes up at once
+    const ALL: [TutorialPrompt; 3] = [
+        TutorialPrompt::ChooseUpgrade,
+        TutorialPrompt::Move,
+        TutorialPrompt::CollectExperience,
+    ];
+
+    /// Stored in the save once seen, never change it once shipped
+    fn id(&self) -> &'static str {
+        match self {
+            TutorialPrompt::Move => "tutorial.move",
+            TutorialPrompt::CollectExperience => "tutorial.collect_experience",
+            TutorialPrompt::ChooseUpgrade => "tutorial.choose_upgrade",
+        }
+    }
+
+    /// Worded for whichever device is being played with
+    fn text(&self, device: ActiveInputDevice) -> String {
+        let choice = |i: usize| device.glyph(CHOICE_KEYS[i], CHOICE_BUTTONS[i]);
+        match self {
+            TutorialPrompt::Move => match device {
+                ActiveInputDevice::Keyboard => return "Move with the arrow keys".to_string(),
+                ActiveInputDevice::Gamepad => return "Move with the left stick".to_string(),
+            },
+            TutorialPrompt::CollectExperience => {
+                return "Walk over gems to collect experience".to_string();
+            }
+            TutorialPrompt::ChooseUpgrade => match device {
+                ActiveInputDevice::Keyboard => {
+                    return format!(
+                        "Click an upgrade or press {}-{} to pick it",
+                        choice(0),
+                        choice(2)
+                    );
+                }
+                ActiveInputDevice::Gamepad => {
+                    return format!(
+                        "Press {}, {} or {} to pick an upgrade",
+                        choice(0),
+                        choice(1),
+                        choice(2)
+                    );
+                }
+            },
+        }
+    }
+}
+
+/// The prompt on screen, only one is up at a time
+#[derive(Component)]
+struct PromptBox {
+    prompt: TutorialPrompt,
+    timer: Timer,
+}
+
+/// The text in a `PromptBox`, reworded when the device being played with
+/// changes while it's up
+#[derive(Component)]
+struct PromptText(TutorialPrompt);
+
+/// Checked every frame rather than reacted to once, so a prompt held back
+/// by another one still shows after it
+fn show_prompts(
+    mut commands: Commands,
+    mut save: ResMut<SaveData>,
+    prompts: Query<(), With<PromptBox>>,
+    players: Query<(), With<Player>>,
+    pickups: Query<&Pickup>,
+    menus: Query<(), With<LevelUpMenu>>,
+    device: Res<ActiveInputDevice>,
+) {
+    if !prompts.is_empty() {
+        return;
+    }
+
+    let due = TutorialPrompt::ALL.into_iter().find(|prompt| {
+        let happening = match prompt {
+            TutorialPrompt::Move => !players.is_empty(),
+            TutorialPrompt::CollectExperience => pickups
+                .iter()
+                .any(|pickup| matches!(pickup.kind, PickupKind::Experience(_))),
+            TutorialPrompt::ChooseUpgrade => !menus.is_empty(),
+        };
+        happening && !save.tutorials_seen.contains(prompt.id())
+    });
+    let Some(prompt) = due else {
+        return;
+    };
+
+    // seen as soon as it's shown, quitting with it up still counts
+    save.tutorials_seen.insert(prompt.id().to_string());
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            bottom: Val::Px(96.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(10),
+        Name::new("Tutorial Prompt"),
+        PromptBox {
+            prompt,
+            timer: Timer::from_seconds(PROMPT_SECONDS, TimerMode::Once),
+        },
+        StateScoped(GameState::InGame),
+        children![(
+            Node {
+                padding: UiRect::all(Val::Px(12.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.9)),
+            children![(
+                Text::new(prompt.text(*device)),
+                TextFont::from_font_size(22.0),
+                PromptText(prompt),
+            )],
+        )],
+    ));
+}
+
+fn update_prompt_text(device: Res<ActiveInputDevice>, mut texts: Query<(&PromptText, &mut Text)>) {
+    if !device.is_changed() {
+        return;
+    }
+
+    for (prompt, mut text) in texts.iter_mut() {
+        text.0 = prompt.0.text(*device);
+    }
+}
+
+/// Prompts go away once the player does what they ask, or after a while.
+/// Runs on real time so the upgrade prompt times out while paused too
+fn dismiss_prompts(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut prompts: Query<(Entity, &mut PromptBox)>,
+    players: Query<&MoveVector, With<Player>>,
+    mut collected: EventReader<PickupCollected>,
+    menus: Query<(), With<LevelUpMenu>>,
+) {
+    let collected_experience = collected
+        .read()
+        .any(|event| matches!(event.pickup.kind, PickupKind::Experience(_)));
+
+    for (entity, mut prompt) in prompts.iter_mut() {
+        let done = match prompt.prompt {
+            TutorialPrompt::Move => players.iter().any(|move_vec| **move_vec != Vec3::ZERO),
+            TutorialPrompt::CollectExperience => collected_experience,
+            TutorialPrompt::ChooseUpgrade => menus.is_empty(),
+        };
+        if done || prompt.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
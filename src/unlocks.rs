@@ -0,0 +1,53 @@
+use crate::achievements::ACHIEVEMENTS;
+use crate::save::SaveData;
+
+/// What it takes before a character or weapon can be played with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unlock {
+    Free,
+    /// Unlocked along with the achievement with this id
+    Achievement(&'static str),
+    /// Bought for `cost` gold, then kept in the save's unlocks as `id`
+    Gold {
+        id: &'static str,
+        cost: u32,
+    },
+}
+
+impl Unlock {
+    pub fn unlocked(&self, save: &SaveData) -> bool {
+        match self {
+            Unlock::Free => true,
+            Unlock::Achievement(id) | Unlock::Gold { id, .. } => save.unlocks.contains(*id),
+        }
+    }
+
+    /// Spend the gold on a gold unlock, if there's enough of it and it
+    /// isn't already unlocked
+    pub fn try_buy(&self, save: &mut SaveData) -> bool {
+        let Unlock::Gold { id, cost } = *self else {
+            return false;
+        };
+        if self.unlocked(save) || save.gold < cost {
+            return false;
+        }
+        save.gold -= cost;
+        save.unlocks.insert(id.to_string());
+        return true;
+    }
+
+    /// How to unlock it, for showing next to locked entries
+    pub fn condition(&self) -> String {
+        match self {
+            Unlock::Free => String::new(),
+            Unlock::Achievement(id) => {
+                let achievement = ACHIEVEMENTS.iter().find(|a| a.id == *id);
+                match achievement {
+                    Some(achievement) => format!("Unlock: {}", achievement.description),
+                    None => "Unlock: ???".to_string(),
+                }
+            }
+            Unlock::Gold { cost, .. } => format!("Unlock for {cost}g"),
+        }
+    }
+}
@@ -0,0 +1,790 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::InputMap;
+use rand::seq::IndexedRandom;
+use serde::Deserialize;
+
+use crate::animation::{AtlasRegistry, SpriteScale};
+use crate::chests::ChestMenu;
+use crate::controls::Action;
+use crate::experience::LevelUp;
+use crate::input_device::ActiveInputDevice;
+use crate::loading::GameAssets;
+use crate::rng::GameRng;
+use crate::ron_asset::RonAssetLoader;
+use crate::save::SaveData;
+use crate::stats::{ModifierOp, Stat, StatModifier, Stats};
+use crate::weapons::{EVOLUTIONS, Evolution, MAX_WEAPONS, WeaponKind, Weapons};
+use crate::{GameState, GameplaySet, Player, PlayerSlot};
+
+/// Upgrade icons are cells of the sprite sheet, laid out at this scale
+pub(crate) const ICON_SCALE: SpriteScale = SpriteScale::X16;
+const CHOICES_PER_LEVEL: usize = 3;
+pub(crate) const CHOICE_KEYS: [KeyCode; CHOICES_PER_LEVEL] =
+    [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+/// Reroll, banish and skip, numbered on from the choices
+pub(crate) const OPTION_KEYS: [KeyCode; 3] = [KeyCode::Digit4, KeyCode::Digit5, KeyCode::Digit6];
+/// The choices on the face buttons other than dash, left to right. Abilities
+/// and interact share them, so those wait out the menu with `no_level_up_menu`
+pub(crate) const CHOICE_BUTTONS: [GamepadButton; CHOICES_PER_LEVEL] = [
+    GamepadButton::West,
+    GamepadButton::North,
+    GamepadButton::East,
+];
+/// Reroll, banish and skip on the d-pad, left to right like their buttons
+pub(crate) const OPTION_BUTTONS: [GamepadButton; 3] = [
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadRight,
+];
+/// Different passive upgrades a player can hold, more of one they hold stack
+pub const MAX_PASSIVES: usize = 4;
+const STARTING_REROLLS: u32 = 2;
+const STARTING_BANISHES: u32 = 2;
+const STARTING_SKIPS: u32 = 2;
+const INVENTORY_ICON_SIZE: f32 = 32.0;
+
+pub struct UpgradesPlugin;
+
+impl Plugin for UpgradesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<UpgradePool>();
+        app.register_asset_loader(RonAssetLoader::<UpgradePool>::new(&["upgrade.ron"]));
+        app.register_type::<LevelUpCharges>();
+        app.init_resource::<PendingLevelUps>();
+        app.add_event::<UpgradeChosen>();
+        app.add_systems(OnEnter(GameState::InGame), reset_pending_level_ups);
+        app.add_systems(
+            Update,
+            (
+                queue_level_ups,
+                open_level_up_menu.run_if(level_up_waiting),
+                choose_upgrade,
+                update_hotkey_labels,
+                update_inventory_hud,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Every upgrade a level up can offer
+///
+/// Loaded from `assets/upgrades.upgrade.ron`, read fresh on every roll so
+/// edits apply to the next level up
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct UpgradePool {
+    pub upgrades: Vec<UpgradeDefinition>,
+}
+
+impl UpgradePool {
+    /// The passive upgrades `stats` has been granted
+    pub fn held_passives<'a>(
+        &'a self,
+        stats: &'a Stats,
+    ) -> impl Iterator<Item = &'a UpgradeDefinition> + 'a {
+        return self
+            .upgrades
+            .iter()
+            .filter(|upgrade| upgrade.is_passive() && stats.has_source(&upgrade.name));
+    }
+
+    /// Whether `upgrade` fits in the inventory, weapons and passives already
+    /// held can always be offered again
+    /// Weapons still locked are only offered to players who already carry
+    /// them, e.g. from their character
+    pub fn can_offer(
+        &self,
+        upgrade: &UpgradeDefinition,
+        stats: &Stats,
+        weapons: &Weapons,
+        save: &SaveData,
+    ) -> bool {
+        match upgrade.weapon {
+            Some(weapon) => {
+                let available = weapon.unlock().unlocked(save) || weapons.get(weapon).is_some();
+                available && weapons.can_grant(weapon)
+            }
+            None => {
+                stats.has_source(&upgrade.name) || self.held_passives(stats).count() < MAX_PASSIVES
+            }
+        }
+    }
+
+    /// Icon of the upgrade granting `kind`, or of the evolution into it
+    fn weapon_icon(&self, kind: WeaponKind) -> Option<usize> {
+        let evolution = EVOLUTIONS
+            .iter()
+            .find(|evolution| evolution.evolved == kind);
+        return self
+            .upgrades
+            .iter()
+            .find(|upgrade| upgrade.weapon == Some(kind))
+            .map(|upgrade| upgrade.icon)
+            .or(evolution.map(|evolution| evolution.icon));
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpgradeDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Cell of the icon sheet
+    pub icon: usize,
+    pub rarity: UpgradeRarity,
+    #[serde(default)]
+    pub modifiers: Vec<(Stat, ModifierOp)>,
+    #[serde(default)]
+    pub weapon: Option<WeaponKind>,
+}
+
+impl UpgradeDefinition {
+    /// Passives are every upgrade that doesn't grant a weapon
+    pub fn is_passive(&self) -> bool {
+        return self.weapon.is_none();
+    }
+
+    /// Add the upgrade's modifiers and weapon to the player
+    pub fn apply(&self, stats: &mut Stats, weapons: &mut Weapons) {
+        for (stat, op) in &self.modifiers {
+            stats.add_modifier(StatModifier {
+                source: self.name.clone().into(),
+                stat: *stat,
+                op: *op,
+            });
+        }
+
+        if let Some(weapon) = self.weapon {
+            weapons.grant(weapon);
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeRarity {
+    Common,
+    Rare,
+    Epic,
+}
+
+impl UpgradeRarity {
+    /// Relative chance of being offered
+    pub fn weight(&self) -> f32 {
+        match self {
+            UpgradeRarity::Common => 10.0,
+            UpgradeRarity::Rare => 4.0,
+            UpgradeRarity::Epic => 1.0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            UpgradeRarity::Common => Color::srgb(0.8, 0.8, 0.8),
+            UpgradeRarity::Rare => Color::srgb(0.3, 0.6, 1.0),
+            UpgradeRarity::Epic => Color::srgb(0.8, 0.4, 1.0),
+        }
+    }
+}
+
+/// Something offered on level up, or won from a chest
+#[derive(Clone)]
+pub(crate) enum LevelUpChoice {
+    Upgrade(UpgradeDefinition),
+    Evolution(&'static Evolution),
+}
+
+impl LevelUpChoice {
+    pub(crate) fn icon(&self) -> usize {
+        match self {
+            LevelUpChoice::Upgrade(upgrade) => upgrade.icon,
+            LevelUpChoice::Evolution(evolution) => evolution.icon,
+        }
+    }
+
+    pub(crate) fn color(&self) -> Color {
+        match self {
+            LevelUpChoice::Upgrade(upgrade) => upgrade.rarity.color(),
+            LevelUpChoice::Evolution(_) => Color::srgb(1.0, 0.8, 0.2),
+        }
+    }
+
+    pub(crate) fn label(&self) -> String {
+        match self {
+            LevelUpChoice::Upgrade(upgrade) => format!("{}\n{}", upgrade.name, upgrade.description),
+            LevelUpChoice::Evolution(evolution) => format!(
+                "{}\nEvolve {} with {}",
+                evolution.evolved.name(),
+                evolution.weapon.name(),
+                evolution.passive
+            ),
+        }
+    }
+
+    pub(crate) fn apply(&self, stats: &mut Stats, weapons: &mut Weapons) {
+        match self {
+            LevelUpChoice::Upgrade(upgrade) => upgrade.apply(stats, weapons),
+            LevelUpChoice::Evolution(evolution) => weapons.evolve(evolution, stats),
+        }
+    }
+}
+
+/// Level ups still waiting for the player to pick an upgrade, by the player
+/// who levelled, oldest first
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct PendingLevelUps(pub VecDeque<Entity>);
+
+/// The player picked one of the level up choices, by its position in the
+/// menu. Reroll, banish and skip come after the choices
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UpgradeChosen {
+    pub index: usize,
+}
+
+/// Ways out of a level up's choices, each use spends a charge
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct LevelUpCharges {
+    /// Roll a fresh set of choices
+    pub rerolls: u32,
+    /// Take an upgrade out of the pool for the rest of the run
+    pub banishes: u32,
+    /// Take nothing this level
+    pub skips: u32,
+    /// Names of banished upgrades
+    pub banished: Vec<String>,
+}
+
+impl Default for LevelUpCharges {
+    fn default() -> Self {
+        return Self {
+            rerolls: STARTING_REROLLS,
+            banishes: STARTING_BANISHES,
+            skips: STARTING_SKIPS,
+            banished: Vec::new(),
+        };
+    }
+}
+
+impl LevelUpCharges {
+    fn remaining(&self, option: LevelUpOption) -> u32 {
+        match option {
+            LevelUpOption::Reroll => self.rerolls,
+            LevelUpOption::Banish => self.banishes,
+            LevelUpOption::Skip => self.skips,
+        }
+    }
+
+    fn spend(&mut self, option: LevelUpOption) -> bool {
+        let charges = match option {
+            LevelUpOption::Reroll => &mut self.rerolls,
+            LevelUpOption::Banish => &mut self.banishes,
+            LevelUpOption::Skip => &mut self.skips,
+        };
+        if *charges == 0 {
+            return false;
+        }
+        *charges -= 1;
+        return true;
+    }
+}
+
+/// Picked from the level up menu instead of a choice, in `OPTION_KEYS` order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LevelUpOption {
+    Reroll,
+    /// Picking a choice after this banishes it instead of taking it
+    Banish,
+    Skip,
+}
+
+impl LevelUpOption {
+    const ALL: [LevelUpOption; 3] = [
+        LevelUpOption::Reroll,
+        LevelUpOption::Banish,
+        LevelUpOption::Skip,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            LevelUpOption::Reroll => "Reroll",
+            LevelUpOption::Banish => "Banish",
+            LevelUpOption::Skip => "Skip",
+        }
+    }
+}
+
+/// The upgrade picker, the game is paused while it's open
+#[derive(Component)]
+pub struct LevelUpMenu {
+    /// Who the upgrade goes to
+    player: Entity,
+    /// In button order
+    choices: Vec<LevelUpChoice>,
+    /// The next choice picked is banished
+    banishing: bool,
+}
+
+#[derive(Component)]
+struct UpgradeButton(usize);
+
+#[derive(Component)]
+struct OptionButton(LevelUpOption);
+
+/// A menu label led by the key or button that picks it, reworded when the
+/// device being played with changes while it's up
+#[derive(Component)]
+struct HotkeyLabel {
+    key: KeyCode,
+    button: GamepadButton,
+    text: String,
+}
+
+impl HotkeyLabel {
+    fn text(&self, device: ActiveInputDevice) -> String {
+        return format!("{}. {}", device.glyph(self.key, self.button), self.text);
+    }
+}
+
+/// A player's weapons and passives, in the top left
+#[derive(Component)]
+struct InventoryHud {
+    player: Entity,
+}
+
+fn reset_pending_level_ups(mut pending: ResMut<PendingLevelUps>) {
+    pending.clear();
+}
+
+fn queue_level_ups(
+    mut level_ups: EventReader<LevelUp>,
+    players: Query<(), With<Player>>,
+    mut pending: ResMut<PendingLevelUps>,
+) {
+    for level_up in level_ups.read() {
+        if players.contains(level_up.entity) {
+            pending.push_back(level_up.entity);
+        }
+    }
+}
+
+/// Level ups wait for any open chest, so only one menu holds the pause
+fn level_up_waiting(
+    pending: Res<PendingLevelUps>,
+    menus: Query<(), Or<(With<LevelUpMenu>, With<ChestMenu>)>>,
+) -> bool {
+    return !pending.is_empty() && menus.is_empty();
+}
+
+fn open_level_up_menu(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut time: ResMut<Time<Virtual>>,
+    (assets, atlases, pools, save): (
+        Res<GameAssets>,
+        Res<AtlasRegistry>,
+        Res<Assets<UpgradePool>>,
+        Res<SaveData>,
+    ),
+    mut pending: ResMut<PendingLevelUps>,
+    players: Query<(&Stats, &Weapons, &LevelUpCharges, &PlayerSlot), With<Player>>,
+    device: Res<ActiveInputDevice>,
+) {
+    let (Some(pool), Some(&player)) = (pools.get(&assets.upgrades), pending.front()) else {
+        return;
+    };
+    let Ok((stats, weapons, charges, slot)) = players.get(player) else {
+        // died before getting to pick
+        finish_level_up(&mut pending, &mut time);
+        return;
+    };
+
+    // evolutions are always offered, upgrades fill the remaining slots
+    let mut choices: Vec<LevelUpChoice> = weapons
+        .available_evolutions(stats)
+        .take(CHOICES_PER_LEVEL)
+        .map(LevelUpChoice::Evolution)
+        .collect();
+
+    let offerable: Vec<&UpgradeDefinition> = pool
+        .upgrades
+        .iter()
+        .filter(|upgrade| {
+            pool.can_offer(upgrade, stats, weapons, &save)
+                && !charges.banished.contains(&upgrade.name)
+        })
+        .collect();
+    if let Ok(upgrades) = offerable.choose_multiple_weighted(
+        &mut **rng,
+        CHOICES_PER_LEVEL - choices.len(),
+        |upgrade| upgrade.rarity.weight(),
+    ) {
+        choices.extend(upgrades.map(|upgrade| LevelUpChoice::Upgrade((*upgrade).clone())));
+    }
+
+    if choices.is_empty() {
+        finish_level_up(&mut pending, &mut time);
+        return;
+    }
+
+    let title = match players.iter().len() {
+        1 => "Level Up!".to_string(),
+        _ => format!("Player {} Level Up!", **slot + 1),
+    };
+
+    time.pause();
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            Name::new("Level Up Menu"),
+        ))
+        .with_children(|menu| {
+            menu.spawn((Text::new(title), TextFont::from_font_size(40.0)));
+
+            for (i, choice) in choices.iter().enumerate() {
+                menu.spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(420.0),
+                        padding: UiRect::all(Val::Px(8.0)),
+                        column_gap: Val::Px(12.0),
+                        align_items: AlignItems::Center,
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderColor(choice.color()),
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.9)),
+                    UpgradeButton(i),
+                ))
+                .with_children(|button| {
+                    button.spawn((
+                        ImageNode::from_atlas_image(
+                            assets.spritesheet.clone(),
+                            TextureAtlas {
+                                layout: atlases.layout(ICON_SCALE),
+                                index: choice.icon(),
+                            },
+                        ),
+                        Node {
+                            width: Val::Px(48.0),
+                            height: Val::Px(48.0),
+                            ..default()
+                        },
+                    ));
+                    let label = HotkeyLabel {
+                        key: CHOICE_KEYS[i],
+                        button: CHOICE_BUTTONS[i],
+                        text: choice.label(),
+                    };
+                    button.spawn((
+                        Text::new(label.text(*device)),
+                        TextFont::from_font_size(18.0),
+                        TextColor(choice.color()),
+                        label,
+                    ));
+                });
+            }
+
+            menu.spawn(Node {
+                column_gap: Val::Px(12.0),
+                ..default()
+            })
+            .with_children(|options| {
+                for (i, option) in LevelUpOption::ALL.into_iter().enumerate() {
+                    let remaining = charges.remaining(option);
+                    let color = match remaining {
+                        0 => Color::srgb(0.4, 0.4, 0.4),
+                        _ => Color::WHITE,
+                    };
+                    let label = HotkeyLabel {
+                        key: OPTION_KEYS[i],
+                        button: OPTION_BUTTONS[i],
+                        text: format!("{} ({remaining})", option.name()),
+                    };
+                    options
+                        .spawn((
+                            Button,
+                            Node {
+                                padding: UiRect::all(Val::Px(8.0)),
+                                border: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BorderColor(color),
+                            BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.9)),
+                            OptionButton(option),
+                        ))
+                        .with_child((
+                            Text::new(label.text(*device)),
+                            TextFont::from_font_size(16.0),
+                            TextColor(color),
+                            label,
+                        ));
+                }
+            });
+        })
+        .insert(LevelUpMenu {
+            player,
+            choices,
+            banishing: false,
+        });
+}
+
+/// Run condition for gameplay input on the same buttons as the menu. Systems
+/// using it go before `choose_upgrade`, so the press closing the menu isn't
+/// seen by them either
+pub(crate) fn no_level_up_menu(menus: Query<(), With<LevelUpMenu>>) -> bool {
+    return menus.is_empty();
+}
+
+/// Picking a choice takes it, or banishes it and rerolls if banish was
+/// picked first. Rerolling closes the menu without finishing the level up,
+/// so it opens again with new choices
+pub(crate) fn choose_upgrade(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    mut pending: ResMut<PendingLevelUps>,
+    mut chosen: EventWriter<UpgradeChosen>,
+    (keys, gamepads, buttons, mut option_buttons): (
+        Res<ButtonInput<KeyCode>>,
+        Query<(Entity, &Gamepad)>,
+        Query<(&Interaction, &UpgradeButton), Changed<Interaction>>,
+        Query<(Ref<Interaction>, &OptionButton, &mut BorderColor)>,
+    ),
+    mut menus: Query<(Entity, &mut LevelUpMenu)>,
+    mut players: Query<
+        (
+            &mut Stats,
+            &mut Weapons,
+            &mut LevelUpCharges,
+            Option<&InputMap<Action>>,
+        ),
+        With<Player>,
+    >,
+) {
+    let Ok((menu, mut level_up)) = menus.single_mut() else {
+        return;
+    };
+    let Ok((mut stats, mut weapons, mut charges, input_map)) = players.get_mut(level_up.player)
+    else {
+        // left the game with the menu open
+        commands.entity(menu).despawn();
+        finish_level_up(&mut pending, &mut time);
+        return;
+    };
+
+    // only the levelling player's pad, or any pad if they haven't been given one
+    let own_pad = input_map.and_then(InputMap::gamepad);
+    let button_pressed = |button: &GamepadButton| {
+        return gamepads.iter().any(|(entity, gamepad)| {
+            own_pad.is_none_or(|own_pad| own_pad == entity) && gamepad.just_pressed(*button)
+        });
+    };
+
+    let clicked_option = option_buttons
+        .iter()
+        .find(|(interaction, ..)| interaction.is_changed() && **interaction == Interaction::Pressed)
+        .map(|(_, button, _)| button.0);
+    let pressed_option = OPTION_KEYS
+        .iter()
+        .position(|key| keys.just_pressed(*key))
+        .or_else(|| OPTION_BUTTONS.iter().position(&button_pressed))
+        .map(|i| LevelUpOption::ALL[i]);
+    if let Some(option) = clicked_option.or(pressed_option) {
+        let index = LevelUpOption::ALL
+            .iter()
+            .position(|o| *o == option)
+            .unwrap_or(0);
+        match option {
+            LevelUpOption::Banish if charges.banishes > 0 => {
+                level_up.banishing = !level_up.banishing;
+                for (_, button, mut border) in option_buttons.iter_mut() {
+                    if button.0 == LevelUpOption::Banish {
+                        *border = BorderColor(match level_up.banishing {
+                            true => Color::srgb(1.0, 0.3, 0.3),
+                            false => Color::WHITE,
+                        });
+                    }
+                }
+            }
+            LevelUpOption::Banish => return,
+            _ if !charges.spend(option) => return,
+            LevelUpOption::Reroll => commands.entity(menu).despawn(),
+            LevelUpOption::Skip => {
+                commands.entity(menu).despawn();
+                finish_level_up(&mut pending, &mut time);
+            }
+        }
+        chosen.write(UpgradeChosen {
+            index: CHOICES_PER_LEVEL + index,
+        });
+        return;
+    }
+
+    let clicked = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.0);
+    let pressed = CHOICE_KEYS
+        .iter()
+        .position(|key| keys.just_pressed(*key))
+        .or_else(|| CHOICE_BUTTONS.iter().position(&button_pressed));
+    let Some((index, choice)) = clicked
+        .or(pressed)
+        .and_then(|i| Some((i, level_up.choices.get(i)?)))
+    else {
+        return;
+    };
+
+    if level_up.banishing {
+        // evolutions aren't in the pool to be banished from
+        let LevelUpChoice::Upgrade(upgrade) = choice else {
+            return;
+        };
+        if !charges.spend(LevelUpOption::Banish) {
+            return;
+        }
+        charges.banished.push(upgrade.name.clone());
+        chosen.write(UpgradeChosen { index });
+        commands.entity(menu).despawn();
+        return;
+    }
+
+    chosen.write(UpgradeChosen { index });
+    choice.apply(&mut stats, &mut weapons);
+    commands.entity(menu).despawn();
+    finish_level_up(&mut pending, &mut time);
+}
+
+fn update_hotkey_labels(
+    device: Res<ActiveInputDevice>,
+    mut labels: Query<(&HotkeyLabel, &mut Text)>,
+) {
+    if !device.is_changed() {
+        return;
+    }
+
+    for (label, mut text) in labels.iter_mut() {
+        text.0 = label.text(*device);
+    }
+}
+
+/// Rebuilt whenever a player's weapons or passives change, with an empty
+/// box for each free inventory slot
+fn update_inventory_hud(
+    mut commands: Commands,
+    (assets, atlases, pools): (
+        Res<GameAssets>,
+        Res<AtlasRegistry>,
+        Res<Assets<UpgradePool>>,
+    ),
+    players: Query<
+        (Entity, &PlayerSlot, &Stats, &Weapons),
+        (With<Player>, Or<(Changed<Stats>, Changed<Weapons>)>),
+    >,
+    huds: Query<(Entity, &InventoryHud)>,
+    all_players: Query<(), With<Player>>,
+) {
+    for (hud, link) in huds.iter() {
+        if !all_players.contains(link.player) {
+            commands.entity(hud).despawn();
+        }
+    }
+
+    let Some(pool) = pools.get(&assets.upgrades) else {
+        return;
+    };
+    for (player, slot, stats, weapons) in players.iter() {
+        let hud = match huds.iter().find(|(_, link)| link.player == player) {
+            Some((hud, _)) => {
+                commands.entity(hud).despawn_related::<Children>();
+                hud
+            }
+            None => commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(16.0 + **slot as f32 * (INVENTORY_ICON_SIZE * 2.0 + 16.0)),
+                        left: Val::Px(16.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    InventoryHud { player },
+                    Name::new(format!("Inventory {}", **slot + 1)),
+                    StateScoped(GameState::InGame),
+                ))
+                .id(),
+        };
+
+        let weapon_icons: Vec<(Option<usize>, Option<u32>)> = weapons
+            .iter()
+            .map(|weapon| (pool.weapon_icon(weapon.kind), Some(weapon.level)))
+            .collect();
+        let passive_icons: Vec<(Option<usize>, Option<u32>)> = pool
+            .held_passives(stats)
+            .map(|upgrade| (Some(upgrade.icon), None))
+            .collect();
+        let rows = [(weapon_icons, MAX_WEAPONS), (passive_icons, MAX_PASSIVES)];
+
+        commands.entity(hud).with_children(|hud| {
+            for (icons, max) in rows {
+                hud.spawn(Node {
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for i in 0..max.max(icons.len()) {
+                        let mut icon = row.spawn((
+                            Node {
+                                width: Val::Px(INVENTORY_ICON_SIZE),
+                                height: Val::Px(INVENTORY_ICON_SIZE),
+                                align_items: AlignItems::End,
+                                justify_content: JustifyContent::End,
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+                        ));
+                        let Some(&(index, level)) = icons.get(i) else {
+                            continue;
+                        };
+                        if let Some(index) = index {
+                            icon.insert(ImageNode::from_atlas_image(
+                                assets.spritesheet.clone(),
+                                TextureAtlas {
+                                    layout: atlases.layout(ICON_SCALE),
+                                    index,
+                                },
+                            ));
+                        }
+                        if let Some(level) = level {
+                            icon.with_child((
+                                Text::new(level.to_string()),
+                                TextFont::from_font_size(12.0),
+                            ));
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// The game stays paused until every pending level up is dealt with
+fn finish_level_up(pending: &mut PendingLevelUps, time: &mut Time<Virtual>) {
+    pending.pop_front();
+    if pending.is_empty() {
+        time.unpause();
+    }
+}
@@ -0,0 +1,487 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::combat::{DamageType, Faction};
+use crate::controls::{AimDirection, aim_player};
+use crate::elites::{ELITE_HIGHLIGHT, Elite};
+use crate::highlight::Highlight;
+use crate::pool::{Pool, PoolKey};
+use crate::projectile::{
+    Bounce, Homing, Pierce, Projectile, ProjectileAssets, SplitOnHit, Trajectory, ZoneOnHit,
+    projectile_bundle,
+};
+use crate::props::Piercing;
+use crate::spatial::{SpatialGrid, SpatialGridPlugin, SpatialSet};
+use crate::stats::{Stat, Stats};
+use crate::unlocks::Unlock;
+use crate::{Enemy, GameplaySet, Player};
+
+const CARROT_RANGE: f32 = 30.0;
+const CARROT_SPEED: f32 = 30.0;
+const CARROT_LIFETIME: f32 = 2.0;
+/// Angle between neighbouring bolts of an aimed shot
+const AIMED_BOLT_SPREAD: f32 = 0.15;
+/// Carrot Barrage bolts splatter, leaving a patch that keeps burning enemies
+const SPLATTER_RADIUS: f32 = 1.5;
+const SPLATTER_SECONDS: f32 = 2.0;
+/// Splatter damage per second, as a share of the bolt's damage
+const SPLATTER_DPS: f32 = 0.5;
+/// Outline drawn around the enemy auto-aimed bolts go for first
+const TARGET_HIGHLIGHT: Color = Color::srgb(1.0, 0.2, 0.2);
+/// How far out a boomerang flies, and how far it swings to the side
+const BOOMERANG_REACH: f32 = 12.0;
+const BOOMERANG_WIDTH: f32 = 3.0;
+/// Seconds for a boomerang to get there and back
+const BOOMERANG_SECONDS: f32 = 1.6;
+/// Angle between neighbouring boomerangs of a throw
+const BOOMERANG_SPREAD: f32 = 0.6;
+const THORN_RADIUS: f32 = 4.0;
+const BRAMBLE_RADIUS: f32 = 7.0;
+/// Weapons reach well past a cell, but most enemies are far beyond that
+const ENEMY_CELL_SIZE: f32 = 8.0;
+/// Extra damage per weapon level past the first, as a fraction of base damage
+const DAMAGE_PER_LEVEL: f32 = 0.25;
+/// Extra aura radius per level past the first, as a fraction of base radius
+const RADIUS_PER_LEVEL: f32 = 0.1;
+/// Weapons stop levelling here, and can evolve once they reach it
+pub const MAX_WEAPON_LEVEL: u32 = 5;
+/// Different weapons a player can carry at once
+pub const MAX_WEAPONS: usize = 4;
+
+/// A maxed `weapon` held alongside the `passive` upgrade can evolve into
+/// `evolved`, which replaces both
+pub struct Evolution {
+    pub weapon: WeaponKind,
+    /// Name of the upgrade in the upgrade pool
+    pub passive: &'static str,
+    pub evolved: WeaponKind,
+    /// Cell of the upgrade icon sheet
+    pub icon: usize,
+}
+
+pub const EVOLUTIONS: &[Evolution] = &[
+    Evolution {
+        weapon: WeaponKind::CarrotBolt,
+        passive: "Quick Thumping",
+        evolved: WeaponKind::CarrotBarrage,
+        icon: 10,
+    },
+    Evolution {
+        weapon: WeaponKind::ThornAura,
+        passive: "Thick Fur",
+        evolved: WeaponKind::BrambleField,
+        icon: 11,
+    },
+];
+
+pub struct WeaponsPlugin;
+
+impl Plugin for WeaponsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Weapons>();
+        app.add_plugins(SpatialGridPlugin::<Enemy>::new(ENEMY_CELL_SIZE));
+        app.add_systems(
+            Update,
+            (
+                fire_weapons.after(SpatialSet).after(aim_player),
+                highlight_auto_aim_targets.after(SpatialSet),
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Deserialize)]
+pub enum WeaponKind {
+    /// Fires bolts at the nearest enemies, one more every few levels
+    CarrotBolt,
+    /// Periodically damages every enemy in a field around the player
+    ThornAura,
+    /// Evolved `CarrotBolt`, fires a spread of bolts at once
+    CarrotBarrage,
+    /// Thrown out in a loop that comes back round to the player, hitting
+    /// enemies both ways
+    Boomerang,
+    /// A pet following the player around, shooting at enemies near it
+    Pet,
+    /// Evolved `ThornAura`, wider and slows what it hits
+    BrambleField,
+}
+
+impl WeaponKind {
+    pub const ALL: [WeaponKind; 6] = [
+        WeaponKind::CarrotBolt,
+        WeaponKind::ThornAura,
+        WeaponKind::CarrotBarrage,
+        WeaponKind::Boomerang,
+        WeaponKind::Pet,
+        WeaponKind::BrambleField,
+    ];
+
+    pub fn base_damage(&self) -> f32 {
+        match self {
+            WeaponKind::CarrotBolt => 8.0,
+            WeaponKind::ThornAura => 4.0,
+            WeaponKind::CarrotBarrage => 14.0,
+            WeaponKind::Boomerang => 10.0,
+            WeaponKind::Pet => 5.0,
+            WeaponKind::BrambleField => 7.0,
+        }
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        match self {
+            WeaponKind::CarrotBolt
+            | WeaponKind::ThornAura
+            | WeaponKind::CarrotBarrage
+            | WeaponKind::Boomerang => DamageType::Physical,
+            WeaponKind::Pet => DamageType::Arcane,
+            WeaponKind::BrambleField => DamageType::Poison,
+        }
+    }
+
+    pub fn base_cooldown(&self) -> f32 {
+        match self {
+            WeaponKind::CarrotBolt => 1.0,
+            WeaponKind::ThornAura => 0.5,
+            WeaponKind::CarrotBarrage => 0.8,
+            WeaponKind::Boomerang => 1.8,
+            WeaponKind::Pet => 0.8,
+            WeaponKind::BrambleField => 0.5,
+        }
+    }
+
+    /// What it takes before level ups can offer it
+    pub fn unlock(&self) -> Unlock {
+        match self {
+            WeaponKind::Boomerang => Unlock::Achievement("achievement.hare_raiser"),
+            WeaponKind::Pet => Unlock::Gold {
+                id: "weapon.pet",
+                cost: 250,
+            },
+            _ => Unlock::Free,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeaponKind::CarrotBolt => "Carrot Bolt",
+            WeaponKind::ThornAura => "Thorn Aura",
+            WeaponKind::CarrotBarrage => "Carrot Barrage",
+            WeaponKind::Boomerang => "Boomerang",
+            WeaponKind::Pet => "Bunny Buddy",
+            WeaponKind::BrambleField => "Bramble Field",
+        }
+    }
+
+    /// Bolts fired per shot, for the bolt weapons, or boomerangs per throw
+    fn bolts(&self, level: u32) -> usize {
+        match self {
+            WeaponKind::CarrotBarrage => 4 + level as usize / 2,
+            _ => 1 + level as usize / 3,
+        }
+    }
+
+    /// Whether it fires where the player aims, when they do
+    pub fn manual_aim(&self) -> bool {
+        return matches!(
+            self,
+            WeaponKind::CarrotBolt | WeaponKind::CarrotBarrage | WeaponKind::Boomerang
+        );
+    }
+
+    fn radius(&self) -> f32 {
+        match self {
+            WeaponKind::BrambleField => BRAMBLE_RADIUS,
+            _ => THORN_RADIUS,
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone)]
+pub struct Weapon {
+    pub kind: WeaponKind,
+    pub level: u32,
+    cooldown: Timer,
+}
+
+impl Weapon {
+    pub fn new(kind: WeaponKind) -> Self {
+        return Self {
+            kind,
+            level: 1,
+            cooldown: Timer::from_seconds(kind.base_cooldown(), TimerMode::Repeating),
+        };
+    }
+
+    pub fn damage(&self, stats: &Stats) -> f32 {
+        let level_bonus = 1.0 + DAMAGE_PER_LEVEL * (self.level - 1) as f32;
+        return self.kind.base_damage() * level_bonus * stats.get(Stat::Damage);
+    }
+
+    /// How far an aura weapon reaches, growing with level and area
+    pub fn aura_radius(&self, stats: &Stats) -> f32 {
+        let level_bonus = 1.0 + RADIUS_PER_LEVEL * (self.level - 1) as f32;
+        return self.kind.radius() * level_bonus * stats.get(Stat::Area);
+    }
+
+    /// Whether its cooldown finished this frame, `fire_weapons` ticks it
+    pub fn just_fired(&self) -> bool {
+        return self.cooldown.just_finished();
+    }
+}
+
+/// Every weapon an entity is carrying
+#[derive(Component, Reflect, Deref, DerefMut, Default, Debug)]
+#[reflect(Component)]
+pub struct Weapons(Vec<Weapon>);
+
+impl Weapons {
+    pub fn new(kinds: impl IntoIterator<Item = WeaponKind>) -> Self {
+        return Self(kinds.into_iter().map(Weapon::new).collect());
+    }
+
+    pub fn get(&self, kind: WeaponKind) -> Option<&Weapon> {
+        return self.iter().find(|weapon| weapon.kind == kind);
+    }
+
+    /// Whether granting `kind` would do anything, it isn't maxed or already
+    /// evolved, and there's room for it if it's new
+    pub fn can_grant(&self, kind: WeaponKind) -> bool {
+        let evolved = EVOLUTIONS
+            .iter()
+            .any(|e| e.weapon == kind && self.get(e.evolved).is_some());
+        let fits = match self.get(kind) {
+            Some(weapon) => weapon.level < MAX_WEAPON_LEVEL,
+            None => self.len() < MAX_WEAPONS,
+        };
+        return !evolved && fits;
+    }
+
+    /// Add a weapon, or level it up if it's already carried
+    pub fn grant(&mut self, kind: WeaponKind) {
+        if !self.can_grant(kind) {
+            return;
+        }
+
+        match self.iter_mut().find(|weapon| weapon.kind == kind) {
+            Some(weapon) => weapon.level += 1,
+            None => self.push(Weapon::new(kind)),
+        }
+    }
+
+    /// Evolutions whose weapon is maxed and whose passive is held
+    pub fn available_evolutions<'a>(
+        &'a self,
+        stats: &'a Stats,
+    ) -> impl Iterator<Item = &'static Evolution> + 'a {
+        return EVOLUTIONS.iter().filter(|evolution| {
+            self.get(evolution.weapon)
+                .is_some_and(|w| w.level >= MAX_WEAPON_LEVEL)
+                && stats.has_source(evolution.passive)
+        });
+    }
+
+    /// Swap the base weapon and the passive's modifiers for the evolved weapon
+    pub fn evolve(&mut self, evolution: &Evolution, stats: &mut Stats) {
+        let Some(index) = self.iter().position(|w| w.kind == evolution.weapon) else {
+            return;
+        };
+
+        self[index] = Weapon::new(evolution.evolved);
+        stats.remove_source(evolution.passive);
+    }
+}
+
+pub fn fire_weapons(
+    mut commands: Commands,
+    time: Res<Time>,
+    (assets, mut pool): (Res<ProjectileAssets>, ResMut<Pool>),
+    mut players: Query<(Entity, &Transform, &Stats, &mut Weapons, &AimDirection), With<Player>>,
+    enemies: Res<SpatialGrid<Enemy>>,
+) {
+    for (player, transform, stats, mut weapons, aim) in players.iter_mut() {
+        let pos = transform.translation.xz();
+        // a lower cooldown stat makes every weapon tick faster
+        let delta = time.delta().div_f32(stats.get(Stat::Cooldown).max(0.1));
+
+        for weapon in weapons.iter_mut() {
+            if !weapon.cooldown.tick(delta).just_finished() {
+                continue;
+            }
+
+            let amount = weapon.damage(stats);
+            match weapon.kind {
+                WeaponKind::CarrotBolt | WeaponKind::CarrotBarrage => {
+                    let bolts = weapon.kind.bolts(weapon.level);
+                    let pierce = stats.get(Stat::Pierce).max(0.0) as u32;
+                    let bounce = stats.get(Stat::Bounce).max(0.0) as u32;
+                    let split = stats.get(Stat::Split).max(0.0) as u32;
+                    let homing = stats.get(Stat::Homing);
+                    let targets: Vec<Vec2> = match aim.0.filter(|_| weapon.kind.manual_aim()) {
+                        // fan the bolts out evenly around the aim
+                        Some(aim) => (0..bolts)
+                            .map(|i| {
+                                let offset = i as f32 - (bolts - 1) as f32 / 2.0;
+                                Vec2::from_angle(offset * AIMED_BOLT_SPREAD).rotate(aim)
+                            })
+                            .collect(),
+                        None => {
+                            let mut targets: Vec<Vec2> = enemies
+                                .within_radius(pos, CARROT_RANGE)
+                                .map(|(_, enemy)| enemy - pos)
+                                .collect();
+                            targets
+                                .sort_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+                            targets.truncate(bolts);
+                            targets
+                        }
+                    };
+
+                    for offset in targets {
+                        let dir = Vec3::new(offset.x, 0.0, offset.y).normalize_or(Vec3::X);
+                        let bolt = pool.spawn(
+                            &mut commands,
+                            PoolKey::Projectile,
+                            projectile_bundle(
+                                assets.mesh.clone(),
+                                assets.player_material.clone(),
+                                transform.translation + dir,
+                                dir * CARROT_SPEED,
+                                Projectile {
+                                    damage: amount,
+                                    hits: Faction::Enemy,
+                                    lifetime: Timer::from_seconds(CARROT_LIFETIME, TimerMode::Once),
+                                    owner: Some(player),
+                                    weapon: Some(weapon.kind),
+                                    kind: weapon.kind.damage_type(),
+                                },
+                            ),
+                        );
+                        if weapon.kind == WeaponKind::CarrotBarrage {
+                            commands.entity(bolt).insert((
+                                Piercing,
+                                ZoneOnHit {
+                                    radius: SPLATTER_RADIUS,
+                                    dps: amount * SPLATTER_DPS,
+                                    seconds: SPLATTER_SECONDS,
+                                },
+                            ));
+                        }
+                        if pierce > 0 {
+                            commands.entity(bolt).insert(Pierce(pierce));
+                        }
+                        if bounce > 0 {
+                            commands.entity(bolt).insert(Bounce(bounce));
+                        }
+                        if split > 0 {
+                            commands.entity(bolt).insert(SplitOnHit { count: split });
+                        }
+                        if homing > 0.0 {
+                            // picks the nearest enemy as it goes
+                            commands.entity(bolt).insert(Homing {
+                                turn_rate: homing,
+                                target: None,
+                            });
+                        }
+                    }
+                }
+                WeaponKind::Boomerang => {
+                    let throws = weapon.kind.bolts(weapon.level);
+                    let forward = aim
+                        .0
+                        .or_else(|| {
+                            let (_, enemy) = enemies.nearest(pos, BOOMERANG_REACH)?;
+                            return Some(enemy - pos);
+                        })
+                        .and_then(Vec2::try_normalize)
+                        .unwrap_or(Vec2::X);
+
+                    for i in 0..throws {
+                        let offset = i as f32 - (throws - 1) as f32 / 2.0;
+                        let dir = Vec2::from_angle(offset * BOOMERANG_SPREAD).rotate(forward);
+                        let boomerang = pool.spawn(
+                            &mut commands,
+                            PoolKey::Projectile,
+                            projectile_bundle(
+                                assets.mesh.clone(),
+                                assets.player_material.clone(),
+                                transform.translation,
+                                Vec3::ZERO,
+                                Projectile {
+                                    damage: amount,
+                                    hits: Faction::Enemy,
+                                    lifetime: Timer::from_seconds(
+                                        BOOMERANG_SECONDS,
+                                        TimerMode::Once,
+                                    ),
+                                    owner: Some(player),
+                                    weapon: Some(weapon.kind),
+                                    kind: weapon.kind.damage_type(),
+                                },
+                            ),
+                        );
+                        commands.entity(boomerang).insert((
+                            Trajectory::Boomerang {
+                                origin: transform.translation,
+                                reach: Vec3::new(dir.x, 0.0, dir.y) * BOOMERANG_REACH,
+                                width: BOOMERANG_WIDTH,
+                            },
+                            // comes back for whatever it passed on the way out
+                            Pierce(u32::MAX),
+                        ));
+                    }
+                }
+                // the player's `Pet` does the attacking
+                WeaponKind::Pet => {}
+                // the player's `Aura` deals the damage
+                WeaponKind::ThornAura | WeaponKind::BrambleField => {}
+            }
+        }
+    }
+}
+
+/// Outline the enemy each player's auto-aimed bolts go for first, nearest
+/// in range. Players aiming by hand have no target
+fn highlight_auto_aim_targets(
+    mut commands: Commands,
+    mut targeted: Local<Vec<Entity>>,
+    players: Query<(&Transform, &Weapons, &AimDirection), With<Player>>,
+    enemies: Res<SpatialGrid<Enemy>>,
+    elites: Query<(), With<Elite>>,
+) {
+    let targets: Vec<Entity> = players
+        .iter()
+        .filter(|(_, weapons, aim)| {
+            aim.0.is_none()
+                && weapons.iter().any(|weapon| {
+                    matches!(
+                        weapon.kind,
+                        WeaponKind::CarrotBolt | WeaponKind::CarrotBarrage
+                    )
+                })
+        })
+        .filter_map(|(transform, _, _)| {
+            let pos = transform.translation.xz();
+            let (nearest, _) =
+                enemies
+                    .within_radius(pos, CARROT_RANGE)
+                    .min_by(|(_, a), (_, b)| {
+                        a.distance_squared(pos).total_cmp(&b.distance_squared(pos))
+                    })?;
+            return Some(nearest);
+        })
+        .collect();
+
+    for &old in targeted.iter().filter(|entity| !targets.contains(entity)) {
+        // back to an elite's own outline, or none
+        match elites.contains(old) {
+            true => commands.entity(old).try_insert(Highlight(ELITE_HIGHLIGHT)),
+            false => commands.entity(old).try_remove::<Highlight>(),
+        };
+    }
+    for &new in targets.iter().filter(|entity| !targeted.contains(entity)) {
+        commands.entity(new).try_insert(Highlight(TARGET_HIGHLIGHT));
+    }
+    *targeted = targets;
+}
@@ -0,0 +1,314 @@
+use std::f32::consts::TAU;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::Player;
+use crate::hazards::{HazardAssets, HazardKind, hazard};
+use crate::level::ActiveLevel;
+use crate::platforms::{
+    PlatformAssets, SurfaceProperties, bounce_pad, moving_platform, surface_patch,
+};
+use crate::prefabs::GroundBundle;
+use crate::props::{PropAssets, spawn_random_prop};
+use crate::rng::GameSeed;
+use crate::shrines::{ShrineAssets, shrine};
+
+/// Width of a square ground chunk
+pub const CHUNK_SIZE: f32 = 32.0;
+/// Chunks kept loaded in each direction around the player
+const STREAM_RADIUS: i32 = 3;
+/// Extra distance, in chunks, before a loaded chunk is unloaded, so walking
+/// back and forth over a chunk border doesn't churn chunks
+const UNLOAD_MARGIN: i32 = 1;
+const MAX_PROPS_PER_CHUNK: u32 = 3;
+const RAMP_CHANCE: f64 = 0.15;
+const RAMP_ANGLE: f32 = 15.0;
+const HAZARD_CHANCE: f64 = 0.1;
+const PLATFORM_CHANCE: f64 = 0.08;
+/// How far a platform travels from one end of its path to the other
+const PLATFORM_TRAVEL: f32 = 14.0;
+const PLATFORM_SPEED: f32 = 4.0;
+const BOUNCE_PAD_CHANCE: f64 = 0.1;
+/// Straight up, enough to clear a few enemies
+const BOUNCE_PAD_SPEED: f32 = 50.0;
+const SURFACE_CHANCE: f64 = 0.12;
+const CONVEYOR_SPEED: f32 = 8.0;
+const SHRINE_CHANCE: f64 = 0.04;
+/// Keep the player's spawn point free of props
+const CLEAR_RADIUS: f32 = 10.0;
+
+pub struct WorldPlugin;
+
+impl Plugin for WorldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadedChunks>();
+        app.add_systems(Startup, setup_world_assets);
+        app.add_systems(Update, stream_chunks.run_if(level_has_terrain));
+    }
+}
+
+/// Shared meshes and materials for generated terrain
+#[derive(Resource)]
+pub struct WorldAssets {
+    pub chunk_mesh: Handle<Mesh>,
+    /// Alternated in a checkerboard so movement is readable
+    pub ground_materials: [Handle<StandardMaterial>; 2],
+    pub ramp_mesh: Handle<Mesh>,
+    pub ramp_material: Handle<StandardMaterial>,
+}
+
+/// A ground tile at `coord`, in chunks from the origin
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component)]
+pub struct Chunk(pub IVec2);
+
+/// Every chunk currently spawned, by coordinate
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct LoadedChunks(HashMap<IVec2, Entity>);
+
+fn setup_world_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(WorldAssets {
+        chunk_mesh: meshes.add(
+            Plane3d::default()
+                .mesh()
+                .size(CHUNK_SIZE, CHUNK_SIZE)
+                .subdivisions(4),
+        ),
+        ground_materials: [
+            materials.add(Color::srgb(0.35, 0.55, 0.25)),
+            materials.add(Color::srgb(0.32, 0.5, 0.23)),
+        ],
+        ramp_mesh: meshes.add(Cuboid::new(8.0, 1.0, 16.0)),
+        ramp_material: materials.add(Color::srgb(0.55, 0.45, 0.3)),
+    });
+}
+
+/// The RNG a chunk is generated from, so a chunk always comes out the same
+/// for a seed no matter which order chunks are generated in
+pub fn chunk_rng(seed: u64, coord: IVec2) -> StdRng {
+    let x = coord.x as u32 as u64;
+    let y = coord.y as u32 as u64;
+    return StdRng::seed_from_u64(
+        seed ^ x.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ y.wrapping_mul(0xC2B2_AE3D_27D4_EB4F),
+    );
+}
+
+/// The coordinate of the chunk containing `position`
+pub fn chunk_coord(position: Vec3) -> IVec2 {
+    return (position.xz() / CHUNK_SIZE).round().as_ivec2();
+}
+
+/// Where the center of the chunk at `coord` sits in the world
+pub fn chunk_center(coord: IVec2) -> Vec3 {
+    return Vec3::new(coord.x as f32, 0.0, coord.y as f32) * CHUNK_SIZE;
+}
+
+/// Spawn the ground tile at `coord` with its props, ramps, platforms, bounce
+/// pads, surfaces and shrines as children, so despawning the chunk cleans all
+/// of it up
+pub fn spawn_chunk(
+    commands: &mut Commands,
+    assets: &WorldAssets,
+    prop_assets: &PropAssets,
+    (hazard_assets, platform_assets, shrine_assets): (
+        &HazardAssets,
+        &PlatformAssets,
+        &ShrineAssets,
+    ),
+    seed: u64,
+    coord: IVec2,
+) -> Entity {
+    let mut rng = chunk_rng(seed, coord);
+    let center = chunk_center(coord);
+    let checker = (coord.x + coord.y).rem_euclid(2) as usize;
+
+    let mut chunk = commands.spawn((
+        Mesh3d(assets.chunk_mesh.clone()),
+        MeshMaterial3d(assets.ground_materials[checker].clone()),
+        Transform::from_translation(center),
+        Name::new(format!("Chunk {}, {}", coord.x, coord.y)),
+        Chunk(coord),
+        RigidBody::Fixed,
+        GroundBundle::new(Collider::cuboid(CHUNK_SIZE / 2.0, 0.1, CHUNK_SIZE / 2.0)),
+    ));
+
+    chunk.with_children(|chunk| {
+        let half = CHUNK_SIZE / 2.0;
+        for _ in 0..rng.random_range(0..=MAX_PROPS_PER_CHUNK) {
+            let offset = Vec3::new(
+                rng.random_range(-half..half),
+                0.0,
+                rng.random_range(-half..half),
+            );
+            if (center + offset).xz().length() < CLEAR_RADIUS {
+                continue;
+            }
+
+            spawn_random_prop(chunk, prop_assets, offset, &mut rng);
+        }
+
+        if rng.random_bool(RAMP_CHANCE) && center.xz().length() >= CLEAR_RADIUS {
+            let yaw = rng.random_range(0..4) as f32 * TAU / 4.0;
+            chunk.spawn((
+                Mesh3d(assets.ramp_mesh.clone()),
+                MeshMaterial3d(assets.ramp_material.clone()),
+                Transform::from_translation(Vec3::Y * 1.5).with_rotation(
+                    Quat::from_rotation_y(yaw) * Quat::from_rotation_x(RAMP_ANGLE.to_radians()),
+                ),
+                Name::new("Ramp"),
+                GroundBundle::new(Collider::cuboid(4.0, 0.5, 8.0)),
+            ));
+        }
+
+        if rng.random_bool(HAZARD_CHANCE) {
+            let offset = Vec3::new(
+                rng.random_range(-half..half),
+                0.0,
+                rng.random_range(-half..half),
+            );
+            let kind = match rng.random_bool(0.5) {
+                true => HazardKind::Spikes,
+                false => HazardKind::PoisonPool,
+            };
+            let radius = rng.random_range(2.0..4.0);
+            if (center + offset).xz().length() >= CLEAR_RADIUS + radius {
+                chunk.spawn(hazard(hazard_assets, kind, offset, radius));
+            }
+        }
+
+        // back and forth along one axis, low enough to step onto
+        if rng.random_bool(PLATFORM_CHANCE) && center.xz().length() >= CLEAR_RADIUS {
+            let axis = match rng.random_bool(0.5) {
+                true => Vec3::X,
+                false => Vec3::Z,
+            };
+            let start = Vec3::Y * 0.25 - axis * PLATFORM_TRAVEL / 2.0;
+            chunk.spawn(moving_platform(
+                platform_assets,
+                vec![start, start + axis * PLATFORM_TRAVEL],
+                PLATFORM_SPEED,
+            ));
+        }
+
+        if rng.random_bool(BOUNCE_PAD_CHANCE) {
+            let offset = Vec3::new(
+                rng.random_range(-half..half),
+                0.0,
+                rng.random_range(-half..half),
+            );
+            if (center + offset).xz().length() >= CLEAR_RADIUS {
+                chunk.spawn(bounce_pad(platform_assets, offset, BOUNCE_PAD_SPEED));
+            }
+        }
+
+        if rng.random_bool(SURFACE_CHANCE) {
+            let offset = Vec3::new(
+                rng.random_range(-half / 2.0..half / 2.0),
+                0.0,
+                rng.random_range(-half / 2.0..half / 2.0),
+            );
+            let size = Vec2::new(rng.random_range(6.0..14.0), rng.random_range(6.0..14.0));
+            let surface = match rng.random_range(0..3) {
+                0 => SurfaceProperties::ice(),
+                1 => SurfaceProperties::mud(),
+                _ => {
+                    let yaw = rng.random_range(0..4) as f32 * TAU / 4.0;
+                    SurfaceProperties::conveyor(
+                        Quat::from_rotation_y(yaw) * Vec3::X * CONVEYOR_SPEED,
+                    )
+                }
+            };
+            if (center + offset).xz().length() >= CLEAR_RADIUS + size.max_element() {
+                chunk.spawn(surface_patch(platform_assets, surface, offset, size));
+            }
+        }
+
+        if rng.random_bool(SHRINE_CHANCE) {
+            let offset = Vec3::new(
+                rng.random_range(-half / 2.0..half / 2.0),
+                0.0,
+                rng.random_range(-half / 2.0..half / 2.0),
+            );
+            if (center + offset).xz().length() >= CLEAR_RADIUS {
+                chunk.spawn(shrine(shrine_assets, coord, offset));
+            }
+        }
+    });
+
+    return chunk.id();
+}
+
+fn level_has_terrain(level: Option<Res<ActiveLevel>>) -> bool {
+    return level.is_some_and(|level| level.terrain);
+}
+
+/// Keep ground under and around the player, or the origin outside of runs
+fn stream_chunks(
+    mut commands: Commands,
+    assets: Res<WorldAssets>,
+    prop_assets: Res<PropAssets>,
+    (hazard_assets, platform_assets, shrine_assets): (
+        Res<HazardAssets>,
+        Res<PlatformAssets>,
+        Res<ShrineAssets>,
+    ),
+    seed: Res<GameSeed>,
+    mut loaded: ResMut<LoadedChunks>,
+    players: Query<&Transform, With<Player>>,
+) {
+    // terrain streams in around every player
+    let mut centers: Vec<IVec2> = players
+        .iter()
+        .map(|transform| chunk_coord(transform.translation))
+        .collect();
+    if centers.is_empty() {
+        centers.push(IVec2::ZERO);
+    }
+
+    // a new run brings a new seed, and with it different terrain
+    if seed.is_changed() {
+        for (_, entity) in loaded.drain() {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    loaded.retain(|coord, entity| {
+        let distance = centers
+            .iter()
+            .map(|center| (*coord - *center).abs().max_element())
+            .min()
+            .unwrap_or(0);
+        if distance <= STREAM_RADIUS + UNLOAD_MARGIN {
+            return true;
+        }
+        commands.entity(*entity).despawn();
+        return false;
+    });
+
+    for center in centers {
+        for x in -STREAM_RADIUS..=STREAM_RADIUS {
+            for y in -STREAM_RADIUS..=STREAM_RADIUS {
+                let coord = center + IVec2::new(x, y);
+                if !loaded.contains_key(&coord) {
+                    let chunk = spawn_chunk(
+                        &mut commands,
+                        &assets,
+                        &prop_assets,
+                        (&hazard_assets, &platform_assets, &shrine_assets),
+                        **seed,
+                        coord,
+                    );
+                    loaded.insert(coord, chunk);
+                }
+            }
+        }
+    }
+}
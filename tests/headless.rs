@@ -0,0 +1,382 @@
+//! Drives the game without a window, stepping frames by hand
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy_rapier3d::prelude::{Collider, CollisionGroups, RigidBody, Velocity};
+use bunvivor::cli::LaunchOptions;
+use bunvivor::enemies::spawn_enemy;
+use bunvivor::physics::layers;
+use bunvivor::{App, AppConfig, GameState, MainCamera, MoveVector, Player, PlayerSlot};
+
+/// Loading finishes asynchronously, so give it plenty of frames
+const MAX_LOADING_FRAMES: usize = 600;
+/// How far a replayed body may end up from where it was recorded
+const REPLAY_TOLERANCE: f32 = 0.25;
+
+fn headless_app() -> App {
+    let mut app = App::new_headless();
+    app.world_mut()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )));
+    return app;
+}
+
+fn state(app: &App) -> GameState {
+    return *app.world().resource::<State<GameState>>().get();
+}
+
+fn finish_loading(app: &mut App) {
+    for _ in 0..MAX_LOADING_FRAMES {
+        app.update();
+        if state(app) != GameState::Loading {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    panic!("assets never finished loading");
+}
+
+fn start_run(app: &mut App) {
+    finish_loading(app);
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::InGame);
+    app.update();
+}
+
+#[test]
+fn loading_leads_to_the_main_menu() {
+    let mut app = headless_app();
+    finish_loading(&mut app);
+    assert_eq!(state(&app), GameState::MainMenu);
+}
+
+#[test]
+fn starting_a_run_spawns_the_player() {
+    let mut app = headless_app();
+    start_run(&mut app);
+
+    let players = app
+        .world_mut()
+        .query_filtered::<(), With<Player>>()
+        .iter(app.world())
+        .count();
+    assert_eq!(players, 1);
+}
+
+#[test]
+fn runs_survive_the_player_going_missing() {
+    let mut app = headless_app();
+    start_run(&mut app);
+
+    let players: Vec<Entity> = app
+        .world_mut()
+        .query_filtered::<Entity, With<Player>>()
+        .iter(app.world())
+        .collect();
+    for player in players {
+        app.world_mut().despawn(player);
+    }
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(state(&app), GameState::InGame);
+}
+
+#[test]
+fn co_op_runs_spawn_a_player_per_slot() {
+    let mut app = App::with_config(AppConfig {
+        launch: LaunchOptions {
+            players: Some(2),
+            skip_menu: true,
+            ..default()
+        },
+        ..AppConfig::headless()
+    });
+    finish_loading(&mut app);
+    app.update();
+
+    let mut slots: Vec<usize> = app
+        .world_mut()
+        .query_filtered::<&PlayerSlot, With<Player>>()
+        .iter(app.world())
+        .map(|slot| **slot)
+        .collect();
+    slots.sort();
+    assert_eq!(slots, [0, 1]);
+}
+
+#[test]
+fn grunts_chase_the_player() {
+    let mut app = headless_app();
+    start_run(&mut app);
+
+    let grunt = spawn_enemy(app.world_mut(), "grunt", Vec3::new(20.0, 1.0, 0.0)).unwrap();
+    for _ in 0..5 {
+        app.update();
+    }
+
+    let move_vec = app.world().get::<MoveVector>(grunt).unwrap();
+    assert!(
+        move_vec.x < 0.0,
+        "grunt should head for the player at the origin"
+    );
+}
+
+#[test]
+fn stragglers_are_brought_back_near_the_player() {
+    let mut app = headless_app();
+    start_run(&mut app);
+
+    let straggler = spawn_enemy(app.world_mut(), "grunt", Vec3::new(500.0, 1.0, 0.0)).unwrap();
+    app.update();
+
+    let distance = app
+        .world()
+        .get::<Transform>(straggler)
+        .unwrap()
+        .translation
+        .xz()
+        .length();
+    assert!(distance < 100.0, "straggler left {distance} away");
+}
+
+#[test]
+fn the_camera_comes_in_past_walls_hiding_the_player() {
+    let mut app = headless_app();
+    start_run(&mut app);
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let camera_distance = |app: &mut App| {
+        app.world_mut()
+            .query_filtered::<&Transform, With<MainCamera>>()
+            .single(app.world())
+            .unwrap()
+            .translation
+            .length()
+    };
+    let clear = camera_distance(&mut app);
+
+    // a wall halfway along the camera's line of sight
+    let midpoint = app
+        .world_mut()
+        .query_filtered::<&Transform, With<MainCamera>>()
+        .single(app.world())
+        .unwrap()
+        .translation
+        / 2.0;
+    app.world_mut().spawn((
+        Transform::from_translation(midpoint),
+        RigidBody::Fixed,
+        Collider::cuboid(5.0, 5.0, 0.5),
+        layers::ground(),
+    ));
+    for _ in 0..60 {
+        app.update();
+    }
+
+    let hidden = camera_distance(&mut app);
+    assert!(
+        hidden < clear / 2.0,
+        "camera stayed {hidden} away, {clear} when clear"
+    );
+}
+
+#[test]
+fn every_collider_is_given_collision_groups() {
+    let mut app = headless_app();
+    start_run(&mut app);
+
+    spawn_enemy(app.world_mut(), "grunt", Vec3::new(20.0, 1.0, 0.0)).unwrap();
+    app.update();
+
+    let ungrouped: Vec<_> = app
+        .world_mut()
+        .query_filtered::<NameOrEntity, (With<Collider>, Without<CollisionGroups>)>()
+        .iter(app.world())
+        .map(|name| name.to_string())
+        .collect();
+    assert!(
+        ungrouped.is_empty(),
+        "colliders without groups: {ungrouped:?}"
+    );
+}
+
+#[test]
+fn replays_play_back_the_same_run() {
+    const FRAMES: usize = 300;
+    let path = std::env::temp_dir().join(format!("bunvivor-replay-{}.ron", std::process::id()));
+
+    let mut recording = App::with_config(AppConfig {
+        launch: LaunchOptions {
+            seed: Some(7),
+            skip_menu: true,
+            record: Some(path.clone()),
+            ..default()
+        },
+        ..AppConfig::headless()
+    });
+    recording
+        .world_mut()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )));
+    finish_loading(&mut recording);
+    for _ in 0..FRAMES {
+        recording.update();
+    }
+    recording.world_mut().send_event(AppExit::Success);
+    recording.update();
+
+    let mut playback = App::with_config(AppConfig {
+        launch: LaunchOptions {
+            replay: Some(path.clone()),
+            ..default()
+        },
+        ..AppConfig::headless()
+    });
+    finish_loading(&mut playback);
+    for _ in 0..FRAMES + 1 {
+        playback.update();
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    // rapier isn't bit for bit deterministic between apps, so allow bodies
+    // resting against each other to settle slightly differently
+    let recorded = positions(&mut recording);
+    let played = positions(&mut playback);
+    assert_eq!(recorded.len(), played.len());
+    for position in recorded {
+        assert!(
+            played
+                .iter()
+                .any(|other| other.distance(position) < REPLAY_TOLERANCE),
+            "nothing played back near {position}, got {played:?}"
+        );
+    }
+}
+
+/// Where every physics body is
+fn positions(app: &mut App) -> Vec<Vec3> {
+    return app
+        .world_mut()
+        .query_filtered::<&Transform, With<Velocity>>()
+        .iter(app.world())
+        .map(|transform| transform.translation)
+        .collect();
+}
+
+#[test]
+fn the_main_menu_shows_online_scores() {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        // read the whole request, closing with some of it unread resets the
+        // connection before the client sees the response
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            let read = stream.read(&mut buf).unwrap();
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..read]);
+        }
+        let body = "[(survived_seconds: 600.0, level: 30, kills: 900, gold: 50, character: \"Online Bun\")]";
+        write!(
+            stream,
+            "HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+    });
+
+    let mut app = App::with_config(AppConfig {
+        launch: LaunchOptions {
+            leaderboard: Some(endpoint),
+            ..default()
+        },
+        ..AppConfig::headless()
+    });
+    finish_loading(&mut app);
+
+    let shown = |app: &mut App| {
+        app.world_mut()
+            .query::<&Text>()
+            .iter(app.world())
+            .any(|text| text.0.contains("Online Bun"))
+    };
+    // the request runs in the background, give it as long as it could take
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    while std::time::Instant::now() < deadline {
+        app.update();
+        if shown(&mut app) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    panic!("online scores never showed up");
+}
+
+#[cfg(feature = "net")]
+#[test]
+fn joining_an_online_game_follows_the_host() {
+    use bunvivor::Enemy;
+    use bunvivor::net::{HostPort, NetRole};
+
+    const FRAMES: usize = 180;
+
+    let online = |role, skip_menu| {
+        let mut app = App::with_config(AppConfig {
+            launch: LaunchOptions {
+                seed: Some(11),
+                skip_menu,
+                net: Some(role),
+                ..default()
+            },
+            ..AppConfig::headless()
+        });
+        app.world_mut()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1.0 / 60.0,
+            )));
+        return app;
+    };
+    // any free port, so tests running alongside can't collide
+    let mut host = online(NetRole::Host { port: 0 }, true);
+    let port = **host.world().resource::<HostPort>();
+    let mut client = online(
+        NetRole::Join {
+            address: ([127, 0, 0, 1], port).into(),
+        },
+        false,
+    );
+    finish_loading(&mut host);
+    finish_loading(&mut client);
+    for _ in 0..FRAMES {
+        host.update();
+        client.update();
+    }
+
+    assert_eq!(state(&client), GameState::InGame);
+    let enemies = |app: &mut App| {
+        app.world_mut()
+            .query_filtered::<(), With<Enemy>>()
+            .iter(app.world())
+            .count()
+    };
+    // enemies that just died linger on the client until the host stops
+    // mentioning them for long enough
+    let hosted = enemies(&mut host);
+    assert!(hosted > 0);
+    assert!(enemies(&mut client) >= hosted);
+}
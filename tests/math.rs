@@ -0,0 +1,65 @@
+//! The vector helpers movement, the camera and AI share
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bunvivor::math::{VecTools, damped_lerp};
+
+const EPSILON: f32 = 1e-5;
+
+#[test]
+fn horizontal_drops_the_height() {
+    assert_eq!(
+        Vec3::new(1.0, 5.0, -2.0).horizontal(),
+        Vec3::new(1.0, 0.0, -2.0)
+    );
+}
+
+#[test]
+fn projecting_onto_a_plane_removes_the_normal_part() {
+    let projected = Vec3::new(3.0, 4.0, 5.0).project_onto_plane(Vec3::Y * 2.0);
+    assert_eq!(projected, Vec3::new(3.0, 0.0, 5.0));
+
+    // a slope's normal, the result should run along the slope
+    let normal = Vec3::new(0.0, 1.0, 1.0).normalize();
+    let along = Vec3::Z.project_onto_plane(normal);
+    assert!(along.dot(normal).abs() < EPSILON);
+
+    // no plane to project onto leaves it alone
+    assert_eq!(Vec3::ONE.project_onto_plane(Vec3::ZERO), Vec3::ONE);
+}
+
+#[test]
+fn moving_toward_stops_on_the_target() {
+    let moved = Vec3::ZERO.move_toward(Vec3::X * 10.0, 3.0);
+    assert!(moved.abs_diff_eq(Vec3::X * 3.0, EPSILON));
+
+    assert_eq!(Vec3::ZERO.move_toward(Vec3::X, 3.0), Vec3::X);
+    assert_eq!(Vec3::X.move_toward(Vec3::X, 0.0), Vec3::X);
+}
+
+#[test]
+fn angles_over_the_ground_ignore_height() {
+    let angle = Vec3::X.angle_to_xz(Vec3::new(0.0, 8.0, 1.0));
+    assert!((angle - FRAC_PI_2).abs() < EPSILON);
+
+    let angle = Vec3::X.angle_to_xz(Vec3::NEG_Z);
+    assert!((angle + FRAC_PI_2).abs() < EPSILON);
+}
+
+#[test]
+fn damped_lerp_is_the_same_at_any_framerate() {
+    let mut fast = 0.0;
+    for _ in 0..60 {
+        fast = damped_lerp(fast, 10.0, 3.0, 1.0 / 60.0);
+    }
+    let mut slow = 0.0;
+    for _ in 0..10 {
+        slow = damped_lerp(slow, 10.0, 3.0, 1.0 / 10.0);
+    }
+    assert!((fast - slow).abs() < 1e-3);
+    assert!(fast < 10.0);
+
+    // a huge frame gets there without overshooting
+    assert!((damped_lerp(0.0, 10.0, 3.0, 100.0) - 10.0).abs() < EPSILON);
+}